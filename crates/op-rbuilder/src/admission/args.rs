@@ -0,0 +1,41 @@
+use clap::{Args, Parser};
+use reth_optimism_cli::commands::Commands;
+
+use crate::args::Cli;
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct AdmissionArgs {
+    /// Reject transactions from senders that exhausted their gas limiter budget or whose
+    /// recent transactions mostly reverted before they enter the pool, and build the
+    /// transactions of senders with a high revert share after those of the other senders
+    #[arg(long = "admission.enabled", env)]
+    pub admission_enabled: bool,
+
+    /// Share of reverted transactions, in percent, above which the transactions of a sender are
+    /// included after the transactions of the other senders
+    #[arg(
+        long = "admission.deprioritize-revert-percent",
+        env,
+        default_value = "25"
+    )]
+    pub admission_deprioritize_revert_percent: u64,
+
+    /// Share of reverted transactions, in percent, above which the transactions of a sender are
+    /// rejected
+    #[arg(long = "admission.max-revert-percent", env, default_value = "50")]
+    pub admission_max_revert_percent: u64,
+
+    /// Minimum number of simulated transactions before the revert share of a sender is used
+    #[arg(long = "admission.min-simulated-txs", env, default_value = "10")]
+    pub admission_min_simulated_txs: u64,
+}
+
+impl Default for AdmissionArgs {
+    fn default() -> Self {
+        let args = Cli::parse_from(["dummy", "node"]);
+        let Commands::Node(node_command) = args.command else {
+            unreachable!()
+        };
+        node_command.ext.admission
+    }
+}
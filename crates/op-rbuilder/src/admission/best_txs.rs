@@ -0,0 +1,119 @@
+use alloy_primitives::Address;
+use reth_payload_util::PayloadTransactions;
+use reth_transaction_pool::PoolTransaction;
+use std::collections::{HashMap, VecDeque};
+
+/// Yields the transactions of deprioritized senders after the transactions of the other senders.
+///
+/// Once a transaction of a sender is deferred, its following transactions are deferred as well,
+/// so that the transactions of a sender are still yielded in nonce order.
+pub(crate) struct DeprioritizedBestTransactions<'a, I: PayloadTransactions, F> {
+    inner: &'a mut I,
+    is_deprioritized: F,
+    /// Whether each sender seen so far is deprioritized, checked once per sender.
+    senders: HashMap<Address, bool>,
+    /// Transactions of deprioritized senders, in the order they were yielded by the inner
+    /// iterator.
+    deferred: VecDeque<I::Transaction>,
+}
+
+impl<'a, I, F> DeprioritizedBestTransactions<'a, I, F>
+where
+    I: PayloadTransactions<Transaction: PoolTransaction>,
+    F: Fn(&Address) -> bool,
+{
+    pub(crate) fn new(inner: &'a mut I, is_deprioritized: F) -> Self {
+        Self {
+            inner,
+            is_deprioritized,
+            senders: HashMap::new(),
+            deferred: VecDeque::new(),
+        }
+    }
+}
+
+impl<I, F> PayloadTransactions for DeprioritizedBestTransactions<'_, I, F>
+where
+    I: PayloadTransactions<Transaction: PoolTransaction>,
+    F: Fn(&Address) -> bool,
+{
+    type Transaction = I::Transaction;
+
+    fn next(&mut self, ctx: ()) -> Option<Self::Transaction> {
+        while let Some(tx) = self.inner.next(ctx) {
+            let deprioritized = *self
+                .senders
+                .entry(tx.sender())
+                .or_insert_with_key(|sender| (self.is_deprioritized)(sender));
+            if !deprioritized {
+                return Some(tx);
+            }
+            self.deferred.push_back(tx);
+        }
+        self.deferred.pop_front()
+    }
+
+    fn mark_invalid(&mut self, sender: Address, nonce: u64) {
+        self.inner.mark_invalid(sender, nonce);
+        self.deferred
+            .retain(|tx| tx.sender() != sender || tx.nonce() < nonce);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_tx::{MockFbTransaction, MockFbTransactionFactory};
+    use reth_payload_util::BestPayloadTransactions;
+    use reth_transaction_pool::{CoinbaseTipOrdering, pool::PendingPool};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_deprioritized_senders_come_last() {
+        let mut pool = PendingPool::new(CoinbaseTipOrdering::<MockFbTransaction>::default());
+        let mut f = MockFbTransactionFactory::default();
+        let spammer = Address::from([0x1; 20]);
+        let honest = Address::from([0x2; 20]);
+
+        pool.add_transaction(Arc::new(f.validated(f.eip1559_with_tip(spammer, 0, 30))), 0);
+        pool.add_transaction(Arc::new(f.validated(f.eip1559_with_tip(spammer, 1, 30))), 0);
+        pool.add_transaction(Arc::new(f.validated(f.eip1559_with_tip(honest, 0, 10))), 0);
+        pool.add_transaction(Arc::new(f.validated(f.eip1559_with_tip(honest, 1, 5))), 0);
+
+        let mut best = BestPayloadTransactions::new(pool.best());
+        let mut iterator =
+            DeprioritizedBestTransactions::new(&mut best, |sender| *sender == spammer);
+
+        let order: Vec<_> = std::iter::from_fn(|| iterator.next(()))
+            .map(|tx| (tx.sender(), tx.nonce()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![(honest, 0), (honest, 1), (spammer, 0), (spammer, 1)]
+        );
+    }
+
+    #[test]
+    fn test_mark_invalid_drops_deferred_transactions() {
+        let mut pool = PendingPool::new(CoinbaseTipOrdering::<MockFbTransaction>::default());
+        let mut f = MockFbTransactionFactory::default();
+        let spammer = Address::from([0x1; 20]);
+        let honest = Address::from([0x2; 20]);
+
+        pool.add_transaction(Arc::new(f.validated(f.eip1559_with_tip(spammer, 0, 30))), 0);
+        pool.add_transaction(Arc::new(f.validated(f.eip1559_with_tip(spammer, 1, 30))), 0);
+        pool.add_transaction(Arc::new(f.validated(f.eip1559_with_tip(honest, 0, 10))), 0);
+
+        let mut best = BestPayloadTransactions::new(pool.best());
+        let mut iterator =
+            DeprioritizedBestTransactions::new(&mut best, |sender| *sender == spammer);
+
+        let tx = iterator.next(()).unwrap();
+        assert_eq!((tx.sender(), tx.nonce()), (honest, 0));
+
+        let tx = iterator.next(()).unwrap();
+        assert_eq!((tx.sender(), tx.nonce()), (spammer, 0));
+        iterator.mark_invalid(spammer, 0);
+        assert!(iterator.next(()).is_none());
+    }
+}
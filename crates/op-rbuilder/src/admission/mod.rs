@@ -0,0 +1,327 @@
+//! Admission control of the transaction pool.
+//!
+//! Transactions are checked before the regular pool validation, so that the transactions of
+//! known spammers are rejected before they churn the pool and cost simulation time. The
+//! transactions of senders that revert often, but not enough to be rejected, are built after
+//! those of the other senders.
+
+use std::{any::Any, sync::Arc};
+
+use alloy_primitives::Address;
+use dashmap::DashSet;
+use metrics::Counter;
+use reth_metrics::Metrics;
+use reth_transaction_pool::error::PoolTransactionError;
+
+use crate::{
+    admission::args::AdmissionArgs,
+    gas_limiter::{AddressGasLimiter, error::GasLimitError},
+    reputation::SenderReputation,
+};
+
+pub mod args;
+mod best_txs;
+mod pool;
+
+pub(crate) use best_txs::DeprioritizedBestTransactions;
+pub use pool::{AdmissionValidator, FBPoolBuilder, FBTransactionPool};
+
+/// Gas used by the cheapest transaction. A sender that can't cover it has an empty bucket.
+const MIN_TRANSACTION_GAS: u64 = 21_000;
+
+/// Maximum number of deprioritized senders remembered to count each of them once. Senders that
+/// are no longer tracked by the reputation are forgotten above it.
+const MAX_DEPRIORITIZED_SENDERS: usize = 10_000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AdmissionError {
+    #[error("gas limiter budget exhausted: {0}")]
+    GasLimitExhausted(#[from] GasLimitError),
+    #[error("sender {sender} reverted {reverted} of its last {simulated} simulated transactions")]
+    HighRevertRate {
+        sender: Address,
+        reverted: u64,
+        simulated: u64,
+    },
+}
+
+impl PoolTransactionError for AdmissionError {
+    fn is_bad_transaction(&self) -> bool {
+        // the transaction itself is valid, the sender is throttled
+        false
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Metrics, Clone)]
+#[metrics(scope = "op_rbuilder.admission")]
+struct AdmissionMetrics {
+    /// Transactions rejected because the gas limiter budget of their sender was exhausted
+    gas_limit_rejections: Counter,
+    /// Transactions rejected because their sender reverted too many transactions
+    revert_rate_rejections: Counter,
+    /// Senders whose transactions were built after those of the other senders, counted once
+    /// each time they become deprioritized
+    deprioritized_senders: Counter,
+}
+
+/// Decides whether transactions are admitted to the pool, based on the address gas limiter
+/// budget and the reputation of their sender.
+#[derive(Clone)]
+pub struct AdmissionControl {
+    config: AdmissionArgs,
+    gas_limiter: AddressGasLimiter,
+    reputation: SenderReputation,
+    /// Senders that are currently deprioritized, so that each is counted once
+    deprioritized: Arc<DashSet<Address>>,
+    metrics: AdmissionMetrics,
+}
+
+impl core::fmt::Debug for AdmissionControl {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AdmissionControl")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AdmissionControl {
+    pub fn new(
+        config: AdmissionArgs,
+        gas_limiter: AddressGasLimiter,
+        reputation: SenderReputation,
+    ) -> Self {
+        Self {
+            config,
+            gas_limiter,
+            reputation,
+            deprioritized: Default::default(),
+            metrics: Default::default(),
+        }
+    }
+
+    /// Checks whether a transaction of `sender` calling `to` is admitted to the pool.
+    pub fn check(
+        &self,
+        sender: Address,
+        to: Option<Address>,
+        gas_limit: u64,
+    ) -> Result<(), AdmissionError> {
+        if !self.config.admission_enabled {
+            return Ok(());
+        }
+
+        // the gas used is only known after simulation, only reject senders that can't pay
+        // for any transaction
+        if let Err(err) = self
+            .gas_limiter
+            .check_gas(sender, to, gas_limit.min(MIN_TRANSACTION_GAS))
+        {
+            self.metrics.gas_limit_rejections.increment(1);
+            return Err(err.into());
+        }
+
+        if let Some(stats) = self.reputation.stats(&sender)
+            && stats.simulated >= self.config.admission_min_simulated_txs
+            && stats.revert_percent() > self.config.admission_max_revert_percent
+        {
+            self.metrics.revert_rate_rejections.increment(1);
+            return Err(AdmissionError::HighRevertRate {
+                sender,
+                reverted: stats.reverted,
+                simulated: stats.simulated,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Whether the transactions of `sender` are built after the transactions of the other
+    /// senders, because too many of its recent transactions reverted.
+    pub fn is_deprioritized(&self, sender: &Address) -> bool {
+        if !self.config.admission_enabled {
+            return false;
+        }
+
+        let deprioritized = self.reputation.stats(sender).is_some_and(|stats| {
+            stats.simulated >= self.config.admission_min_simulated_txs
+                && stats.revert_percent() > self.config.admission_deprioritize_revert_percent
+        });
+        if !deprioritized {
+            self.deprioritized.remove(sender);
+        } else if self.deprioritized.insert(*sender) {
+            self.metrics.deprioritized_senders.increment(1);
+            if self.deprioritized.len() > MAX_DEPRIORITIZED_SENDERS {
+                self.deprioritized
+                    .retain(|sender| self.reputation.stats(sender).is_some());
+            }
+        }
+        deprioritized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gas_limiter::args::GasLimiterArgs, primitives::reth::TxnExecutionResult,
+        reputation::args::ReputationArgs,
+    };
+    use alloy_primitives::TxHash;
+
+    fn admission(gas_limiter: AddressGasLimiter) -> (AdmissionControl, SenderReputation) {
+        let reputation = SenderReputation::new(ReputationArgs {
+            reputation_window_blocks: 10,
+            reputation_short_window_blocks: 1,
+            reputation_max_tracked_senders: 100,
+        });
+        let admission = AdmissionControl::new(
+            AdmissionArgs {
+                admission_enabled: true,
+                admission_deprioritize_revert_percent: 25,
+                admission_max_revert_percent: 50,
+                admission_min_simulated_txs: 4,
+            },
+            gas_limiter,
+            reputation.clone(),
+        );
+        (admission, reputation)
+    }
+
+    fn gas_limiter(max_gas_per_address: u64) -> AddressGasLimiter {
+        AddressGasLimiter::new(GasLimiterArgs {
+            gas_limiter_enabled: true,
+            max_gas_per_address,
+            refill_rate_per_block: 0,
+            cleanup_interval: 100,
+            max_tracked_addresses: 100,
+            gas_limiter_policy_file: None,
+            gas_limiter_check_bundles: false,
+        })
+    }
+
+    #[test]
+    fn test_exhausted_budget_is_rejected() {
+        let gas_limiter = gas_limiter(100_000);
+        let (admission, _) = admission(gas_limiter.clone());
+        let sender = Address::from([0x1; 20]);
+
+        // the gas limit can exceed the budget, only the minimum is required
+        assert!(admission.check(sender, None, 1_000_000).is_ok());
+
+        gas_limiter.consume_gas(sender, None, 90_000).unwrap();
+        assert!(matches!(
+            admission.check(sender, None, 1_000_000),
+            Err(AdmissionError::GasLimitExhausted(_))
+        ));
+        // a cheaper transaction still fits
+        assert!(admission.check(sender, None, 10_000).is_ok());
+    }
+
+    #[test]
+    fn test_high_revert_rate_is_rejected() {
+        let (admission, reputation) = admission(gas_limiter(1_000_000));
+        let sender = Address::from([0x1; 20]);
+
+        for _ in 0..3 {
+            reputation.record(
+                sender,
+                TxHash::random(),
+                &TxnExecutionResult::RevertedAndExcluded,
+                21_000,
+            );
+        }
+        // not enough samples yet
+        assert!(admission.check(sender, None, 21_000).is_ok());
+
+        reputation.record(
+            sender,
+            TxHash::random(),
+            &TxnExecutionResult::Success,
+            21_000,
+        );
+        assert!(matches!(
+            admission.check(sender, None, 21_000),
+            Err(AdmissionError::HighRevertRate {
+                reverted: 3,
+                simulated: 4,
+                ..
+            })
+        ));
+
+        reputation.record(
+            sender,
+            TxHash::random(),
+            &TxnExecutionResult::Success,
+            21_000,
+        );
+        reputation.record(
+            sender,
+            TxHash::random(),
+            &TxnExecutionResult::Success,
+            21_000,
+        );
+        assert!(admission.check(sender, None, 21_000).is_ok());
+    }
+
+    #[test]
+    fn test_moderate_revert_rate_is_deprioritized() {
+        let (admission, reputation) = admission(gas_limiter(1_000_000));
+        let sender = Address::from([0x1; 20]);
+
+        for _ in 0..2 {
+            reputation.record(
+                sender,
+                TxHash::random(),
+                &TxnExecutionResult::RevertedAndExcluded,
+                21_000,
+            );
+        }
+        reputation.record(
+            sender,
+            TxHash::random(),
+            &TxnExecutionResult::Success,
+            21_000,
+        );
+        // not enough samples yet
+        assert!(!admission.is_deprioritized(&sender));
+
+        reputation.record(
+            sender,
+            TxHash::random(),
+            &TxnExecutionResult::Success,
+            21_000,
+        );
+        assert!(admission.is_deprioritized(&sender));
+        // deprioritized senders are still admitted
+        assert!(admission.check(sender, None, 21_000).is_ok());
+
+        for _ in 0..4 {
+            reputation.record(
+                sender,
+                TxHash::random(),
+                &TxnExecutionResult::Success,
+                21_000,
+            );
+        }
+        assert!(!admission.is_deprioritized(&sender));
+    }
+
+    #[test]
+    fn test_disabled_admission_accepts_everything() {
+        let gas_limiter = gas_limiter(0);
+        let admission = AdmissionControl::new(
+            AdmissionArgs {
+                admission_enabled: false,
+                ..AdmissionArgs::default()
+            },
+            gas_limiter,
+            SenderReputation::default(),
+        );
+        assert!(admission.check(Address::ZERO, None, 21_000).is_ok());
+        assert!(!admission.is_deprioritized(&Address::ZERO));
+    }
+}
@@ -0,0 +1,177 @@
+use alloy_consensus::Transaction;
+use reth_node_api::{FullNodeTypes, NodeTypes};
+use reth_node_builder::{
+    BuilderContext,
+    components::{PoolBuilder, TxPoolBuilder, create_blob_store},
+};
+use reth_optimism_chainspec::OpChainSpec;
+use reth_optimism_forks::OpHardforks;
+use reth_optimism_node::node::OpPoolBuilder;
+use reth_optimism_primitives::OpPrimitives;
+use reth_optimism_txpool::{OpTransactionValidator, maintain, supervisor::SupervisorClient};
+use reth_primitives_traits::{Block, SealedBlock};
+use reth_provider::CanonStateSubscriptions;
+use reth_transaction_pool::{
+    CoinbaseTipOrdering, EthPoolTransaction, Pool, PoolTransaction, TransactionOrigin,
+    TransactionValidationOutcome, TransactionValidationTaskExecutor, TransactionValidator,
+    blobstore::DiskFileBlobStore, error::InvalidPoolTransactionError,
+};
+use tracing::{debug, info};
+
+use crate::{admission::AdmissionControl, tx::FBPooledTransaction};
+
+/// Transaction validator that applies the [`AdmissionControl`] before the wrapped validator.
+#[derive(Debug)]
+pub struct AdmissionValidator<V> {
+    inner: V,
+    admission: AdmissionControl,
+}
+
+impl<V> AdmissionValidator<V> {
+    pub fn new(inner: V, admission: AdmissionControl) -> Self {
+        Self { inner, admission }
+    }
+}
+
+impl<V> TransactionValidator for AdmissionValidator<V>
+where
+    V: TransactionValidator<Transaction = FBPooledTransaction>,
+{
+    type Transaction = FBPooledTransaction;
+
+    async fn validate_transaction(
+        &self,
+        origin: TransactionOrigin,
+        transaction: Self::Transaction,
+    ) -> TransactionValidationOutcome<Self::Transaction> {
+        if let Err(err) = self.admission.check(
+            transaction.sender(),
+            transaction.to(),
+            transaction.gas_limit(),
+        ) {
+            debug!(
+                target: "admission",
+                tx_hash = ?transaction.hash(),
+                sender = ?transaction.sender(),
+                error = %err,
+                "Transaction rejected by admission control"
+            );
+            return TransactionValidationOutcome::Invalid(
+                transaction,
+                InvalidPoolTransactionError::Other(Box::new(err)),
+            );
+        }
+
+        self.inner.validate_transaction(origin, transaction).await
+    }
+
+    fn on_new_head_block<B>(&self, new_tip_block: &SealedBlock<B>)
+    where
+        B: Block,
+    {
+        self.inner.on_new_head_block(new_tip_block)
+    }
+}
+
+/// Pool builder of the builder node, an [`OpPoolBuilder`] whose validator is wrapped in an
+/// [`AdmissionValidator`].
+#[derive(Debug, Clone)]
+pub struct FBPoolBuilder {
+    inner: OpPoolBuilder<FBPooledTransaction>,
+    admission: AdmissionControl,
+}
+
+impl FBPoolBuilder {
+    pub fn new(inner: OpPoolBuilder<FBPooledTransaction>, admission: AdmissionControl) -> Self {
+        Self { inner, admission }
+    }
+}
+
+/// Transaction pool built by the [`FBPoolBuilder`].
+pub type FBTransactionPool<Client> = Pool<
+    TransactionValidationTaskExecutor<
+        AdmissionValidator<OpTransactionValidator<Client, FBPooledTransaction>>,
+    >,
+    CoinbaseTipOrdering<FBPooledTransaction>,
+    DiskFileBlobStore,
+>;
+
+impl<Node> PoolBuilder<Node> for FBPoolBuilder
+where
+    Node: FullNodeTypes<Types: NodeTypes<ChainSpec = OpChainSpec, Primitives = OpPrimitives>>,
+    FBPooledTransaction: EthPoolTransaction,
+{
+    type Pool = FBTransactionPool<Node::Provider>;
+
+    async fn build_pool(self, ctx: &BuilderContext<Node>) -> eyre::Result<Self::Pool> {
+        // Copy of `OpPoolBuilder::build_pool` of reth v1.9.3 with the validator wrapped in the
+        // admission control, as the upstream builder doesn't allow wrapping its validator. It
+        // must be diffed against upstream on each reth bump; revert protection relies on the
+        // conditional maintenance task, see the `conditional_maintenance_task` test.
+        let Self { inner, admission } = self;
+        let interop_active = ctx
+            .chain_spec()
+            .is_interop_active_at_timestamp(ctx.head().timestamp);
+
+        let supervisor_client = SupervisorClient::builder(inner.supervisor_http.clone())
+            .minimum_safety(inner.supervisor_safety_level)
+            .build()
+            .await;
+
+        let blob_store = create_blob_store(ctx)?;
+        let validator = TransactionValidationTaskExecutor::eth_builder(ctx.provider().clone())
+            .no_eip4844()
+            .with_head_timestamp(ctx.head().timestamp)
+            .with_max_tx_input_bytes(ctx.config().txpool.max_tx_input_bytes)
+            .kzg_settings(ctx.kzg_settings()?)
+            .set_tx_fee_cap(ctx.config().rpc.rpc_tx_fee_cap)
+            .with_max_tx_gas_limit(ctx.config().txpool.max_tx_gas_limit)
+            .with_minimum_priority_fee(ctx.config().txpool.minimum_priority_fee)
+            .with_additional_tasks(
+                inner
+                    .pool_config_overrides
+                    .additional_validation_tasks
+                    .unwrap_or_else(|| ctx.config().txpool.additional_validation_tasks),
+            )
+            .build_with_tasks(ctx.task_executor().clone(), blob_store.clone())
+            .map(|validator| {
+                let validator = OpTransactionValidator::new(validator)
+                    // In --dev mode we can't require gas fees because we're unable to decode
+                    // the L1 block info
+                    .require_l1_data_gas_fee(!ctx.config().dev.dev)
+                    .with_supervisor(supervisor_client.clone());
+                AdmissionValidator::new(validator, admission)
+            });
+
+        let pool_config = inner.pool_config_overrides.apply(ctx.pool_config());
+        let transaction_pool = TxPoolBuilder::new(ctx)
+            .with_validator(validator)
+            .build_and_spawn_maintenance_task(blob_store, pool_config)?;
+        info!(target: "reth::cli", "Transaction pool initialized");
+
+        if interop_active {
+            ctx.task_executor().spawn_critical(
+                "Op txpool interop maintenance task",
+                maintain::maintain_transaction_pool_interop_future(
+                    transaction_pool.clone(),
+                    ctx.provider().canonical_state_stream(),
+                    supervisor_client,
+                ),
+            );
+            debug!(target: "reth::cli", "Spawned Op interop txpool maintenance task");
+        }
+
+        if inner.enable_tx_conditional {
+            ctx.task_executor().spawn_critical(
+                "Op txpool conditional maintenance task",
+                maintain::maintain_transaction_pool_conditional_future(
+                    transaction_pool.clone(),
+                    ctx.provider().canonical_state_stream(),
+                ),
+            );
+            debug!(target: "reth::cli", "Spawned Op conditional txpool maintenance task");
+        }
+
+        Ok(transaction_pool)
+    }
+}
@@ -5,7 +5,12 @@
 //! clap [Args](clap::Args) for optimism rollup configuration
 
 use crate::{
-    flashtestations::args::FlashtestationsArgs, gas_limiter::args::GasLimiterArgs,
+    admission::args::AdmissionArgs,
+    flashtestations::args::FlashtestationsArgs,
+    gas_limiter::args::GasLimiterArgs,
+    primitives::bundle::{BundleRangeLimits, DEFAULT_MAX_BLOCK_RANGE_BLOCKS},
+    private_tx::args::PrivateTxArgs,
+    reputation::args::ReputationArgs,
     tx_signer::Signer,
 };
 use alloy_primitives::Address;
@@ -73,6 +78,14 @@ pub struct OpRbuilderArgs {
     pub flashtestations: FlashtestationsArgs,
     #[command(flatten)]
     pub gas_limiter: GasLimiterArgs,
+    #[command(flatten)]
+    pub reputation: ReputationArgs,
+    #[command(flatten)]
+    pub admission: AdmissionArgs,
+    #[command(flatten)]
+    pub bundle: BundleArgs,
+    #[command(flatten)]
+    pub private_tx: PrivateTxArgs,
 }
 
 impl Default for OpRbuilderArgs {
@@ -239,6 +252,49 @@ pub struct FlashblocksP2pArgs {
         default_value = "50"
     )]
     pub p2p_max_peer_count: u32,
+
+    /// Enable leader election between the builders connected over p2p.
+    /// Only the leader publishes flashblocks, standbys sync the payloads of the leader.
+    #[arg(
+        long = "flashblocks.p2p_leader_election",
+        env = "FLASHBLOCK_P2P_LEADER_ELECTION",
+        default_value = "false"
+    )]
+    pub p2p_leader_election: bool,
+
+    /// Leader election priority of this builder, the highest priority builder becomes leader
+    #[arg(
+        long = "flashblocks.p2p_leader_priority",
+        env = "FLASHBLOCK_P2P_LEADER_PRIORITY",
+        default_value = "0"
+    )]
+    pub p2p_leader_priority: u64,
+
+    /// Comma-separated list of the peer IDs of the builders taking part in leader election,
+    /// heartbeats of other peers are ignored. Defaults to the peer IDs of the known peers.
+    #[arg(
+        long = "flashblocks.p2p_leader_peers",
+        env = "FLASHBLOCK_P2P_LEADER_PEERS"
+    )]
+    pub p2p_leader_peers: Option<String>,
+
+    /// Interval in milliseconds between leader election heartbeats
+    #[arg(
+        long = "flashblocks.p2p_heartbeat_interval",
+        env = "FLASHBLOCK_P2P_HEARTBEAT_INTERVAL",
+        default_value = "50"
+    )]
+    pub p2p_heartbeat_interval: u64,
+
+    /// Time in milliseconds without heartbeats after which a peer is no longer considered
+    /// for leadership. Should be lower than the flashblock interval so a standby takes over
+    /// within one flashblock.
+    #[arg(
+        long = "flashblocks.p2p_leader_lease",
+        env = "FLASHBLOCK_P2P_LEADER_LEASE",
+        default_value = "200"
+    )]
+    pub p2p_leader_lease: u64,
 }
 
 /// Parameters for telemetry configuration
@@ -260,3 +316,122 @@ pub struct TelemetryArgs {
     )]
     pub sampling_ratio: u64,
 }
+
+/// Parameters for the block range of bundles sent through `eth_sendBundle`
+#[derive(Debug, Clone, PartialEq, Eq, clap::Args)]
+pub struct BundleArgs {
+    /// Maximum number of blocks between the latest block and the max block number of a bundle
+    #[arg(
+        long = "bundle.max-block-range",
+        env = "BUNDLE_MAX_BLOCK_RANGE",
+        default_value_t = DEFAULT_MAX_BLOCK_RANGE_BLOCKS
+    )]
+    pub max_block_range: u64,
+
+    /// Number of blocks after the latest block until which a bundle without a max block number
+    /// is valid. Defaults to the maximum block range.
+    #[arg(
+        long = "bundle.default-block-range",
+        env = "BUNDLE_DEFAULT_BLOCK_RANGE"
+    )]
+    pub default_block_range: Option<u64>,
+
+    /// Maximum block range in seconds, converted to blocks with the chain block time.
+    /// Takes precedence over `--bundle.max-block-range`.
+    #[arg(long = "bundle.max-range-secs", env = "BUNDLE_MAX_RANGE_SECS")]
+    pub max_range_secs: Option<u64>,
+
+    /// Default block range in seconds, converted to blocks with the chain block time.
+    /// Takes precedence over `--bundle.default-block-range`.
+    #[arg(long = "bundle.default-range-secs", env = "BUNDLE_DEFAULT_RANGE_SECS")]
+    pub default_range_secs: Option<u64>,
+}
+
+impl BundleArgs {
+    /// Returns the block range limits of bundles for the given chain block time in
+    /// milliseconds. Ranges are at least one block, and the default range is capped by the
+    /// maximum range.
+    pub fn range_limits(&self, chain_block_time_ms: u64) -> BundleRangeLimits {
+        let secs_to_blocks = |secs: u64| secs.saturating_mul(1000) / chain_block_time_ms.max(1);
+
+        let max_range_blocks = self
+            .max_range_secs
+            .map_or(self.max_block_range, secs_to_blocks)
+            .max(1);
+        let default_range_blocks = self
+            .default_range_secs
+            .map(secs_to_blocks)
+            .or(self.default_block_range)
+            .unwrap_or(max_range_blocks)
+            .clamp(1, max_range_blocks);
+
+        BundleRangeLimits {
+            max_range_blocks,
+            default_range_blocks,
+        }
+    }
+}
+
+impl Default for BundleArgs {
+    fn default() -> Self {
+        let args = crate::args::Cli::parse_from(["dummy", "node"]);
+        let Commands::Node(node_command) = args.command else {
+            unreachable!()
+        };
+        node_command.ext.bundle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundle_range_limits_default() {
+        assert_eq!(
+            BundleArgs::default().range_limits(1000),
+            BundleRangeLimits::default()
+        );
+    }
+
+    #[test]
+    fn bundle_range_limits_in_seconds() {
+        let args = BundleArgs {
+            max_range_secs: Some(10),
+            default_range_secs: Some(2),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            args.range_limits(200),
+            BundleRangeLimits {
+                max_range_blocks: 50,
+                default_range_blocks: 10,
+            }
+        );
+        assert_eq!(
+            args.range_limits(1000),
+            BundleRangeLimits {
+                max_range_blocks: 10,
+                default_range_blocks: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn bundle_default_range_is_capped() {
+        let args = BundleArgs {
+            max_block_range: 5,
+            default_block_range: Some(20),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            args.range_limits(1000),
+            BundleRangeLimits {
+                max_range_blocks: 5,
+                default_range_blocks: 5,
+            }
+        );
+    }
+}
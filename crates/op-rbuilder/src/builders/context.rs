@@ -30,22 +30,26 @@ use reth_optimism_txpool::{
     interop::{MaybeInteropTransaction, is_valid_interop},
 };
 use reth_payload_builder::PayloadId;
+use reth_payload_util::PayloadTransactions;
 use reth_primitives::SealedHeader;
 use reth_primitives_traits::{InMemorySize, SignedTransaction};
 use reth_revm::{State, context::Block};
 use reth_transaction_pool::{BestTransactionsAttributes, PoolTransaction};
 use revm::{DatabaseCommit, context::result::ResultAndState, interpreter::as_u64_saturated};
-use std::{sync::Arc, time::Instant};
+use std::{collections::HashSet, sync::Arc, time::Instant};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, trace};
 
 use crate::{
+    admission::{AdmissionControl, DeprioritizedBestTransactions},
     gas_limiter::AddressGasLimiter,
-    metrics::OpRBuilderMetrics,
+    metrics::{OpRBuilderMetrics, record_gas_limiter_rejection},
     primitives::reth::{ExecutionInfo, TxnExecutionResult},
+    private_tx::{PrivateBestTransactions, PrivateTxPool},
+    reputation::SenderReputation,
     traits::PayloadTxsBounds,
-    tx::MaybeRevertingTransaction,
-    tx_data_store::{TxData, TxDataStore},
+    tx::{MaybeFlashblockFilter, MaybeRevertingTransaction},
+    tx_data_store::{BackrunBundleStatus, TxData, TxDataStore},
     tx_signer::Signer,
 };
 
@@ -80,6 +84,12 @@ pub struct OpPayloadBuilderCtx<ExtraCtx: Debug + Default = ()> {
     pub address_gas_limiter: AddressGasLimiter,
     /// Unified transaction data store (backrun bundles + resource metering)
     pub tx_data_store: TxDataStore,
+    /// Execution history of senders, used by the pool admission control.
+    pub sender_reputation: SenderReputation,
+    /// Admission control, deciding which senders have their transactions built last.
+    pub admission: AdmissionControl,
+    /// Transactions sent through `eth_sendPrivateTransaction`.
+    pub private_txs: PrivateTxPool,
 }
 
 impl<ExtraCtx: Debug + Default> OpPayloadBuilderCtx<ExtraCtx> {
@@ -380,7 +390,11 @@ impl<ExtraCtx: Debug + Default> OpPayloadBuilderCtx<ExtraCtx> {
 
     /// Executes the given best transactions and updates the execution info.
     ///
+    /// `inclusion_time_ms` is the time at which the transactions are made public, in Unix epoch
+    /// milliseconds. Bundle flashblock time conditions are checked against it.
+    ///
     /// Returns `Ok(Some(())` if the job was cancelled.
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn execute_best_transactions<E: Debug + Default>(
         &self,
         info: &mut ExecutionInfo<E>,
@@ -389,6 +403,7 @@ impl<ExtraCtx: Debug + Default> OpPayloadBuilderCtx<ExtraCtx> {
         block_gas_limit: u64,
         block_da_limit: Option<u64>,
         block_da_footprint_limit: Option<u64>,
+        inclusion_time_ms: u64,
     ) -> Result<Option<()>, PayloadBuilderError> {
         let execute_txs_start_time = Instant::now();
         let mut num_txs_considered = 0;
@@ -410,6 +425,24 @@ impl<ExtraCtx: Debug + Default> OpPayloadBuilderCtx<ExtraCtx> {
             block_gas_limit = ?block_gas_limit,
         );
 
+        // private transactions are merged with the best transactions of the pool, leaving out
+        // the ones already executed in a previous flashblock
+        let private_txs = if self.private_txs.is_empty() {
+            Vec::new()
+        } else {
+            let executed: HashSet<_> = info
+                .executed_transactions
+                .iter()
+                .map(|tx| tx.tx_hash())
+                .collect();
+            self.private_txs
+                .best_transactions(self.block_number(), |hash| executed.contains(hash))
+        };
+        let mut private_best_txs = PrivateBestTransactions::new(best_txs, private_txs, base_fee);
+        let mut best_txs = DeprioritizedBestTransactions::new(&mut private_best_txs, |sender| {
+            self.admission.is_deprioritized(sender)
+        });
+
         let block_attr = BlockConditionalAttributes {
             number: self.block_number(),
             timestamp: self.attributes().timestamp(),
@@ -419,6 +452,8 @@ impl<ExtraCtx: Debug + Default> OpPayloadBuilderCtx<ExtraCtx> {
             let interop = tx.interop_deadline();
             let reverted_hashes = tx.reverted_hashes().clone();
             let conditional = tx.conditional().cloned();
+            let flashblock_time_min = tx.flashblock_time_min();
+            let flashblock_time_max = tx.flashblock_time_max();
 
             let tx_da_size = tx.estimated_da_size();
             let tx = tx.into_consensus();
@@ -458,6 +493,13 @@ impl<ExtraCtx: Debug + Default> OpPayloadBuilderCtx<ExtraCtx> {
                 continue;
             }
 
+            if flashblock_time_min.is_some_and(|min| inclusion_time_ms < min)
+                || flashblock_time_max.is_some_and(|max| inclusion_time_ms > max)
+            {
+                best_txs.mark_invalid(tx.signer(), tx.nonce());
+                continue;
+            }
+
             // TODO: remove this condition and feature once we are comfortable enabling interop for everything
             if cfg!(feature = "interop") {
                 // We skip invalid cross chain txs, they would be removed on the next block update in
@@ -538,9 +580,16 @@ impl<ExtraCtx: Debug + Default> OpPayloadBuilderCtx<ExtraCtx> {
             let gas_used = result.gas_used();
             if self
                 .address_gas_limiter
-                .consume_gas(tx.signer(), gas_used)
+                .consume_gas(tx.signer(), tx.to(), gas_used)
                 .is_err()
             {
+                record_gas_limiter_rejection(tx.signer(), "transaction", gas_used);
+                self.sender_reputation.record(
+                    tx.signer(),
+                    tx_hash,
+                    &TxnExecutionResult::MaxGasUsageExceeded,
+                    gas_used,
+                );
                 log_txn(TxnExecutionResult::MaxGasUsageExceeded);
                 best_txs.mark_invalid(tx.signer(), tx.nonce());
                 continue;
@@ -559,6 +608,12 @@ impl<ExtraCtx: Debug + Default> OpPayloadBuilderCtx<ExtraCtx> {
                     num_bundles_reverted += 1;
                 }
                 if exclude_reverting_txs {
+                    self.sender_reputation.record(
+                        tx.signer(),
+                        tx_hash,
+                        &TxnExecutionResult::RevertedAndExcluded,
+                        gas_used,
+                    );
                     log_txn(TxnExecutionResult::RevertedAndExcluded);
                     info!(target: "payload_builder", tx_hash = ?tx.tx_hash(), result = ?result, "skipping reverted transaction");
                     best_txs.mark_invalid(tx.signer(), tx.nonce());
@@ -573,11 +628,26 @@ impl<ExtraCtx: Debug + Default> OpPayloadBuilderCtx<ExtraCtx> {
             if let Some(max_gas_per_txn) = self.max_gas_per_txn
                 && gas_used > max_gas_per_txn
             {
+                self.sender_reputation.record(
+                    tx.signer(),
+                    tx_hash,
+                    &TxnExecutionResult::MaxGasUsageExceeded,
+                    gas_used,
+                );
                 log_txn(TxnExecutionResult::MaxGasUsageExceeded);
                 best_txs.mark_invalid(tx.signer(), tx.nonce());
                 continue;
             }
 
+            // the transaction is included from here on, whether it reverted or not
+            let outcome = if is_success {
+                TxnExecutionResult::Success
+            } else {
+                TxnExecutionResult::Reverted
+            };
+            self.sender_reputation
+                .record(tx.signer(), tx_hash, &outcome, gas_used);
+
             info.cumulative_gas_used += gas_used;
             // record tx da size
             info.cumulative_da_bytes_used += tx_da_size;
@@ -610,7 +680,9 @@ impl<ExtraCtx: Debug + Default> OpPayloadBuilderCtx<ExtraCtx> {
                 let backrun_start_time = Instant::now();
 
                 // Bundles are pre-sorted by total_priority_fee (descending) from the store
-                'bundle_loop: for stored_bundle in backrun_bundles {
+                'bundle_loop: for (bundle_index, stored_bundle) in
+                    backrun_bundles.iter().enumerate()
+                {
                     info!(
                         target: "payload_builder",
                         message = "Executing backrun bundle",
@@ -635,6 +707,13 @@ impl<ExtraCtx: Debug + Default> OpPayloadBuilderCtx<ExtraCtx> {
                             total_effective_tip = total_effective_tip,
                             "Backrun bundle rejected: total effective tip below target tx"
                         );
+                        // the following bundles have lower tips and are not considered
+                        for rejected_bundle in &backrun_bundles[bundle_index..] {
+                            self.tx_data_store.set_bundle_status(
+                                rejected_bundle.bundle_id,
+                                BackrunBundleStatus::RejectedLowFee,
+                            );
+                        }
                         break 'bundle_loop;
                     }
 
@@ -667,6 +746,10 @@ impl<ExtraCtx: Debug + Default> OpPayloadBuilderCtx<ExtraCtx> {
                             result = ?result,
                             "Backrun bundle rejected: exceeds block limits"
                         );
+                        self.tx_data_store.set_bundle_status(
+                            stored_bundle.bundle_id,
+                            BackrunBundleStatus::RejectedOverLimits,
+                        );
                         continue 'bundle_loop;
                     }
 
@@ -682,6 +765,37 @@ impl<ExtraCtx: Debug + Default> OpPayloadBuilderCtx<ExtraCtx> {
                             }
                         };
 
+                        // Charge the sender for every simulation, as for pool transactions,
+                        // so that always-reverting backruns can't be spammed for free.
+                        let backrun_gas_used = result.gas_used();
+                        if let Err(err) = self.address_gas_limiter.consume_gas(
+                            backrun_tx.sender(),
+                            backrun_tx.to(),
+                            backrun_gas_used,
+                        ) {
+                            self.metrics
+                                .backrun_bundles_rejected_gas_limit_total
+                                .increment(1);
+                            record_gas_limiter_rejection(
+                                backrun_tx.sender(),
+                                "backrun",
+                                backrun_gas_used,
+                            );
+                            info!(
+                                target: "payload_builder",
+                                target_tx = ?tx_hash,
+                                failed_tx = ?backrun_tx.hash(),
+                                bundle_id = ?stored_bundle.bundle_id,
+                                error = %err,
+                                "Backrun bundle rejected: gas limit exceeded"
+                            );
+                            self.tx_data_store.set_bundle_status(
+                                stored_bundle.bundle_id,
+                                BackrunBundleStatus::RejectedGasLimit,
+                            );
+                            continue 'bundle_loop;
+                        }
+
                         if !result.is_success() {
                             self.metrics.backrun_bundles_reverted_total.increment(1);
                             info!(
@@ -692,6 +806,10 @@ impl<ExtraCtx: Debug + Default> OpPayloadBuilderCtx<ExtraCtx> {
                                 gas_used = result.gas_used(),
                                 "Backrun bundle reverted (all-or-nothing)"
                             );
+                            self.tx_data_store.set_bundle_status(
+                                stored_bundle.bundle_id,
+                                BackrunBundleStatus::Reverted,
+                            );
                             continue 'bundle_loop;
                         }
 
@@ -725,6 +843,8 @@ impl<ExtraCtx: Debug + Default> OpPayloadBuilderCtx<ExtraCtx> {
                     }
 
                     self.metrics.backrun_bundles_landed_total.increment(1);
+                    self.tx_data_store
+                        .set_bundle_status(stored_bundle.bundle_id, BackrunBundleStatus::Landed);
                 }
 
                 self.metrics
@@ -289,7 +289,7 @@ impl BuilderTransactions<FlashblocksExtraCtx, FlashblocksExecutionInfo>
                 && self.use_permit
             {
                 self.signed_increment_flashblocks_permit_tx(
-                    flashtestations.tee_signer(),
+                    &flashtestations.tee_signer(),
                     ctx,
                     &mut evm,
                 )
@@ -18,22 +18,6 @@ pub struct FlashblocksConfig {
     /// per block is equal to the block time divided by the flashblock interval.
     pub interval: Duration,
 
-    /// How much time would be deducted from block build time to account for latencies in
-    /// milliseconds.
-    ///
-    /// If dynamic_adjustment is false this value would be deducted from first flashblock and
-    /// it shouldn't be more than interval
-    ///
-    /// If dynamic_adjustment is true this value would be deducted from first flashblock and
-    /// it shouldn't be more than interval
-    pub leeway_time: Duration,
-
-    /// Disables dynamic flashblocks number adjustment based on FCU arrival time
-    pub fixed: bool,
-
-    /// Should we disable state root calculation for each flashblock
-    pub disable_state_root: bool,
-
     /// The address of the flashblocks number contract.
     ///
     /// If set a builder tx will be added to the start of every flashblock instead of the regular builder tx.
@@ -56,6 +40,21 @@ pub struct FlashblocksConfig {
 
     /// Maximum number of peers for the p2p node
     pub p2p_max_peer_count: u32,
+
+    /// Whether to run leader election between the builders connected over p2p
+    pub p2p_leader_election: bool,
+
+    /// Leader election priority of this builder
+    pub p2p_leader_priority: u64,
+
+    /// Comma-separated peer IDs of the builders taking part in leader election
+    pub p2p_leader_peers: Option<String>,
+
+    /// Interval between leader election heartbeats
+    pub p2p_heartbeat_interval: Duration,
+
+    /// Time without heartbeats after which a peer loses its leadership lease
+    pub p2p_leader_lease: Duration,
 }
 
 impl Default for FlashblocksConfig {
@@ -63,9 +62,6 @@ impl Default for FlashblocksConfig {
         Self {
             ws_addr: SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 1111),
             interval: Duration::from_millis(250),
-            leeway_time: Duration::from_millis(50),
-            fixed: false,
-            disable_state_root: false,
             flashblocks_number_contract_address: None,
             flashblocks_number_contract_use_permit: false,
             p2p_enabled: false,
@@ -73,6 +69,11 @@ impl Default for FlashblocksConfig {
             p2p_private_key_file: None,
             p2p_known_peers: None,
             p2p_max_peer_count: 50,
+            p2p_leader_election: false,
+            p2p_leader_priority: 0,
+            p2p_leader_peers: None,
+            p2p_heartbeat_interval: Duration::from_millis(50),
+            p2p_leader_lease: Duration::from_millis(200),
         }
     }
 }
@@ -88,12 +89,6 @@ impl TryFrom<OpRbuilderArgs> for FlashblocksConfig {
             args.flashblocks.flashblocks_port,
         );
 
-        let leeway_time = Duration::from_millis(args.flashblocks.flashblocks_leeway_time);
-
-        let fixed = args.flashblocks.flashblocks_fixed;
-
-        let disable_state_root = args.flashblocks.flashblocks_disable_state_root;
-
         let flashblocks_number_contract_address =
             args.flashblocks.flashblocks_number_contract_address;
 
@@ -103,9 +98,6 @@ impl TryFrom<OpRbuilderArgs> for FlashblocksConfig {
         Ok(Self {
             ws_addr,
             interval,
-            leeway_time,
-            fixed,
-            disable_state_root,
             flashblocks_number_contract_address,
             flashblocks_number_contract_use_permit,
             p2p_enabled: args.flashblocks.p2p.p2p_enabled,
@@ -113,6 +105,13 @@ impl TryFrom<OpRbuilderArgs> for FlashblocksConfig {
             p2p_private_key_file: args.flashblocks.p2p.p2p_private_key_file,
             p2p_known_peers: args.flashblocks.p2p.p2p_known_peers,
             p2p_max_peer_count: args.flashblocks.p2p.p2p_max_peer_count,
+            p2p_leader_election: args.flashblocks.p2p.p2p_leader_election,
+            p2p_leader_priority: args.flashblocks.p2p.p2p_leader_priority,
+            p2p_leader_peers: args.flashblocks.p2p.p2p_leader_peers,
+            p2p_heartbeat_interval: Duration::from_millis(
+                args.flashblocks.p2p.p2p_heartbeat_interval,
+            ),
+            p2p_leader_lease: Duration::from_millis(args.flashblocks.p2p.p2p_leader_lease),
         })
     }
 }
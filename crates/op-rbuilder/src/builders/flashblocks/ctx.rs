@@ -1,7 +1,12 @@
 use crate::{
+    admission::{AdmissionControl, args::AdmissionArgs},
     builders::{BuilderConfig, OpPayloadBuilderCtx, flashblocks::FlashblocksConfig},
     gas_limiter::{AddressGasLimiter, args::GasLimiterArgs},
     metrics::OpRBuilderMetrics,
+    primitives::bundle::BundleRangeLimits,
+    private_tx::PrivateTxPool,
+    reputation::SenderReputation,
+    runtime_config::RuntimeConfig,
     traits::ClientBounds,
     tx_data_store::TxDataStore,
 };
@@ -26,12 +31,14 @@ pub(super) struct OpPayloadSyncerCtx {
     da_config: OpDAConfig,
     /// The chainspec
     chain_spec: Arc<OpChainSpec>,
-    /// Max gas that can be used by a transaction.
-    max_gas_per_txn: Option<u64>,
+    /// Runtime settings, providing the max gas that can be used by a transaction.
+    runtime_config: RuntimeConfig,
     /// The metrics for the builder
     metrics: Arc<OpRBuilderMetrics>,
     /// Unified transaction data store (backrun bundles + resource metering)
     tx_data_store: TxDataStore,
+    /// Block range limits of bundles forwarded by peers
+    bundle_range: BundleRangeLimits,
 }
 
 impl OpPayloadSyncerCtx {
@@ -49,9 +56,10 @@ impl OpPayloadSyncerCtx {
             evm_config,
             da_config: builder_config.da_config.clone(),
             chain_spec,
-            max_gas_per_txn: builder_config.max_gas_per_txn,
+            runtime_config: builder_config.runtime_config,
             metrics,
             tx_data_store: builder_config.tx_data_store,
+            bundle_range: builder_config.bundle_range,
         })
     }
 
@@ -60,7 +68,19 @@ impl OpPayloadSyncerCtx {
     }
 
     pub(super) fn max_gas_per_txn(&self) -> Option<u64> {
-        self.max_gas_per_txn
+        self.runtime_config.load().max_gas_per_txn
+    }
+
+    pub(super) fn metrics(&self) -> &OpRBuilderMetrics {
+        &self.metrics
+    }
+
+    pub(super) fn tx_data_store(&self) -> &TxDataStore {
+        &self.tx_data_store
+    }
+
+    pub(super) fn bundle_range(&self) -> BundleRangeLimits {
+        self.bundle_range
     }
 
     pub(super) fn into_op_payload_builder_ctx(
@@ -70,6 +90,7 @@ impl OpPayloadSyncerCtx {
         block_env_attributes: OpNextBlockEnvAttributes,
         cancel: CancellationToken,
     ) -> OpPayloadBuilderCtx {
+        let max_gas_per_txn = self.max_gas_per_txn();
         OpPayloadBuilderCtx {
             evm_config: self.evm_config,
             da_config: self.da_config,
@@ -82,9 +103,16 @@ impl OpPayloadSyncerCtx {
             builder_signer: None,
             metrics: self.metrics,
             extra_ctx: (),
-            max_gas_per_txn: self.max_gas_per_txn,
+            max_gas_per_txn,
             address_gas_limiter: AddressGasLimiter::new(GasLimiterArgs::default()),
             tx_data_store: self.tx_data_store.clone(),
+            sender_reputation: SenderReputation::default(),
+            admission: AdmissionControl::new(
+                AdmissionArgs::default(),
+                AddressGasLimiter::new(GasLimiterArgs::default()),
+                SenderReputation::default(),
+            ),
+            private_txs: PrivateTxPool::default(),
         }
     }
 }
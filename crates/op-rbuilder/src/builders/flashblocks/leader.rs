@@ -0,0 +1,429 @@
+use crate::{
+    builders::flashblocks::p2p::{Heartbeat, Message},
+    metrics::OpRBuilderMetrics,
+};
+use p2p::{Keypair, PeerId, PublicKey};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use tokio::{sync::mpsc, time::Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Shared view of whether this builder is currently the active (leader) builder.
+///
+/// Only the leader publishes flashblocks over the websocket and broadcasts its payloads
+/// to peers. Standbys keep building locally so they can take over at any time, and sync
+/// the payloads of the leader through [`PayloadHandler`](super::payload_handler::PayloadHandler).
+#[derive(Debug, Clone)]
+pub(super) struct LeaderState(Arc<AtomicBool>);
+
+impl LeaderState {
+    /// Returns a state that is always the leader, used when leader election is disabled.
+    pub(super) fn always_leader() -> Self {
+        Self(Arc::new(AtomicBool::new(true)))
+    }
+
+    pub(super) fn is_leader(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn set(&self, is_leader: bool) -> bool {
+        self.0.swap(is_leader, Ordering::Relaxed)
+    }
+}
+
+/// Reasons a heartbeat received from a peer is ignored.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+enum HeartbeatError {
+    #[error("peer is not a configured leader election peer")]
+    UnknownPeer,
+    #[error("invalid public key")]
+    InvalidPublicKey,
+    #[error("public key does not match the peer ID")]
+    PeerIdMismatch,
+    #[error("invalid signature")]
+    InvalidSignature,
+    #[error("heartbeat is not newer than the last heartbeat of the peer")]
+    Stale,
+}
+
+/// Lease-based leader election between builders connected over p2p.
+///
+/// Every builder periodically broadcasts a [`Heartbeat`] with its priority, signed with its p2p
+/// key. Heartbeats are only accepted from the configured peers. A peer holds a lease for `lease`
+/// after each of its heartbeats is received, measured on the local clock so that clock skew
+/// between builders doesn't matter; the leader is the builder with the highest `(priority, node_id)`
+/// among this builder and the peers holding a lease. Since every builder sees the same
+/// heartbeats, they all converge on the same leader.
+pub(super) struct LeaderElection {
+    node_id: Uuid,
+    priority: u64,
+    lease: Duration,
+    /// p2p identity of this builder, heartbeats are signed with it.
+    keypair: Keypair,
+    /// Peers allowed to take part in the election.
+    peer_ids: HashSet<PeerId>,
+    /// Sequence number of the next heartbeat of this builder.
+    sequence: u64,
+    /// Last heartbeat of every peer holding a lease.
+    peers: HashMap<Uuid, PeerLease>,
+    state: LeaderState,
+    metrics: Arc<OpRBuilderMetrics>,
+}
+
+impl LeaderElection {
+    pub(super) fn new(
+        priority: u64,
+        lease: Duration,
+        keypair: Keypair,
+        peer_ids: HashSet<PeerId>,
+        metrics: Arc<OpRBuilderMetrics>,
+    ) -> Self {
+        Self {
+            node_id: Uuid::new_v4(),
+            priority,
+            lease,
+            keypair,
+            peer_ids,
+            sequence: 0,
+            peers: HashMap::new(),
+            // start as a standby, leadership is only taken once peers had a chance to announce themselves
+            state: LeaderState(Arc::new(AtomicBool::new(false))),
+            metrics,
+        }
+    }
+
+    pub(super) fn state(&self) -> LeaderState {
+        self.state.clone()
+    }
+
+    fn heartbeat(&mut self) -> eyre::Result<Heartbeat> {
+        let sequence = self.sequence;
+        self.sequence += 1;
+        let payload = Heartbeat::signing_payload(self.node_id, self.priority, sequence);
+        let signature = self.keypair.sign(&payload)?;
+        Ok(Heartbeat {
+            node_id: self.node_id,
+            priority: self.priority,
+            sequence,
+            public_key: self.keypair.public().encode_protobuf().into(),
+            signature: signature.into(),
+        })
+    }
+
+    /// Checks that the heartbeat was signed by the peer it was received from, which is one of
+    /// the configured peers, and that it is newer than the last heartbeat of the peer.
+    fn verify_heartbeat(
+        &self,
+        peer_id: PeerId,
+        heartbeat: &Heartbeat,
+    ) -> Result<(), HeartbeatError> {
+        if !self.peer_ids.contains(&peer_id) {
+            return Err(HeartbeatError::UnknownPeer);
+        }
+        let public_key = PublicKey::try_decode_protobuf(&heartbeat.public_key)
+            .map_err(|_| HeartbeatError::InvalidPublicKey)?;
+        if public_key.to_peer_id() != peer_id {
+            return Err(HeartbeatError::PeerIdMismatch);
+        }
+        let payload =
+            Heartbeat::signing_payload(heartbeat.node_id, heartbeat.priority, heartbeat.sequence);
+        if !public_key.verify(&payload, &heartbeat.signature) {
+            return Err(HeartbeatError::InvalidSignature);
+        }
+        if self
+            .peers
+            .get(&heartbeat.node_id)
+            .is_some_and(|lease| heartbeat.sequence <= lease.sequence)
+        {
+            return Err(HeartbeatError::Stale);
+        }
+        Ok(())
+    }
+
+    fn on_heartbeat(
+        &mut self,
+        peer_id: PeerId,
+        heartbeat: Heartbeat,
+        now: Instant,
+    ) -> Result<(), HeartbeatError> {
+        self.verify_heartbeat(peer_id, &heartbeat)?;
+        if heartbeat.node_id != self.node_id {
+            self.peers.insert(
+                heartbeat.node_id,
+                PeerLease {
+                    priority: heartbeat.priority,
+                    sequence: heartbeat.sequence,
+                    received_at: now,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Drops expired leases and recomputes the leader, returns whether this builder is the leader.
+    fn update(&mut self, now: Instant) -> bool {
+        self.peers
+            .retain(|_, lease| now.saturating_duration_since(lease.received_at) < self.lease);
+
+        let (leader_priority, leader_id) = self
+            .peers
+            .iter()
+            .map(|(node_id, lease)| (lease.priority, *node_id))
+            .chain(std::iter::once((self.priority, self.node_id)))
+            .max()
+            .expect("self is always a candidate");
+        let is_leader = leader_id == self.node_id;
+
+        if self.state.set(is_leader) != is_leader {
+            self.metrics.leader_changes_count.increment(1);
+            if is_leader {
+                info!(node_id = %self.node_id, priority = self.priority, "became flashblocks leader");
+            } else {
+                info!(node_id = %self.node_id, leader = %leader_id, leader_priority, "became flashblocks standby");
+            }
+        }
+        self.metrics
+            .flashblocks_leader
+            .set(if is_leader { 1.0 } else { 0.0 });
+
+        is_leader
+    }
+
+    /// Sends heartbeats every `heartbeat_interval` and processes the heartbeats of peers
+    /// until cancelled.
+    pub(super) async fn run(
+        mut self,
+        mut heartbeat_rx: mpsc::Receiver<(PeerId, Message)>,
+        p2p_tx: mpsc::Sender<Message>,
+        heartbeat_interval: Duration,
+        cancel: CancellationToken,
+    ) {
+        info!(
+            node_id = %self.node_id,
+            priority = self.priority,
+            peers = ?self.peer_ids,
+            "flashblocks leader election started"
+        );
+
+        let mut timer = tokio::time::interval(heartbeat_interval);
+        // the first tick completes immediately, announce ourselves before electing a leader
+        timer.tick().await;
+        self.send_heartbeat(&p2p_tx).await;
+
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => {
+                    debug!("cancellation token triggered, stopping leader election");
+                    return;
+                }
+                _ = timer.tick() => {
+                    self.update(Instant::now());
+                    if !self.send_heartbeat(&p2p_tx).await {
+                        warn!("p2p channel closed, stopping leader election");
+                        return;
+                    }
+                }
+                Some((peer_id, message)) = heartbeat_rx.recv() => {
+                    match message {
+                        Message::Heartbeat(heartbeat) => {
+                            match self.on_heartbeat(peer_id, heartbeat, Instant::now()) {
+                                Ok(()) => {}
+                                // unknown peers keep sending heartbeats, they are only logged at debug
+                                Err(e @ HeartbeatError::UnknownPeer) => {
+                                    debug!(%peer_id, error = %e, "ignoring leader election heartbeat");
+                                }
+                                Err(e) => {
+                                    warn!(%peer_id, error = %e, "ignoring invalid leader election heartbeat");
+                                }
+                            }
+                        }
+                        message => {
+                            warn!(%peer_id, ?message, "unexpected message on leader election protocol");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Broadcasts a heartbeat to peers, returns false if the p2p channel is closed.
+    async fn send_heartbeat(&mut self, p2p_tx: &mpsc::Sender<Message>) -> bool {
+        match self.heartbeat() {
+            Ok(heartbeat) => p2p_tx.send(Message::Heartbeat(heartbeat)).await.is_ok(),
+            Err(e) => {
+                warn!(error = %e, "failed to sign leader election heartbeat");
+                true
+            }
+        }
+    }
+}
+
+/// Last heartbeat received from a peer.
+struct PeerLease {
+    priority: u64,
+    sequence: u64,
+    /// Local time the heartbeat was received at, the lease is held for `lease` after it.
+    received_at: Instant,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEASE: Duration = Duration::from_millis(200);
+
+    fn election(priority: u64) -> LeaderElection {
+        LeaderElection::new(
+            priority,
+            LEASE,
+            Keypair::generate_ed25519(),
+            HashSet::new(),
+            Arc::new(OpRBuilderMetrics::default()),
+        )
+    }
+
+    fn peer_id(election: &LeaderElection) -> PeerId {
+        election.keypair.public().to_peer_id()
+    }
+
+    /// Configures `a` and `b` as leader election peers of each other.
+    fn allow(a: &mut LeaderElection, b: &mut LeaderElection) {
+        a.peer_ids.insert(peer_id(b));
+        b.peer_ids.insert(peer_id(a));
+    }
+
+    fn send_heartbeat(
+        from: &mut LeaderElection,
+        to: &mut LeaderElection,
+        now: Instant,
+    ) -> Result<(), HeartbeatError> {
+        to.on_heartbeat(peer_id(from), from.heartbeat().unwrap(), now)
+    }
+
+    #[test]
+    fn single_builder_is_leader() {
+        let mut election = election(0);
+        assert!(!election.state().is_leader());
+        assert!(election.update(Instant::now()));
+        assert!(election.state().is_leader());
+    }
+
+    #[test]
+    fn highest_priority_is_leader() {
+        let now = Instant::now();
+        let mut low = election(1);
+        let mut high = election(2);
+        allow(&mut low, &mut high);
+
+        send_heartbeat(&mut high, &mut low, now).unwrap();
+        send_heartbeat(&mut low, &mut high, now).unwrap();
+
+        assert!(!low.update(now));
+        assert!(high.update(now));
+    }
+
+    #[test]
+    fn equal_priority_is_resolved_by_node_id() {
+        let now = Instant::now();
+        let mut a = election(1);
+        let mut b = election(1);
+        allow(&mut a, &mut b);
+
+        send_heartbeat(&mut b, &mut a, now).unwrap();
+        send_heartbeat(&mut a, &mut b, now).unwrap();
+
+        assert_ne!(a.update(now), b.update(now));
+    }
+
+    #[test]
+    fn standby_takes_over_when_lease_expires() {
+        let now = Instant::now();
+        let mut standby = election(1);
+        let mut leader = election(2);
+        allow(&mut standby, &mut leader);
+
+        send_heartbeat(&mut leader, &mut standby, now).unwrap();
+        assert!(!standby.update(now + LEASE / 2));
+        assert!(standby.update(now + LEASE));
+        assert!(standby.state().is_leader());
+    }
+
+    #[test]
+    fn leader_steps_down_for_higher_priority_peer() {
+        let now = Instant::now();
+        let mut leader = election(1);
+        let mut peer = election(2);
+        allow(&mut leader, &mut peer);
+        assert!(leader.update(now));
+
+        send_heartbeat(&mut peer, &mut leader, now).unwrap();
+        assert!(!leader.update(now));
+        assert!(!leader.state().is_leader());
+    }
+
+    #[test]
+    fn heartbeats_of_unknown_peers_are_ignored() {
+        let now = Instant::now();
+        let mut leader = election(1);
+        let mut intruder = election(u64::MAX);
+
+        assert_eq!(
+            send_heartbeat(&mut intruder, &mut leader, now),
+            Err(HeartbeatError::UnknownPeer)
+        );
+        assert!(leader.update(now));
+    }
+
+    #[test]
+    fn forged_heartbeats_are_ignored() {
+        let now = Instant::now();
+        let mut leader = election(1);
+        let mut peer = election(2);
+        allow(&mut leader, &mut peer);
+
+        // heartbeat of another key, relayed by a configured peer
+        let mut other = election(3);
+        let heartbeat = other.heartbeat().unwrap();
+        assert_eq!(
+            leader.on_heartbeat(peer_id(&peer), heartbeat, now),
+            Err(HeartbeatError::PeerIdMismatch)
+        );
+
+        // heartbeat of a configured peer with a tampered priority
+        let mut heartbeat = peer.heartbeat().unwrap();
+        heartbeat.priority = u64::MAX;
+        assert_eq!(
+            leader.on_heartbeat(peer_id(&peer), heartbeat, now),
+            Err(HeartbeatError::InvalidSignature)
+        );
+
+        assert!(leader.update(now));
+    }
+
+    #[test]
+    fn replayed_heartbeats_are_ignored() {
+        let now = Instant::now();
+        let mut leader = election(1);
+        let mut peer = election(2);
+        allow(&mut leader, &mut peer);
+
+        let old = peer.heartbeat().unwrap();
+        let new = peer.heartbeat().unwrap();
+        leader.on_heartbeat(peer_id(&peer), new, now).unwrap();
+        assert_eq!(
+            leader.on_heartbeat(peer_id(&peer), old, now + LEASE / 2),
+            Err(HeartbeatError::Stale)
+        );
+
+        // the replayed heartbeat doesn't extend the lease of the peer
+        assert!(leader.update(now + LEASE));
+    }
+}
@@ -7,10 +7,12 @@ mod best_txs;
 mod builder_tx;
 mod config;
 mod ctx;
+mod leader;
 mod p2p;
 mod payload;
 mod payload_handler;
 mod service;
+mod validation;
 mod wspub;
 
 /// Block building strategy that progressively builds chunks of a block and makes them available
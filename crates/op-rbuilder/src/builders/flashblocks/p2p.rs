@@ -1,21 +1,102 @@
-use alloy_primitives::U256;
+use crate::tx_data_store::TxDataUpdate;
+use alloy_primitives::{B256, Bytes, U256};
+use p2p::PeerId;
 use reth::{core::primitives::SealedBlock, payload::PayloadId};
 use reth_optimism_payload_builder::OpBuiltPayload as RethOpBuiltPayload;
-use reth_optimism_primitives::OpBlock;
+use reth_optimism_primitives::{OpBlock, OpTransactionSigned};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use uuid::Uuid;
 
 pub(super) const AGENT_VERSION: &str = "op-rbuilder/1.0.0";
 pub(super) const FLASHBLOCKS_STREAM_PROTOCOL: p2p::StreamProtocol =
     p2p::StreamProtocol::new("/flashblocks/1.0.0");
+pub(super) const LEADER_STREAM_PROTOCOL: p2p::StreamProtocol =
+    p2p::StreamProtocol::new("/flashblocks/leader/1.0.0");
+pub(super) const BUNDLES_STREAM_PROTOCOL: p2p::StreamProtocol =
+    p2p::StreamProtocol::new("/flashblocks/bundles/1.0.0");
+pub(super) const METERING_STREAM_PROTOCOL: p2p::StreamProtocol =
+    p2p::StreamProtocol::new("/flashblocks/metering/1.0.0");
+pub(super) const CANCELLATION_STREAM_PROTOCOL: p2p::StreamProtocol =
+    p2p::StreamProtocol::new("/flashblocks/cancellation/1.0.0");
+
+/// Protocols carrying the messages handled by
+/// [`PayloadHandler`](super::payload_handler::PayloadHandler).
+pub(super) const PAYLOAD_HANDLER_PROTOCOLS: [p2p::StreamProtocol; 4] = [
+    FLASHBLOCKS_STREAM_PROTOCOL,
+    BUNDLES_STREAM_PROTOCOL,
+    METERING_STREAM_PROTOCOL,
+    CANCELLATION_STREAM_PROTOCOL,
+];
 
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub(super) enum Message {
     OpBuiltPayload(OpBuiltPayload),
+    FlashblockDelta(FlashblockDelta),
+    /// Request to the leader to send the next flashblock of the payload in full, sent by a peer
+    /// that can't apply the deltas of the payload.
+    FlashblockResync(PayloadId),
+    Heartbeat(Heartbeat),
+    TxData(TxDataUpdate),
 }
 
 impl p2p::Message for Message {
     fn protocol(&self) -> p2p::StreamProtocol {
-        FLASHBLOCKS_STREAM_PROTOCOL
+        match self {
+            Message::OpBuiltPayload(_)
+            | Message::FlashblockDelta(_)
+            | Message::FlashblockResync(_) => FLASHBLOCKS_STREAM_PROTOCOL,
+            Message::Heartbeat(_) => LEADER_STREAM_PROTOCOL,
+            Message::TxData(update) => match update {
+                TxDataUpdate::ForwardedBundle(_) | TxDataUpdate::BackrunBundle(_) => {
+                    BUNDLES_STREAM_PROTOCOL
+                }
+                TxDataUpdate::Metering { .. } | TxDataUpdate::ClearMetering => {
+                    METERING_STREAM_PROTOCOL
+                }
+                TxDataUpdate::CancelBackrunBundles { .. } => CANCELLATION_STREAM_PROTOCOL,
+            },
+        }
+    }
+}
+
+/// Messages received from peers on the protocols handled by
+/// [`PayloadHandler`](super::payload_handler::PayloadHandler), one receiver per protocol.
+pub(super) struct IncomingMessages {
+    pub(super) flashblocks: mpsc::Receiver<(PeerId, Message)>,
+    pub(super) bundles: mpsc::Receiver<(PeerId, Message)>,
+    pub(super) metering: mpsc::Receiver<(PeerId, Message)>,
+    pub(super) cancellation: mpsc::Receiver<(PeerId, Message)>,
+}
+
+impl IncomingMessages {
+    /// Takes the receivers of every protocol in [`PAYLOAD_HANDLER_PROTOCOLS`] out of the
+    /// receivers returned by the p2p node.
+    pub(super) fn from_node(
+        rxs: &mut HashMap<p2p::StreamProtocol, mpsc::Receiver<(PeerId, Message)>>,
+    ) -> eyre::Result<Self> {
+        let mut take = |protocol: p2p::StreamProtocol| {
+            rxs.remove(&protocol)
+                .ok_or_else(|| eyre::eyre!("p2p protocol {protocol} not found in receiver map"))
+        };
+        Ok(Self {
+            flashblocks: take(FLASHBLOCKS_STREAM_PROTOCOL)?,
+            bundles: take(BUNDLES_STREAM_PROTOCOL)?,
+            metering: take(METERING_STREAM_PROTOCOL)?,
+            cancellation: take(CANCELLATION_STREAM_PROTOCOL)?,
+        })
+    }
+
+    /// Receivers that never yield a message, used when p2p is disabled.
+    pub(super) fn disconnected() -> Self {
+        let rx = || mpsc::channel(1).1;
+        Self {
+            flashblocks: rx(),
+            bundles: rx(),
+            metering: rx(),
+            cancellation: rx(),
+        }
     }
 }
 
@@ -31,6 +112,108 @@ pub(crate) struct OpBuiltPayload {
     pub(crate) fees: U256,
 }
 
+/// Incremental update to a payload that was previously sent in full.
+///
+/// Only the transactions added since the previous flashblock index are carried,
+/// the receiver applies them on top of its cached execution state for the payload.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) struct FlashblockDelta {
+    /// Identifier of the payload the flashblock belongs to
+    pub(crate) id: PayloadId,
+    /// Index of the flashblock within the payload
+    pub(crate) index: u64,
+    /// Transactions added since the previous flashblock
+    pub(crate) transactions: Vec<OpTransactionSigned>,
+    /// Cumulative gas used by the block once the transactions are applied
+    pub(crate) gas_used: u64,
+    /// State root of the block once the transactions are applied
+    pub(crate) state_root: B256,
+    /// Receipts root of the block once the transactions are applied
+    pub(crate) receipts_root: B256,
+    /// Hash of the block once the transactions are applied
+    pub(crate) block_hash: B256,
+}
+
+/// Periodic announcement of a builder taking part in leader election.
+///
+/// Heartbeats are signed with the p2p key of the builder, so that only the configured peers
+/// can take part in the election.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) struct Heartbeat {
+    /// Random identifier of the builder, generated on startup
+    pub(crate) node_id: Uuid,
+    /// Election priority of the builder, the highest priority builder is the leader
+    pub(crate) priority: u64,
+    /// Sequence number of the heartbeat, incremented with every heartbeat of the builder
+    pub(crate) sequence: u64,
+    /// Protobuf encoded p2p public key of the builder
+    pub(crate) public_key: Bytes,
+    /// Signature of [`Heartbeat::signing_payload`] with the p2p key of the builder
+    pub(crate) signature: Bytes,
+}
+
+impl Heartbeat {
+    /// Domain separation tag of the heartbeat signatures.
+    const SIGNATURE_DOMAIN: &'static [u8] = b"op-rbuilder/flashblocks-leader-heartbeat/v1";
+
+    /// Returns the message signed by the builder.
+    pub(crate) fn signing_payload(node_id: Uuid, priority: u64, sequence: u64) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(Self::SIGNATURE_DOMAIN.len() + 32);
+        payload.extend_from_slice(Self::SIGNATURE_DOMAIN);
+        payload.extend_from_slice(node_id.as_bytes());
+        payload.extend_from_slice(&priority.to_be_bytes());
+        payload.extend_from_slice(&sequence.to_be_bytes());
+        payload
+    }
+}
+
+/// Tracks what was last sent to peers for the current payload, so that only
+/// the first flashblock of a payload is sent in full and the following ones
+/// are sent as [`FlashblockDelta`]s.
+#[derive(Debug, Default)]
+pub(super) struct FlashblockDeltaTracker {
+    /// Payload id, flashblock index and transaction count of the last message sent.
+    last_sent: Option<(PayloadId, u64, usize)>,
+}
+
+impl FlashblockDeltaTracker {
+    /// Returns the message to broadcast to peers for a newly built payload.
+    pub(super) fn next_message(&mut self, payload: RethOpBuiltPayload) -> Message {
+        let id = payload.id();
+        let transactions = &payload.block().body().transactions;
+
+        match self.last_sent {
+            Some((last_id, last_index, last_tx_count))
+                if last_id == id && last_tx_count <= transactions.len() =>
+            {
+                let index = last_index + 1;
+                let delta = FlashblockDelta {
+                    id,
+                    index,
+                    transactions: transactions[last_tx_count..].to_vec(),
+                    gas_used: payload.block().header().gas_used,
+                    state_root: payload.block().header().state_root,
+                    receipts_root: payload.block().header().receipts_root,
+                    block_hash: payload.block().hash(),
+                };
+                self.last_sent = Some((id, index, transactions.len()));
+                Message::FlashblockDelta(delta)
+            }
+            _ => {
+                self.last_sent = Some((id, 0, transactions.len()));
+                payload.into()
+            }
+        }
+    }
+
+    /// Sends the next flashblock of the payload in full, for peers that missed a previous one.
+    pub(super) fn resync(&mut self, id: PayloadId) {
+        if self.last_sent.is_some_and(|(last_id, ..)| last_id == id) {
+            self.last_sent = None;
+        }
+    }
+}
+
 impl From<RethOpBuiltPayload> for Message {
     fn from(value: RethOpBuiltPayload) -> Self {
         Message::OpBuiltPayload(value.into())
@@ -58,3 +241,149 @@ impl From<RethOpBuiltPayload> for OpBuiltPayload {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_consensus::{BlockBody, Header, SignableTransaction, TxEip1559};
+    use alloy_primitives::Signature;
+    use op_alloy_consensus::OpTxEnvelope;
+    use reth_node_api::Block as _;
+    use std::sync::Arc;
+
+    fn test_tx(nonce: u64) -> OpTransactionSigned {
+        let tx = TxEip1559 {
+            nonce,
+            gas_limit: 21_000,
+            ..Default::default()
+        };
+        OpTxEnvelope::Eip1559(tx.into_signed(Signature::test_signature()))
+    }
+
+    fn test_payload(id: PayloadId, transactions: Vec<OpTransactionSigned>) -> RethOpBuiltPayload {
+        let gas_used = transactions.len() as u64 * 21_000;
+        let block = OpBlock::new(
+            Header {
+                gas_used,
+                ..Default::default()
+            },
+            BlockBody {
+                transactions,
+                ..Default::default()
+            },
+        );
+        RethOpBuiltPayload::new(id, Arc::new(block.seal_slow()), U256::from(gas_used), None)
+    }
+
+    #[test]
+    fn first_flashblock_is_sent_in_full() {
+        let mut tracker = FlashblockDeltaTracker::default();
+        let id = PayloadId::new([1; 8]);
+
+        let message = tracker.next_message(test_payload(id, vec![test_tx(0)]));
+        assert!(matches!(message, Message::OpBuiltPayload(payload) if payload.id == id));
+    }
+
+    #[test]
+    fn following_flashblocks_are_sent_as_deltas() {
+        let mut tracker = FlashblockDeltaTracker::default();
+        let id = PayloadId::new([1; 8]);
+        let txs = (0..5).map(test_tx).collect::<Vec<_>>();
+
+        tracker.next_message(test_payload(id, txs[..1].to_vec()));
+
+        let payload = test_payload(id, txs[..3].to_vec());
+        let block_hash = payload.block().hash();
+        let Message::FlashblockDelta(delta) = tracker.next_message(payload) else {
+            panic!("expected flashblock delta");
+        };
+        assert_eq!(delta.id, id);
+        assert_eq!(delta.index, 1);
+        assert_eq!(delta.transactions, txs[1..3].to_vec());
+        assert_eq!(delta.gas_used, 3 * 21_000);
+        assert_eq!(delta.block_hash, block_hash);
+
+        let Message::FlashblockDelta(delta) = tracker.next_message(test_payload(id, txs.clone()))
+        else {
+            panic!("expected flashblock delta");
+        };
+        assert_eq!(delta.index, 2);
+        assert_eq!(delta.transactions, txs[3..].to_vec());
+    }
+
+    #[test]
+    fn new_payload_resets_tracker() {
+        let mut tracker = FlashblockDeltaTracker::default();
+        let first = PayloadId::new([1; 8]);
+        let second = PayloadId::new([2; 8]);
+
+        tracker.next_message(test_payload(first, vec![test_tx(0)]));
+        tracker.next_message(test_payload(first, vec![test_tx(0), test_tx(1)]));
+
+        let message = tracker.next_message(test_payload(second, vec![test_tx(0)]));
+        assert!(matches!(message, Message::OpBuiltPayload(payload) if payload.id == second));
+
+        let message = tracker.next_message(test_payload(second, vec![test_tx(0), test_tx(1)]));
+        assert!(matches!(message, Message::FlashblockDelta(delta) if delta.index == 1));
+    }
+
+    #[test]
+    fn resync_sends_next_flashblock_in_full() {
+        let mut tracker = FlashblockDeltaTracker::default();
+        let id = PayloadId::new([1; 8]);
+        let txs = (0..3).map(test_tx).collect::<Vec<_>>();
+
+        tracker.next_message(test_payload(id, txs[..1].to_vec()));
+        // a resync of another payload is ignored
+        tracker.resync(PayloadId::new([2; 8]));
+        let message = tracker.next_message(test_payload(id, txs[..2].to_vec()));
+        assert!(matches!(message, Message::FlashblockDelta(delta) if delta.index == 1));
+
+        tracker.resync(id);
+        let message = tracker.next_message(test_payload(id, txs.clone()));
+        assert!(
+            matches!(message, Message::OpBuiltPayload(payload) if payload.block.body().transactions.len() == 3)
+        );
+        let message = tracker.next_message(test_payload(id, txs));
+        assert!(matches!(message, Message::FlashblockDelta(delta) if delta.index == 1));
+    }
+
+    #[test]
+    fn tx_data_updates_use_their_own_protocols() {
+        use p2p::Message as _;
+
+        let protocol = |update| Message::TxData(update).protocol();
+        assert_eq!(
+            protocol(TxDataUpdate::ClearMetering),
+            METERING_STREAM_PROTOCOL
+        );
+        assert_eq!(
+            protocol(TxDataUpdate::CancelBackrunBundles {
+                target_tx_hash: B256::ZERO
+            }),
+            CANCELLATION_STREAM_PROTOCOL
+        );
+        assert_eq!(
+            protocol(TxDataUpdate::ForwardedBundle(Default::default())),
+            BUNDLES_STREAM_PROTOCOL
+        );
+    }
+
+    #[test]
+    fn delta_round_trips_through_serialization() {
+        use p2p::Message as _;
+
+        let message = Message::FlashblockDelta(FlashblockDelta {
+            id: PayloadId::new([3; 8]),
+            index: 4,
+            transactions: vec![test_tx(7)],
+            gas_used: 21_000,
+            state_root: B256::repeat_byte(0x01),
+            receipts_root: B256::repeat_byte(0x02),
+            block_hash: B256::repeat_byte(0xab),
+        });
+
+        let encoded = message.to_string().unwrap();
+        assert_eq!(Message::from_str(&encoded).unwrap(), message);
+    }
+}
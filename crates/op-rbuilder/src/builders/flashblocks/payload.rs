@@ -4,12 +4,19 @@ use crate::{
         BuilderConfig,
         builder_tx::BuilderTransactions,
         context::OpPayloadBuilderCtx,
-        flashblocks::{best_txs::BestFlashblocksTxs, config::FlashBlocksConfigExt},
+        flashblocks::{
+            best_txs::BestFlashblocksTxs, config::FlashBlocksConfigExt, leader::LeaderState,
+        },
         generator::{BlockCell, BuildArguments, PayloadBuilder},
     },
+    flashtestations::{
+        builder_tx::{TeeRegistrationHandle, compute_block_content_hash},
+        flashblock_signature::sign_flashblock,
+    },
     gas_limiter::AddressGasLimiter,
     metrics::OpRBuilderMetrics,
     primitives::reth::ExecutionInfo,
+    runtime_config::RuntimeSettings,
     traits::{ClientBounds, PoolBounds},
 };
 use alloy_consensus::{
@@ -94,6 +101,14 @@ pub struct FlashblocksExtraCtx {
     da_footprint_per_batch: Option<u64>,
     /// Whether to disable state root calculation for each flashblock
     disable_state_root: bool,
+    /// Time at which the last flashblock is due, in Unix epoch milliseconds
+    flashblocks_deadline_ms: u64,
+    /// Time between two flashblocks, in milliseconds
+    flashblocks_interval_ms: u64,
+    /// Whether the base of the block was published with one of its flashblocks. A builder
+    /// taking over in the middle of a block publishes the base with its first flashblock, so
+    /// that consumers can follow the block from there.
+    base_published: bool,
 }
 
 impl FlashblocksExtraCtx {
@@ -133,6 +148,17 @@ impl OpPayloadBuilderCtx<FlashblocksExtraCtx> {
     pub(crate) fn is_last_flashblock(&self) -> bool {
         self.flashblock_index() == self.target_flashblock_count()
     }
+
+    /// Returns the time at which the current flashblock is expected to be published, in Unix
+    /// epoch milliseconds.
+    pub(crate) fn flashblock_time_ms(&self) -> u64 {
+        let remaining = self
+            .target_flashblock_count()
+            .saturating_sub(self.flashblock_index());
+        self.extra_ctx
+            .flashblocks_deadline_ms
+            .saturating_sub(remaining * self.extra_ctx.flashblocks_interval_ms)
+    }
 }
 
 /// Optimism's payload builder
@@ -158,6 +184,11 @@ pub(super) struct OpPayloadBuilder<Pool, Client, BuilderTx> {
     pub builder_tx: BuilderTx,
     /// Rate limiting based on gas. This is an optional feature.
     pub address_gas_limiter: AddressGasLimiter,
+    /// Whether this builder is the leader, only the leader publishes flashblocks.
+    pub leader: LeaderState,
+    /// TEE registration whose key signs the published flashblocks, when flashblock signatures
+    /// are enabled.
+    pub tee_registration: Option<TeeRegistrationHandle>,
 }
 
 impl<Pool, Client, BuilderTx> OpPayloadBuilder<Pool, Client, BuilderTx> {
@@ -172,8 +203,10 @@ impl<Pool, Client, BuilderTx> OpPayloadBuilder<Pool, Client, BuilderTx> {
         payload_tx: mpsc::Sender<OpBuiltPayload>,
         ws_pub: Arc<WebSocketPublisher>,
         metrics: Arc<OpRBuilderMetrics>,
+        leader: LeaderState,
+        tee_registration: Option<TeeRegistrationHandle>,
     ) -> Self {
-        let address_gas_limiter = AddressGasLimiter::new(config.gas_limiter_config.clone());
+        let address_gas_limiter = config.address_gas_limiter.clone();
         Self {
             evm_config,
             pool,
@@ -184,6 +217,40 @@ impl<Pool, Client, BuilderTx> OpPayloadBuilder<Pool, Client, BuilderTx> {
             metrics,
             builder_tx,
             address_gas_limiter,
+            leader,
+            tee_registration,
+        }
+    }
+
+    /// Signs the flashblock with the TEE key over the content of the block so far. A flashblock
+    /// that can't be signed is published unsigned.
+    fn sign_flashblock(
+        &self,
+        ctx: &OpPayloadBuilderCtx<FlashblocksExtraCtx>,
+        info: &ExecutionInfo<FlashblocksExecutionInfo>,
+        fb_payload: &mut FlashblocksPayloadV1,
+    ) {
+        let Some(tee_registration) = &self.tee_registration else {
+            return;
+        };
+        let block_content_hash = compute_block_content_hash(
+            &info.executed_transactions,
+            ctx.parent_hash(),
+            ctx.block_number(),
+            ctx.timestamp(),
+        );
+        if let Err(e) = sign_flashblock(
+            fb_payload,
+            block_content_hash,
+            &tee_registration.current().tee_service_signer,
+        ) {
+            warn!(
+                target: "payload_builder",
+                index = fb_payload.index,
+                error = %e,
+                "Failed to sign flashblock, publishing it unsigned"
+            );
+            ctx.metrics.flashblock_signing_failures.increment(1);
         }
     }
 }
@@ -229,6 +296,7 @@ where
         >,
         cancel: CancellationToken,
         extra_ctx: FlashblocksExtraCtx,
+        settings: &RuntimeSettings,
     ) -> eyre::Result<OpPayloadBuilderCtx<FlashblocksExtraCtx>> {
         let chain_spec = self.client.chain_spec();
         let timestamp = config.attributes.timestamp();
@@ -280,9 +348,12 @@ where
             builder_signer: self.config.builder_signer,
             metrics: Default::default(),
             extra_ctx,
-            max_gas_per_txn: self.config.max_gas_per_txn,
+            max_gas_per_txn: settings.max_gas_per_txn,
             address_gas_limiter: self.address_gas_limiter.clone(),
             tx_data_store: self.config.tx_data_store.clone(),
+            sender_reputation: self.config.sender_reputation.clone(),
+            admission: self.config.admission.clone(),
+            private_txs: self.config.private_txs.clone(),
         })
     }
 
@@ -324,7 +395,10 @@ where
         );
 
         let timestamp = config.attributes.timestamp();
-        let disable_state_root = self.config.specific.disable_state_root;
+        // settings updated through the admin RPC take effect from the next block, the whole
+        // block is built with this snapshot
+        let settings = self.config.runtime_config.load();
+        let disable_state_root = settings.flashblocks_disable_state_root;
         let ctx = self
             .get_op_payload_builder_ctx(
                 config.clone(),
@@ -334,12 +408,20 @@ where
                     disable_state_root,
                     ..Default::default()
                 },
+                &settings,
             )
             .map_err(|e| PayloadBuilderError::Other(e.into()))?;
 
         let state_provider = self.client.state_by_block_hash(ctx.parent().hash())?;
+        self.config
+            .private_txs
+            .prune(ctx.block_number(), &state_provider);
         let db = StateProviderDatabase::new(&state_provider);
+        self.address_gas_limiter.reconfigure(&settings.gas_limiter);
         self.address_gas_limiter.refresh(ctx.block_number());
+        self.config
+            .sender_reputation
+            .on_new_block(ctx.block_number());
 
         // 1. execute the pre steps and seal an early block with that
         let sequencer_tx_start_time = Instant::now();
@@ -366,13 +448,16 @@ where
             );
         };
 
-        let (payload, fb_payload) = build_block(
+        let (payload, mut fb_payload) = build_block(
             &mut state,
             &ctx,
             &mut info,
             !disable_state_root || ctx.attributes().no_tx_pool, // need to calculate state root for CL sync
         )?;
 
+        if let Some(executed) = payload.executed_block() {
+            self.config.pending_block.set(executed);
+        }
         self.payload_tx
             .send(payload.clone())
             .await
@@ -385,8 +470,11 @@ where
             payload_id = fb_payload.payload_id.to_string(),
         );
 
-        // not emitting flashblock if no_tx_pool in FCU, it's just syncing
-        if !ctx.attributes().no_tx_pool {
+        // not emitting flashblock if no_tx_pool in FCU, it's just syncing,
+        // or if we are a standby, the leader is publishing flashblocks
+        let base_published = !ctx.attributes().no_tx_pool && self.leader.is_leader();
+        if base_published {
+            self.sign_flashblock(&ctx, &info, &mut fb_payload);
             let flashblock_byte_size = self
                 .ws_pub
                 .publish(&fb_payload)
@@ -421,7 +509,7 @@ where
         }
         // We adjust our flashblocks timings based on time_drift if dynamic adjustment enable
         let (flashblocks_per_block, first_flashblock_offset) =
-            self.calculate_flashblocks(timestamp);
+            self.calculate_flashblocks(timestamp, &settings);
         info!(
             target: "payload_builder",
             message = "Performed flashblocks timing derivation",
@@ -465,11 +553,15 @@ where
             da_footprint_per_batch,
             disable_state_root,
             target_da_footprint_for_batch: da_footprint_per_batch,
+            flashblocks_deadline_ms: (timestamp * 1000)
+                .saturating_sub(settings.flashblocks_leeway_time.as_millis() as u64),
+            flashblocks_interval_ms: self.config.specific.interval.as_millis() as u64,
+            base_published,
         };
 
         let mut fb_cancel = block_cancel.child_token();
         let mut ctx = self
-            .get_op_payload_builder_ctx(config, fb_cancel.clone(), extra_ctx)
+            .get_op_payload_builder_ctx(config, fb_cancel.clone(), extra_ctx, &settings)
             .map_err(|e| PayloadBuilderError::Other(e.into()))?;
 
         // Create best_transaction iterator
@@ -688,6 +780,7 @@ where
             target_gas_for_batch.min(ctx.block_gas_limit()),
             target_da_for_batch,
             target_da_footprint_for_batch,
+            ctx.flashblock_time_ms(),
         )
         .wrap_err("failed to execute best transactions")?;
         // Extract last transactions
@@ -747,8 +840,13 @@ where
                 Err(err).wrap_err("failed to build payload")
             }
             Ok((new_payload, mut fb_payload)) => {
+                // leadership is checked for every flashblock, so that a standby taking over
+                // publishes from the next flashblock
+                let publishes = self.leader.is_leader();
                 fb_payload.index = flashblock_index;
-                fb_payload.base = None;
+                if !publishes || ctx.extra_ctx.base_published {
+                    fb_payload.base = None;
+                }
 
                 // If main token got canceled in here that means we received get_payload and we should drop everything and now update best_payload
                 // To ensure that we will return same blocks as rollup-boost (to leverage caches)
@@ -762,10 +860,19 @@ where
                     );
                     return Ok(None);
                 }
-                let flashblock_byte_size = self
-                    .ws_pub
-                    .publish(&fb_payload)
-                    .wrap_err("failed to publish flashblock via websocket")?;
+                if publishes {
+                    self.sign_flashblock(ctx, info, &mut fb_payload);
+                    let flashblock_byte_size = self
+                        .ws_pub
+                        .publish(&fb_payload)
+                        .wrap_err("failed to publish flashblock via websocket")?;
+                    ctx.metrics
+                        .flashblock_byte_size_histogram
+                        .record(flashblock_byte_size as f64);
+                }
+                if let Some(executed) = new_payload.executed_block() {
+                    self.config.pending_block.set(executed);
+                }
                 self.payload_tx
                     .send(new_payload.clone())
                     .await
@@ -776,9 +883,6 @@ where
                 ctx.metrics
                     .flashblock_build_duration
                     .record(flashblock_build_start_time.elapsed());
-                ctx.metrics
-                    .flashblock_byte_size_histogram
-                    .record(flashblock_byte_size as f64);
                 ctx.metrics
                     .flashblock_num_tx_histogram
                     .record(info.executed_transactions.len() as f64);
@@ -804,11 +908,12 @@ where
                     *footprint += da_footprint_limit;
                 }
 
-                let next_extra_ctx = ctx.extra_ctx.clone().next(
+                let mut next_extra_ctx = ctx.extra_ctx.clone().next(
                     target_gas_for_batch,
                     target_da_for_batch,
                     target_da_footprint_for_batch,
                 );
+                next_extra_ctx.base_published |= publishes;
 
                 info!(
                     target: "payload_builder",
@@ -859,12 +964,19 @@ where
 
     /// Calculate number of flashblocks.
     /// If dynamic is enabled this function will take time drift into the account.
-    pub(super) fn calculate_flashblocks(&self, timestamp: u64) -> (u64, Duration) {
-        if self.config.specific.fixed {
+    pub(super) fn calculate_flashblocks(
+        &self,
+        timestamp: u64,
+        settings: &RuntimeSettings,
+    ) -> (u64, Duration) {
+        if settings.flashblocks_fixed {
             return (
                 self.config.flashblocks_per_block(),
                 // We adjust first FB to ensure that we have at least some time to make all FB in time
-                self.config.specific.interval - self.config.specific.leeway_time,
+                self.config
+                    .specific
+                    .interval
+                    .saturating_sub(settings.flashblocks_leeway_time),
             );
         }
 
@@ -875,7 +987,7 @@ where
         // FCU(a) could arrive with `delay < fb_time` - in this case we will shrink first flashblock
         // FCU(a) could arrive with `fb_time < delay < block_time - fb_time` - in this case we will issue less flashblocks
         let target_time = std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(timestamp)
-            - self.config.specific.leeway_time;
+            - settings.flashblocks_leeway_time;
         let now = std::time::SystemTime::now();
         let Ok(time_drift) = target_time.duration_since(now) else {
             error!(
@@ -1,14 +1,27 @@
 use crate::{
-    builders::flashblocks::{
-        ctx::OpPayloadSyncerCtx, p2p::Message, payload::FlashblocksExecutionInfo,
+    builders::{
+        OpPayloadBuilderCtx,
+        flashblocks::{
+            ctx::OpPayloadSyncerCtx,
+            leader::LeaderState,
+            p2p::{FlashblockDelta, FlashblockDeltaTracker, IncomingMessages, Message},
+            payload::FlashblocksExecutionInfo,
+            validation::{HeaderCommitments, HeaderMismatchReport},
+        },
+    },
+    metrics::{OpRBuilderMetrics, record_flashblock_header_mismatch},
+    primitives::{bundle::BundleRangeLimits, reth::ExecutionInfo},
+    revert_protection::{
+        add_bundle_transaction, bundle_pool_transaction, cancel_bundle_transaction,
     },
-    primitives::reth::ExecutionInfo,
-    traits::ClientBounds,
+    traits::{ClientBounds, PoolBounds},
+    tx_data_store::{TxDataStore, TxDataUpdate},
 };
 use alloy_evm::eth::receipt_builder::ReceiptBuilderCtx;
 use alloy_primitives::B64;
-use eyre::{WrapErr as _, bail};
+use eyre::{OptionExt as _, WrapErr as _, bail};
 use op_alloy_consensus::OpTxEnvelope;
+use p2p::{PeerId, PeerScoreUpdate};
 use reth::revm::{State, database::StateProviderDatabase};
 use reth_basic_payload_builder::PayloadConfig;
 use reth_evm::FromRecoveredTx;
@@ -18,53 +31,89 @@ use reth_optimism_evm::{OpEvmConfig, OpNextBlockEnvAttributes};
 use reth_optimism_node::{OpEngineTypes, OpPayloadBuilderAttributes};
 use reth_optimism_payload_builder::OpBuiltPayload;
 use reth_optimism_primitives::{OpReceipt, OpTransactionSigned};
-use reth_payload_builder::EthPayloadBuilderAttributes;
-use rollup_boost::FlashblocksPayloadV1;
-use std::sync::Arc;
-use tokio::sync::mpsc;
+use reth_payload_builder::{EthPayloadBuilderAttributes, PayloadId};
+use reth_provider::StateProviderBox;
+use reth_transaction_pool::TransactionOrigin;
+use std::{collections::HashSet, sync::Arc};
+use tokio::sync::{broadcast, mpsc};
 use tracing::warn;
 
+/// Score penalty for a peer that sent a flashblock whose header doesn't match its re-execution.
+const INVALID_FLASHBLOCK_PENALTY: i32 = -50;
+
+/// Score reward for a peer that sent a flashblock that was successfully re-executed.
+const VALID_FLASHBLOCK_REWARD: i32 = 1;
+
+/// Score penalty for a peer that isn't a configured builder and sent a bundle, metering
+/// information or a cancellation notice.
+const UNAUTHORIZED_TX_DATA_PENALTY: i32 = -50;
+
+/// Number of received flashblocks waiting to be executed, further flashblocks are dropped.
+const SYNC_CHANNEL_SIZE: usize = 64;
+
 /// Handles newly built or received flashblock payloads.
 ///
-/// In the case of a payload built by this node, it is broadcast to peers and an event is sent to the payload builder.
-/// In the case of a payload received from a peer, it is executed and if successful, an event is sent to the payload builder.
-pub(crate) struct PayloadHandler<Client> {
+/// In the case of a payload built by this node, an event is sent to the payload builder and, if this node is the leader, it is broadcast to peers.
+/// In the case of a payload received from a peer, it is executed and its header commitments are verified. If successful,
+/// an event is sent to the payload builder, otherwise the payload is rejected and the peer is penalized.
+///
+/// Updates of the [`TxDataStore`] received through the local RPC are shared with peers, and updates
+/// received from peers are applied locally: bundles, metering information and cancellation notices
+/// each use their own protocol. Updates are only accepted from the configured builder peers.
+pub(crate) struct PayloadHandler<Client, Pool> {
     // receives new payloads built by this builder.
     built_rx: mpsc::Receiver<OpBuiltPayload>,
     // receives incoming p2p messages from peers.
-    p2p_rx: mpsc::Receiver<Message>,
+    p2p_rx: IncomingMessages,
     // outgoing p2p channel to broadcast new payloads to peers.
     p2p_tx: mpsc::Sender<Message>,
+    // reports score updates of peers based on the validity of the payloads they sent.
+    peer_score_tx: mpsc::Sender<PeerScoreUpdate>,
     // sends a `Events::BuiltPayload` to the reth payload builder when a new payload is received.
     payload_events_handle: tokio::sync::broadcast::Sender<Events<OpEngineTypes>>,
     // context required for execution of blocks during syncing
     ctx: OpPayloadSyncerCtx,
     // chain client
     client: Client,
+    // transaction pool, where bundles forwarded by peers are added.
+    pool: Pool,
+    // whether this builder is the leader, only the leader broadcasts its payloads to peers.
+    leader: LeaderState,
+    // peer IDs of the other builders, the only peers whose tx data updates are applied.
+    builder_peers: HashSet<PeerId>,
     cancel: tokio_util::sync::CancellationToken,
 }
 
-impl<Client> PayloadHandler<Client>
+impl<Client, Pool> PayloadHandler<Client, Pool>
 where
     Client: ClientBounds + 'static,
+    Pool: PoolBounds,
 {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         built_rx: mpsc::Receiver<OpBuiltPayload>,
-        p2p_rx: mpsc::Receiver<Message>,
+        p2p_rx: IncomingMessages,
         p2p_tx: mpsc::Sender<Message>,
+        peer_score_tx: mpsc::Sender<PeerScoreUpdate>,
         payload_events_handle: tokio::sync::broadcast::Sender<Events<OpEngineTypes>>,
         ctx: OpPayloadSyncerCtx,
         client: Client,
+        pool: Pool,
+        leader: LeaderState,
+        builder_peers: HashSet<PeerId>,
         cancel: tokio_util::sync::CancellationToken,
     ) -> Self {
         Self {
             built_rx,
             p2p_rx,
             p2p_tx,
+            peer_score_tx,
             payload_events_handle,
             ctx,
             client,
+            pool,
+            leader,
+            builder_peers,
             cancel,
         }
     }
@@ -74,54 +123,114 @@ where
             mut built_rx,
             mut p2p_rx,
             p2p_tx,
+            peer_score_tx,
             payload_events_handle,
             ctx,
             client,
+            pool,
+            leader,
+            builder_peers,
             cancel,
         } = self;
 
         tracing::debug!("flashblocks payload handler started");
 
+        let tx_data_store = ctx.tx_data_store().clone();
+        let bundle_range = ctx.bundle_range();
+        let mut tx_data_updates = tx_data_store.subscribe_updates();
+
+        // received flashblocks are executed in order on a thread where blocking is acceptable,
+        // as it's potentially a heavy operation and deltas build on top of each other.
+        let (sync_tx, sync_rx) = mpsc::channel(SYNC_CHANNEL_SIZE);
+        let syncer = FlashblockSyncer {
+            ctx,
+            client: client.clone(),
+            payload_events_handle: payload_events_handle.clone(),
+            peer_score_tx: peer_score_tx.clone(),
+            p2p_tx: p2p_tx.clone(),
+            cancel,
+            synced: None,
+            resync_requested: None,
+        };
+        tokio::task::spawn_blocking(move || syncer.run(sync_rx));
+
+        let mut delta_tracker = FlashblockDeltaTracker::default();
+
         loop {
             tokio::select! {
                 Some(payload) = built_rx.recv() => {
                     if let Err(e) = payload_events_handle.send(Events::BuiltPayload(payload.clone())) {
                         warn!(e = ?e, "failed to send BuiltPayload event");
                     }
+                    if !leader.is_leader() {
+                        // peers only sync the payloads of the leader, so the next payload
+                        // sent after taking over has to be sent in full.
+                        delta_tracker = FlashblockDeltaTracker::default();
+                        continue;
+                    }
                     // ignore error here; if p2p was disabled, the channel will be closed.
-                    let _ = p2p_tx.send(payload.into()).await;
+                    let _ = p2p_tx.send(delta_tracker.next_message(payload)).await;
+                }
+                Some((peer_id, message)) = p2p_rx.flashblocks.recv() => {
+                    if let Message::FlashblockResync(id) = message {
+                        if leader.is_leader() {
+                            tracing::debug!(%id, %peer_id, "peer requested a flashblock resync");
+                            delta_tracker.resync(id);
+                        }
+                        continue;
+                    }
+                    // a dropped flashblock breaks the following deltas of the payload, which
+                    // the syncer recovers from by requesting a resync
+                    match sync_tx.try_send((peer_id, message)) {
+                        Ok(()) => {}
+                        Err(mpsc::error::TrySendError::Full(_)) => {
+                            warn!(%peer_id, "flashblock syncer is lagging behind, dropping received message");
+                        }
+                        Err(mpsc::error::TrySendError::Closed(_)) => {
+                            warn!("flashblock syncer stopped, dropping received message");
+                        }
+                    }
                 }
-                Some(message) = p2p_rx.recv() => {
-                    match message {
-                        Message::OpBuiltPayload(payload) => {
-                            let payload: OpBuiltPayload = payload.into();
-                            let ctx = ctx.clone();
-                            let client = client.clone();
-                            let payload_events_handle = payload_events_handle.clone();
-                            let cancel = cancel.clone();
-
-                            // execute the flashblock on a thread where blocking is acceptable,
-                            // as it's potentially a heavy operation
-                            tokio::task::spawn_blocking(move || {
-                                let res = execute_flashblock(
-                                    payload,
-                                    ctx,
-                                    client,
-                                    cancel,
-                                );
-                                match res {
-                                    Ok((payload, _)) => {
-                                        tracing::info!(hash = payload.block().hash().to_string(), block_number = payload.block().header().number, "successfully executed received flashblock");
-                                        if let Err(e) = payload_events_handle.send(Events::BuiltPayload(payload)) {
-                                            warn!(e = ?e, "failed to send BuiltPayload event on synced block");
-                                        }
-                                    }
-                                    Err(e) => {
-                                        tracing::error!(error = ?e, "failed to execute received flashblock");
-                                    }
-                                }
-                            });
+                update = tx_data_updates.recv() => {
+                    match update {
+                        // ignore error here; if p2p was disabled, the channel will be closed.
+                        Ok(update) => {
+                            let _ = p2p_tx.send(Message::TxData(update)).await;
                         }
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            warn!(skipped, "lagging behind tx data updates, some were not shared with peers");
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {}
+                    }
+                }
+                Some((peer_id, message)) = p2p_rx.bundles.recv() => {
+                    if !is_builder_peer(&builder_peers, &peer_score_tx, peer_id) {
+                        continue;
+                    }
+                    let (last_block_number, last_block_timestamp) = client
+                        .latest_header()
+                        .ok()
+                        .flatten()
+                        .map(|header| (header.number, header.timestamp))
+                        .unwrap_or_default();
+                    if let Err(e) = handle_bundle_message(message, &tx_data_store, &pool, bundle_range, last_block_number, last_block_timestamp).await {
+                        warn!(error = ?e, %peer_id, "failed to handle bundle received from peer");
+                    }
+                }
+                Some((peer_id, message)) = p2p_rx.metering.recv() => {
+                    if !is_builder_peer(&builder_peers, &peer_score_tx, peer_id) {
+                        continue;
+                    }
+                    if let Err(e) = handle_metering_message(message, &tx_data_store) {
+                        warn!(error = ?e, %peer_id, "failed to handle metering update received from peer");
+                    }
+                }
+                Some((peer_id, message)) = p2p_rx.cancellation.recv() => {
+                    if !is_builder_peer(&builder_peers, &peer_score_tx, peer_id) {
+                        continue;
+                    }
+                    if let Err(e) = handle_cancellation_message(message, &tx_data_store) {
+                        warn!(error = ?e, %peer_id, "failed to handle cancellation notice received from peer");
                     }
                 }
                 else => break,
@@ -130,12 +239,248 @@ where
     }
 }
 
+/// Handles a bundle shared by a peer: bundles sent through `eth_sendBundle` are added to the
+/// pool, backrun bundles are added to the [`TxDataStore`].
+async fn handle_bundle_message<Pool: PoolBounds>(
+    message: Message,
+    tx_data_store: &TxDataStore,
+    pool: &Pool,
+    bundle_range: BundleRangeLimits,
+    last_block_number: u64,
+    last_block_timestamp: u64,
+) -> eyre::Result<()> {
+    match message {
+        Message::TxData(TxDataUpdate::ForwardedBundle(bundle)) => {
+            if let Some(replacement_uuid) = bundle.replacement_uuid
+                && bundle.is_cancellation()
+            {
+                let signer = bundle
+                    .cancellation_signer()
+                    .ok_or_eyre("forwarded cancellation is not signed")?;
+                cancel_bundle_transaction(pool, tx_data_store, replacement_uuid, signer)
+                    .await
+                    .wrap_err("invalid forwarded cancellation")?;
+                return Ok(());
+            }
+            let transaction = bundle_pool_transaction(
+                &bundle,
+                bundle_range,
+                last_block_number,
+                last_block_timestamp,
+            )
+            .wrap_err("invalid forwarded bundle")?;
+            add_bundle_transaction(
+                pool,
+                tx_data_store,
+                bundle.replacement_uuid,
+                transaction,
+                TransactionOrigin::External,
+            )
+            .await
+            .wrap_err("failed to add forwarded bundle to the pool")?;
+        }
+        Message::TxData(update @ TxDataUpdate::BackrunBundle(_)) => {
+            tx_data_store
+                .apply_update(update)
+                .map_err(|e| eyre::eyre!("failed to store backrun bundle: {e}"))?;
+        }
+        message => bail!("unexpected message on bundles protocol: {message:?}"),
+    }
+    Ok(())
+}
+
+/// Handles metering information shared by a peer.
+fn handle_metering_message(message: Message, tx_data_store: &TxDataStore) -> eyre::Result<()> {
+    match message {
+        Message::TxData(update @ (TxDataUpdate::Metering { .. } | TxDataUpdate::ClearMetering)) => {
+            tx_data_store
+                .apply_update(update)
+                .map_err(|e| eyre::eyre!(e))
+        }
+        message => bail!("unexpected message on metering protocol: {message:?}"),
+    }
+}
+
+/// Handles a cancellation notice shared by a peer.
+fn handle_cancellation_message(message: Message, tx_data_store: &TxDataStore) -> eyre::Result<()> {
+    match message {
+        Message::TxData(update @ TxDataUpdate::CancelBackrunBundles { .. }) => tx_data_store
+            .apply_update(update)
+            .map_err(|e| eyre::eyre!(e)),
+        message => bail!("unexpected message on cancellation protocol: {message:?}"),
+    }
+}
+
+/// Execution state of the last flashblock received from a peer, kept around so that
+/// following [`FlashblockDelta`]s for the same payload can be applied on top of it.
+struct SyncedFlashblock {
+    id: PayloadId,
+    index: u64,
+    state: State<StateProviderDatabase<StateProviderBox>>,
+    builder_ctx: OpPayloadBuilderCtx,
+    info: ExecutionInfo<FlashblocksExecutionInfo>,
+}
+
+/// Executes flashblocks received from peers, in the order they were received.
+struct FlashblockSyncer<Client> {
+    ctx: OpPayloadSyncerCtx,
+    client: Client,
+    payload_events_handle: tokio::sync::broadcast::Sender<Events<OpEngineTypes>>,
+    peer_score_tx: mpsc::Sender<PeerScoreUpdate>,
+    // requests the leader to resend a payload in full when its deltas can't be applied.
+    p2p_tx: mpsc::Sender<Message>,
+    cancel: tokio_util::sync::CancellationToken,
+    synced: Option<SyncedFlashblock>,
+    // payload whose resync was last requested, so that it is requested once per payload.
+    resync_requested: Option<PayloadId>,
+}
+
+impl<Client> FlashblockSyncer<Client>
+where
+    Client: ClientBounds,
+{
+    fn run(mut self, mut rx: mpsc::Receiver<(PeerId, Message)>) {
+        while let Some((peer_id, message)) = rx.blocking_recv() {
+            let res = match message {
+                Message::OpBuiltPayload(payload) => {
+                    // a full payload always starts from scratch, so the cached state is dropped
+                    self.synced = None;
+                    self.resync_requested = None;
+                    execute_flashblock(
+                        payload.into(),
+                        self.ctx.clone(),
+                        &self.client,
+                        self.cancel.clone(),
+                    )
+                    .map(|(payload, synced)| {
+                        self.synced = Some(synced);
+                        payload
+                    })
+                }
+                Message::FlashblockDelta(delta) => match self.synced.take() {
+                    Some(mut synced)
+                        if synced.id == delta.id && synced.index + 1 == delta.index =>
+                    {
+                        execute_flashblock_delta(delta, &mut synced, &self.ctx).map(|payload| {
+                            self.synced = Some(synced);
+                            payload
+                        })
+                    }
+                    synced => {
+                        self.ctx
+                            .metrics()
+                            .unapplied_flashblock_deltas_count
+                            .increment(1);
+                        self.request_resync(delta.id);
+                        Err(eyre::eyre!(
+                            "no cached state for flashblock delta: id={} index={} cached={:?}",
+                            delta.id,
+                            delta.index,
+                            synced.map(|s| (s.id, s.index)),
+                        ))
+                    }
+                },
+                message @ (Message::FlashblockResync(_)
+                | Message::Heartbeat(_)
+                | Message::TxData(_)) => {
+                    warn!(?message, "unexpected message on flashblocks protocol");
+                    continue;
+                }
+            };
+
+            match res {
+                Ok(payload) => {
+                    self.report_peer(peer_id, VALID_FLASHBLOCK_REWARD);
+                    tracing::info!(
+                        hash = payload.block().hash().to_string(),
+                        block_number = payload.block().header().number,
+                        "successfully executed received flashblock"
+                    );
+                    if let Err(e) = self
+                        .payload_events_handle
+                        .send(Events::BuiltPayload(payload))
+                    {
+                        warn!(e = ?e, "failed to send BuiltPayload event on synced block");
+                    }
+                }
+                Err(e) => {
+                    if e.downcast_ref::<HeaderMismatchReport>().is_some() {
+                        self.report_peer(peer_id, INVALID_FLASHBLOCK_PENALTY);
+                    }
+                    tracing::error!(error = ?e, %peer_id, "failed to execute received flashblock");
+                }
+            }
+        }
+    }
+
+    /// Requests the leader to send the next flashblock of the payload in full, so that the
+    /// payload can be synced again after a missed or failed flashblock.
+    fn request_resync(&mut self, id: PayloadId) {
+        if self.resync_requested == Some(id) {
+            return;
+        }
+        self.resync_requested = Some(id);
+        if let Err(e) = self.p2p_tx.try_send(Message::FlashblockResync(id)) {
+            tracing::debug!(error = ?e, %id, "failed to request flashblock resync");
+        }
+    }
+
+    fn report_peer(&self, peer_id: PeerId, delta: i32) {
+        report_peer(&self.peer_score_tx, peer_id, delta);
+    }
+}
+
+fn report_peer(peer_score_tx: &mpsc::Sender<PeerScoreUpdate>, peer_id: PeerId, delta: i32) {
+    // scoring is best effort, don't block syncing if the p2p node is lagging behind
+    if let Err(e) = peer_score_tx.try_send(PeerScoreUpdate { peer_id, delta }) {
+        tracing::debug!(error = ?e, %peer_id, "failed to report peer score update");
+    }
+}
+
+/// Returns whether `peer_id` is one of the other builders. Tx data updates can add or cancel
+/// bundles and overwrite metering information, so they are only accepted from the builders.
+///
+/// Other peers are penalized, unless no builder is configured, in which case they can't know
+/// their updates are unwanted.
+fn is_builder_peer(
+    builder_peers: &HashSet<PeerId>,
+    peer_score_tx: &mpsc::Sender<PeerScoreUpdate>,
+    peer_id: PeerId,
+) -> bool {
+    if builder_peers.contains(&peer_id) {
+        return true;
+    }
+    if builder_peers.is_empty() {
+        tracing::debug!(%peer_id, "ignoring tx data update, no builder peers are configured");
+        return false;
+    }
+    warn!(%peer_id, "ignoring tx data update from a peer that isn't a configured builder");
+    report_peer(peer_score_tx, peer_id, UNAUTHORIZED_TX_DATA_PENALTY);
+    false
+}
+
+/// Verifies the header commitments of a re-executed flashblock against the received ones,
+/// recording every mismatching field.
+fn verify_commitments(
+    expected: HeaderCommitments,
+    executed: HeaderCommitments,
+    metrics: &OpRBuilderMetrics,
+) -> Result<(), HeaderMismatchReport> {
+    expected.verify(&executed).inspect_err(|report| {
+        tracing::error!(%report, "flashblock header mismatch after execution");
+        metrics.invalid_synced_blocks_count.increment(1);
+        for mismatch in &report.mismatches {
+            record_flashblock_header_mismatch(mismatch.field);
+        }
+    })
+}
+
 fn execute_flashblock<Client>(
     payload: OpBuiltPayload,
     ctx: OpPayloadSyncerCtx,
-    client: Client,
+    client: &Client,
     cancel: tokio_util::sync::CancellationToken,
-) -> eyre::Result<(OpBuiltPayload, FlashblocksPayloadV1)>
+) -> eyre::Result<(OpBuiltPayload, SyncedFlashblock)>
 where
     Client: ClientBounds,
 {
@@ -147,7 +492,6 @@ where
 
     tracing::info!(header = ?payload.block().header(), "executing flashblock");
 
-    let mut cached_reads = reth::revm::cached::CachedReads::default();
     let parent_hash = payload.block().sealed_header().parent_hash;
     let parent_header = client
         .header_by_id(parent_hash.into())
@@ -157,9 +501,8 @@ where
     let state_provider = client
         .state_by_block_hash(parent_hash)
         .wrap_err("failed to get state for parent hash")?;
-    let db = StateProviderDatabase::new(&state_provider);
     let mut state = State::builder()
-        .with_database(cached_reads.as_db_mut(db))
+        .with_database(StateProviderDatabase::new(state_provider))
         .with_bundle_update()
         .build();
 
@@ -241,7 +584,7 @@ where
         cancel,
     );
 
-    let (built_payload, fb_payload) = crate::builders::flashblocks::payload::build_block(
+    let (built_payload, _) = crate::builders::flashblocks::payload::build_block(
         &mut state,
         &builder_ctx,
         &mut info,
@@ -254,20 +597,81 @@ where
         .flashblock_sync_duration
         .record(start.elapsed());
 
-    if built_payload.block().hash() != payload.block().hash() {
-        tracing::error!(
-            expected = %payload.block().hash(),
-            got = %built_payload.block().hash(),
-            "flashblock hash mismatch after execution"
-        );
-        builder_ctx.metrics.invalid_synced_blocks_count.increment(1);
-        bail!("flashblock hash mismatch after execution");
-    }
+    verify_commitments(
+        payload.block().sealed_header().into(),
+        built_payload.block().sealed_header().into(),
+        &builder_ctx.metrics,
+    )?;
 
     builder_ctx.metrics.block_synced_success.increment(1);
 
     tracing::info!(header = ?built_payload.block().header(), "successfully executed flashblock");
-    Ok((built_payload, fb_payload))
+    let synced = SyncedFlashblock {
+        id: payload.id(),
+        index: 0,
+        state,
+        builder_ctx,
+        info,
+    };
+    Ok((built_payload, synced))
+}
+
+/// Applies the transactions of a [`FlashblockDelta`] on top of the cached state of the
+/// previous flashblock of the same payload.
+fn execute_flashblock_delta(
+    delta: FlashblockDelta,
+    synced: &mut SyncedFlashblock,
+    ctx: &OpPayloadSyncerCtx,
+) -> eyre::Result<OpBuiltPayload> {
+    let start = tokio::time::Instant::now();
+
+    tracing::info!(
+        id = %delta.id,
+        index = delta.index,
+        tx_count = delta.transactions.len(),
+        "executing flashblock delta"
+    );
+
+    let expected = HeaderCommitments::from(&delta);
+    let builder_ctx = &synced.builder_ctx;
+    let timestamp = builder_ctx.block_env_attributes.timestamp;
+    execute_transactions(
+        &mut synced.info,
+        &mut synced.state,
+        delta.transactions,
+        delta.gas_used,
+        ctx.evm_config(),
+        builder_ctx.evm_env.clone(),
+        ctx.max_gas_per_txn(),
+        is_canyon_active(&builder_ctx.chain_spec, timestamp),
+        is_regolith_active(&builder_ctx.chain_spec, timestamp),
+    )
+    .wrap_err("failed to execute flashblock delta transactions")?;
+
+    let (built_payload, _) = crate::builders::flashblocks::payload::build_block(
+        &mut synced.state,
+        builder_ctx,
+        &mut synced.info,
+        true,
+    )
+    .wrap_err("failed to build flashblock")?;
+
+    builder_ctx
+        .metrics
+        .flashblock_sync_duration
+        .record(start.elapsed());
+
+    verify_commitments(
+        expected,
+        built_payload.block().sealed_header().into(),
+        &builder_ctx.metrics,
+    )?;
+
+    builder_ctx.metrics.block_synced_success.increment(1);
+    synced.index = delta.index;
+
+    tracing::info!(header = ?built_payload.block().header(), "successfully applied flashblock delta");
+    Ok(built_payload)
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -445,3 +849,187 @@ fn is_regolith_active(chain_spec: &OpChainSpec, timestamp: u64) -> bool {
     use reth_optimism_chainspec::OpHardforks as _;
     chain_spec.is_regolith_active_at_timestamp(timestamp)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        primitives::bundle::Bundle, tx::FBPooledTransaction, tx_data_store::ReplaceableBundle,
+        tx_signer::Signer,
+    };
+    use alloy_primitives::{Bytes, TxHash, U256};
+    use reth_transaction_pool::noop::NoopTransactionPool;
+    use tips_core::MeterBundleResponse;
+    use uuid::Uuid;
+
+    fn metering(gas_used: u64) -> MeterBundleResponse {
+        MeterBundleResponse {
+            bundle_hash: TxHash::random(),
+            bundle_gas_price: U256::ZERO,
+            coinbase_diff: U256::ZERO,
+            eth_sent_to_coinbase: U256::ZERO,
+            gas_fees: U256::ZERO,
+            results: vec![],
+            state_block_number: 0,
+            state_flashblock_index: None,
+            total_gas_used: gas_used,
+            total_execution_time_us: 0,
+        }
+    }
+
+    #[test]
+    fn tx_data_is_only_accepted_from_builder_peers() {
+        let (peer_score_tx, mut peer_score_rx) = mpsc::channel(16);
+        let builder = PeerId::random();
+        let other = PeerId::random();
+
+        let builder_peers = HashSet::from([builder]);
+        assert!(is_builder_peer(&builder_peers, &peer_score_tx, builder));
+        assert!(peer_score_rx.try_recv().is_err());
+        assert!(!is_builder_peer(&builder_peers, &peer_score_tx, other));
+        let update = peer_score_rx.try_recv().unwrap();
+        assert_eq!(update.peer_id, other);
+        assert_eq!(update.delta, UNAUTHORIZED_TX_DATA_PENALTY);
+
+        // peers aren't penalized when no builder is configured
+        assert!(!is_builder_peer(&HashSet::new(), &peer_score_tx, other));
+        assert!(peer_score_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn invalid_forwarded_bundle_is_rejected() {
+        let store = TxDataStore::new(true, 100);
+        let pool = NoopTransactionPool::<FBPooledTransaction>::new();
+
+        let message = Message::TxData(TxDataUpdate::ForwardedBundle(Bundle::default()));
+        assert!(
+            handle_bundle_message(message, &store, &pool, BundleRangeLimits::default(), 0, 0)
+                .await
+                .is_err()
+        );
+    }
+
+    fn cancellation(replacement_uuid: Uuid, signer: &Signer) -> Message {
+        let signature = signer
+            .sign_message(Bundle::cancellation_hash(replacement_uuid))
+            .unwrap();
+        Message::TxData(TxDataUpdate::ForwardedBundle(Bundle {
+            replacement_uuid: Some(replacement_uuid),
+            cancellation_signature: Some(Bytes::copy_from_slice(&signature.as_bytes())),
+            ..Default::default()
+        }))
+    }
+
+    #[tokio::test]
+    async fn forwarded_cancellation_is_accepted() {
+        let store = TxDataStore::new(true, 100);
+        let pool = NoopTransactionPool::<FBPooledTransaction>::new();
+
+        let message = cancellation(Uuid::new_v4(), &Signer::random());
+        handle_bundle_message(message, &store, &pool, BundleRangeLimits::default(), 0, 0)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn forwarded_cancellation_requires_bundle_signer() {
+        let store = TxDataStore::new(true, 100);
+        let pool = NoopTransactionPool::<FBPooledTransaction>::new();
+        let replacement_uuid = Uuid::new_v4();
+        let signer = Signer::random();
+        store
+            .set_replacement(
+                signer.address,
+                replacement_uuid,
+                ReplaceableBundle::Pool(TxHash::random()),
+            )
+            .unwrap();
+
+        let unsigned = Message::TxData(TxDataUpdate::ForwardedBundle(Bundle {
+            replacement_uuid: Some(replacement_uuid),
+            ..Default::default()
+        }));
+        let other_signer = cancellation(replacement_uuid, &Signer::random());
+        for message in [unsigned, other_signer] {
+            assert!(
+                handle_bundle_message(message, &store, &pool, BundleRangeLimits::default(), 0, 0)
+                    .await
+                    .is_err()
+            );
+        }
+        assert!(
+            store
+                .replacement(signer.address, &replacement_uuid)
+                .unwrap()
+                .is_some()
+        );
+
+        let message = cancellation(replacement_uuid, &signer);
+        handle_bundle_message(message, &store, &pool, BundleRangeLimits::default(), 0, 0)
+            .await
+            .unwrap();
+        assert!(
+            store
+                .replacement(signer.address, &replacement_uuid)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn bundles_protocol_rejects_other_updates() {
+        let store = TxDataStore::new(true, 100);
+        let pool = NoopTransactionPool::<FBPooledTransaction>::new();
+
+        let message = Message::TxData(TxDataUpdate::ClearMetering);
+        assert!(
+            handle_bundle_message(message, &store, &pool, BundleRangeLimits::default(), 0, 0)
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn metering_updates_are_applied() {
+        let store = TxDataStore::new(true, 100);
+        let tx_hash = TxHash::random();
+
+        handle_metering_message(
+            Message::TxData(TxDataUpdate::Metering {
+                tx_hash,
+                metering: metering(21_000),
+            }),
+            &store,
+        )
+        .unwrap();
+        assert_eq!(store.get(&tx_hash).metering.unwrap().total_gas_used, 21_000);
+
+        handle_metering_message(Message::TxData(TxDataUpdate::ClearMetering), &store).unwrap();
+        assert!(store.get(&tx_hash).metering.is_none());
+
+        let cancellation = Message::TxData(TxDataUpdate::CancelBackrunBundles {
+            target_tx_hash: tx_hash,
+        });
+        assert!(handle_metering_message(cancellation, &store).is_err());
+    }
+
+    #[test]
+    fn cancellation_notices_are_applied() {
+        let store = TxDataStore::new(true, 100);
+        let tx_hash = TxHash::random();
+
+        store.insert_metering(tx_hash, metering(21_000));
+        handle_cancellation_message(
+            Message::TxData(TxDataUpdate::CancelBackrunBundles {
+                target_tx_hash: tx_hash,
+            }),
+            &store,
+        )
+        .unwrap();
+        // cancelling backrun bundles keeps the metering information of the target
+        assert!(store.get(&tx_hash).metering.is_some());
+
+        let metering = Message::TxData(TxDataUpdate::ClearMetering);
+        assert!(handle_cancellation_message(metering, &store).is_err());
+    }
+}
@@ -5,14 +5,18 @@ use crate::{
         builder_tx::BuilderTransactions,
         flashblocks::{
             builder_tx::{FlashblocksBuilderTx, FlashblocksNumberBuilderTx},
-            p2p::{AGENT_VERSION, FLASHBLOCKS_STREAM_PROTOCOL, Message},
+            leader::{LeaderElection, LeaderState},
+            p2p::{
+                AGENT_VERSION, IncomingMessages, LEADER_STREAM_PROTOCOL, Message,
+                PAYLOAD_HANDLER_PROTOCOLS,
+            },
             payload::{FlashblocksExecutionInfo, FlashblocksExtraCtx},
             payload_handler::PayloadHandler,
             wspub::WebSocketPublisher,
         },
         generator::BlockPayloadJobGenerator,
     },
-    flashtestations::service::bootstrap_flashtestations,
+    flashtestations::{builder_tx::TeeRegistrationHandle, service::bootstrap_flashtestations},
     metrics::OpRBuilderMetrics,
     traits::{NodeBounds, PoolBounds},
 };
@@ -23,7 +27,7 @@ use reth_node_builder::{BuilderContext, components::PayloadServiceBuilder};
 use reth_optimism_evm::OpEvmConfig;
 use reth_payload_builder::{PayloadBuilderHandle, PayloadBuilderService};
 use reth_provider::CanonStateSubscriptions;
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 pub struct FlashblocksServiceBuilder(pub BuilderConfig<FlashblocksConfig>);
 
@@ -33,6 +37,8 @@ impl FlashblocksServiceBuilder {
         ctx: &BuilderContext<Node>,
         pool: Pool,
         builder_tx: BuilderTx,
+        tee_registration: Option<TeeRegistrationHandle>,
+        p2p_keypair: Option<p2p::Keypair>,
     ) -> eyre::Result<PayloadBuilderHandle<<Node::Types as NodeTypes>::Payload>>
     where
         Node: NodeBounds,
@@ -48,64 +54,104 @@ impl FlashblocksServiceBuilder {
         // this is effectively unused right now due to the usage of reth's `task_executor`.
         let cancel = tokio_util::sync::CancellationToken::new();
 
-        let (incoming_message_rx, outgoing_message_tx) = if self.0.specific.p2p_enabled {
-            let mut builder = p2p::NodeBuilder::new();
+        let metrics = Arc::new(OpRBuilderMetrics::default());
 
-            if let Some(ref private_key_file) = self.0.specific.p2p_private_key_file
-                && !private_key_file.is_empty()
-            {
-                let private_key_hex = std::fs::read_to_string(private_key_file)
-                    .wrap_err_with(|| {
-                        format!("failed to read p2p private key file: {private_key_file}")
-                    })?
-                    .trim()
-                    .to_string();
-                builder = builder.with_keypair_hex_string(private_key_hex);
-            }
+        let (incoming_messages, outgoing_message_tx, peer_score_tx, leader, builder_peers) =
+            if let Some(p2p_keypair) = p2p_keypair {
+                let mut builder = p2p::NodeBuilder::new().with_keypair(p2p_keypair);
 
-            let known_peers: Vec<p2p::Multiaddr> =
-                if let Some(ref p2p_known_peers) = self.0.specific.p2p_known_peers {
-                    p2p_known_peers
-                        .split(',')
-                        .map(|s| s.to_string())
-                        .filter_map(|s| s.parse().ok())
-                        .collect()
-                } else {
-                    vec![]
-                };
+                let known_peers: Vec<p2p::Multiaddr> =
+                    if let Some(ref p2p_known_peers) = self.0.specific.p2p_known_peers {
+                        p2p_known_peers
+                            .split(',')
+                            .map(|s| s.to_string())
+                            .filter_map(|s| s.parse().ok())
+                            .collect()
+                    } else {
+                        vec![]
+                    };
 
-            let p2p::NodeBuildResult {
-                node,
-                outgoing_message_tx,
-                mut incoming_message_rxs,
-            } = builder
-                .with_agent_version(AGENT_VERSION.to_string())
-                .with_protocol(FLASHBLOCKS_STREAM_PROTOCOL)
-                .with_known_peers(known_peers)
-                .with_port(self.0.specific.p2p_port)
-                .with_cancellation_token(cancel.clone())
-                .with_max_peer_count(self.0.specific.p2p_max_peer_count)
-                .try_build::<Message>()
-                .wrap_err("failed to build flashblocks p2p node")?;
-            let multiaddrs = node.multiaddrs();
-            ctx.task_executor().spawn(async move {
-                if let Err(e) = node.run().await {
-                    tracing::error!(error = %e, "p2p node exited");
+                for protocol in PAYLOAD_HANDLER_PROTOCOLS {
+                    builder = builder.with_protocol(protocol);
                 }
-            });
-            tracing::info!(multiaddrs = ?multiaddrs, "flashblocks p2p node started");
+                let p2p::NodeBuildResult {
+                    node,
+                    outgoing_message_tx,
+                    mut incoming_message_rxs,
+                    peer_score_tx,
+                    keypair,
+                } = builder
+                    .with_agent_version(AGENT_VERSION.to_string())
+                    .with_protocol(LEADER_STREAM_PROTOCOL)
+                    .with_known_peers(known_peers)
+                    .with_port(self.0.specific.p2p_port)
+                    .with_cancellation_token(cancel.clone())
+                    .with_max_peer_count(self.0.specific.p2p_max_peer_count)
+                    .try_build::<Message>()
+                    .wrap_err("failed to build flashblocks p2p node")?;
+                let multiaddrs = node.multiaddrs();
+                ctx.task_executor().spawn(async move {
+                    if let Err(e) = node.run().await {
+                        tracing::error!(error = %e, "p2p node exited");
+                    }
+                });
+                tracing::info!(multiaddrs = ?multiaddrs, "flashblocks p2p node started");
 
-            let incoming_message_rx = incoming_message_rxs
-                .remove(&FLASHBLOCKS_STREAM_PROTOCOL)
-                .expect("flashblocks p2p protocol must be found in receiver map");
-            (incoming_message_rx, outgoing_message_tx)
-        } else {
-            let (_incoming_message_tx, incoming_message_rx) = tokio::sync::mpsc::channel(16);
-            let (outgoing_message_tx, _outgoing_message_rx) = tokio::sync::mpsc::channel(16);
-            (incoming_message_rx, outgoing_message_tx)
-        };
+                let incoming_messages = IncomingMessages::from_node(&mut incoming_message_rxs)?;
+                let heartbeat_rx = incoming_message_rxs
+                    .remove(&LEADER_STREAM_PROTOCOL)
+                    .expect("leader p2p protocol must be found in receiver map");
 
-        let metrics = Arc::new(OpRBuilderMetrics::default());
+                let builder_peers = builder_peer_ids(
+                    self.0.specific.p2p_leader_peers.as_deref(),
+                    self.0.specific.p2p_known_peers.as_deref(),
+                )?;
+                if builder_peers.is_empty() {
+                    tracing::warn!(
+                        "no builder peers configured, bundles and metering information received from peers will be ignored"
+                    );
+                }
+                let leader = if self.0.specific.p2p_leader_election {
+                    let peer_ids = leader_peer_ids(
+                        self.0.specific.p2p_leader_peers.as_deref(),
+                        self.0.specific.p2p_known_peers.as_deref(),
+                    )?;
+                    let election = LeaderElection::new(
+                        self.0.specific.p2p_leader_priority,
+                        self.0.specific.p2p_leader_lease,
+                        keypair,
+                        peer_ids,
+                        metrics.clone(),
+                    );
+                    let leader = election.state();
+                    ctx.task_executor().spawn(election.run(
+                        heartbeat_rx,
+                        outgoing_message_tx.clone(),
+                        self.0.specific.p2p_heartbeat_interval,
+                        cancel.clone(),
+                    ));
+                    leader
+                } else {
+                    LeaderState::always_leader()
+                };
+                (
+                    incoming_messages,
+                    outgoing_message_tx,
+                    peer_score_tx,
+                    leader,
+                    builder_peers,
+                )
+            } else {
+                let (outgoing_message_tx, _outgoing_message_rx) = tokio::sync::mpsc::channel(16);
+                let (peer_score_tx, _peer_score_rx) = tokio::sync::mpsc::channel(16);
+                (
+                    IncomingMessages::disconnected(),
+                    outgoing_message_tx,
+                    peer_score_tx,
+                    LeaderState::always_leader(),
+                    HashSet::new(),
+                )
+            };
         let (built_payload_tx, built_payload_rx) = tokio::sync::mpsc::channel(16);
 
         let ws_pub: Arc<WebSocketPublisher> =
@@ -114,13 +160,15 @@ impl FlashblocksServiceBuilder {
                 .into();
         let payload_builder = OpPayloadBuilder::new(
             OpEvmConfig::optimism(ctx.chain_spec()),
-            pool,
+            pool.clone(),
             ctx.provider().clone(),
             self.0.clone(),
             builder_tx,
             built_payload_tx,
             ws_pub.clone(),
             metrics.clone(),
+            leader.clone(),
+            tee_registration,
         );
         let payload_job_config = BasicPayloadJobGeneratorConfig::default();
 
@@ -146,11 +194,15 @@ impl FlashblocksServiceBuilder {
 
         let payload_handler = PayloadHandler::new(
             built_payload_rx,
-            incoming_message_rx,
+            incoming_messages,
             outgoing_message_tx,
+            peer_score_tx,
             payload_service.payload_events_handle(),
             syncer_ctx,
             ctx.provider().clone(),
+            pool,
+            leader,
+            builder_peers,
             cancel,
         );
 
@@ -166,6 +218,78 @@ impl FlashblocksServiceBuilder {
     }
 }
 
+/// Returns the peer IDs of the other builders: the configured leader peers, or the peer IDs of
+/// the known peers when unset. Known peers without a `/p2p/` suffix have no peer ID and are
+/// skipped with a warning.
+///
+/// Only these peers take part in leader election and share bundles and metering information.
+fn builder_peer_ids(
+    leader_peers: Option<&str>,
+    known_peers: Option<&str>,
+) -> eyre::Result<HashSet<p2p::PeerId>> {
+    if let Some(leader_peers) = leader_peers {
+        return leader_peers
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                s.parse()
+                    .wrap_err_with(|| format!("invalid leader election peer ID: {s}"))
+            })
+            .collect();
+    }
+    known_peers
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|addr| !addr.is_empty())
+        .filter_map(|addr| {
+            let peer = addr.rsplit_once("/p2p/");
+            if peer.is_none() {
+                tracing::warn!(
+                    %addr,
+                    "known peer has no peer ID and isn't a builder peer, its bundles and metering information will be ignored; set --flashblocks.p2p_leader_peers or add a /p2p/ suffix"
+                );
+            }
+            peer
+        })
+        .map(|(addr, peer_id)| {
+            peer_id
+                .parse()
+                .wrap_err_with(|| format!("invalid peer ID of known peer {addr}: {peer_id}"))
+        })
+        .collect()
+}
+
+/// Returns the peer IDs of the builders taking part in leader election, see
+/// [`builder_peer_ids`].
+///
+/// Fails if no peer is configured, since every builder would then elect itself as the leader,
+/// or if a known peer has no peer ID when the leader peers are derived from the known peers.
+fn leader_peer_ids(
+    leader_peers: Option<&str>,
+    known_peers: Option<&str>,
+) -> eyre::Result<HashSet<p2p::PeerId>> {
+    if leader_peers.is_none()
+        && let Some(addr) = known_peers
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .find(|addr| !addr.is_empty() && !addr.contains("/p2p/"))
+    {
+        eyre::bail!(
+            "known peer {addr} has no peer ID, set --flashblocks.p2p_leader_peers or add a /p2p/ suffix"
+        );
+    }
+    let peer_ids = builder_peer_ids(leader_peers, known_peers)?;
+    if peer_ids.is_empty() {
+        eyre::bail!(
+            "leader election is enabled but no leader election peers are configured, set --flashblocks.p2p_leader_peers or --flashblocks.p2p_known_peers"
+        );
+    }
+    Ok(peer_ids)
+}
+
 impl<Node, Pool> PayloadServiceBuilder<Node, Pool, OpEvmConfig> for FlashblocksServiceBuilder
 where
     Node: NodeBounds,
@@ -177,22 +301,48 @@ where
         pool: Pool,
         _: OpEvmConfig,
     ) -> eyre::Result<PayloadBuilderHandle<<Node::Types as NodeTypes>::Payload>> {
+        // the p2p identity is known before the p2p node starts, so that its peer ID can be
+        // registered along with the TEE attestation
+        let p2p_keypair = self
+            .0
+            .specific
+            .p2p_enabled
+            .then(|| p2p_keypair(self.0.specific.p2p_private_key_file.as_deref()))
+            .transpose()?;
+
         let signer = self.0.builder_signer;
         let flashtestations_builder_tx = if let Some(builder_key) = signer
             && self.0.flashtestations_config.flashtestations_enabled
         {
-            match bootstrap_flashtestations(self.0.flashtestations_config.clone(), builder_key)
-                .await
+            match bootstrap_flashtestations(
+                self.0.flashtestations_config.clone(),
+                builder_key,
+                self.0.block_proofs.clone(),
+                self.0.flashtestations_status.clone(),
+                self.0.address_gas_limiter.clone(),
+                p2p_keypair
+                    .as_ref()
+                    .map(|keypair| keypair.public().to_peer_id()),
+                ctx.task_executor(),
+            )
+            .await
             {
                 Ok(builder_tx) => Some(builder_tx),
                 Err(e) => {
                     tracing::warn!(error = %e, "Failed to bootstrap flashtestations, builder will not include flashtestations txs");
+                    self.0
+                        .flashtestations_status
+                        .set_error(format!("failed to bootstrap flashtestations: {e}"));
                     None
                 }
             }
         } else {
             None
         };
+        let tee_registration = flashtestations_builder_tx
+            .as_ref()
+            .filter(|_| self.0.flashtestations_config.enable_flashblock_signatures)
+            .map(|builder_tx| builder_tx.registration_handle());
 
         if let Some(builder_signer) = signer
             && let Some(flashblocks_number_contract_address) =
@@ -208,13 +358,32 @@ where
                     use_permit,
                     flashtestations_builder_tx,
                 ),
+                tee_registration,
+                p2p_keypair,
             )
         } else {
             self.spawn_payload_builder_service(
                 ctx,
                 pool,
                 FlashblocksBuilderTx::new(signer, flashtestations_builder_tx),
+                tee_registration,
+                p2p_keypair,
             )
         }
     }
 }
+
+/// Returns the identity of the p2p node: the keypair of the private key file when set, or a new
+/// keypair.
+fn p2p_keypair(private_key_file: Option<&str>) -> eyre::Result<p2p::Keypair> {
+    match private_key_file.filter(|file| !file.is_empty()) {
+        Some(private_key_file) => {
+            let private_key_hex =
+                std::fs::read_to_string(private_key_file).wrap_err_with(|| {
+                    format!("failed to read p2p private key file: {private_key_file}")
+                })?;
+            p2p::keypair_from_hex(private_key_hex.trim())
+        }
+        None => Ok(p2p::Keypair::generate_ed25519()),
+    }
+}
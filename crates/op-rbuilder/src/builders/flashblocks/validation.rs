@@ -0,0 +1,151 @@
+use crate::builders::flashblocks::p2p::FlashblockDelta;
+use alloy_consensus::Header;
+use alloy_primitives::B256;
+use core::fmt;
+use reth::primitives::SealedHeader;
+
+/// Header commitments of a flashblock received from a peer.
+///
+/// After re-executing a received flashblock, every commitment of the resulting header must
+/// match the received one before the payload is announced to the engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct HeaderCommitments {
+    pub(super) block_hash: B256,
+    pub(super) state_root: B256,
+    pub(super) receipts_root: B256,
+    pub(super) gas_used: u64,
+}
+
+impl From<&SealedHeader<Header>> for HeaderCommitments {
+    fn from(header: &SealedHeader<Header>) -> Self {
+        Self {
+            block_hash: header.hash(),
+            state_root: header.state_root,
+            receipts_root: header.receipts_root,
+            gas_used: header.gas_used,
+        }
+    }
+}
+
+impl From<&FlashblockDelta> for HeaderCommitments {
+    fn from(delta: &FlashblockDelta) -> Self {
+        Self {
+            block_hash: delta.block_hash,
+            state_root: delta.state_root,
+            receipts_root: delta.receipts_root,
+            gas_used: delta.gas_used,
+        }
+    }
+}
+
+impl HeaderCommitments {
+    /// Compares the commitments of a re-executed flashblock with the received ones,
+    /// reporting every field that differs.
+    pub(super) fn verify(&self, executed: &HeaderCommitments) -> Result<(), HeaderMismatchReport> {
+        let mut mismatches = Vec::new();
+        let mut check = |field, expected: String, got: String| {
+            if expected != got {
+                mismatches.push(FieldMismatch {
+                    field,
+                    expected,
+                    got,
+                });
+            }
+        };
+
+        check(
+            "state_root",
+            self.state_root.to_string(),
+            executed.state_root.to_string(),
+        );
+        check(
+            "receipts_root",
+            self.receipts_root.to_string(),
+            executed.receipts_root.to_string(),
+        );
+        check(
+            "gas_used",
+            self.gas_used.to_string(),
+            executed.gas_used.to_string(),
+        );
+        check(
+            "block_hash",
+            self.block_hash.to_string(),
+            executed.block_hash.to_string(),
+        );
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(HeaderMismatchReport { mismatches })
+        }
+    }
+}
+
+/// A header field whose value after re-execution differs from the received one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct FieldMismatch {
+    pub(super) field: &'static str,
+    pub(super) expected: String,
+    pub(super) got: String,
+}
+
+/// Every header field of a received flashblock that didn't match after re-execution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct HeaderMismatchReport {
+    pub(super) mismatches: Vec<FieldMismatch>,
+}
+
+impl fmt::Display for HeaderMismatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "flashblock header mismatch:")?;
+        for mismatch in &self.mismatches {
+            write!(
+                f,
+                " {}: expected {} got {};",
+                mismatch.field, mismatch.expected, mismatch.got
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for HeaderMismatchReport {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commitments() -> HeaderCommitments {
+        HeaderCommitments {
+            block_hash: B256::repeat_byte(1),
+            state_root: B256::repeat_byte(2),
+            receipts_root: B256::repeat_byte(3),
+            gas_used: 21_000,
+        }
+    }
+
+    #[test]
+    fn matching_commitments_are_valid() {
+        assert_eq!(commitments().verify(&commitments()), Ok(()));
+    }
+
+    #[test]
+    fn every_mismatching_field_is_reported() {
+        let executed = HeaderCommitments {
+            block_hash: B256::repeat_byte(4),
+            gas_used: 42_000,
+            ..commitments()
+        };
+
+        let report = commitments().verify(&executed).unwrap_err();
+        let fields = report
+            .mismatches
+            .iter()
+            .map(|mismatch| mismatch.field)
+            .collect::<Vec<_>>();
+        assert_eq!(fields, vec!["gas_used", "block_hash"]);
+        assert_eq!(report.mismatches[0].expected, "21000");
+        assert_eq!(report.mismatches[0].got, "42000");
+    }
+}
@@ -8,9 +8,18 @@ use reth_optimism_evm::OpEvmConfig;
 use reth_optimism_payload_builder::config::{OpDAConfig, OpGasLimitConfig};
 
 use crate::{
+    admission::{AdmissionControl, args::AdmissionArgs},
     args::OpRbuilderArgs,
-    flashtestations::args::FlashtestationsArgs,
-    gas_limiter::args::GasLimiterArgs,
+    flashtestations::{
+        args::FlashtestationsArgs, block_proofs::BlockProofStore,
+        status::FlashtestationsStatusTracker,
+    },
+    gas_limiter::AddressGasLimiter,
+    primitives::bundle::BundleRangeLimits,
+    private_tx::PrivateTxPool,
+    reputation::SenderReputation,
+    runtime_config::{RuntimeConfig, RuntimeSettings},
+    simulation::PendingBlock,
     traits::{NodeBounds, PoolBounds},
     tx_signer::Signer,
 };
@@ -122,14 +131,43 @@ pub struct BuilderConfig<Specific: Clone> {
     /// Configuration values that are specific to the block builder implementation used.
     pub specific: Specific,
 
-    /// Maximum gas a transaction can use before being excluded.
-    pub max_gas_per_txn: Option<u64>,
+    /// Settings that can be updated at runtime through the admin RPC, such as the maximum gas
+    /// per transaction and the address gas limiter configuration. Shares the [`OpDAConfig`]
+    /// of `da_config`.
+    pub runtime_config: RuntimeConfig,
 
-    /// Address gas limiter stuff
-    pub gas_limiter_config: GasLimiterArgs,
+    /// Address gas limiter shared by the payload builders and the `eth_sendBundle` RPC. The
+    /// builder signer is exempt.
+    pub address_gas_limiter: AddressGasLimiter,
+
+    /// Reputation of senders, recorded by the payload builders and used for pool admission.
+    pub sender_reputation: SenderReputation,
+
+    /// Admission control of the pool, which also deprioritizes the transactions of senders
+    /// that revert often in the payload builders.
+    pub admission: AdmissionControl,
 
     /// Unified transaction data store (backrun bundles + resource metering)
     pub tx_data_store: TxDataStore,
+
+    /// Block being built by the flashblocks builder, used to simulate bundles against the
+    /// pending state.
+    pub pending_block: PendingBlock,
+
+    /// Block range limits of bundles sent through `eth_sendBundle` or forwarded by peers.
+    pub bundle_range: BundleRangeLimits,
+
+    /// Transactions sent through `eth_sendPrivateTransaction`, merged with the best transactions
+    /// of the pool by the payload builders.
+    pub private_txs: PrivateTxPool,
+
+    /// Block content hashes committed by the flashtestations block proofs, and the inclusion
+    /// proofs served by `base_getBlockProof`.
+    pub block_proofs: BlockProofStore,
+
+    /// Registration status of the TEE service, checked by the flashtestations status monitor
+    /// and served by `flashtestations_status`.
+    pub flashtestations_status: FlashtestationsStatusTracker,
 }
 
 impl<S: Debug + Clone> core::fmt::Debug for BuilderConfig<S> {
@@ -150,8 +188,7 @@ impl<S: Debug + Clone> core::fmt::Debug for BuilderConfig<S> {
             .field("gas_limit_config", &self.gas_limit_config)
             .field("sampling_ratio", &self.sampling_ratio)
             .field("specific", &self.specific)
-            .field("max_gas_per_txn", &self.max_gas_per_txn)
-            .field("gas_limiter_config", &self.gas_limiter_config)
+            .field("runtime_config", &self.runtime_config.view())
             .field("tx_data_store", &self.tx_data_store)
             .finish()
     }
@@ -159,19 +196,37 @@ impl<S: Debug + Clone> core::fmt::Debug for BuilderConfig<S> {
 
 impl<S: Default + Clone> Default for BuilderConfig<S> {
     fn default() -> Self {
+        let da_config = OpDAConfig::default();
+        let address_gas_limiter = AddressGasLimiter::new(Default::default());
+        let sender_reputation = SenderReputation::default();
         Self {
             builder_signer: None,
             revert_protection: false,
             flashtestations_config: FlashtestationsArgs::default(),
             block_time: Duration::from_secs(2),
             block_time_leeway: Duration::from_millis(500),
-            da_config: OpDAConfig::default(),
+            da_config: da_config.clone(),
             gas_limit_config: OpGasLimitConfig::default(),
             specific: S::default(),
             sampling_ratio: 100,
-            max_gas_per_txn: None,
-            gas_limiter_config: GasLimiterArgs::default(),
+            runtime_config: RuntimeConfig::new(
+                RuntimeSettings::default(),
+                da_config,
+                Duration::from_millis(250),
+            ),
+            admission: AdmissionControl::new(
+                AdmissionArgs::default(),
+                address_gas_limiter.clone(),
+                sender_reputation.clone(),
+            ),
+            address_gas_limiter,
+            sender_reputation,
             tx_data_store: TxDataStore::default(),
+            pending_block: PendingBlock::default(),
+            bundle_range: BundleRangeLimits::default(),
+            private_txs: PrivateTxPool::default(),
+            block_proofs: BlockProofStore::default(),
+            flashtestations_status: FlashtestationsStatusTracker::default(),
         }
     }
 }
@@ -183,21 +238,40 @@ where
     type Error = S::Error;
 
     fn try_from(args: OpRbuilderArgs) -> Result<Self, Self::Error> {
+        let da_config = OpDAConfig::default();
+        let address_gas_limiter = AddressGasLimiter::new(args.gas_limiter.clone())
+            .with_exempt(args.builder_signer.map(|signer| signer.address));
+        let sender_reputation = SenderReputation::new(args.reputation.clone());
         Ok(Self {
             builder_signer: args.builder_signer,
             revert_protection: args.enable_revert_protection,
             flashtestations_config: args.flashtestations.clone(),
             block_time: Duration::from_millis(args.chain_block_time),
             block_time_leeway: Duration::from_secs(args.extra_block_deadline_secs),
-            da_config: Default::default(),
+            da_config: da_config.clone(),
             gas_limit_config: Default::default(),
             sampling_ratio: args.telemetry.sampling_ratio,
-            max_gas_per_txn: args.max_gas_per_txn,
-            gas_limiter_config: args.gas_limiter.clone(),
+            admission: AdmissionControl::new(
+                args.admission.clone(),
+                address_gas_limiter.clone(),
+                sender_reputation.clone(),
+            ),
+            address_gas_limiter,
+            sender_reputation,
+            runtime_config: RuntimeConfig::new(
+                RuntimeSettings::from(&args),
+                da_config,
+                Duration::from_millis(args.flashblocks.flashblocks_block_time),
+            ),
             tx_data_store: TxDataStore::new(
                 args.enable_resource_metering,
                 args.tx_data_store_buffer_size,
             ),
+            pending_block: PendingBlock::default(),
+            bundle_range: args.bundle.range_limits(args.chain_block_time),
+            private_txs: PrivateTxPool::new(args.private_tx.clone()),
+            block_proofs: BlockProofStore::new(args.flashtestations.block_proof_batch_size),
+            flashtestations_status: FlashtestationsStatusTracker::default(),
             specific: S::try_from(args)?,
         })
     }
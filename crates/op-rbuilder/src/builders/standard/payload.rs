@@ -67,7 +67,7 @@ impl<Pool, Client, BuilderTx> StandardOpPayloadBuilder<Pool, Client, BuilderTx>
         config: BuilderConfig<()>,
         builder_tx: BuilderTx,
     ) -> Self {
-        let address_gas_limiter = AddressGasLimiter::new(config.gas_limiter_config.clone());
+        let address_gas_limiter = config.address_gas_limiter.clone();
         Self {
             pool,
             client,
@@ -237,6 +237,8 @@ where
             .next_evm_env(&config.parent_header, &block_env_attributes)
             .map_err(PayloadBuilderError::other)?;
 
+        // settings updated through the admin RPC take effect from the next block
+        let settings = self.config.runtime_config.load();
         let ctx = OpPayloadBuilderCtx {
             evm_config: self.evm_config.clone(),
             da_config: self.config.da_config.clone(),
@@ -249,16 +251,26 @@ where
             builder_signer: self.config.builder_signer,
             metrics: self.metrics.clone(),
             extra_ctx: Default::default(),
-            max_gas_per_txn: self.config.max_gas_per_txn,
+            max_gas_per_txn: settings.max_gas_per_txn,
             address_gas_limiter: self.address_gas_limiter.clone(),
             tx_data_store: self.config.tx_data_store.clone(),
+            sender_reputation: self.config.sender_reputation.clone(),
+            admission: self.config.admission.clone(),
+            private_txs: self.config.private_txs.clone(),
         };
 
         let builder = OpBuilder::new(best);
 
+        self.address_gas_limiter.reconfigure(&settings.gas_limiter);
         self.address_gas_limiter.refresh(ctx.block_number());
+        self.config
+            .sender_reputation
+            .on_new_block(ctx.block_number());
 
         let state_provider = self.client.state_by_block_hash(ctx.parent().hash())?;
+        self.config
+            .private_txs
+            .prune(ctx.block_number(), &state_provider);
         let db = StateProviderDatabase::new(&state_provider);
         let metrics = ctx.metrics.clone();
         if ctx.attributes().no_tx_pool {
@@ -415,6 +427,7 @@ impl<Txs: PayloadTxsBounds> OpBuilder<'_, Txs> {
                     block_gas_limit,
                     block_da_limit,
                     block_da_footprint,
+                    ctx.timestamp() * 1000,
                 )?
                 .is_some()
             {
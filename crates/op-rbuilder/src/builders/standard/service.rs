@@ -75,12 +75,23 @@ where
         let flashtestations_builder_tx = if let Some(builder_key) = signer
             && self.0.flashtestations_config.flashtestations_enabled
         {
-            match bootstrap_flashtestations(self.0.flashtestations_config.clone(), builder_key)
-                .await
+            match bootstrap_flashtestations(
+                self.0.flashtestations_config.clone(),
+                builder_key,
+                self.0.block_proofs.clone(),
+                self.0.flashtestations_status.clone(),
+                self.0.address_gas_limiter.clone(),
+                None,
+                ctx.task_executor(),
+            )
+            .await
             {
                 Ok(builder_tx) => Some(builder_tx),
                 Err(e) => {
                     tracing::warn!(error = %e, "Failed to bootstrap flashtestations, builderb will not include flashtestations txs");
+                    self.0
+                        .flashtestations_status
+                        .set_error(format!("failed to bootstrap flashtestations: {e}"));
                     None
                 }
             }
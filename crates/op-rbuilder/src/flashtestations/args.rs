@@ -2,7 +2,10 @@ use alloy_primitives::Address;
 use clap::Parser;
 use reth_optimism_cli::commands::Commands;
 
-use crate::args::Cli;
+use crate::{
+    args::Cli,
+    flashtestations::attestation::{AttestationProviderKind, DEFAULT_CONFIGFS_TSM_PATH},
+};
 
 /// Parameters for Flashtestations configuration
 /// The names in the struct are prefixed with `flashtestations`
@@ -17,7 +20,8 @@ pub struct FlashtestationsArgs {
     )]
     pub flashtestations_enabled: bool,
 
-    /// Whether to use the debug HTTP service for quotes
+    /// Whether to derive the TEE key from `--flashtestations.debug-tee-key-seed` instead of
+    /// loading or generating it
     #[arg(
         long = "flashtestations.debug",
         default_value = "false",
@@ -41,13 +45,78 @@ pub struct FlashtestationsArgs {
     )]
     pub flashtestations_key_path: String,
 
-    // Remote url for attestations
+    // Remote url for attestations, required by the remote attestation provider
     #[arg(
         long = "flashtestations.quote-provider",
         env = "FLASHTESTATIONS_QUOTE_PROVIDER"
     )]
     pub quote_provider: Option<String>,
 
+    /// Source of the TDX quotes: a remote quote provider service, the local TDX device through
+    /// configfs-tsm, or a fixed quote read from a file
+    #[arg(
+        long = "flashtestations.attestation-provider",
+        env = "FLASHTESTATIONS_ATTESTATION_PROVIDER",
+        value_enum,
+        default_value_t = AttestationProviderKind::Remote
+    )]
+    pub attestation_provider: AttestationProviderKind,
+
+    /// Number of retries of failed requests to the remote quote provider
+    #[arg(
+        long = "flashtestations.quote-provider-retries",
+        env = "FLASHTESTATIONS_QUOTE_PROVIDER_RETRIES",
+        default_value = "3"
+    )]
+    pub quote_provider_retries: u32,
+
+    /// Path of the configfs-tsm report directory used by the configfs-tsm provider
+    #[arg(
+        long = "flashtestations.configfs-tsm-path",
+        env = "FLASHTESTATIONS_CONFIGFS_TSM_PATH",
+        default_value = DEFAULT_CONFIGFS_TSM_PATH
+    )]
+    pub configfs_tsm_path: String,
+
+    /// Path of the quote returned by the file provider. DO NOT USE IN PRODUCTION
+    #[arg(
+        long = "flashtestations.quote-file",
+        env = "FLASHTESTATIONS_QUOTE_FILE"
+    )]
+    pub quote_file: Option<String>,
+
+    /// Path to a JSON file with the collateral quotes are verified against, as
+    /// `{"rootCa": "<PEM>", "crls": ["<PEM>", ...]}`. When set, quotes are verified locally before
+    /// being registered onchain.
+    #[arg(
+        long = "flashtestations.collateral-path",
+        env = "FLASHTESTATIONS_COLLATERAL_PATH"
+    )]
+    pub collateral_path: Option<String>,
+
+    /// Add the libp2p peer ID of the flashblocks p2p node to the extended registration data
+    #[arg(
+        long = "flashtestations.registration-peer-id",
+        env = "FLASHTESTATIONS_REGISTRATION_PEER_ID",
+        default_value = "false"
+    )]
+    pub registration_peer_id: bool,
+
+    /// Flashblocks WebSocket URL of the builder, added to the extended registration data
+    #[arg(
+        long = "flashtestations.registration-websocket-url",
+        env = "FLASHTESTATIONS_REGISTRATION_WEBSOCKET_URL"
+    )]
+    pub registration_websocket_url: Option<String>,
+
+    /// Add the version and commit of the builder to the extended registration data
+    #[arg(
+        long = "flashtestations.registration-version",
+        env = "FLASHTESTATIONS_REGISTRATION_VERSION",
+        default_value = "false"
+    )]
+    pub registration_version: bool,
+
     /// The rpc url to post the onchain attestation requests to
     #[arg(long = "flashtestations.rpc-url", env = "FLASHTESTATIONS_RPC_URL")]
     pub rpc_url: Option<String>,
@@ -60,6 +129,28 @@ pub struct FlashtestationsArgs {
     )]
     pub enable_block_proofs: bool,
 
+    /// Number of blocks whose content hashes are committed by a single block proof, as the
+    /// Merkle root of the block content hashes. The proof is added in the blocks whose number is
+    /// a multiple of the batch size, and the inclusion proofs of the blocks are served by
+    /// `base_getBlockProof`.
+    #[arg(
+        long = "flashtestations.block-proof-batch-size",
+        env = "FLASHTESTATIONS_BLOCK_PROOF_BATCH_SIZE",
+        default_value = "1",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub block_proof_batch_size: u64,
+
+    /// Sign each published flashblock with the TEE key, over the payload id, the index and the
+    /// block content hash of the transactions so far. The signature is added to the flashblock
+    /// metadata.
+    #[arg(
+        long = "flashtestations.enable-flashblock-signatures",
+        env = "FLASHTESTATIONS_ENABLE_FLASHBLOCK_SIGNATURES",
+        default_value = "false"
+    )]
+    pub enable_flashblock_signatures: bool,
+
     /// The address of the flashtestations registry contract
     #[arg(
         long = "flashtestations.registry-address",
@@ -76,6 +167,44 @@ pub struct FlashtestationsArgs {
     )]
     pub builder_policy_address: Option<Address>,
 
+    /// Interval between re-attestations of the TEE service. When unset, the service is only
+    /// re-attested when its registration becomes invalid.
+    #[arg(
+        long = "flashtestations.reattestation-interval-secs",
+        env = "FLASHTESTATIONS_REATTESTATION_INTERVAL_SECS",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub reattestation_interval_secs: Option<u64>,
+
+    /// Interval between checks of the registration status of the TEE service through
+    /// `--flashtestations.rpc-url`, or in the local chain state without it. An invalid
+    /// registration triggers a re-attestation.
+    #[arg(
+        long = "flashtestations.status-check-interval-secs",
+        env = "FLASHTESTATIONS_STATUS_CHECK_INTERVAL_SECS",
+        default_value = "60",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub status_check_interval_secs: u64,
+
+    /// Interval between checks of the registration, the workload approval and the block proofs
+    /// of the TEE service against the local chain state, served by `flashtestations_status`
+    #[arg(
+        long = "flashtestations.monitor-interval-secs",
+        env = "FLASHTESTATIONS_MONITOR_INTERVAL_SECS",
+        default_value = "10",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub monitor_interval_secs: u64,
+
+    /// Generate a new TEE key on each re-attestation
+    #[arg(
+        long = "flashtestations.rotate-key",
+        env = "FLASHTESTATIONS_ROTATE_KEY",
+        default_value = "false"
+    )]
+    pub rotate_key: bool,
+
     /// The version of the block builder verification proof
     #[arg(
         long = "flashtestations.builder-proof-version",
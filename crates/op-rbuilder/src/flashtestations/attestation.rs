@@ -1,59 +1,76 @@
+use async_trait::async_trait;
 use reqwest::Client;
-use sha3::{Digest, Keccak256};
-use tracing::info;
-
-const DEBUG_QUOTE_SERVICE_URL: &str = "http://ns31695324.ip-141-94-163.eu:10080/attest";
-
-// Raw TDX v4 quote structure constants
-// Raw quote has a 48-byte header before the TD10ReportBody
-const HEADER_LENGTH: usize = 48;
-const TD_REPORT10_LENGTH: usize = 584;
-
-// TD10ReportBody field offsets
-// These offsets correspond to the Solidity parseRawReportBody implementation
-const OFFSET_TD_ATTRIBUTES: usize = 120;
-const OFFSET_XFAM: usize = 128;
-const OFFSET_MR_TD: usize = 136;
-const OFFSET_MR_CONFIG_ID: usize = 184;
-const OFFSET_MR_OWNER: usize = 232;
-const OFFSET_MR_OWNER_CONFIG: usize = 280;
-const OFFSET_RT_MR0: usize = 328;
-const OFFSET_RT_MR1: usize = 376;
-const OFFSET_RT_MR2: usize = 424;
-const OFFSET_RT_MR3: usize = 472;
-
-// Field lengths
-const MEASUREMENT_REGISTER_LENGTH: usize = 48;
-const ATTRIBUTE_LENGTH: usize = 8;
-
-/// Parsed TDX quote report body containing measurement registers and attributes
-#[derive(Debug, Clone)]
-pub struct ParsedQuote {
-    pub mr_td: [u8; 48],
-    pub rt_mr0: [u8; 48],
-    pub rt_mr1: [u8; 48],
-    pub rt_mr2: [u8; 48],
-    pub rt_mr3: [u8; 48],
-    pub mr_config_id: [u8; 48],
-    pub mr_owner: [u8; 48],
-    pub mr_owner_config: [u8; 48],
-    pub xfam: u64,
-    pub td_attributes: u64,
+use std::{
+    fmt::Debug,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AttestationError {
+    #[error("failed to read quote from {path}: {source}")]
+    ReadQuote {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("configfs-tsm report failed: {0}")]
+    ConfigfsTsm(std::io::Error),
+    #[error("configfs-tsm report was modified concurrently")]
+    ConfigfsTsmConflict,
+    #[error("remote attestation request failed after {attempts} attempts: {source}")]
+    Remote {
+        attempts: u32,
+        source: reqwest::Error,
+    },
+    #[error("attestation task failed: {0}")]
+    Task(#[from] tokio::task::JoinError),
+}
+
+/// Source of TDX quotes, mirrors the `AttestationProvider` of `tdx-quote-provider`.
+#[async_trait]
+pub trait AttestationProvider: Debug + Send + Sync {
+    /// Returns a raw quote with the given report data.
+    async fn get_attestation(&self, report_data: [u8; 64]) -> Result<Vec<u8>, AttestationError>;
+}
+
+/// Kind of [`AttestationProvider`] used by the builder.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum AttestationProviderKind {
+    /// HTTP GET to a quote provider service, such as `tdx-quote-provider`
+    #[default]
+    Remote,
+    /// Local TDX device, through the configfs-tsm interface of the kernel
+    ConfigfsTsm,
+    /// Fixed quote read from a file, for tests
+    File,
 }
 
 /// Configuration for attestation
-#[derive(Default)]
+#[derive(Debug, Default)]
 pub struct AttestationConfig {
-    /// If true, uses the debug HTTP service instead of real TDX hardware
-    pub debug: bool,
+    /// Kind of attestation provider
+    pub provider: AttestationProviderKind,
     /// The URL of the quote provider
     pub quote_provider: Option<String>,
+    /// Number of retries of failed requests to the quote provider
+    pub quote_provider_retries: u32,
+    /// Path of the configfs-tsm report directory
+    pub configfs_tsm_path: PathBuf,
+    /// Path of the quote returned by the file provider
+    pub quote_file: Option<PathBuf>,
 }
+
 /// Remote attestation provider
 #[derive(Debug, Clone)]
 pub struct RemoteAttestationProvider {
     client: Client,
     service_url: String,
+    max_retries: u32,
+    initial_backoff: Duration,
 }
 
 impl RemoteAttestationProvider {
@@ -62,155 +79,171 @@ impl RemoteAttestationProvider {
         Self {
             client,
             service_url,
+            max_retries: 0,
+            initial_backoff: Duration::from_millis(500),
         }
     }
-}
 
-impl RemoteAttestationProvider {
-    pub async fn get_attestation(&self, report_data: [u8; 64]) -> eyre::Result<Vec<u8>> {
-        let report_data_hex = hex::encode(report_data);
-        let url = format!("{}/{}", self.service_url, report_data_hex);
-
-        info!(target: "flashtestations", url = url, "fetching quote from remote attestation provider");
+    /// Retries failed requests up to `max_retries` times, doubling the backoff between attempts.
+    /// Requests rejected with a client error are not retried.
+    pub fn with_retries(mut self, max_retries: u32, initial_backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.initial_backoff = initial_backoff;
+        self
+    }
 
+    async fn request(&self, url: &str) -> Result<Vec<u8>, reqwest::Error> {
         let response = self
             .client
-            .get(&url)
-            .timeout(std::time::Duration::from_secs(10))
+            .get(url)
+            .timeout(Duration::from_secs(10))
             .send()
             .await?
             .error_for_status()?;
-        let body = response.bytes().await?.to_vec();
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+#[async_trait]
+impl AttestationProvider for RemoteAttestationProvider {
+    async fn get_attestation(&self, report_data: [u8; 64]) -> Result<Vec<u8>, AttestationError> {
+        let report_data_hex = hex::encode(report_data);
+        let url = format!("{}/{}", self.service_url, report_data_hex);
+
+        info!(target: "flashtestations", url = url, "fetching quote from remote attestation provider");
 
-        Ok(body)
+        let mut backoff = self.initial_backoff;
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            match self.request(&url).await {
+                Ok(quote) => return Ok(quote),
+                Err(e)
+                    if attempts <= self.max_retries
+                        && !e.status().is_some_and(|status| status.is_client_error()) =>
+                {
+                    warn!(
+                        target: "flashtestations",
+                        error = %e,
+                        attempts,
+                        ?backoff,
+                        "quote request failed, retrying"
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(source) => return Err(AttestationError::Remote { attempts, source }),
+            }
+        }
     }
 }
 
-pub fn get_attestation_provider(config: AttestationConfig) -> RemoteAttestationProvider {
-    if config.debug {
-        RemoteAttestationProvider::new(
-            config
-                .quote_provider
-                .unwrap_or(DEBUG_QUOTE_SERVICE_URL.to_string()),
-        )
-    } else {
-        RemoteAttestationProvider::new(
-            config
-                .quote_provider
-                .expect("remote quote provider must be specified when not in debug mode"),
-        )
+/// Default path of the configfs-tsm report directory.
+pub const DEFAULT_CONFIGFS_TSM_PATH: &str = "/sys/kernel/config/tsm/report";
+
+/// Local TDX attestation provider, generating quotes through the configfs-tsm interface
+/// https://docs.kernel.org/ABI/testing/configfs-tsm
+#[derive(Debug, Clone)]
+pub struct ConfigfsTsmAttestationProvider {
+    path: PathBuf,
+}
+
+impl ConfigfsTsmAttestationProvider {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn generate_quote(path: &Path, report_data: [u8; 64]) -> Result<Vec<u8>, AttestationError> {
+        // a report entry is created per request, so concurrent requests don't interfere
+        let entry = path.join(format!("op-rbuilder-{}", Uuid::new_v4()));
+        fs::create_dir(&entry).map_err(AttestationError::ConfigfsTsm)?;
+
+        let result = (|| {
+            fs::write(entry.join("inblob"), report_data).map_err(AttestationError::ConfigfsTsm)?;
+            let generation = Self::read_generation(&entry)?;
+            let quote = fs::read(entry.join("outblob")).map_err(AttestationError::ConfigfsTsm)?;
+            // the quote is for another report data if the inblob was written meanwhile
+            if Self::read_generation(&entry)? != generation {
+                return Err(AttestationError::ConfigfsTsmConflict);
+            }
+            Ok(quote)
+        })();
+
+        if let Err(e) = fs::remove_dir(&entry) {
+            warn!(target: "flashtestations", error = %e, ?entry, "failed to remove configfs-tsm report entry");
+        }
+        result
+    }
+
+    fn read_generation(entry: &Path) -> Result<String, AttestationError> {
+        fs::read_to_string(entry.join("generation")).map_err(AttestationError::ConfigfsTsm)
     }
 }
 
-/// Parse the TDX report body from a raw quote
-/// Extracts measurement registers and attributes according to TD10ReportBody specification
-/// https://github.com/flashbots/flashtestations/tree/7cc7f68492fe672a823dd2dead649793aac1f216
-pub fn parse_report_body(raw_quote: &[u8]) -> eyre::Result<ParsedQuote> {
-    // Validate quote length
-    if raw_quote.len() < HEADER_LENGTH + TD_REPORT10_LENGTH {
-        eyre::bail!(
-            "invalid quote length: {}, expected at least {}",
-            raw_quote.len(),
-            HEADER_LENGTH + TD_REPORT10_LENGTH
-        );
+#[async_trait]
+impl AttestationProvider for ConfigfsTsmAttestationProvider {
+    async fn get_attestation(&self, report_data: [u8; 64]) -> Result<Vec<u8>, AttestationError> {
+        info!(target: "flashtestations", path = ?self.path, "generating quote with configfs-tsm");
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || Self::generate_quote(&path, report_data)).await?
     }
+}
 
-    // Skip the 48-byte header to get to the TD10ReportBody
-    let report_body = &raw_quote[HEADER_LENGTH..];
-
-    // Extract fields exactly as parseRawReportBody does in Solidity
-    // Using named offset constants to match Solidity implementation exactly
-    let mr_td: [u8; 48] = report_body[OFFSET_MR_TD..OFFSET_MR_TD + MEASUREMENT_REGISTER_LENGTH]
-        .try_into()
-        .map_err(|_| eyre::eyre!("failed to extract mr_td"))?;
-    let rt_mr0: [u8; 48] = report_body[OFFSET_RT_MR0..OFFSET_RT_MR0 + MEASUREMENT_REGISTER_LENGTH]
-        .try_into()
-        .map_err(|_| eyre::eyre!("failed to extract rt_mr0"))?;
-    let rt_mr1: [u8; 48] = report_body[OFFSET_RT_MR1..OFFSET_RT_MR1 + MEASUREMENT_REGISTER_LENGTH]
-        .try_into()
-        .map_err(|_| eyre::eyre!("failed to extract rt_mr1"))?;
-    let rt_mr2: [u8; 48] = report_body[OFFSET_RT_MR2..OFFSET_RT_MR2 + MEASUREMENT_REGISTER_LENGTH]
-        .try_into()
-        .map_err(|_| eyre::eyre!("failed to extract rt_mr2"))?;
-    let rt_mr3: [u8; 48] = report_body[OFFSET_RT_MR3..OFFSET_RT_MR3 + MEASUREMENT_REGISTER_LENGTH]
-        .try_into()
-        .map_err(|_| eyre::eyre!("failed to extract rt_mr3"))?;
-    let mr_config_id: [u8; 48] = report_body
-        [OFFSET_MR_CONFIG_ID..OFFSET_MR_CONFIG_ID + MEASUREMENT_REGISTER_LENGTH]
-        .try_into()
-        .map_err(|_| eyre::eyre!("failed to extract mr_config_id"))?;
-    let mr_owner: [u8; 48] = report_body
-        [OFFSET_MR_OWNER..OFFSET_MR_OWNER + MEASUREMENT_REGISTER_LENGTH]
-        .try_into()
-        .map_err(|_| eyre::eyre!("failed to extract mr_owner"))?;
-    let mr_owner_config: [u8; 48] = report_body
-        [OFFSET_MR_OWNER_CONFIG..OFFSET_MR_OWNER_CONFIG + MEASUREMENT_REGISTER_LENGTH]
-        .try_into()
-        .map_err(|_| eyre::eyre!("failed to extract mr_owner_config"))?;
-
-    // Extract xFAM and tdAttributes (8 bytes each)
-    // In Solidity, bytes8 is treated as big-endian for bitwise operations
-    let xfam = u64::from_be_bytes(
-        report_body[OFFSET_XFAM..OFFSET_XFAM + ATTRIBUTE_LENGTH]
-            .try_into()
-            .map_err(|e| eyre::eyre!("failed to parse xfam: {}", e))?,
-    );
-    let td_attributes = u64::from_be_bytes(
-        report_body[OFFSET_TD_ATTRIBUTES..OFFSET_TD_ATTRIBUTES + ATTRIBUTE_LENGTH]
-            .try_into()
-            .map_err(|e| eyre::eyre!("failed to parse td_attributes: {}", e))?,
-    );
-
-    Ok(ParsedQuote {
-        mr_td,
-        rt_mr0,
-        rt_mr1,
-        rt_mr2,
-        rt_mr3,
-        mr_config_id,
-        mr_owner,
-        mr_owner_config,
-        xfam,
-        td_attributes,
-    })
+/// File attestation provider, returning a fixed quote regardless of the report data
+#[derive(Debug, Clone)]
+pub struct FileAttestationProvider {
+    path: PathBuf,
 }
 
-/// Compute workload ID from parsed quote data
-/// This corresponds to QuoteParser.parseV4VerifierOutput in Solidity implementation
-/// The workload ID uniquely identifies a TEE workload based on its measurement registers
-pub fn compute_workload_id_from_parsed(parsed: &ParsedQuote) -> [u8; 32] {
-    // Concatenate all fields
-    let mut concatenated = Vec::new();
-    concatenated.extend_from_slice(&parsed.mr_td);
-    concatenated.extend_from_slice(&parsed.rt_mr0);
-    concatenated.extend_from_slice(&parsed.rt_mr1);
-    concatenated.extend_from_slice(&parsed.rt_mr2);
-    concatenated.extend_from_slice(&parsed.rt_mr3);
-    concatenated.extend_from_slice(&parsed.mr_config_id);
-    concatenated.extend_from_slice(&parsed.xfam.to_be_bytes());
-    concatenated.extend_from_slice(&parsed.td_attributes.to_be_bytes());
-
-    // Compute keccak256 hash
-    let mut hasher = Keccak256::new();
-    hasher.update(&concatenated);
-    let result = hasher.finalize();
-
-    let mut workload_id = [0u8; 32];
-    workload_id.copy_from_slice(&result);
-
-    workload_id
+impl FileAttestationProvider {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
 }
 
-/// Compute workload ID from raw quote bytes
-/// This is a convenience function that combines parsing and computation
-pub fn compute_workload_id(raw_quote: &[u8]) -> eyre::Result<[u8; 32]> {
-    let parsed = parse_report_body(raw_quote)?;
-    Ok(compute_workload_id_from_parsed(&parsed))
+#[async_trait]
+impl AttestationProvider for FileAttestationProvider {
+    async fn get_attestation(&self, _report_data: [u8; 64]) -> Result<Vec<u8>, AttestationError> {
+        info!(target: "flashtestations", path = ?self.path, "reading quote from file");
+        tokio::fs::read(&self.path)
+            .await
+            .map_err(|source| AttestationError::ReadQuote {
+                path: self.path.clone(),
+                source,
+            })
+    }
+}
+
+pub fn get_attestation_provider(
+    config: AttestationConfig,
+) -> eyre::Result<Arc<dyn AttestationProvider>> {
+    let provider: Arc<dyn AttestationProvider> = match config.provider {
+        AttestationProviderKind::Remote => {
+            let service_url = config.quote_provider.ok_or_else(|| {
+                eyre::eyre!("quote provider must be specified for the remote provider")
+            })?;
+            Arc::new(
+                RemoteAttestationProvider::new(service_url)
+                    .with_retries(config.quote_provider_retries, Duration::from_millis(500)),
+            )
+        }
+        AttestationProviderKind::ConfigfsTsm => Arc::new(ConfigfsTsmAttestationProvider::new(
+            config.configfs_tsm_path,
+        )),
+        AttestationProviderKind::File => {
+            let path = config
+                .quote_file
+                .ok_or_else(|| eyre::eyre!("quote file must be specified for the file provider"))?;
+            Arc::new(FileAttestationProvider::new(path))
+        }
+    };
+    Ok(provider)
 }
 
 #[cfg(test)]
 mod tests {
     use crate::tests::WORKLOAD_ID;
+    use tdx_quote_parser::compute_workload_id;
 
     use super::*;
 
@@ -228,4 +261,57 @@ mod tests {
             "workload ID mismatch for test quote"
         );
     }
+
+    #[tokio::test]
+    async fn test_file_attestation_provider() {
+        let quote = include_bytes!("../tests/framework/artifacts/test-quote.bin");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("quote.bin");
+        fs::write(&path, quote).unwrap();
+
+        let provider = get_attestation_provider(AttestationConfig {
+            provider: AttestationProviderKind::File,
+            quote_file: Some(path),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(provider.get_attestation([1; 64]).await.unwrap(), quote);
+
+        let missing = FileAttestationProvider::new(dir.path().join("missing.bin"));
+        assert!(matches!(
+            missing.get_attestation([1; 64]).await,
+            Err(AttestationError::ReadQuote { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_remote_attestation_provider_retries() {
+        // nothing listens on port 1, so every attempt fails to connect
+        let provider = RemoteAttestationProvider::new("http://127.0.0.1:1".to_string())
+            .with_retries(2, Duration::from_millis(1));
+
+        match provider.get_attestation([0; 64]).await {
+            Err(AttestationError::Remote { attempts, .. }) => assert_eq!(attempts, 3),
+            res => panic!("expected a remote error, got {res:?}"),
+        }
+    }
+
+    #[test]
+    fn test_attestation_provider_config() {
+        assert!(get_attestation_provider(AttestationConfig::default()).is_err());
+        assert!(
+            get_attestation_provider(AttestationConfig {
+                quote_provider: Some("http://127.0.0.1:1".to_string()),
+                ..Default::default()
+            })
+            .is_ok()
+        );
+        assert!(
+            get_attestation_provider(AttestationConfig {
+                provider: AttestationProviderKind::File,
+                ..Default::default()
+            })
+            .is_err()
+        );
+    }
 }
@@ -0,0 +1,448 @@
+use alloy_primitives::{B256, U64, keccak256};
+use futures_util::StreamExt;
+use jsonrpsee::{
+    core::{RpcResult, async_trait},
+    proc_macros::rpc,
+};
+use parking_lot::Mutex;
+use reth_optimism_primitives::{OpPrimitives, OpTransactionSigned};
+use reth_provider::CanonStateNotificationStream;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use super::builder_tx::compute_block_content_hash;
+
+/// Number of blocks the inclusion proofs are kept for.
+const BLOCK_PROOFS_RETENTION: u64 = 100_000;
+
+/// Hashes a pair of nodes in sorted order, as the `MerkleProof` library of OpenZeppelin does,
+/// so that proofs can be verified onchain without the position of the leaf.
+fn hash_pair(a: B256, b: B256) -> B256 {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    keccak256([left.as_slice(), right.as_slice()].concat())
+}
+
+/// Layers of the Merkle tree of the leaves, from the leaves to the root. The last node of a
+/// layer with an odd number of nodes is promoted to the next layer unchanged.
+fn merkle_layers(leaves: &[B256]) -> Vec<Vec<B256>> {
+    let mut layers = vec![leaves.to_vec()];
+    while layers.last().is_some_and(|layer| layer.len() > 1) {
+        let next = layers
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => hash_pair(*a, *b),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+        layers.push(next);
+    }
+    layers
+}
+
+/// Returns the Merkle root of the leaves, zero without leaves.
+pub fn merkle_root(leaves: &[B256]) -> B256 {
+    merkle_layers(leaves)
+        .last()
+        .and_then(|layer| layer.first())
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Returns the siblings on the path from the leaf at `index` to the root.
+pub fn merkle_proof(leaves: &[B256], index: usize) -> Vec<B256> {
+    let layers = merkle_layers(leaves);
+    let mut proof = Vec::new();
+    let mut index = index;
+    for layer in &layers[..layers.len() - 1] {
+        if let Some(sibling) = layer.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        index /= 2;
+    }
+    proof
+}
+
+/// Checks that the leaf is in the tree of the given root.
+pub fn verify_merkle_proof(leaf: B256, proof: &[B256], root: B256) -> bool {
+    proof
+        .iter()
+        .fold(leaf, |node, sibling| hash_pair(node, *sibling))
+        == root
+}
+
+/// Inclusion proof of the content hash of a block in the root committed by a block proof
+/// transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockInclusionProof {
+    pub block_number: U64,
+    pub block_content_hash: B256,
+    /// Block whose proof transaction committed the root
+    pub proof_block_number: U64,
+    /// Root of the content hashes of the batch, verified by the builder policy contract
+    pub root: B256,
+    /// Siblings on the path from the block content hash to the root
+    pub proof: Vec<B256>,
+}
+
+impl BlockInclusionProof {
+    pub fn verify(&self) -> bool {
+        verify_merkle_proof(self.block_content_hash, &self.proof, self.root)
+    }
+}
+
+/// Build of a block by this builder.
+#[derive(Debug, Clone, Copy)]
+struct BlockBuild {
+    block_content_hash: B256,
+    /// Number of transactions hashed into the block content hash, the builder transactions
+    /// added after them are not part of it
+    transaction_count: usize,
+}
+
+#[derive(Debug, Default)]
+struct BlockProofs {
+    /// Last build of the blocks of the current batch, by block number and parent hash
+    builds: BTreeMap<u64, HashMap<B256, BlockBuild>>,
+    /// Hash and content hash of the blocks built by this builder that are included in the
+    /// canonical chain, by block number
+    included: BTreeMap<u64, (B256, B256)>,
+    /// Inclusion proofs of the blocks of committed batches, by block number
+    proofs: BTreeMap<u64, BlockInclusionProof>,
+}
+
+/// Block content hashes of the current batch and inclusion proofs of the committed batches.
+///
+/// Batches are aligned on block numbers: the batch of block `n` ends at the first multiple of the
+/// batch size from `n`, whose block proof transaction commits the root of the batch. With a
+/// batch size of 1, each block proof commits the block content hash itself.
+///
+/// Builds are matched against the canonical chain, see [`track_canonical_blocks`], so that a
+/// batch only commits the blocks of this builder that were included, and proofs are only served
+/// for included blocks.
+#[derive(Debug, Clone)]
+pub struct BlockProofStore {
+    batch_size: u64,
+    inner: Arc<Mutex<BlockProofs>>,
+}
+
+impl Default for BlockProofStore {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl BlockProofStore {
+    pub fn new(batch_size: u64) -> Self {
+        Self {
+            batch_size: batch_size.max(1),
+            inner: Default::default(),
+        }
+    }
+
+    pub fn batch_size(&self) -> u64 {
+        self.batch_size
+    }
+
+    /// Returns whether the block proof of the batch is added in this block.
+    pub fn is_batch_end(&self, block_number: u64) -> bool {
+        block_number.is_multiple_of(self.batch_size)
+    }
+
+    fn batch_start(&self, block_number: u64) -> u64 {
+        block_number.saturating_sub((block_number + self.batch_size - 1) % self.batch_size)
+    }
+
+    /// Records the content hash of a build of a block, replacing the previous build of the block
+    /// on the same parent.
+    pub fn record_block(
+        &self,
+        block_number: u64,
+        parent_hash: B256,
+        block_content_hash: B256,
+        transaction_count: usize,
+    ) {
+        let start = self.batch_start(block_number);
+        let mut inner = self.inner.lock();
+        // builds of previous batches are not committed anymore
+        inner.builds.retain(|number, _| *number >= start);
+        inner.builds.entry(block_number).or_default().insert(
+            parent_hash,
+            BlockBuild {
+                block_content_hash,
+                transaction_count,
+            },
+        );
+    }
+
+    /// Records a block of the canonical chain, which includes the build of this builder with the
+    /// same parent if the content hash of its first transactions matches.
+    pub fn on_canonical_block(
+        &self,
+        block_number: u64,
+        block_hash: B256,
+        parent_hash: B256,
+        timestamp: u64,
+        transactions: &[OpTransactionSigned],
+    ) {
+        let mut inner = self.inner.lock();
+        // the blocks of a reorged chain are replaced
+        inner.included.split_off(&block_number);
+        let build = inner
+            .builds
+            .remove(&block_number)
+            .and_then(|mut builds| builds.remove(&parent_hash));
+        if let Some(build) = build
+            && let Some(transactions) = transactions.get(..build.transaction_count)
+            && compute_block_content_hash(transactions, parent_hash, block_number, timestamp)
+                == build.block_content_hash
+        {
+            inner
+                .included
+                .insert(block_number, (block_hash, build.block_content_hash));
+        }
+        inner
+            .included
+            .retain(|number, _| *number + BLOCK_PROOFS_RETENTION > block_number);
+    }
+
+    /// Computes the root of the batch ending at the block built on `parent_hash` and the
+    /// inclusion proofs of its blocks.
+    ///
+    /// The batch is made of the blocks of this builder included in the canonical chain, of the
+    /// parent block if its inclusion is not processed yet, and of the block itself.
+    pub fn seal_batch(&self, block_number: u64, parent_hash: B256, grandparent_hash: B256) -> B256 {
+        let start = self.batch_start(block_number);
+        let mut inner = self.inner.lock();
+        let parent_number = block_number.saturating_sub(1);
+        let mut batch: Vec<(u64, B256)> = inner
+            .included
+            .range(start..parent_number.max(start))
+            .map(|(number, (_, hash))| (*number, *hash))
+            .collect();
+        if block_number > start {
+            let parent = match inner.included.get(&parent_number) {
+                Some((hash, content_hash)) if *hash == parent_hash => Some(*content_hash),
+                _ => inner
+                    .builds
+                    .get(&parent_number)
+                    .and_then(|builds| builds.get(&grandparent_hash))
+                    .map(|build| build.block_content_hash),
+            };
+            batch.extend(parent.map(|hash| (parent_number, hash)));
+        }
+        batch.extend(
+            inner
+                .builds
+                .get(&block_number)
+                .and_then(|builds| builds.get(&parent_hash))
+                .map(|build| (block_number, build.block_content_hash)),
+        );
+        let leaves: Vec<B256> = batch.iter().map(|(_, hash)| *hash).collect();
+        let root = merkle_root(&leaves);
+
+        // a rebuild of the block replaces the proofs of the previous build
+        inner
+            .proofs
+            .retain(|number, _| *number < start && *number + BLOCK_PROOFS_RETENTION > block_number);
+        for (index, (number, hash)) in batch.into_iter().enumerate() {
+            inner.proofs.insert(
+                number,
+                BlockInclusionProof {
+                    block_number: U64::from(number),
+                    block_content_hash: hash,
+                    proof_block_number: U64::from(block_number),
+                    root,
+                    proof: merkle_proof(&leaves, index),
+                },
+            );
+        }
+        root
+    }
+
+    /// Returns the inclusion proof of a block of a committed batch, once the block and the block
+    /// proof are included in the canonical chain.
+    pub fn proof(&self, block_number: u64) -> Option<BlockInclusionProof> {
+        let inner = self.inner.lock();
+        let proof = inner.proofs.get(&block_number)?;
+        let is_included = |number: u64, content_hash: B256| {
+            inner
+                .included
+                .get(&number)
+                .is_some_and(|(_, hash)| *hash == content_hash)
+        };
+        let proof_block = inner.proofs.get(&proof.proof_block_number.to())?;
+        (is_included(block_number, proof.block_content_hash)
+            && is_included(
+                proof_block.block_number.to(),
+                proof_block.block_content_hash,
+            )
+            && proof_block.root == proof.root)
+            .then(|| proof.clone())
+    }
+}
+
+/// Records the blocks of the canonical chain in the store, see
+/// [`BlockProofStore::on_canonical_block`].
+pub async fn track_canonical_blocks(
+    store: BlockProofStore,
+    mut canonical_stream: CanonStateNotificationStream<OpPrimitives>,
+) {
+    while let Some(notification) = canonical_stream.next().await {
+        for block in notification.committed().blocks_iter() {
+            let header = block.header();
+            store.on_canonical_block(
+                header.number,
+                block.hash(),
+                header.parent_hash,
+                header.timestamp,
+                &block.body().transactions,
+            );
+        }
+    }
+}
+
+#[cfg_attr(not(test), rpc(server, namespace = "base"))]
+#[cfg_attr(test, rpc(server, client, namespace = "base"))]
+pub trait BlockProofApi {
+    #[method(name = "getBlockProof")]
+    async fn get_block_proof(&self, block_number: U64) -> RpcResult<Option<BlockInclusionProof>>;
+}
+
+pub struct BlockProofExt {
+    store: BlockProofStore,
+}
+
+impl BlockProofExt {
+    pub fn new(store: BlockProofStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl BlockProofApiServer for BlockProofExt {
+    async fn get_block_proof(&self, block_number: U64) -> RpcResult<Option<BlockInclusionProof>> {
+        Ok(self.store.proof(block_number.to()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(n: u8) -> Vec<B256> {
+        (0..n).map(|i| B256::repeat_byte(i + 1)).collect()
+    }
+
+    #[test]
+    fn test_merkle_proofs() {
+        assert_eq!(merkle_root(&[]), B256::ZERO);
+        let leaf = B256::repeat_byte(1);
+        assert_eq!(merkle_root(&[leaf]), leaf);
+        assert!(merkle_proof(&[leaf], 0).is_empty());
+
+        for n in 2..=9 {
+            let leaves = leaves(n);
+            let root = merkle_root(&leaves);
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = merkle_proof(&leaves, index);
+                assert!(verify_merkle_proof(*leaf, &proof, root));
+                assert!(!verify_merkle_proof(B256::ZERO, &proof, root));
+            }
+        }
+
+        let leaves = leaves(2);
+        assert_eq!(merkle_root(&leaves), hash_pair(leaves[1], leaves[0]));
+    }
+
+    fn block_hash(number: u64) -> B256 {
+        B256::with_last_byte(number as u8)
+    }
+
+    /// Records a build of the block without transactions, returns its content hash.
+    fn build(store: &BlockProofStore, number: u64) -> B256 {
+        let hash = compute_block_content_hash(&[], block_hash(number - 1), number, number);
+        store.record_block(number, block_hash(number - 1), hash, 0);
+        hash
+    }
+
+    /// Includes a block without transactions in the canonical chain.
+    fn include(store: &BlockProofStore, number: u64) {
+        store.on_canonical_block(
+            number,
+            block_hash(number),
+            block_hash(number - 1),
+            number,
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_batches() {
+        let store = BlockProofStore::new(4);
+        assert!(!store.is_batch_end(5));
+        assert!(store.is_batch_end(8));
+
+        // blocks 5 to 8, with block 6 rebuilt
+        let mut hashes = vec![build(&store, 5)];
+        include(&store, 5);
+        store.record_block(6, block_hash(5), B256::ZERO, 0);
+        hashes.push(build(&store, 6));
+        include(&store, 6);
+        hashes.push(build(&store, 7));
+        // the inclusion of the parent is not processed before the batch is sealed
+        hashes.push(build(&store, 8));
+
+        let root = store.seal_batch(8, block_hash(7), block_hash(6));
+        assert_eq!(root, merkle_root(&hashes));
+        // proofs are served once the block proof is included
+        assert_eq!(store.proof(5), None);
+        include(&store, 7);
+        include(&store, 8);
+        for (number, hash) in (5..=8).zip(hashes) {
+            let proof = store.proof(number).unwrap();
+            assert_eq!(proof.block_content_hash, hash);
+            assert_eq!(proof.proof_block_number, U64::from(8));
+            assert_eq!(proof.root, root);
+            assert!(proof.verify());
+        }
+
+        // the next batch only has the blocks of this builder that were included
+        let first = build(&store, 9);
+        include(&store, 9);
+        store.record_block(10, block_hash(9), B256::ZERO, 0);
+        include(&store, 10);
+        let last = build(&store, 12);
+        assert_eq!(
+            store.seal_batch(12, block_hash(11), block_hash(10)),
+            merkle_root(&[first, last])
+        );
+        include(&store, 11);
+        include(&store, 12);
+        assert!(store.proof(9).unwrap().verify());
+        assert_eq!(store.proof(10), None);
+        assert!(store.proof(5).unwrap().verify());
+    }
+
+    #[test]
+    fn test_single_block_batches() {
+        let store = BlockProofStore::default();
+        assert!(store.is_batch_end(1));
+        let hash = build(&store, 1);
+        // the block proof commits the block content hash itself
+        assert_eq!(store.seal_batch(1, block_hash(0), B256::ZERO), hash);
+        assert_eq!(store.proof(1), None);
+        include(&store, 1);
+        assert!(store.proof(1).unwrap().proof.is_empty());
+
+        // a block of another builder replaces the block after a reorg
+        store.on_canonical_block(1, B256::repeat_byte(0xff), block_hash(0), 1, &[]);
+        assert_eq!(store.proof(1), None);
+    }
+}
@@ -6,12 +6,16 @@ use alloy_rpc_types_eth::TransactionInput;
 use alloy_sol_types::{SolCall, SolEvent, SolValue};
 use core::fmt::Debug;
 use op_alloy_rpc_types::OpTransactionRequest;
+use parking_lot::RwLock;
 use reth_evm::{ConfigureEvm, Evm, precompiles::PrecompilesMap};
 use reth_optimism_primitives::OpTransactionSigned;
 use reth_provider::StateProvider;
 use reth_revm::{State, database::StateProviderDatabase};
 use revm::{DatabaseCommit, DatabaseRef, inspector::NoOpInspector};
-use std::sync::{Arc, atomic::AtomicBool};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
 use tracing::{debug, info, warn};
 
 use crate::{
@@ -24,6 +28,7 @@ use crate::{
         IBlockBuilderPolicy::{self, BlockBuilderProofVerified},
         IERC20Permit,
         IFlashtestationRegistry::{self, TEEServiceRegistered},
+        block_proofs::BlockProofStore,
     },
     primitives::reth::ExecutionInfo,
     tx_signer::Signer,
@@ -37,32 +42,120 @@ pub struct FlashtestationsBuilderTxArgs {
     pub builder_policy_address: Address,
     pub builder_proof_version: u8,
     pub enable_block_proofs: bool,
+    pub block_proofs: BlockProofStore,
     pub registered: bool,
     pub builder_key: Signer,
 }
 
+/// Attestation and key of the TEE service, replaced as a whole on re-attestation so that a block
+/// never mixes the key of one attestation with the quote of another.
+#[derive(Debug)]
+pub struct TeeRegistration {
+    // Attestation for the builder
+    pub attestation: Vec<u8>,
+    // Extra registration data for the builder
+    pub extra_registration_data: Bytes,
+    // TEE service generated key
+    pub tee_service_signer: Signer,
+    // Whether the workload and address has been registered
+    registered: AtomicBool,
+}
+
+impl TeeRegistration {
+    pub fn new(
+        attestation: Vec<u8>,
+        extra_registration_data: Bytes,
+        tee_service_signer: Signer,
+        registered: bool,
+    ) -> Self {
+        Self {
+            attestation,
+            extra_registration_data,
+            tee_service_signer,
+            registered: AtomicBool::new(registered),
+        }
+    }
+
+    pub fn is_registered(&self) -> bool {
+        self.registered.load(Ordering::SeqCst)
+    }
+
+    pub fn set_registered(&self, registered: bool) {
+        self.registered.store(registered, Ordering::SeqCst);
+    }
+}
+
+/// Shared handle on the current [`TeeRegistration`], used by the re-attestation service to swap
+/// in a new attestation or key.
+#[derive(Debug, Clone)]
+pub struct TeeRegistrationHandle(Arc<RwLock<Arc<TeeRegistration>>>);
+
+impl TeeRegistrationHandle {
+    pub fn new(registration: TeeRegistration) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(registration))))
+    }
+
+    /// Returns the current registration.
+    pub fn current(&self) -> Arc<TeeRegistration> {
+        self.0.read().clone()
+    }
+
+    /// Replaces the registration, blocks being built keep the registration they started with.
+    pub fn replace(&self, registration: TeeRegistration) {
+        *self.0.write() = Arc::new(registration);
+    }
+}
+
+/// Computes the block content hash according to the formula:
+/// keccak256(abi.encode(parentHash, blockNumber, timestamp, transactionHashes))
+/// https://github.com/flashbots/rollup-boost/blob/main/specs/flashtestations.md#block-building-process
+pub fn compute_block_content_hash(
+    transactions: &[OpTransactionSigned],
+    parent_hash: B256,
+    block_number: u64,
+    timestamp: u64,
+) -> B256 {
+    // Create ordered list of transaction hashes
+    let transaction_hashes: Vec<B256> = transactions
+        .iter()
+        .map(|tx| {
+            // RLP encode the transaction and hash it
+            let mut encoded = Vec::new();
+            tx.encode_2718(&mut encoded);
+            keccak256(&encoded)
+        })
+        .collect();
+
+    // Create struct and ABI encode
+    let block_data = BlockData {
+        parentHash: parent_hash,
+        blockNumber: U256::from(block_number),
+        timestamp: U256::from(timestamp),
+        transactionHashes: transaction_hashes,
+    };
+
+    let encoded = block_data.abi_encode();
+    keccak256(&encoded)
+}
+
 #[derive(Debug, Clone)]
 pub struct FlashtestationsBuilderTx<ExtraCtx = (), Extra = ()>
 where
     ExtraCtx: Debug + Default,
     Extra: Debug + Default,
 {
-    // Attestation for the builder
-    attestation: Vec<u8>,
-    // Extra registration data for the builder
-    extra_registration_data: Bytes,
-    // TEE service generated key
-    tee_service_signer: Signer,
+    // Attestation and key of the TEE service
+    registration: TeeRegistrationHandle,
     // Registry address for the attestation
     registry_address: Address,
     // Builder policy address for the block builder proof
     builder_policy_address: Address,
     // Builder proof version
     builder_proof_version: u8,
-    // Whether the workload and address has been registered
-    registered: Arc<AtomicBool>,
     // Whether block proofs are enabled
     enable_block_proofs: bool,
+    // Block content hashes committed by the block proofs
+    block_proofs: BlockProofStore,
     // Builder key for the flashtestation permit tx
     builder_signer: Signer,
     // Extra context and data
@@ -76,57 +169,33 @@ where
 {
     pub fn new(args: FlashtestationsBuilderTxArgs) -> Self {
         Self {
-            attestation: args.attestation,
-            extra_registration_data: args.extra_registration_data,
-            tee_service_signer: args.tee_service_signer,
+            registration: TeeRegistrationHandle::new(TeeRegistration::new(
+                args.attestation,
+                args.extra_registration_data,
+                args.tee_service_signer,
+                args.registered,
+            )),
             registry_address: args.registry_address,
             builder_policy_address: args.builder_policy_address,
             builder_proof_version: args.builder_proof_version,
-            registered: Arc::new(AtomicBool::new(args.registered)),
             enable_block_proofs: args.enable_block_proofs,
+            block_proofs: args.block_proofs,
             builder_signer: args.builder_key,
             _marker: std::marker::PhantomData,
         }
     }
 
-    pub fn tee_signer(&self) -> &Signer {
-        &self.tee_service_signer
+    pub fn tee_signer(&self) -> Signer {
+        self.registration.current().tee_service_signer
     }
 
-    /// Computes the block content hash according to the formula:
-    /// keccak256(abi.encode(parentHash, blockNumber, timestamp, transactionHashes))
-    /// https://github.com/flashbots/rollup-boost/blob/main/specs/flashtestations.md#block-building-process
-    fn compute_block_content_hash(
-        transactions: &[OpTransactionSigned],
-        parent_hash: B256,
-        block_number: u64,
-        timestamp: u64,
-    ) -> B256 {
-        // Create ordered list of transaction hashes
-        let transaction_hashes: Vec<B256> = transactions
-            .iter()
-            .map(|tx| {
-                // RLP encode the transaction and hash it
-                let mut encoded = Vec::new();
-                tx.encode_2718(&mut encoded);
-                keccak256(&encoded)
-            })
-            .collect();
-
-        // Create struct and ABI encode
-        let block_data = BlockData {
-            parentHash: parent_hash,
-            blockNumber: U256::from(block_number),
-            timestamp: U256::from(timestamp),
-            transactionHashes: transaction_hashes,
-        };
-
-        let encoded = block_data.abi_encode();
-        keccak256(&encoded)
+    pub fn registration_handle(&self) -> TeeRegistrationHandle {
+        self.registration.clone()
     }
 
     fn set_registered(
         &self,
+        registration: &TeeRegistration,
         state_provider: impl StateProvider + Clone,
         ctx: &OpPayloadBuilderCtx<ExtraCtx>,
     ) -> Result<(), BuilderTransactionError> {
@@ -143,25 +212,25 @@ where
             cfg.disable_nonce_check = true;
         });
         let calldata = IFlashtestationRegistry::getRegistrationStatusCall {
-            teeAddress: self.tee_service_signer.address,
+            teeAddress: registration.tee_service_signer.address,
         };
         let SimulationSuccessResult { output, .. } =
             self.flashtestations_contract_read(self.registry_address, calldata, ctx, &mut evm)?;
         if output.isValid {
-            self.registered
-                .store(true, std::sync::atomic::Ordering::SeqCst);
+            registration.set_registered(true);
         }
         Ok(())
     }
 
     fn get_permit_nonce(
         &self,
+        registration: &TeeRegistration,
         contract_address: Address,
         ctx: &OpPayloadBuilderCtx<ExtraCtx>,
         evm: &mut OpEvm<impl Database + DatabaseRef, NoOpInspector, PrecompilesMap>,
     ) -> Result<U256, BuilderTransactionError> {
         let calldata = IERC20Permit::noncesCall {
-            owner: self.tee_service_signer.address,
+            owner: registration.tee_service_signer.address,
         };
         let SimulationSuccessResult { output, .. } =
             self.flashtestations_contract_read(contract_address, calldata, ctx, evm)?;
@@ -170,13 +239,14 @@ where
 
     fn registration_permit_signature(
         &self,
+        registration: &TeeRegistration,
         permit_nonce: U256,
         ctx: &OpPayloadBuilderCtx<ExtraCtx>,
         evm: &mut OpEvm<impl Database + DatabaseRef, NoOpInspector, PrecompilesMap>,
     ) -> Result<Signature, BuilderTransactionError> {
         let struct_hash_calldata = IFlashtestationRegistry::computeStructHashCall {
-            rawQuote: self.attestation.clone().into(),
-            extendedRegistrationData: self.extra_registration_data.clone(),
+            rawQuote: registration.attestation.clone().into(),
+            extendedRegistrationData: registration.extra_registration_data.clone(),
             nonce: permit_nonce,
             deadline: U256::from(ctx.timestamp()),
         };
@@ -194,20 +264,21 @@ where
             ctx,
             evm,
         )?;
-        let signature = self.tee_service_signer.sign_message(output)?;
+        let signature = registration.tee_service_signer.sign_message(output)?;
         Ok(signature)
     }
 
     fn signed_registration_permit_tx(
         &self,
+        registration: &TeeRegistration,
         ctx: &OpPayloadBuilderCtx<ExtraCtx>,
         evm: &mut OpEvm<&mut State<impl Database + DatabaseRef>, NoOpInspector, PrecompilesMap>,
     ) -> Result<BuilderTransactionCtx, BuilderTransactionError> {
-        let permit_nonce = self.get_permit_nonce(self.registry_address, ctx, evm)?;
-        let signature = self.registration_permit_signature(permit_nonce, ctx, evm)?;
+        let permit_nonce = self.get_permit_nonce(registration, self.registry_address, ctx, evm)?;
+        let signature = self.registration_permit_signature(registration, permit_nonce, ctx, evm)?;
         let calldata = IFlashtestationRegistry::permitRegisterTEEServiceCall {
-            rawQuote: self.attestation.clone().into(),
-            extendedRegistrationData: self.extra_registration_data.clone(),
+            rawQuote: registration.attestation.clone().into(),
+            extendedRegistrationData: registration.extra_registration_data.clone(),
             nonce: permit_nonce,
             deadline: U256::from(ctx.timestamp()),
             signature: signature.as_bytes().into(),
@@ -245,6 +316,7 @@ where
 
     fn block_proof_permit_signature(
         &self,
+        registration: &TeeRegistration,
         permit_nonce: U256,
         block_content_hash: B256,
         ctx: &OpPayloadBuilderCtx<ExtraCtx>,
@@ -269,25 +341,26 @@ where
             ctx,
             evm,
         )?;
-        let signature = self.tee_service_signer.sign_message(output)?;
+        let signature = registration.tee_service_signer.sign_message(output)?;
         Ok(signature)
     }
 
     fn signed_block_proof_permit_tx(
         &self,
-        transactions: &[OpTransactionSigned],
+        registration: &TeeRegistration,
+        block_content_hash: B256,
         ctx: &OpPayloadBuilderCtx<ExtraCtx>,
         evm: &mut OpEvm<impl Database + DatabaseRef, NoOpInspector, PrecompilesMap>,
     ) -> Result<BuilderTransactionCtx, BuilderTransactionError> {
-        let permit_nonce = self.get_permit_nonce(self.builder_policy_address, ctx, evm)?;
-        let block_content_hash = Self::compute_block_content_hash(
-            transactions,
-            ctx.parent_hash(),
-            ctx.block_number(),
-            ctx.timestamp(),
-        );
-        let signature =
-            self.block_proof_permit_signature(permit_nonce, block_content_hash, ctx, evm)?;
+        let permit_nonce =
+            self.get_permit_nonce(registration, self.builder_policy_address, ctx, evm)?;
+        let signature = self.block_proof_permit_signature(
+            registration,
+            permit_nonce,
+            block_content_hash,
+            ctx,
+            evm,
+        )?;
         let calldata = IBlockBuilderPolicy::permitVerifyBlockBuilderProofCall {
             blockContentHash: block_content_hash,
             nonce: permit_nonce,
@@ -378,9 +451,12 @@ where
         db: &mut State<impl Database + DatabaseRef>,
         _top_of_block: bool,
     ) -> Result<Vec<BuilderTransactionCtx>, BuilderTransactionError> {
+        // the whole block uses the same registration, even if it is replaced meanwhile
+        let registration = self.registration.current();
+
         // set registered by simulating against the committed state
-        if !self.registered.load(std::sync::atomic::Ordering::SeqCst) {
-            self.set_registered(state_provider, ctx)?;
+        if !registration.is_registered() {
+            self.set_registered(&registration, state_provider, ctx)?;
         }
 
         let mut evm = ctx.evm_config.evm_with_env(&mut *db, ctx.evm_env.clone());
@@ -391,16 +467,37 @@ where
 
         let mut builder_txs = Vec::<BuilderTransactionCtx>::new();
 
-        if !self.registered.load(std::sync::atomic::Ordering::SeqCst) {
+        if !registration.is_registered() {
             info!(target: "flashtestations", "tee service not registered yet, attempting to register");
-            let register_tx = self.signed_registration_permit_tx(ctx, &mut evm)?;
+            let register_tx = self.signed_registration_permit_tx(&registration, ctx, &mut evm)?;
             builder_txs.push(register_tx);
         }
 
         // don't return on error for block proof as previous txs in builder_txs will not be returned
         if self.enable_block_proofs {
-            debug!(target: "flashtestations", "adding permit verify block proof tx");
-            match self.signed_block_proof_permit_tx(&info.executed_transactions, ctx, &mut evm) {
+            let block_content_hash = compute_block_content_hash(
+                &info.executed_transactions,
+                ctx.parent_hash(),
+                ctx.block_number(),
+                ctx.timestamp(),
+            );
+            self.block_proofs.record_block(
+                ctx.block_number(),
+                ctx.parent_hash(),
+                block_content_hash,
+                info.executed_transactions.len(),
+            );
+        }
+        if self.enable_block_proofs && self.block_proofs.is_batch_end(ctx.block_number()) {
+            // the proof commits the root of the content hashes of the batch, which is the block
+            // content hash itself for batches of a single block
+            let batch_root = self.block_proofs.seal_batch(
+                ctx.block_number(),
+                ctx.parent_hash(),
+                ctx.parent().parent_hash,
+            );
+            debug!(target: "flashtestations", %batch_root, "adding permit verify block proof tx");
+            match self.signed_block_proof_permit_tx(&registration, batch_root, ctx, &mut evm) {
                 Ok(block_proof_tx) => builder_txs.push(block_proof_tx),
                 Err(e) => {
                     warn!(target: "flashtestations", error = ?e, "failed to add permit block proof transaction")
@@ -410,3 +507,37 @@ where
         Ok(builder_txs)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx_signer::generate_signer;
+
+    #[test]
+    fn test_registration_handle_replace() {
+        let handle = TeeRegistrationHandle::new(TeeRegistration::new(
+            vec![1],
+            Bytes::new(),
+            generate_signer(),
+            true,
+        ));
+        let snapshot = handle.current();
+
+        let rotated = generate_signer();
+        handle.replace(TeeRegistration::new(vec![2], Bytes::new(), rotated, false));
+
+        // a block being built keeps its registration
+        assert_eq!(snapshot.attestation, vec![1]);
+        assert!(snapshot.is_registered());
+
+        let current = handle.current();
+        assert_eq!(current.attestation, vec![2]);
+        assert_eq!(current.tee_service_signer, rotated);
+        assert!(!current.is_registered());
+
+        // the registered flag of the replaced registration doesn't leak into the new one
+        snapshot.set_registered(false);
+        current.set_registered(true);
+        assert!(handle.current().is_registered());
+    }
+}
@@ -0,0 +1,139 @@
+use alloy_primitives::{Address, B256, Bytes, Signature, SignatureError, keccak256};
+use alloy_rpc_types_engine::PayloadId;
+use alloy_sol_types::SolValue;
+use rollup_boost::FlashblocksPayloadV1;
+use serde::{Deserialize, Serialize};
+
+use super::FlashblockData;
+use crate::tx_signer::Signer;
+
+/// Key of the TEE signature in the metadata of a flashblock.
+pub const TEE_SIGNATURE_METADATA_KEY: &str = "tee_signature";
+
+#[derive(Debug, thiserror::Error)]
+pub enum FlashblockSignatureError {
+    #[error("flashblock has no TEE signature")]
+    MissingSignature,
+    #[error("invalid TEE signature metadata: {0}")]
+    InvalidMetadata(#[from] serde_json::Error),
+    #[error("invalid TEE signature: {0}")]
+    InvalidSignature(#[from] SignatureError),
+    #[error("flashblock signed by {recovered}, expected the TEE address {expected}")]
+    UnexpectedSigner {
+        expected: Address,
+        recovered: Address,
+    },
+}
+
+/// TEE signature of a flashblock, carried in its metadata.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlashblockSignature {
+    /// Block content hash of the transactions up to and including this flashblock
+    pub block_content_hash: B256,
+    /// Signature of the TEE key over the flashblock signing hash
+    pub signature: Bytes,
+}
+
+/// Computes the hash signed by the TEE key for a flashblock:
+/// keccak256(abi.encode(payloadId, index, blockContentHash))
+pub fn flashblock_signing_hash(
+    payload_id: PayloadId,
+    index: u64,
+    block_content_hash: B256,
+) -> B256 {
+    let data = FlashblockData {
+        payloadId: payload_id.0,
+        index,
+        blockContentHash: block_content_hash,
+    };
+    keccak256(data.abi_encode())
+}
+
+/// Signs the flashblock with the TEE key, over the content hash of the block so far. Must be
+/// called once the index of the flashblock is set.
+pub fn sign_flashblock(
+    payload: &mut FlashblocksPayloadV1,
+    block_content_hash: B256,
+    tee_signer: &Signer,
+) -> Result<(), secp256k1::Error> {
+    let hash = flashblock_signing_hash(payload.payload_id, payload.index, block_content_hash);
+    let signature = tee_signer.sign_message(hash)?;
+    let signature = FlashblockSignature {
+        block_content_hash,
+        signature: signature.as_bytes().into(),
+    };
+    payload.metadata[TEE_SIGNATURE_METADATA_KEY] =
+        serde_json::to_value(signature).expect("flashblock signature is serializable");
+    Ok(())
+}
+
+/// Checks that the flashblock is signed by the given TEE address, usually the registered TEE
+/// address of the builder. Returns the signed block content hash, which consumers tracking the
+/// transactions of the block can compare to their own.
+pub fn verify_flashblock_signature(
+    payload: &FlashblocksPayloadV1,
+    tee_address: Address,
+) -> Result<B256, FlashblockSignatureError> {
+    let signature = payload
+        .metadata
+        .get(TEE_SIGNATURE_METADATA_KEY)
+        .ok_or(FlashblockSignatureError::MissingSignature)?;
+    let FlashblockSignature {
+        block_content_hash,
+        signature,
+    } = serde_json::from_value(signature.clone())?;
+
+    let hash = flashblock_signing_hash(payload.payload_id, payload.index, block_content_hash);
+    let recovered = Signature::try_from(signature.as_ref())?.recover_address_from_prehash(&hash)?;
+    if recovered != tee_address {
+        return Err(FlashblockSignatureError::UnexpectedSigner {
+            expected: tee_address,
+            recovered,
+        });
+    }
+    Ok(block_content_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx_signer::generate_signer;
+
+    fn payload(index: u64) -> FlashblocksPayloadV1 {
+        FlashblocksPayloadV1 {
+            payload_id: PayloadId::new([1; 8]),
+            index,
+            metadata: serde_json::json!({ "block_number": 1 }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_flashblock_signature() {
+        let tee_signer = generate_signer();
+        let block_content_hash = B256::repeat_byte(1);
+
+        let mut fb = payload(2);
+        sign_flashblock(&mut fb, block_content_hash, &tee_signer).unwrap();
+        assert_eq!(fb.metadata["block_number"], 1);
+        assert_eq!(
+            verify_flashblock_signature(&fb, tee_signer.address).unwrap(),
+            block_content_hash
+        );
+
+        // signed by another key
+        assert!(matches!(
+            verify_flashblock_signature(&fb, generate_signer().address),
+            Err(FlashblockSignatureError::UnexpectedSigner { .. })
+        ));
+
+        // the signature doesn't hold for another index
+        fb.index = 3;
+        assert!(verify_flashblock_signature(&fb, tee_signer.address).is_err());
+
+        assert!(matches!(
+            verify_flashblock_signature(&payload(0), tee_signer.address),
+            Err(FlashblockSignatureError::MissingSignature)
+        ));
+    }
+}
@@ -77,6 +77,8 @@ sol!(
 
         function getHashedTypeDataV4(bytes32 structHash) external view returns (bytes32);
 
+        function isAllowedPolicy(address teeAddress) external view returns (bool allowed, bytes32 workloadId);
+
         /// @notice Emitted when a block builder proof is successfully verified
         /// @param caller The address that called the verification function (TEE address)
         /// @param workloadId The workload identifier of the TEE
@@ -114,11 +116,31 @@ sol!(
         bytes32[] transactionHashes;
     }
 
+    /// Flashblock signed by the TEE key of the builder
+    struct FlashblockData {
+        bytes8 payloadId;
+        uint64 index;
+        bytes32 blockContentHash;
+    }
+
+    /// Extended registration data of the builder, hashed into the report data of its quote
+    struct BuilderRegistrationData {
+        string peerId;
+        string websocketUrl;
+        string version;
+        string commitHash;
+    }
+
     type WorkloadId is bytes32;
 );
 
 pub mod args;
 pub mod attestation;
+pub mod block_proofs;
 pub mod builder_tx;
+pub mod flashblock_signature;
+pub mod report_data;
 pub mod service;
+pub mod status;
 pub mod tx_manager;
+pub mod verifier;
@@ -0,0 +1,158 @@
+//! Report data of the quotes registered with the flashtestations registry.
+//!
+//! The layout of the report data is the one checked by `FlashtestationRegistry` at the commit
+//! the bindings of [`super`] are generated from: the TEE address in `reportData[0:20]` and the
+//! keccak256 hash of the `extendedRegistrationData` passed to `registerTEEService` in
+//! `reportData[20:52]`. The registry leaves the content of the extended registration data to
+//! the application, the builder registers the ABI encoding of [`BuilderRegistrationData`].
+
+use alloy_primitives::{Address, Bytes, keccak256};
+use alloy_sol_types::SolValue;
+
+use super::{BuilderRegistrationData, args::FlashtestationsArgs};
+use crate::metrics::{CARGO_PKG_VERSION, VERGEN_GIT_SHA};
+
+/// Length of the report data of a TDX quote.
+pub const REPORT_DATA_LENGTH: usize = 64;
+
+/// Extended registration data of the builder, registered with its quote.
+///
+/// The registry checks that the keccak256 hash of the extended registration data is in the
+/// report data of the quote, so the data is bound to the TEE.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtendedRegistrationData {
+    /// libp2p peer ID of the builder
+    pub peer_id: Option<String>,
+    /// Flashblocks WebSocket URL of the builder
+    pub websocket_url: Option<String>,
+    /// Version of the builder
+    pub version: Option<String>,
+    /// Commit the builder was built from
+    pub commit_hash: Option<String>,
+}
+
+impl ExtendedRegistrationData {
+    /// Builds the registration data enabled in `args`, with the peer ID of the flashblocks p2p
+    /// node if it runs.
+    pub fn from_args(args: &FlashtestationsArgs, p2p_peer_id: Option<p2p::PeerId>) -> Self {
+        Self {
+            peer_id: p2p_peer_id
+                .filter(|_| args.registration_peer_id)
+                .map(|peer_id| peer_id.to_string()),
+            websocket_url: args.registration_websocket_url.clone(),
+            version: args
+                .registration_version
+                .then(|| CARGO_PKG_VERSION.to_string()),
+            commit_hash: args
+                .registration_version
+                .then(|| VERGEN_GIT_SHA.to_string()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Encodes the data as an ABI encoded `BuilderRegistrationData`, with unset fields as empty
+    /// strings. Empty data is encoded as empty bytes, as registered by builders without extended
+    /// registration data.
+    pub fn encode(&self) -> Bytes {
+        if self.is_empty() {
+            return Bytes::new();
+        }
+
+        BuilderRegistrationData {
+            peerId: self.peer_id.clone().unwrap_or_default(),
+            websocketUrl: self.websocket_url.clone().unwrap_or_default(),
+            version: self.version.clone().unwrap_or_default(),
+            commitHash: self.commit_hash.clone().unwrap_or_default(),
+        }
+        .abi_encode()
+        .into()
+    }
+}
+
+/// Builds the report data of a quote:
+/// - TEE address (20 bytes) at reportData[0:20]
+/// - Extended registration data hash (32 bytes) at reportData[20:52]
+/// - Total: 52 bytes, padded to 64 bytes with zeros
+pub fn build_report_data(
+    tee_address: Address,
+    extended_registration_data: &Bytes,
+) -> [u8; REPORT_DATA_LENGTH] {
+    let mut report_data = [0u8; REPORT_DATA_LENGTH];
+    report_data[0..20].copy_from_slice(tee_address.as_slice());
+    report_data[20..52].copy_from_slice(keccak256(extended_registration_data).as_slice());
+    report_data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{address, b256};
+
+    #[test]
+    fn test_report_data_layout() {
+        let tee_address = address!("0x1111111111111111111111111111111111111111");
+        let report_data = build_report_data(tee_address, &Bytes::new());
+
+        assert_eq!(&report_data[0..20], tee_address.as_slice());
+        // keccak256 of empty bytes
+        assert_eq!(
+            &report_data[20..52],
+            b256!("0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470").as_slice()
+        );
+        assert_eq!(&report_data[52..], &[0u8; 12]);
+    }
+
+    #[test]
+    fn test_empty_registration_data() {
+        let data = ExtendedRegistrationData::default();
+        assert!(data.is_empty());
+        assert!(data.encode().is_empty());
+    }
+
+    #[test]
+    fn test_registration_data_encoding() {
+        let data = ExtendedRegistrationData {
+            peer_id: Some("12D3KooWAbc".to_string()),
+            websocket_url: Some("wss://builder.example/ws".to_string()),
+            version: None,
+            commit_hash: Some("abcdef12".to_string()),
+        };
+        let encoded = data.encode();
+
+        let decoded = BuilderRegistrationData::abi_decode(&encoded).unwrap();
+        assert_eq!(decoded.peerId, "12D3KooWAbc");
+        assert_eq!(decoded.websocketUrl, "wss://builder.example/ws");
+        assert_eq!(decoded.version, "");
+        assert_eq!(decoded.commitHash, "abcdef12");
+
+        let report_data = build_report_data(Address::ZERO, &encoded);
+        assert_eq!(&report_data[20..52], keccak256(&encoded).as_slice());
+    }
+
+    #[test]
+    fn test_registration_data_from_args() {
+        let peer_id = p2p::Keypair::generate_ed25519().public().to_peer_id();
+        let args = FlashtestationsArgs {
+            registration_peer_id: true,
+            registration_version: true,
+            ..Default::default()
+        };
+        let data = ExtendedRegistrationData::from_args(&args, Some(peer_id));
+        assert_eq!(data.peer_id, Some(peer_id.to_string()));
+        assert_eq!(data.websocket_url, None);
+        assert_eq!(data.version.as_deref(), Some(CARGO_PKG_VERSION));
+        assert_eq!(data.commit_hash.as_deref(), Some(VERGEN_GIT_SHA));
+
+        // without the p2p node, there is no peer ID to register
+        let data = ExtendedRegistrationData::from_args(&args, None);
+        assert_eq!(data.peer_id, None);
+
+        assert!(
+            ExtendedRegistrationData::from_args(&FlashtestationsArgs::default(), Some(peer_id))
+                .is_empty()
+        );
+    }
+}
@@ -1,19 +1,31 @@
-use alloy_primitives::{B256, Bytes, keccak256};
+use alloy_primitives::{Address, B256, Bytes};
+use reth::tasks::TaskExecutor;
 use std::{
     fs::{self, OpenOptions},
     io::Write,
     os::unix::fs::OpenOptionsExt,
     path::Path,
+    sync::Arc,
+    time::Duration,
 };
+use tokio::time::{Instant, Interval, MissedTickBehavior, interval_at};
 use tracing::{info, warn};
 
 use super::{
     args::FlashtestationsArgs,
-    attestation::{AttestationConfig, get_attestation_provider},
+    attestation::{AttestationConfig, AttestationProvider, get_attestation_provider},
+    block_proofs::BlockProofStore,
+    report_data::{ExtendedRegistrationData, build_report_data},
+    status::FlashtestationsStatusTracker,
     tx_manager::TxManager,
+    verifier::{QuoteCollateral, QuoteVerifier},
 };
 use crate::{
-    flashtestations::builder_tx::{FlashtestationsBuilderTx, FlashtestationsBuilderTxArgs},
+    flashtestations::builder_tx::{
+        FlashtestationsBuilderTx, FlashtestationsBuilderTxArgs, TeeRegistration,
+        TeeRegistrationHandle,
+    },
+    gas_limiter::AddressGasLimiter,
     metrics::record_tee_metrics,
     tx_signer::{Signer, generate_key_from_seed, generate_signer},
 };
@@ -22,6 +34,11 @@ use std::fmt::Debug;
 pub async fn bootstrap_flashtestations<ExtraCtx, Extra>(
     args: FlashtestationsArgs,
     builder_key: Signer,
+    block_proofs: BlockProofStore,
+    status: FlashtestationsStatusTracker,
+    gas_limiter: AddressGasLimiter,
+    p2p_peer_id: Option<p2p::PeerId>,
+    task_executor: &TaskExecutor,
 ) -> eyre::Result<FlashtestationsBuilderTx<ExtraCtx, Extra>>
 where
     ExtraCtx: Debug + Default,
@@ -37,6 +54,8 @@ where
         "Flashtestations TEE address: {}",
         tee_service_signer.address
     );
+    // the registration transactions of the TEE key go through the pool when sent to the rpc
+    gas_limiter.add_exempt(tee_service_signer.address);
 
     let registry_address = args
         .registry_address
@@ -45,75 +64,269 @@ where
         .builder_policy_address
         .expect("builder policy address required when flashtestations enabled");
 
+    // Load the collateral first, so that a bad path is reported before requesting a quote
+    let quote_verifier = args
+        .collateral_path
+        .as_ref()
+        .map(|path| QuoteCollateral::from_file(path).map(QuoteVerifier::new))
+        .transpose()?;
+
     let attestation_provider = get_attestation_provider(AttestationConfig {
-        debug: args.debug,
-        quote_provider: args.quote_provider,
+        provider: args.attestation_provider,
+        quote_provider: args.quote_provider.clone(),
+        quote_provider_retries: args.quote_provider_retries,
+        configfs_tsm_path: args.configfs_tsm_path.clone().into(),
+        quote_file: args.quote_file.clone().map(Into::into),
+    })?;
+
+    if args.registration_peer_id && p2p_peer_id.is_none() {
+        warn!(
+            target: "flashtestations",
+            "the peer ID is not registered, the flashblocks p2p node is disabled"
+        );
+    }
+    let extended_registration_data = ExtendedRegistrationData::from_args(&args, p2p_peer_id);
+    if !extended_registration_data.is_empty() {
+        info!(
+            target: "flashtestations",
+            ?extended_registration_data,
+            "registering extended registration data"
+        );
+    }
+
+    let attestor = Attestor {
+        attestation_provider,
+        quote_verifier,
+        builder_key,
+        extended_registration_data: extended_registration_data.encode(),
+        rpc_url: args.rpc_url.clone(),
+        registry_address,
+    };
+    let registration = attestor.attest(tee_service_signer).await?;
+
+    let flashtestations_builder_tx = FlashtestationsBuilderTx::new(FlashtestationsBuilderTxArgs {
+        attestation: registration.attestation,
+        extra_registration_data: registration.extra_registration_data,
+        tee_service_signer,
+        registry_address,
+        builder_policy_address,
+        builder_proof_version: args.builder_proof_version,
+        enable_block_proofs: args.enable_block_proofs,
+        block_proofs,
+        registered: registration.registered,
+        builder_key,
     });
+    status.set_registration(flashtestations_builder_tx.registration_handle());
 
-    // Prepare report data:
-    // - TEE address (20 bytes) at reportData[0:20]
-    // - Extended registration data hash (32 bytes) at reportData[20:52]
-    // - Total: 52 bytes, padded to 64 bytes with zeros
+    let service = ReattestationService {
+        attestor,
+        registration: flashtestations_builder_tx.registration_handle(),
+        status,
+        reattestation_interval: args.reattestation_interval_secs.map(Duration::from_secs),
+        status_check_interval: Duration::from_secs(args.status_check_interval_secs),
+        rotate_key: args.rotate_key,
+        key_path: (!args.debug).then(|| args.flashtestations_key_path.clone()),
+        gas_limiter,
+    };
+    task_executor.spawn(service.run());
 
-    // Extract TEE address as 20 bytes
-    let tee_address_bytes: [u8; 20] = tee_service_signer.address.into();
+    Ok(flashtestations_builder_tx)
+}
 
-    // Calculate keccak256 hash of empty bytes (32 bytes)
-    let ext_data = Bytes::from(b"");
-    let ext_data_hash = keccak256(&ext_data);
+/// Outcome of an attestation.
+struct Attestation {
+    attestation: Vec<u8>,
+    extra_registration_data: Bytes,
+    registered: bool,
+}
 
-    // Create 64-byte report data array
-    let mut report_data = [0u8; 64];
+/// Requests quotes for a TEE key and registers them.
+struct Attestor {
+    attestation_provider: Arc<dyn AttestationProvider>,
+    quote_verifier: Option<QuoteVerifier>,
+    builder_key: Signer,
+    /// Extended registration data, hashed into the report data of the quotes.
+    extended_registration_data: Bytes,
+    /// External rpc used to register the TEE service. Without it, the TEE service is registered
+    /// by the builder transactions.
+    rpc_url: Option<String>,
+    registry_address: Address,
+}
 
-    // Copy TEE address (20 bytes) to positions 0-19
-    report_data[0..20].copy_from_slice(&tee_address_bytes);
+impl Attestor {
+    async fn attest(&self, tee_service_signer: Signer) -> eyre::Result<Attestation> {
+        let ext_data = self.extended_registration_data.clone();
+        let report_data = build_report_data(tee_service_signer.address, &ext_data);
 
-    // Copy extended registration data hash (32 bytes) to positions 20-51
-    report_data[20..52].copy_from_slice(ext_data_hash.as_ref());
+        // Request TDX attestation
+        info!(target: "flashtestations", "requesting TDX attestation");
+        let attestation = self
+            .attestation_provider
+            .get_attestation(report_data)
+            .await?;
 
-    // Request TDX attestation
-    info!(target: "flashtestations", "requesting TDX attestation");
-    let attestation = attestation_provider.get_attestation(report_data).await?;
+        // Reject a corrupted quote before spending a registration transaction on it
+        if let Some(quote_verifier) = &self.quote_verifier {
+            quote_verifier.verify(&attestation)?;
+            info!(target: "flashtestations", "verified TDX quote against the local collateral");
+        }
 
-    // Record TEE metrics (workload ID, MRTD, RTMR0)
-    record_tee_metrics(&attestation, &tee_service_signer.address)?;
+        // Record TEE metrics (workload ID, MRTD, RTMR0)
+        record_tee_metrics(&attestation, &tee_service_signer.address)?;
 
-    // Use an external rpc when the builder is not the same as the builder actively building blocks onchain
-    let registered = if let Some(rpc_url) = args.rpc_url {
-        let tx_manager = TxManager::new(
-            tee_service_signer,
-            builder_key,
-            rpc_url.clone(),
-            registry_address,
+        // Use an external rpc when the builder is not the same as the builder actively building blocks onchain
+        let registered = if let Some(tx_manager) = self.tx_manager(tee_service_signer) {
+            // Submit report onchain by registering the key of the tee service
+            match tx_manager
+                .register_tee_service(attestation.clone(), ext_data.clone())
+                .await
+            {
+                Ok(_) => true,
+                Err(e) => {
+                    warn!(error = %e, "Failed to register tee service via rpc");
+                    false
+                }
+            }
+        } else {
+            false
+        };
+
+        Ok(Attestation {
+            attestation,
+            extra_registration_data: ext_data,
+            registered,
+        })
+    }
+
+    fn tx_manager(&self, tee_service_signer: Signer) -> Option<TxManager> {
+        self.rpc_url.as_ref().map(|rpc_url| {
+            TxManager::new(
+                tee_service_signer,
+                self.builder_key,
+                rpc_url.clone(),
+                self.registry_address,
+            )
+        })
+    }
+}
+
+/// Refreshes the attestation of the TEE service on a schedule, or when its registration becomes
+/// invalid, optionally with a new TEE key.
+///
+/// The new registration is swapped into the [`FlashtestationsBuilderTx`], which registers it
+/// through the permit transaction when it couldn't be registered through the external rpc.
+struct ReattestationService {
+    attestor: Attestor,
+    registration: TeeRegistrationHandle,
+    /// Registration status in the local chain state, checked without an external rpc.
+    status: FlashtestationsStatusTracker,
+    reattestation_interval: Option<Duration>,
+    status_check_interval: Duration,
+    rotate_key: bool,
+    /// Where rotated keys are saved, unset in debug mode.
+    key_path: Option<String>,
+    /// Rotated keys are exempted from the gas limiter, like the initial key.
+    gas_limiter: AddressGasLimiter,
+}
+
+impl ReattestationService {
+    async fn run(self) {
+        let mut reattestation = self.reattestation_interval.map(|period| {
+            let mut interval = interval_at(Instant::now() + period, period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        });
+        let mut status_check = interval_at(
+            Instant::now() + self.status_check_interval,
+            self.status_check_interval,
         );
-        // Submit report onchain by registering the key of the tee service
-        match tx_manager
-            .register_tee_service(attestation.clone(), ext_data.clone())
-            .await
-        {
-            Ok(_) => true,
+        status_check.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = tick(&mut reattestation) => {
+                    info!(target: "flashtestations", "scheduled TEE re-attestation");
+                }
+                _ = status_check.tick() => {
+                    if self.registration_is_valid().await {
+                        continue;
+                    }
+                    warn!(target: "flashtestations", "TEE registration is invalid, re-attesting");
+                }
+            }
+
+            if let Err(e) = self.reattest().await {
+                warn!(target: "flashtestations", error = %e, "TEE re-attestation failed");
+            }
+        }
+    }
+
+    /// Returns false only when the registry reports the registration as invalid, errors are
+    /// logged and retried on the next check.
+    ///
+    /// Without an external rpc, the status of the local chain state is used. A TEE address that
+    /// was never registered, which is registered by the builder transactions, is valid.
+    async fn registration_is_valid(&self) -> bool {
+        let current = self.registration.current();
+        let Some(tx_manager) = self.attestor.tx_manager(current.tee_service_signer) else {
+            let status = self.status.status();
+            return status.tee_address != Some(current.tee_service_signer.address)
+                || status.registered
+                || status.quote_hash.is_zero();
+        };
+        match tx_manager.registration_status().await {
+            Ok(valid) => valid,
             Err(e) => {
-                warn!(error = %e, "Failed to register tee service via rpc");
-                false
+                warn!(target: "flashtestations", error = %e, "failed to check TEE registration status");
+                true
             }
         }
-    } else {
-        false
-    };
+    }
 
-    let flashtestations_builder_tx = FlashtestationsBuilderTx::new(FlashtestationsBuilderTxArgs {
-        attestation,
-        extra_registration_data: ext_data,
-        tee_service_signer,
-        registry_address,
-        builder_policy_address,
-        builder_proof_version: args.builder_proof_version,
-        enable_block_proofs: args.enable_block_proofs,
-        registered,
-        builder_key,
-    });
+    async fn reattest(&self) -> eyre::Result<()> {
+        let current = self.registration.current();
+        let tee_service_signer = match (&self.key_path, self.rotate_key) {
+            (Some(_), true) => generate_signer(),
+            (None, true) => {
+                warn!(target: "flashtestations", "TEE key rotation is disabled in debug mode");
+                current.tee_service_signer
+            }
+            (_, false) => current.tee_service_signer,
+        };
+        self.gas_limiter.add_exempt(tee_service_signer.address);
 
-    Ok(flashtestations_builder_tx)
+        let attestation = self.attestor.attest(tee_service_signer).await?;
+
+        if tee_service_signer != current.tee_service_signer {
+            info!(
+                target: "flashtestations",
+                old_address = %current.tee_service_signer.address,
+                new_address = %tee_service_signer.address,
+                "rotated TEE key"
+            );
+            if let Some(key_path) = &self.key_path {
+                save_tee_key(Path::new(key_path), &tee_service_signer);
+            }
+        }
+
+        self.registration.replace(TeeRegistration::new(
+            attestation.attestation,
+            attestation.extra_registration_data,
+            tee_service_signer,
+            attestation.registered,
+        ));
+        Ok(())
+    }
+}
+
+/// Ticks the interval, or never completes when there is none.
+async fn tick(interval: &mut Option<Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
 }
 
 /// Load ephemeral TEE key from file, or generate and save a new one
@@ -132,7 +345,13 @@ fn load_or_generate_tee_key(key_path: &str, debug: bool, debug_seed: &str) -> ey
     // Generate new key
     info!("Generating new ephemeral TEE key");
     let signer = generate_signer();
+    save_tee_key(path, &signer);
+
+    Ok(signer)
+}
 
+/// Save the TEE key to file, so that it is reused after a restart
+fn save_tee_key(path: &Path, signer: &Signer) {
     let key_hex = hex::encode(signer.secret.secret_bytes());
 
     // Create file with 0600 permissions atomically
@@ -143,10 +362,8 @@ fn load_or_generate_tee_key(key_path: &str, debug: bool, debug_seed: &str) -> ey
         .mode(0o600)
         .open(path)
         .and_then(|mut file| file.write_all(key_hex.as_bytes()))
-        .inspect_err(|e| warn!("Failed to write key to {}: {:?}", key_path, e))
+        .inspect_err(|e| warn!("Failed to write key to {:?}: {:?}", path, e))
         .ok();
-
-    Ok(signer)
 }
 
 fn load_tee_key(path: &Path) -> Option<Signer> {
@@ -0,0 +1,388 @@
+use alloy_consensus::TxReceipt;
+use alloy_evm::Database;
+use alloy_op_evm::OpEvm;
+use alloy_primitives::{Address, B256, U64};
+use alloy_sol_types::{SolCall, SolEvent};
+use eyre::eyre;
+use jsonrpsee::{
+    core::{RpcResult, async_trait},
+    proc_macros::rpc,
+};
+use metrics::{Counter, Gauge};
+use parking_lot::RwLock;
+use reth_evm::{ConfigureEvm, Evm, precompiles::PrecompilesMap};
+use reth_metrics::Metrics;
+use reth_optimism_evm::{OpEvmConfig, OpNextBlockEnvAttributes};
+use reth_optimism_primitives::{OpReceipt, OpTransactionSigned};
+use reth_provider::{ReceiptProvider, TransactionsProvider};
+use reth_revm::{State, database::StateProviderDatabase};
+use revm::{context::result::ExecutionResult, inspector::NoOpInspector};
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+use tdx_quote_parser::compute_workload_id;
+use tokio::time::{MissedTickBehavior, interval};
+use tracing::{debug, warn};
+
+use crate::{
+    flashtestations::{
+        IBlockBuilderPolicy::{self, BlockBuilderProofVerified},
+        IFlashtestationRegistry,
+        args::FlashtestationsArgs,
+        builder_tx::TeeRegistrationHandle,
+    },
+    traits::ClientBounds,
+};
+
+/// Number of blocks scanned for block proofs on the first check, and at most on each check.
+const MAX_SCANNED_BLOCKS: u64 = 1_000;
+
+/// Block proof of the TEE address verified by the builder policy contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockProofStatus {
+    /// Block containing the proof transaction
+    pub block_number: U64,
+    /// Block content hash, or root of the batch, committed by the proof
+    pub block_content_hash: B256,
+    pub tx_hash: B256,
+}
+
+/// Registration status of the TEE service and its last verified block proof, as read from the
+/// local chain state.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlashtestationsStatus {
+    /// TEE address of the builder, unset until flashtestations is bootstrapped
+    pub tee_address: Option<Address>,
+    /// Whether the registry reports the registration of the TEE address as valid
+    pub registered: bool,
+    /// Hash of the registered quote
+    pub quote_hash: B256,
+    /// Workload ID of the quote of the TEE service
+    pub workload_id: Option<B256>,
+    /// Whether the builder policy allows the workload of the TEE address
+    pub workload_approved: bool,
+    /// Latest block the status was checked against
+    pub block_number: Option<U64>,
+    /// Last block proof of the TEE address verified onchain
+    pub last_block_proof: Option<BlockProofStatus>,
+    /// Blocks since the last block proof
+    pub blocks_since_block_proof: Option<U64>,
+    /// Error of the last check, or of the bootstrap of flashtestations
+    pub error: Option<String>,
+}
+
+/// Status of the TEE service, shared between the payload builder that bootstraps it, the
+/// [`FlashtestationsStatusMonitor`] and the `flashtestations_status` RPC.
+#[derive(Debug, Clone, Default)]
+pub struct FlashtestationsStatusTracker {
+    registration: Arc<RwLock<Option<TeeRegistrationHandle>>>,
+    status: Arc<RwLock<FlashtestationsStatus>>,
+}
+
+impl FlashtestationsStatusTracker {
+    /// Sets the registration of the bootstrapped TEE service, checked by the monitor.
+    pub fn set_registration(&self, registration: TeeRegistrationHandle) {
+        *self.registration.write() = Some(registration);
+    }
+
+    pub fn set_error(&self, error: impl ToString) {
+        self.status.write().error = Some(error.to_string());
+    }
+
+    pub fn status(&self) -> FlashtestationsStatus {
+        self.status.read().clone()
+    }
+
+    fn registration(&self) -> Option<TeeRegistrationHandle> {
+        self.registration.read().clone()
+    }
+}
+
+#[derive(Metrics, Clone)]
+#[metrics(scope = "op_rbuilder.flashtestations")]
+struct StatusMetrics {
+    /// Whether the TEE address is registered in the registry
+    registered: Gauge,
+    /// Whether the workload of the TEE address is approved by the builder policy
+    workload_approved: Gauge,
+    /// Block of the last block proof of the TEE address verified onchain
+    last_block_proof: Gauge,
+    /// Blocks since the last block proof, or since the monitor started without any
+    blocks_since_block_proof: Gauge,
+    /// Status checks that failed
+    status_check_failures: Counter,
+}
+
+/// Periodically reads the registration status of the TEE address in the registry and the
+/// approval of its workload in the builder policy from the latest local state, and scans the new
+/// blocks for the block proofs of the TEE address.
+///
+/// An invalid registration resets the registered flag of the registration, so that the builder
+/// transactions register it again.
+pub struct FlashtestationsStatusMonitor<Provider> {
+    tracker: FlashtestationsStatusTracker,
+    provider: Provider,
+    evm_config: OpEvmConfig,
+    registry_address: Address,
+    builder_policy_address: Address,
+    interval: Duration,
+    /// First block scanned for block proofs
+    first_scanned_block: Option<u64>,
+    /// Last block scanned for block proofs
+    last_scanned_block: Option<u64>,
+    metrics: StatusMetrics,
+}
+
+impl<Provider> FlashtestationsStatusMonitor<Provider>
+where
+    Provider: ClientBounds
+        + TransactionsProvider<Transaction = OpTransactionSigned>
+        + ReceiptProvider<Receipt = OpReceipt>
+        + 'static,
+{
+    pub fn new(
+        args: &FlashtestationsArgs,
+        tracker: FlashtestationsStatusTracker,
+        provider: Provider,
+    ) -> Self {
+        Self {
+            tracker,
+            evm_config: OpEvmConfig::optimism(provider.chain_spec()),
+            provider,
+            registry_address: args
+                .registry_address
+                .expect("registry address required when flashtestations enabled"),
+            builder_policy_address: args
+                .builder_policy_address
+                .expect("builder policy address required when flashtestations enabled"),
+            interval: Duration::from_secs(args.monitor_interval_secs),
+            first_scanned_block: None,
+            last_scanned_block: None,
+            metrics: StatusMetrics::default(),
+        }
+    }
+
+    pub async fn run(self) {
+        let mut interval = interval(self.interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut monitor = self;
+        loop {
+            interval.tick().await;
+            // the state reads and the block scan hit the database, keep them off the async runtime
+            let (checked, result) = match tokio::task::spawn_blocking(move || {
+                let result = monitor.check();
+                (monitor, result)
+            })
+            .await
+            {
+                Ok(checked) => checked,
+                Err(e) => {
+                    warn!(target: "flashtestations", error = %e, "flashtestations status monitor stopped");
+                    return;
+                }
+            };
+            monitor = checked;
+            if let Err(e) = result {
+                warn!(target: "flashtestations", error = %e, "failed to check flashtestations status");
+                monitor.metrics.status_check_failures.increment(1);
+                monitor.tracker.set_error(e);
+            }
+        }
+    }
+
+    fn check(&mut self) -> eyre::Result<()> {
+        // nothing to check until flashtestations is bootstrapped
+        let Some(registration) = self.tracker.registration() else {
+            return Ok(());
+        };
+        let registration = registration.current();
+        let tee_address = registration.tee_service_signer.address;
+
+        // the block proofs of a rotated key are not proofs of the new TEE address
+        let previous_tee_address = self.tracker.status.read().tee_address;
+        if previous_tee_address.is_some_and(|address| address != tee_address) {
+            debug!(target: "flashtestations", %tee_address, "TEE address changed, scanning block proofs again");
+            self.first_scanned_block = None;
+            self.last_scanned_block = None;
+            self.tracker.status.write().last_block_proof = None;
+        }
+
+        let latest = self
+            .provider
+            .latest_header()?
+            .ok_or_else(|| eyre!("latest block not found"))?;
+        let state = self.provider.state_by_block_hash(latest.hash())?;
+        let evm_env = self
+            .evm_config
+            .next_evm_env(
+                latest.header(),
+                &OpNextBlockEnvAttributes {
+                    timestamp: latest.timestamp,
+                    suggested_fee_recipient: latest.beneficiary,
+                    prev_randao: latest.mix_hash,
+                    gas_limit: latest.gas_limit,
+                    parent_beacon_block_root: latest.parent_beacon_block_root,
+                    extra_data: latest.extra_data.clone(),
+                },
+            )
+            .map_err(|e| eyre!("failed to build the evm environment: {e}"))?;
+        let mut db = State::builder()
+            .with_database(StateProviderDatabase::new(state))
+            .build();
+        let mut evm = self.evm_config.evm_with_env(&mut db, evm_env);
+
+        let registration_status = contract_read(
+            &mut evm,
+            self.registry_address,
+            IFlashtestationRegistry::getRegistrationStatusCall {
+                teeAddress: tee_address,
+            },
+        )?;
+        let policy = contract_read(
+            &mut evm,
+            self.builder_policy_address,
+            IBlockBuilderPolicy::isAllowedPolicyCall {
+                teeAddress: tee_address,
+            },
+        )?;
+        drop(evm);
+
+        if !registration_status.isValid && registration.is_registered() {
+            warn!(target: "flashtestations", %tee_address, "TEE registration is no longer valid");
+            registration.set_registered(false);
+        }
+
+        let last_block_proof = self.scan_block_proofs(tee_address, latest.number)?;
+        let mut status = self.tracker.status.write();
+        if let Some(block_proof) = last_block_proof {
+            status.last_block_proof = Some(block_proof);
+        }
+        let last_block_proof_number = status
+            .last_block_proof
+            .as_ref()
+            .map(|block_proof| block_proof.block_number.to::<u64>());
+        status.tee_address = Some(tee_address);
+        status.registered = registration_status.isValid;
+        status.quote_hash = registration_status.quoteHash;
+        status.workload_id = compute_workload_id(&registration.attestation)
+            .ok()
+            .map(B256::from);
+        status.workload_approved = policy.allowed;
+        status.block_number = Some(U64::from(latest.number));
+        status.blocks_since_block_proof =
+            last_block_proof_number.map(|number| U64::from(latest.number.saturating_sub(number)));
+        status.error = None;
+        debug!(target: "flashtestations", ?status, "checked flashtestations status");
+
+        self.metrics
+            .registered
+            .set(registration_status.isValid as u8 as f64);
+        self.metrics
+            .workload_approved
+            .set(policy.allowed as u8 as f64);
+        if let Some(number) = last_block_proof_number {
+            self.metrics.last_block_proof.set(number as f64);
+        }
+        let since = last_block_proof_number
+            .or(self
+                .first_scanned_block
+                .map(|number| number.saturating_sub(1)))
+            .unwrap_or(latest.number);
+        self.metrics
+            .blocks_since_block_proof
+            .set(latest.number.saturating_sub(since) as f64);
+        Ok(())
+    }
+
+    /// Returns the last block proof of the TEE address in the blocks since the previous scan.
+    fn scan_block_proofs(
+        &mut self,
+        tee_address: Address,
+        latest: u64,
+    ) -> eyre::Result<Option<BlockProofStatus>> {
+        let start = self
+            .last_scanned_block
+            // rescan the blocks of a reorg
+            .filter(|number| *number <= latest)
+            .map_or(0, |number| number + 1)
+            .max(latest.saturating_sub(MAX_SCANNED_BLOCKS - 1));
+        self.first_scanned_block.get_or_insert(start);
+        self.last_scanned_block = Some(latest);
+
+        let mut last_block_proof = None;
+        for number in start..=latest {
+            let (Some(transactions), Some(receipts)) = (
+                self.provider.transactions_by_block(number.into())?,
+                self.provider.receipts_by_block(number.into())?,
+            ) else {
+                continue;
+            };
+            for (tx, receipt) in transactions.iter().zip(&receipts) {
+                for log in receipt.logs() {
+                    if log.address != self.builder_policy_address {
+                        continue;
+                    }
+                    if let Ok(event) = BlockBuilderProofVerified::decode_log_data(&log.data)
+                        && event.caller == tee_address
+                    {
+                        last_block_proof = Some(BlockProofStatus {
+                            block_number: U64::from(number),
+                            block_content_hash: event.blockContentHash,
+                            tx_hash: tx.tx_hash(),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(last_block_proof)
+    }
+}
+
+/// Calls a view function of a contract, without committing the state changes.
+fn contract_read<T: SolCall>(
+    evm: &mut OpEvm<impl Database, NoOpInspector, PrecompilesMap>,
+    contract_address: Address,
+    calldata: T,
+) -> eyre::Result<T::Return> {
+    let result = evm
+        .transact_system_call(
+            Address::ZERO,
+            contract_address,
+            calldata.abi_encode().into(),
+        )
+        .map_err(|e| eyre!("failed to call {contract_address}: {e}"))?;
+    match result.result {
+        ExecutionResult::Success { output, .. } => Ok(T::abi_decode_returns(output.data())?),
+        ExecutionResult::Revert { output, .. } => {
+            Err(eyre!("call to {contract_address} reverted: {output}"))
+        }
+        ExecutionResult::Halt { reason, .. } => {
+            Err(eyre!("call to {contract_address} halted: {reason:?}"))
+        }
+    }
+}
+
+#[cfg_attr(not(test), rpc(server, namespace = "flashtestations"))]
+#[cfg_attr(test, rpc(server, client, namespace = "flashtestations"))]
+pub trait FlashtestationsStatusApi {
+    #[method(name = "status")]
+    async fn status(&self) -> RpcResult<FlashtestationsStatus>;
+}
+
+pub struct FlashtestationsStatusExt {
+    tracker: FlashtestationsStatusTracker,
+}
+
+impl FlashtestationsStatusExt {
+    pub fn new(tracker: FlashtestationsStatusTracker) -> Self {
+        Self { tracker }
+    }
+}
+
+#[async_trait]
+impl FlashtestationsStatusApiServer for FlashtestationsStatusExt {
+    async fn status(&self) -> RpcResult<FlashtestationsStatus> {
+        Ok(self.tracker.status())
+    }
+}
@@ -36,6 +36,8 @@ pub enum TxManagerError {
     SignerError(ecdsa::Error),
     #[error("error signing message: {0}")]
     SignatureError(secp256k1::Error),
+    #[error("error decoding call output: {0}")]
+    DecodeError(alloy_sol_types::Error),
 }
 
 #[derive(Debug, Clone)]
@@ -160,6 +162,29 @@ impl TxManager {
         }
     }
 
+    /// Returns whether the registration of the TEE service is valid in the registry.
+    pub async fn registration_status(&self) -> Result<bool, TxManagerError> {
+        let provider = ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .network::<Optimism>()
+            .connect(self.rpc_url.as_str())
+            .await?;
+
+        let status_call = IFlashtestationRegistry::getRegistrationStatusCall {
+            teeAddress: self.tee_service_signer.address,
+        };
+        let status_tx = TransactionRequest {
+            to: Some(TxKind::Call(self.registry_address)),
+            input: status_call.abi_encode().into(),
+            ..Default::default()
+        };
+        let output = provider.call(status_tx.into()).await?;
+        let status =
+            IFlashtestationRegistry::getRegistrationStatusCall::abi_decode_returns(&output)
+                .map_err(TxManagerError::DecodeError)?;
+        Ok(status.isValid)
+    }
+
     /// Processes a pending transaction and logs whether the transaction succeeded or not
     async fn process_pending_tx(
         pending_tx_result: TransportResult<PendingTransactionBuilder<Optimism>>,
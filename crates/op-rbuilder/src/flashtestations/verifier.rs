@@ -0,0 +1,533 @@
+use p256::ecdsa::{Signature, VerifyingKey, signature::Verifier};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use x509_parser::{
+    certificate::X509Certificate, parse_x509_certificate, parse_x509_crl, pem::Pem,
+    revocation_list::CertificateRevocationList, time::ASN1Time,
+};
+
+use tdx_quote_parser::{HEADER_LENGTH, TD_REPORT10_LENGTH};
+
+// Header fields of a TDX v4 quote
+const QUOTE_VERSION: u16 = 4;
+const ATTESTATION_KEY_TYPE_ECDSA_P256: u16 = 2;
+const TEE_TYPE_TDX: u32 = 0x81;
+
+// ECDSA quote signature data, following the report body
+const SIGNATURE_LENGTH: usize = 64;
+const ATTESTATION_KEY_LENGTH: usize = 64;
+const QE_REPORT_LENGTH: usize = 384;
+const OFFSET_QE_REPORT_DATA: usize = 320;
+
+// Certification data types
+const CERT_DATA_TYPE_PCK_CERT_CHAIN: u16 = 5;
+const CERT_DATA_TYPE_QE_REPORT: u16 = 6;
+
+#[derive(Debug, thiserror::Error)]
+pub enum QuoteVerificationError {
+    #[error("quote is truncated: {0}")]
+    Truncated(&'static str),
+    #[error("unsupported quote version: {0}")]
+    UnsupportedVersion(u16),
+    #[error("unsupported attestation key type: {0}")]
+    UnsupportedAttestationKeyType(u16),
+    #[error("unsupported tee type: {0:#x}")]
+    UnsupportedTeeType(u32),
+    #[error("unexpected certification data type: {0}")]
+    UnexpectedCertificationDataType(u16),
+    #[error("invalid attestation key: {0}")]
+    InvalidAttestationKey(p256::ecdsa::Error),
+    #[error("invalid quote signature")]
+    InvalidQuoteSignature,
+    #[error("invalid QE report signature")]
+    InvalidQeReportSignature,
+    #[error("QE report data does not match the attestation key")]
+    QeReportDataMismatch,
+    #[error("invalid certificate: {0}")]
+    InvalidCertificate(String),
+    #[error("invalid PCK certificate chain: {0}")]
+    InvalidCertificateChain(String),
+    #[error("PCK certificate chain does not end at the configured root CA")]
+    UntrustedRoot,
+    #[error("certificate {0} is expired or not yet valid")]
+    CertificateNotValid(String),
+    #[error("certificate {0} is revoked")]
+    CertificateRevoked(String),
+    #[error("invalid CRL: {0}")]
+    InvalidCrl(String),
+    #[error("failed to read collateral: {0}")]
+    Collateral(String),
+}
+
+/// Collateral the quotes are verified against, loaded from a local file.
+#[derive(Debug, Clone)]
+pub struct QuoteCollateral {
+    /// DER encoded root CA the PCK certificate chains must end at.
+    pub root_ca: Vec<u8>,
+    /// DER encoded CRLs of the root and intermediate CAs.
+    pub crls: Vec<Vec<u8>>,
+}
+
+/// Collateral file, with PEM encoded certificates and CRLs.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CollateralFile {
+    root_ca: String,
+    #[serde(default)]
+    crls: Vec<String>,
+}
+
+impl QuoteCollateral {
+    /// Loads the collateral from a JSON file of the form
+    /// `{"rootCa": "<PEM>", "crls": ["<PEM>", ...]}`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, QuoteVerificationError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| QuoteVerificationError::Collateral(e.to_string()))?;
+        Self::from_json(&content)
+    }
+
+    pub fn from_json(content: &str) -> Result<Self, QuoteVerificationError> {
+        let file: CollateralFile = serde_json::from_str(content)
+            .map_err(|e| QuoteVerificationError::Collateral(e.to_string()))?;
+
+        let root_ca = pem_contents(file.root_ca.as_bytes())?
+            .into_iter()
+            .next()
+            .ok_or_else(|| QuoteVerificationError::Collateral("missing root CA".to_string()))?;
+        // the root CA must be a valid certificate
+        parse_certificate(&root_ca)?;
+
+        let mut crls = Vec::new();
+        for crl in &file.crls {
+            crls.extend(pem_contents(crl.as_bytes())?);
+        }
+
+        Ok(Self { root_ca, crls })
+    }
+}
+
+/// Verifies TDX quotes offline: the quote header, the attestation key signature, the QE report
+/// and the PCK certificate chain against the configured collateral.
+#[derive(Debug, Clone)]
+pub struct QuoteVerifier {
+    collateral: QuoteCollateral,
+}
+
+impl QuoteVerifier {
+    pub fn new(collateral: QuoteCollateral) -> Self {
+        Self { collateral }
+    }
+
+    pub fn verify(&self, raw_quote: &[u8]) -> Result<(), QuoteVerificationError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        self.verify_at(raw_quote, now)
+    }
+
+    /// Verifies a quote, with the certificate validity checked at the given unix timestamp.
+    pub fn verify_at(
+        &self,
+        raw_quote: &[u8],
+        timestamp: i64,
+    ) -> Result<(), QuoteVerificationError> {
+        let quote = SignedQuote::parse(raw_quote)?;
+        quote.verify_header()?;
+
+        // the attestation key signs the header and the report body
+        let attestation_key = VerifyingKey::from_sec1_bytes(&quote.attestation_key_sec1())
+            .map_err(QuoteVerificationError::InvalidAttestationKey)?;
+        if !verify_signature(&attestation_key, quote.signed_data, quote.signature) {
+            return Err(QuoteVerificationError::InvalidQuoteSignature);
+        }
+
+        let chain = quote.pck_cert_chain()?;
+        self.verify_cert_chain(&chain, timestamp)?;
+
+        // the PCK certificate signs the QE report, which binds the attestation key
+        let pck_cert = parse_certificate(&chain[0])?;
+        let pck_key = VerifyingKey::from_sec1_bytes(&pck_cert.public_key().subject_public_key.data)
+            .map_err(|e| QuoteVerificationError::InvalidCertificate(e.to_string()))?;
+        if !verify_signature(&pck_key, quote.qe_report, quote.qe_report_signature) {
+            return Err(QuoteVerificationError::InvalidQeReportSignature);
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(quote.attestation_key);
+        hasher.update(quote.qe_auth_data);
+        let expected_report_data = hasher.finalize();
+        let report_data = &quote.qe_report[OFFSET_QE_REPORT_DATA..];
+        if report_data[..32] != expected_report_data[..]
+            || report_data[32..].iter().any(|b| *b != 0)
+        {
+            return Err(QuoteVerificationError::QeReportDataMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Checks that each certificate is signed by the next one, that the chain ends at the root
+    /// CA, that all certificates are valid at the timestamp and that none is revoked.
+    ///
+    /// The collateral may hold the CRLs of CAs outside of the chain, for example of both the
+    /// platform and processor PCK CAs, such CRLs are skipped. The CRLs of the CAs of the chain
+    /// must not be past their next update.
+    fn verify_cert_chain(
+        &self,
+        chain: &[Vec<u8>],
+        timestamp: i64,
+    ) -> Result<(), QuoteVerificationError> {
+        let time = ASN1Time::from_timestamp(timestamp)
+            .map_err(|e| QuoteVerificationError::InvalidCertificate(e.to_string()))?;
+        let root = parse_certificate(&self.collateral.root_ca)?;
+        let certs = chain
+            .iter()
+            .map(|der| parse_certificate(der))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for pair in certs.windows(2) {
+            pair[0]
+                .verify_signature(Some(pair[1].public_key()))
+                .map_err(|e| {
+                    QuoteVerificationError::InvalidCertificateChain(format!(
+                        "{} is not signed by {}: {e}",
+                        pair[0].subject(),
+                        pair[1].subject()
+                    ))
+                })?;
+        }
+
+        // the chain may or may not include the root CA
+        let last = certs.last().expect("chain is not empty");
+        if chain.last().expect("chain is not empty") != &self.collateral.root_ca
+            && last.verify_signature(Some(root.public_key())).is_err()
+        {
+            return Err(QuoteVerificationError::UntrustedRoot);
+        }
+
+        for cert in certs.iter().chain(std::iter::once(&root)) {
+            if !cert.validity().is_valid_at(time) {
+                return Err(QuoteVerificationError::CertificateNotValid(
+                    cert.subject().to_string(),
+                ));
+            }
+        }
+
+        for crl in &self.collateral.crls {
+            let crl = parse_crl(crl)?;
+            let Some(issuer) = certs
+                .iter()
+                .chain(std::iter::once(&root))
+                .find(|cert| cert.subject() == crl.issuer())
+            else {
+                continue;
+            };
+            crl.verify_signature(issuer.public_key())
+                .map_err(|e| QuoteVerificationError::InvalidCrl(e.to_string()))?;
+            if let Some(next_update) = crl.next_update()
+                && next_update < time
+            {
+                return Err(QuoteVerificationError::InvalidCrl(format!(
+                    "CRL of {} is past its next update {next_update}",
+                    crl.issuer()
+                )));
+            }
+
+            for cert in certs.iter().filter(|cert| cert.issuer() == crl.issuer()) {
+                if crl
+                    .iter_revoked_certificates()
+                    .any(|revoked| revoked.raw_serial() == cert.raw_serial())
+                {
+                    return Err(QuoteVerificationError::CertificateRevoked(
+                        cert.subject().to_string(),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Signed parts of a TDX v4 quote.
+struct SignedQuote<'a> {
+    header: &'a [u8],
+    /// Header and report body, signed by the attestation key.
+    signed_data: &'a [u8],
+    signature: &'a [u8],
+    attestation_key: &'a [u8],
+    qe_report: &'a [u8],
+    qe_report_signature: &'a [u8],
+    qe_auth_data: &'a [u8],
+    /// PEM encoded PCK certificate chain, from the PCK certificate to the root CA.
+    pck_cert_chain: &'a [u8],
+}
+
+impl<'a> SignedQuote<'a> {
+    fn parse(raw_quote: &'a [u8]) -> Result<Self, QuoteVerificationError> {
+        let mut reader = Reader(raw_quote);
+        let signed_data = reader.take(HEADER_LENGTH + TD_REPORT10_LENGTH, "report body")?;
+        let signature_data_length = reader.u32("signature data length")? as usize;
+        let mut reader = Reader(reader.take(signature_data_length, "signature data")?);
+
+        let signature = reader.take(SIGNATURE_LENGTH, "quote signature")?;
+        let attestation_key = reader.take(ATTESTATION_KEY_LENGTH, "attestation key")?;
+
+        let certification_data_type = reader.u16("certification data type")?;
+        if certification_data_type != CERT_DATA_TYPE_QE_REPORT {
+            return Err(QuoteVerificationError::UnexpectedCertificationDataType(
+                certification_data_type,
+            ));
+        }
+        let certification_data_length = reader.u32("certification data length")? as usize;
+        let mut reader = Reader(reader.take(certification_data_length, "certification data")?);
+
+        let qe_report = reader.take(QE_REPORT_LENGTH, "QE report")?;
+        let qe_report_signature = reader.take(SIGNATURE_LENGTH, "QE report signature")?;
+        let qe_auth_data_length = reader.u16("QE auth data length")? as usize;
+        let qe_auth_data = reader.take(qe_auth_data_length, "QE auth data")?;
+
+        let pck_data_type = reader.u16("PCK certification data type")?;
+        if pck_data_type != CERT_DATA_TYPE_PCK_CERT_CHAIN {
+            return Err(QuoteVerificationError::UnexpectedCertificationDataType(
+                pck_data_type,
+            ));
+        }
+        let pck_data_length = reader.u32("PCK certification data length")? as usize;
+        let pck_cert_chain = reader.take(pck_data_length, "PCK certificate chain")?;
+
+        Ok(Self {
+            header: &signed_data[..HEADER_LENGTH],
+            signed_data,
+            signature,
+            attestation_key,
+            qe_report,
+            qe_report_signature,
+            qe_auth_data,
+            pck_cert_chain,
+        })
+    }
+
+    fn verify_header(&self) -> Result<(), QuoteVerificationError> {
+        let version = u16::from_le_bytes([self.header[0], self.header[1]]);
+        if version != QUOTE_VERSION {
+            return Err(QuoteVerificationError::UnsupportedVersion(version));
+        }
+        let key_type = u16::from_le_bytes([self.header[2], self.header[3]]);
+        if key_type != ATTESTATION_KEY_TYPE_ECDSA_P256 {
+            return Err(QuoteVerificationError::UnsupportedAttestationKeyType(
+                key_type,
+            ));
+        }
+        let tee_type = u32::from_le_bytes(self.header[4..8].try_into().expect("4 bytes"));
+        if tee_type != TEE_TYPE_TDX {
+            return Err(QuoteVerificationError::UnsupportedTeeType(tee_type));
+        }
+        Ok(())
+    }
+
+    /// Returns the attestation key as an uncompressed SEC1 point.
+    fn attestation_key_sec1(&self) -> Vec<u8> {
+        let mut key = Vec::with_capacity(ATTESTATION_KEY_LENGTH + 1);
+        key.push(0x04);
+        key.extend_from_slice(self.attestation_key);
+        key
+    }
+
+    /// Returns the DER encoded certificates of the PCK certificate chain.
+    fn pck_cert_chain(&self) -> Result<Vec<Vec<u8>>, QuoteVerificationError> {
+        // the chain is null terminated
+        let pem = self
+            .pck_cert_chain
+            .strip_suffix(&[0])
+            .unwrap_or(self.pck_cert_chain);
+        let chain = pem_contents(pem)?;
+        if chain.is_empty() {
+            return Err(QuoteVerificationError::InvalidCertificateChain(
+                "empty chain".to_string(),
+            ));
+        }
+        Ok(chain)
+    }
+}
+
+/// Little endian reader over the quote bytes.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(
+        &mut self,
+        len: usize,
+        field: &'static str,
+    ) -> Result<&'a [u8], QuoteVerificationError> {
+        if self.0.len() < len {
+            return Err(QuoteVerificationError::Truncated(field));
+        }
+        let (data, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(data)
+    }
+
+    fn u16(&mut self, field: &'static str) -> Result<u16, QuoteVerificationError> {
+        let bytes = self.take(2, field)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self, field: &'static str) -> Result<u32, QuoteVerificationError> {
+        let bytes = self.take(4, field)?;
+        Ok(u32::from_le_bytes(bytes.try_into().expect("4 bytes")))
+    }
+}
+
+/// Verifies a raw `r || s` ECDSA P-256 signature over the SHA-256 digest of the data.
+fn verify_signature(key: &VerifyingKey, data: &[u8], signature: &[u8]) -> bool {
+    Signature::from_slice(signature).is_ok_and(|signature| key.verify(data, &signature).is_ok())
+}
+
+fn pem_contents(pem: &[u8]) -> Result<Vec<Vec<u8>>, QuoteVerificationError> {
+    Pem::iter_from_buffer(pem)
+        .map(|pem| {
+            pem.map(|pem| pem.contents)
+                .map_err(|e| QuoteVerificationError::InvalidCertificate(e.to_string()))
+        })
+        .collect()
+}
+
+fn parse_certificate(der: &[u8]) -> Result<X509Certificate<'_>, QuoteVerificationError> {
+    parse_x509_certificate(der)
+        .map(|(_, cert)| cert)
+        .map_err(|e| QuoteVerificationError::InvalidCertificate(e.to_string()))
+}
+
+fn parse_crl(der: &[u8]) -> Result<CertificateRevocationList<'_>, QuoteVerificationError> {
+    parse_x509_crl(der)
+        .map(|(_, crl)| crl)
+        .map_err(|e| QuoteVerificationError::InvalidCrl(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_QUOTE: &[u8] = include_bytes!("../tests/framework/artifacts/test-quote.bin");
+    const TEST_COLLATERAL: &str = include_str!("../tests/framework/artifacts/test-collateral.json");
+
+    /// 2026-01-01, when the certificates of the test quote are valid.
+    const TIMESTAMP: i64 = 1_767_225_600;
+
+    fn verifier() -> QuoteVerifier {
+        QuoteVerifier::new(QuoteCollateral::from_json(TEST_COLLATERAL).unwrap())
+    }
+
+    #[test]
+    fn test_verify_quote() {
+        verifier().verify_at(TEST_QUOTE, TIMESTAMP).unwrap();
+    }
+
+    #[test]
+    fn test_verify_synthetic_quote() {
+        use tdx_quote_parser::mock_quote::{
+            MOCK_COLLATERAL, MockMeasurements, SyntheticAttestationProvider,
+        };
+
+        // 2027-01-01, when the mock certificates are valid
+        const MOCK_TIMESTAMP: i64 = 1_798_761_600;
+
+        let quote = SyntheticAttestationProvider::new(MockMeasurements::default()).quote([7; 64]);
+        QuoteVerifier::new(QuoteCollateral::from_json(MOCK_COLLATERAL).unwrap())
+            .verify_at(&quote, MOCK_TIMESTAMP)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_corrupted_quote() {
+        // a measurement register of the report body
+        let mut quote = TEST_QUOTE.to_vec();
+        quote[HEADER_LENGTH + 136] ^= 1;
+        assert!(matches!(
+            verifier().verify_at(&quote, TIMESTAMP),
+            Err(QuoteVerificationError::InvalidQuoteSignature)
+        ));
+
+        // the report data of the QE report
+        let mut quote = TEST_QUOTE.to_vec();
+        let qe_report_offset =
+            HEADER_LENGTH + TD_REPORT10_LENGTH + 4 + SIGNATURE_LENGTH + ATTESTATION_KEY_LENGTH + 6;
+        quote[qe_report_offset + OFFSET_QE_REPORT_DATA] ^= 1;
+        assert!(matches!(
+            verifier().verify_at(&quote, TIMESTAMP),
+            Err(QuoteVerificationError::InvalidQeReportSignature)
+        ));
+
+        let mut quote = TEST_QUOTE.to_vec();
+        quote[0] = 3;
+        assert!(matches!(
+            verifier().verify_at(&quote, TIMESTAMP),
+            Err(QuoteVerificationError::UnsupportedVersion(3))
+        ));
+
+        assert!(matches!(
+            verifier().verify_at(&TEST_QUOTE[..1000], TIMESTAMP),
+            Err(QuoteVerificationError::Truncated(_))
+        ));
+    }
+
+    #[test]
+    fn test_untrusted_root() {
+        // the intermediate CA of the chain is not a root of the chain
+        let chain = SignedQuote::parse(TEST_QUOTE)
+            .unwrap()
+            .pck_cert_chain()
+            .unwrap();
+        let verifier = QuoteVerifier::new(QuoteCollateral {
+            root_ca: chain[1].clone(),
+            crls: vec![],
+        });
+        assert!(matches!(
+            verifier.verify_at(TEST_QUOTE, TIMESTAMP),
+            Err(QuoteVerificationError::UntrustedRoot)
+        ));
+    }
+
+    /// Verifies the chain of a PCK certificate issued by a test root CA, against collateral
+    /// holding a CRL of the root CA valid until 2026-06-01 and a CRL of another CA.
+    fn verify_crl_test_chain(timestamp: i64) -> Result<(), QuoteVerificationError> {
+        let collateral = QuoteCollateral::from_json(include_str!(
+            "../tests/framework/artifacts/test-crl-collateral.json"
+        ))
+        .unwrap();
+        let chain = pem_contents(include_bytes!(
+            "../tests/framework/artifacts/test-crl-leaf.pem"
+        ))
+        .unwrap();
+        QuoteVerifier::new(collateral).verify_cert_chain(&chain, timestamp)
+    }
+
+    #[test]
+    fn test_crl_of_other_ca_is_skipped() {
+        verify_crl_test_chain(TIMESTAMP).unwrap();
+    }
+
+    #[test]
+    fn test_crl_past_next_update() {
+        // 2026-07-01
+        assert!(matches!(
+            verify_crl_test_chain(1_782_864_000),
+            Err(QuoteVerificationError::InvalidCrl(_))
+        ));
+    }
+
+    #[test]
+    fn test_expired_certificate() {
+        // the PCK certificate expires in 2031
+        assert!(matches!(
+            verifier().verify_at(TEST_QUOTE, 2_000_000_000),
+            Err(QuoteVerificationError::CertificateNotValid(_))
+        ));
+    }
+}
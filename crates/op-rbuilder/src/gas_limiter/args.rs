@@ -1,6 +1,9 @@
 use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
-#[derive(Debug, Clone, Default, PartialEq, Eq, Args)]
+#[derive(Debug, Clone, PartialEq, Eq, Args, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GasLimiterArgs {
     /// Enable address-based gas rate limiting
     #[arg(long = "gas-limiter.enabled", env)]
@@ -25,4 +28,56 @@ pub struct GasLimiterArgs {
     /// How many blocks to wait before cleaning up stale buckets for addresses.
     #[arg(long = "gas-limiter.cleanup-interval", env, default_value = "100")]
     pub cleanup_interval: u64,
+
+    /// Maximum number of sender buckets tracked at the end of a block. When exceeded, the least
+    /// recently used buckets are evicted, full ones first. Defaults to 100 thousand.
+    #[arg(
+        long = "gas-limiter.max-tracked-addresses",
+        env,
+        default_value = "100000"
+    )]
+    pub max_tracked_addresses: u64,
+
+    /// Path to a TOML or JSON policy file setting limits per sender and per contract, and
+    /// the senders that bypass the limiter. The file is reloaded when it changes.
+    #[arg(long = "gas-limiter.policy-file", env)]
+    pub gas_limiter_policy_file: Option<PathBuf>,
+
+    /// Reject `eth_sendBundle` requests when the bucket of the sender, or of the called
+    /// contract, cannot cover the gas limit of the bundle transaction.
+    #[arg(long = "gas-limiter.check-bundles", env)]
+    pub gas_limiter_check_bundles: bool,
+}
+
+impl Default for GasLimiterArgs {
+    /// Same as the CLI defaults.
+    fn default() -> Self {
+        Self {
+            gas_limiter_enabled: false,
+            max_gas_per_address: 10_000_000,
+            refill_rate_per_block: 1_000_000,
+            cleanup_interval: 100,
+            max_tracked_addresses: 100_000,
+            gas_limiter_policy_file: None,
+            gas_limiter_check_bundles: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        gas_limiter: GasLimiterArgs,
+    }
+
+    #[test]
+    fn test_default_matches_cli_defaults() {
+        let cli = Cli::parse_from(["op-rbuilder"]);
+        assert_eq!(cli.gas_limiter, GasLimiterArgs::default());
+    }
 }
@@ -10,4 +10,24 @@ pub enum GasLimitError {
         requested: u64,
         available: u64,
     },
+    #[error(
+        "Contract {contract} exceeded gas limit: {requested} gwei requested, {available} gwei available"
+    )]
+    ContractLimitExceeded {
+        contract: Address,
+        requested: u64,
+        available: u64,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PolicyError {
+    #[error("failed to read gas limiter policy: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid gas limiter policy: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("invalid gas limiter policy: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("address {0} is listed more than once in the gas limiter policy")]
+    DuplicateAddress(Address),
 }
@@ -20,6 +20,24 @@ pub(super) struct GasLimiterMetrics {
 
     /// Time to refill buckets
     pub refresh_duration: Histogram,
+
+    /// Gas used by allowlisted senders, which bypass the limiter
+    pub allowlisted_gas: Counter,
+
+    /// Number of times the policy file was loaded
+    pub policy_reloads: Counter,
+
+    /// Number of times the policy file failed to load
+    pub policy_reload_errors: Counter,
+
+    /// Number of sender buckets tracked after the last refresh
+    pub tracked_buckets: Gauge,
+
+    /// Buckets dropped after being full and unused for the cleanup interval
+    pub idle_evictions: Counter,
+
+    /// Buckets evicted because the tracked addresses cap was exceeded
+    pub capacity_evictions: Counter,
 }
 
 impl GasLimiterMetrics {
@@ -39,9 +57,18 @@ impl GasLimiterMetrics {
         self.check_time.record(duration);
     }
 
-    pub(super) fn record_refresh(&self, removed_addresses: usize, duration: Duration) {
+    pub(super) fn record_refresh(
+        &self,
+        idle_evictions: usize,
+        capacity_evictions: usize,
+        tracked_buckets: usize,
+        duration: Duration,
+    ) {
         self.active_address_count
-            .decrement(removed_addresses as f64);
+            .decrement((idle_evictions + capacity_evictions) as f64);
+        self.idle_evictions.increment(idle_evictions as u64);
+        self.capacity_evictions.increment(capacity_evictions as u64);
+        self.tracked_buckets.set(tracked_buckets as f64);
         self.refresh_duration.record(duration);
     }
 }
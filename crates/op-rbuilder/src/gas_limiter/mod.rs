@@ -1,77 +1,390 @@
-use std::{cmp::min, sync::Arc, time::Instant};
+use std::{
+    cmp::min,
+    collections::HashSet,
+    path::PathBuf,
+    sync::Arc,
+    time::{Instant, SystemTime},
+};
 
 use alloy_primitives::Address;
 use dashmap::DashMap;
+use parking_lot::RwLock;
+use tracing::{info, warn};
 
-use crate::gas_limiter::{args::GasLimiterArgs, error::GasLimitError, metrics::GasLimiterMetrics};
+use crate::gas_limiter::{
+    args::GasLimiterArgs,
+    error::{GasLimitError, PolicyError},
+    metrics::GasLimiterMetrics,
+    policy::{BucketLimits, GasLimiterPolicy, SenderPolicy},
+};
 
 pub mod args;
 pub mod error;
 mod metrics;
+pub mod policy;
 
 #[derive(Debug, Clone)]
 pub struct AddressGasLimiter {
-    inner: Option<AddressGasLimiterInner>,
+    // Shared between clones so that the limiter can be reconfigured at runtime
+    inner: Arc<RwLock<Option<AddressGasLimiterInner>>>,
+    // Senders that are never limited, regardless of the policy
+    exempt: Arc<RwLock<HashSet<Address>>>,
 }
 
 #[derive(Debug, Clone)]
 struct AddressGasLimiterInner {
     config: GasLimiterArgs,
+    policy: GasLimiterPolicy,
+    // Modification time of the policy file when it was last loaded
+    policy_modified: Option<SystemTime>,
+    // Whether the last load of the policy file failed, so that a missing or invalid file is
+    // only reported once until it changes
+    policy_load_failed: bool,
+    // Block number of the last refresh, used to track when buckets were last used
+    current_block: u64,
     // We don't need an Arc<Mutex<_>> here, we can get away with RefCell, but
     // the reth PayloadBuilder trait needs this to be Send + Sync
     address_buckets: Arc<DashMap<Address, TokenBucket>>,
+    // Buckets of the contracts listed in the policy, charged for every call
+    contract_buckets: Arc<DashMap<Address, TokenBucket>>,
     metrics: GasLimiterMetrics,
 }
 
+/// Policy file to check for changes, along with the state of its last load.
+struct PolicyFile {
+    path: PathBuf,
+    last_modified: Option<SystemTime>,
+    last_load_failed: bool,
+}
+
+/// Policy file read and parsed without holding the lock of the limiter.
+struct PolicyLoad {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    result: Result<GasLimiterPolicy, PolicyError>,
+}
+
+impl PolicyFile {
+    /// Returns a policy file that was never loaded.
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            last_modified: None,
+            last_load_failed: false,
+        }
+    }
+
+    /// Reads the policy file if it was modified since it was last loaded. A missing file is
+    /// retried once it shows up.
+    fn read(self) -> Option<PolicyLoad> {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if modified == self.last_modified && (modified.is_some() || self.last_load_failed) {
+            return None;
+        }
+
+        let result = GasLimiterPolicy::load(&self.path);
+        Some(PolicyLoad {
+            path: self.path,
+            modified,
+            result,
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 struct TokenBucket {
     capacity: u64,
     available: u64,
+    refill_rate: u64,
+    // Block number at which the bucket was last checked
+    last_used: u64,
 }
 
 impl AddressGasLimiter {
     pub fn new(config: GasLimiterArgs) -> Self {
+        let load = config
+            .gas_limiter_enabled
+            .then(|| PolicyFile::new(config.gas_limiter_policy_file.clone()?).read())
+            .flatten();
         Self {
-            inner: AddressGasLimiterInner::try_new(config),
+            inner: Arc::new(RwLock::new(AddressGasLimiterInner::try_new(config, load))),
+            exempt: Default::default(),
+        }
+    }
+
+    /// Exempts addresses from limiting, such as the builder signer.
+    pub fn with_exempt(self, addresses: impl IntoIterator<Item = Address>) -> Self {
+        self.exempt.write().extend(addresses);
+        self
+    }
+
+    /// Exempts an address from limiting once the limiter is running, such as a TEE key
+    /// generated on startup or rotated.
+    pub fn add_exempt(&self, address: Address) {
+        self.exempt.write().insert(address);
+    }
+
+    /// Applies a new configuration. Buckets are kept as long as the limiter stays enabled,
+    /// their capacity is adjusted to the new maximum.
+    pub fn reconfigure(&self, config: &GasLimiterArgs) {
+        let current = self.inner.read().as_ref().map(|inner| inner.config.clone());
+        if current.as_ref() == Some(config) || (current.is_none() && !config.gas_limiter_enabled) {
+            return;
+        }
+
+        // a new policy file is read before taking the lock, so that the limiter isn't blocked
+        // on file I/O
+        let load = config
+            .gas_limiter_policy_file
+            .clone()
+            .filter(|path| {
+                config.gas_limiter_enabled
+                    && current.as_ref().is_none_or(|current| {
+                        current.gas_limiter_policy_file.as_ref() != Some(path)
+                    })
+            })
+            .and_then(|path| PolicyFile::new(path).read());
+
+        let mut inner = self.inner.write();
+        match inner.as_mut() {
+            Some(inner) if config.gas_limiter_enabled => inner.reconfigure(config, load),
+            _ => *inner = AddressGasLimiterInner::try_new(config.clone(), load),
         }
     }
 
-    /// Check if there's enough gas for this address and consume it. Returns
-    /// Ok(()) if there's enough otherwise returns an error.
-    pub fn consume_gas(&self, address: Address, gas_requested: u64) -> Result<(), GasLimitError> {
-        if let Some(inner) = &self.inner {
-            inner.consume_gas(address, gas_requested)
+    /// Check if there's enough gas for this sender, and for the called contract if it has its
+    /// own bucket, and consume it. Returns Ok(()) if there's enough otherwise returns an error.
+    pub fn consume_gas(
+        &self,
+        address: Address,
+        to: Option<Address>,
+        gas_requested: u64,
+    ) -> Result<(), GasLimitError> {
+        if self.exempt.read().contains(&address) {
+            return Ok(());
+        }
+
+        if let Some(inner) = self.inner.read().as_ref() {
+            inner.consume_gas(address, to, gas_requested)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks if there's enough gas for this sender, and for the called contract if it has its
+    /// own bucket, without consuming it.
+    pub fn check_gas(
+        &self,
+        address: Address,
+        to: Option<Address>,
+        gas_requested: u64,
+    ) -> Result<(), GasLimitError> {
+        if self.exempt.read().contains(&address) {
+            return Ok(());
+        }
+
+        if let Some(inner) = self.inner.read().as_ref() {
+            inner.check_gas(address, to, gas_requested)
         } else {
             Ok(())
         }
     }
 
-    /// Should be called upon each new block. Refills buckets/Garbage collection
+    /// Should be called upon each new block. Reloads the policy file if it changed,
+    /// refills buckets, drops idle buckets and evicts buckets above the tracked addresses cap
     pub fn refresh(&self, block_number: u64) {
-        if let Some(inner) = self.inner.as_ref() {
+        // the policy file is read before taking the lock, so that the limiter isn't blocked on
+        // file I/O
+        let policy_file = self
+            .inner
+            .read()
+            .as_ref()
+            .and_then(AddressGasLimiterInner::policy_file);
+        let load = policy_file.and_then(PolicyFile::read);
+
+        if let Some(inner) = self.inner.write().as_mut() {
+            if let Some(load) = load {
+                inner.apply_policy_load(load);
+            }
             inner.refresh(block_number)
         }
     }
 }
 
 impl AddressGasLimiterInner {
-    fn try_new(config: GasLimiterArgs) -> Option<Self> {
+    fn try_new(config: GasLimiterArgs, load: Option<PolicyLoad>) -> Option<Self> {
         if !config.gas_limiter_enabled {
             return None;
         }
 
-        Some(Self {
+        let mut inner = Self {
             config,
+            policy: Default::default(),
+            policy_modified: None,
+            policy_load_failed: false,
+            current_block: 0,
             address_buckets: Default::default(),
+            contract_buckets: Default::default(),
             metrics: Default::default(),
+        };
+        if let Some(load) = load {
+            inner.apply_policy_load(load);
+        }
+        Some(inner)
+    }
+
+    /// Applies a new configuration, along with the new policy file read by the caller when it
+    /// changed.
+    fn reconfigure(&mut self, config: &GasLimiterArgs, load: Option<PolicyLoad>) {
+        if self.config == *config {
+            return;
+        }
+
+        let policy_file_changed =
+            self.config.gas_limiter_policy_file != config.gas_limiter_policy_file;
+        self.config = config.clone();
+        if policy_file_changed {
+            self.policy = Default::default();
+            self.policy_modified = None;
+            self.policy_load_failed = false;
+            if let Some(load) = load {
+                self.apply_policy_load(load);
+            }
+        }
+        self.update_bucket_limits();
+    }
+
+    /// Returns the policy file of the current configuration, to check it for changes.
+    fn policy_file(&self) -> Option<PolicyFile> {
+        Some(PolicyFile {
+            path: self.config.gas_limiter_policy_file.clone()?,
+            last_modified: self.policy_modified,
+            last_load_failed: self.policy_load_failed,
         })
     }
 
+    /// Applies a policy file read by the caller, unless the policy file was reconfigured since.
+    /// If the new policy is invalid, the current one is kept.
+    fn apply_policy_load(&mut self, load: PolicyLoad) {
+        if self.config.gas_limiter_policy_file.as_ref() != Some(&load.path) {
+            return;
+        }
+
+        let path = &load.path;
+        match load.result {
+            Ok(policy) => {
+                info!(
+                    target: "gas_limiter",
+                    path = %path.display(),
+                    "Loaded gas limiter policy"
+                );
+                self.policy = policy;
+                self.policy_load_failed = false;
+                self.metrics.policy_reloads.increment(1);
+            }
+            Err(e) => {
+                warn!(
+                    target: "gas_limiter",
+                    path = %path.display(),
+                    error = %e,
+                    "Failed to load gas limiter policy, keeping the current one"
+                );
+                self.policy_load_failed = true;
+                self.metrics.policy_reload_errors.increment(1);
+            }
+        }
+        self.policy_modified = load.modified;
+        self.update_bucket_limits();
+    }
+
+    fn default_limits(&self) -> BucketLimits {
+        self.policy.default_limits().unwrap_or(BucketLimits {
+            capacity: self.config.max_gas_per_address,
+            refill_rate: self.config.refill_rate_per_block,
+        })
+    }
+
+    /// Applies the limits of the current configuration and policy to the existing buckets.
+    fn update_bucket_limits(&self) {
+        let default_limits = self.default_limits();
+        self.address_buckets.iter_mut().for_each(|mut bucket| {
+            let limits = match self.policy.sender_policy(bucket.key()) {
+                SenderPolicy::Limited(limits) => limits.unwrap_or(default_limits),
+                SenderPolicy::Exempt | SenderPolicy::Allowlisted => default_limits,
+            };
+            bucket.set_limits(limits);
+        });
+        self.contract_buckets.retain(|contract, bucket| {
+            match self.policy.contract_limits(contract) {
+                Some(limits) => {
+                    bucket.set_limits(limits);
+                    true
+                }
+                None => false,
+            }
+        });
+    }
+
+    fn check_gas(
+        &self,
+        address: Address,
+        to: Option<Address>,
+        gas_requested: u64,
+    ) -> Result<(), GasLimitError> {
+        let limits = match self.policy.sender_policy(&address) {
+            SenderPolicy::Exempt | SenderPolicy::Allowlisted => return Ok(()),
+            SenderPolicy::Limited(limits) => limits.unwrap_or_else(|| self.default_limits()),
+        };
+
+        // senders without a bucket have a full one
+        let available = self
+            .address_buckets
+            .get(&address)
+            .map_or(limits.capacity, |bucket| bucket.available);
+        if gas_requested > available {
+            return Err(GasLimitError::AddressLimitExceeded {
+                address,
+                requested: gas_requested,
+                available,
+            });
+        }
+
+        if let Some(contract) = to
+            && let Some(contract_limits) = self.policy.contract_limits(&contract)
+        {
+            let available = self
+                .contract_buckets
+                .get(&contract)
+                .map_or(contract_limits.capacity, |bucket| bucket.available);
+            if gas_requested > available {
+                return Err(GasLimitError::ContractLimitExceeded {
+                    contract,
+                    requested: gas_requested,
+                    available,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
     fn consume_gas_inner(
         &self,
         address: Address,
+        to: Option<Address>,
         gas_requested: u64,
     ) -> Result<bool, GasLimitError> {
+        let limits = match self.policy.sender_policy(&address) {
+            SenderPolicy::Exempt => return Ok(false),
+            SenderPolicy::Allowlisted => {
+                self.metrics.allowlisted_gas.increment(gas_requested);
+                return Ok(false);
+            }
+            SenderPolicy::Limited(limits) => limits.unwrap_or_else(|| self.default_limits()),
+        };
+
         let mut created_new_bucket = false;
         let mut bucket = self
             .address_buckets
@@ -79,8 +392,9 @@ impl AddressGasLimiterInner {
             // if we don't find a bucket we need to initialize a new one
             .or_insert_with(|| {
                 created_new_bucket = true;
-                TokenBucket::new(self.config.max_gas_per_address)
+                TokenBucket::new(limits)
             });
+        bucket.last_used = self.current_block;
 
         if gas_requested > bucket.available {
             return Err(GasLimitError::AddressLimitExceeded {
@@ -90,55 +404,138 @@ impl AddressGasLimiterInner {
             });
         }
 
+        // the sender is only charged if the contract bucket has enough gas as well
+        if let Some(contract) = to
+            && let Some(contract_limits) = self.policy.contract_limits(&contract)
+        {
+            let mut contract_bucket = self
+                .contract_buckets
+                .entry(contract)
+                .or_insert_with(|| TokenBucket::new(contract_limits));
+            contract_bucket.last_used = self.current_block;
+
+            if gas_requested > contract_bucket.available {
+                return Err(GasLimitError::ContractLimitExceeded {
+                    contract,
+                    requested: gas_requested,
+                    available: contract_bucket.available,
+                });
+            }
+
+            contract_bucket.available -= gas_requested;
+        }
+
         bucket.available -= gas_requested;
 
         Ok(created_new_bucket)
     }
 
-    fn consume_gas(&self, address: Address, gas_requested: u64) -> Result<(), GasLimitError> {
+    fn consume_gas(
+        &self,
+        address: Address,
+        to: Option<Address>,
+        gas_requested: u64,
+    ) -> Result<(), GasLimitError> {
         let start = Instant::now();
-        let result = self.consume_gas_inner(address, gas_requested);
+        let result = self.consume_gas_inner(address, to, gas_requested);
 
         self.metrics.record_gas_check(&result, start.elapsed());
 
         result.map(|_| ())
     }
 
-    fn refresh_inner(&self, block_number: u64) -> usize {
-        let active_addresses = self.address_buckets.len();
-
-        self.address_buckets.iter_mut().for_each(|mut bucket| {
-            bucket.available = min(
-                bucket.capacity,
-                bucket.available + self.config.refill_rate_per_block,
-            )
-        });
+    fn refresh_inner(&mut self, block_number: u64) -> (usize, usize) {
+        self.current_block = block_number;
+
+        self.address_buckets
+            .iter_mut()
+            .chain(self.contract_buckets.iter_mut())
+            .for_each(|mut bucket| {
+                bucket.available = min(
+                    bucket.capacity,
+                    bucket.available.saturating_add(bucket.refill_rate),
+                )
+            });
 
-        // Only clean up stale buckets every `cleanup_interval` blocks
+        // Only clean up stale buckets every `cleanup_interval` blocks. A bucket is stale once
+        // it is full and was not used for `cleanup_interval` blocks, dropping it is then
+        // equivalent to creating a new one on the next transaction of the sender.
+        let mut idle_evictions = 0;
         if block_number.is_multiple_of(self.config.cleanup_interval) {
+            let tracked_addresses = self.address_buckets.len();
+            let cleanup_interval = self.config.cleanup_interval;
             self.address_buckets
-                .retain(|_, bucket| bucket.available <= bucket.capacity);
+                .retain(|_, bucket| !bucket.is_idle(block_number, cleanup_interval));
+            idle_evictions = tracked_addresses - self.address_buckets.len();
         }
 
-        active_addresses - self.address_buckets.len()
+        (idle_evictions, self.evict_above_cap())
     }
 
-    fn refresh(&self, block_number: u64) {
-        let start = Instant::now();
-        let removed_addresses = self.refresh_inner(block_number);
+    /// Evicts buckets until at most `max_tracked_addresses` are tracked. Full buckets are
+    /// evicted first as this does not change the limits of their senders, then the least
+    /// recently used ones.
+    fn evict_above_cap(&self) -> usize {
+        let max_tracked_addresses = self.config.max_tracked_addresses as usize;
+        let excess = self
+            .address_buckets
+            .len()
+            .saturating_sub(max_tracked_addresses);
+        if excess == 0 {
+            return 0;
+        }
 
-        self.metrics
-            .record_refresh(removed_addresses, start.elapsed());
+        let mut candidates: Vec<_> = self
+            .address_buckets
+            .iter()
+            .map(|bucket| {
+                (
+                    (bucket.available < bucket.capacity, bucket.last_used),
+                    *bucket.key(),
+                )
+            })
+            .collect();
+        candidates.select_nth_unstable(excess - 1);
+        for (_, address) in &candidates[..excess] {
+            self.address_buckets.remove(address);
+        }
+
+        excess
+    }
+
+    fn refresh(&mut self, block_number: u64) {
+        let start = Instant::now();
+        let (idle_evictions, capacity_evictions) = self.refresh_inner(block_number);
+
+        self.metrics.record_refresh(
+            idle_evictions,
+            capacity_evictions,
+            self.address_buckets.len(),
+            start.elapsed(),
+        );
     }
 }
 
 impl TokenBucket {
-    fn new(capacity: u64) -> Self {
+    fn new(limits: BucketLimits) -> Self {
         Self {
-            capacity,
-            available: capacity,
+            capacity: limits.capacity,
+            available: limits.capacity,
+            refill_rate: limits.refill_rate,
+            last_used: 0,
         }
     }
+
+    fn is_idle(&self, block_number: u64, cleanup_interval: u64) -> bool {
+        self.available >= self.capacity
+            && block_number.saturating_sub(self.last_used) >= cleanup_interval
+    }
+
+    fn set_limits(&mut self, limits: BucketLimits) {
+        self.capacity = limits.capacity;
+        self.available = min(self.available, limits.capacity);
+        self.refill_rate = limits.refill_rate;
+    }
 }
 
 #[cfg(test)]
@@ -152,9 +549,20 @@ mod tests {
             max_gas_per_address: max_gas,
             refill_rate_per_block: refill_rate,
             cleanup_interval,
+            max_tracked_addresses: 100_000,
+            gas_limiter_policy_file: None,
+            gas_limiter_check_bundles: false,
         }
     }
 
+    fn tracked_addresses(limiter: &AddressGasLimiter) -> usize {
+        limiter
+            .inner
+            .read()
+            .as_ref()
+            .map_or(0, |inner| inner.address_buckets.len())
+    }
+
     fn test_address() -> Address {
         Address::from([1u8; 20])
     }
@@ -165,13 +573,13 @@ mod tests {
         let limiter = AddressGasLimiter::new(config);
 
         // Consume all gas
-        assert!(limiter.consume_gas(test_address(), 1000).is_ok());
-        assert!(limiter.consume_gas(test_address(), 1).is_err());
+        assert!(limiter.consume_gas(test_address(), None, 1000).is_ok());
+        assert!(limiter.consume_gas(test_address(), None, 1).is_err());
 
         // Refill and check available gas increased
         limiter.refresh(1);
-        assert!(limiter.consume_gas(test_address(), 200).is_ok());
-        assert!(limiter.consume_gas(test_address(), 1).is_err());
+        assert!(limiter.consume_gas(test_address(), None, 200).is_ok());
+        assert!(limiter.consume_gas(test_address(), None, 1).is_err());
     }
 
     #[test]
@@ -180,7 +588,7 @@ mod tests {
         let limiter = AddressGasLimiter::new(config);
 
         // Request more than capacity should fail
-        let result = limiter.consume_gas(test_address(), 1500);
+        let result = limiter.consume_gas(test_address(), None, 1500);
         assert!(result.is_err());
 
         if let Err(GasLimitError::AddressLimitExceeded { available, .. }) = result {
@@ -188,7 +596,7 @@ mod tests {
         }
 
         // Bucket should still be full after failed request
-        assert!(limiter.consume_gas(test_address(), 1000).is_ok());
+        assert!(limiter.consume_gas(test_address(), None, 1000).is_ok());
     }
 
     #[test]
@@ -202,22 +610,272 @@ mod tests {
         let attacker = Address::from([0x3; 20]);
 
         // Normal searchers use reasonable amounts
-        assert!(limiter.consume_gas(searcher1, 500_000).is_ok());
-        assert!(limiter.consume_gas(searcher2, 750_000).is_ok());
+        assert!(limiter.consume_gas(searcher1, None, 500_000).is_ok());
+        assert!(limiter.consume_gas(searcher2, None, 750_000).is_ok());
 
         // Attacker tries to consume massive amounts
-        assert!(limiter.consume_gas(attacker, 15_000_000).is_err()); // Should fail - over capacity
-        assert!(limiter.consume_gas(attacker, 5_000_000).is_ok()); // Should succeed - within capacity
+        assert!(limiter.consume_gas(attacker, None, 15_000_000).is_err()); // Should fail - over capacity
+        assert!(limiter.consume_gas(attacker, None, 5_000_000).is_ok()); // Should succeed - within capacity
 
         // Attacker tries to consume more
-        assert!(limiter.consume_gas(attacker, 6_000_000).is_err()); // Should fail - would exceed remaining
+        assert!(limiter.consume_gas(attacker, None, 6_000_000).is_err()); // Should fail - would exceed remaining
 
         // New block - refill
         limiter.refresh(1);
 
         // Everyone should get some gas back
-        assert!(limiter.consume_gas(searcher1, 1_000_000).is_ok()); // Had 9.5M + 1M refill, now 9.5M
-        assert!(limiter.consume_gas(searcher2, 1_000_000).is_ok()); // Had 9.25M + 1M refill, now 9.25M  
-        assert!(limiter.consume_gas(attacker, 1_000_000).is_ok()); // Had 5M + 1M refill, now 5M
+        assert!(limiter.consume_gas(searcher1, None, 1_000_000).is_ok()); // Had 9.5M + 1M refill, now 9.5M
+        assert!(limiter.consume_gas(searcher2, None, 1_000_000).is_ok()); // Had 9.25M + 1M refill, now 9.25M  
+        assert!(limiter.consume_gas(attacker, None, 1_000_000).is_ok()); // Had 5M + 1M refill, now 5M
+    }
+
+    #[test]
+    fn test_reconfigure() {
+        let limiter = AddressGasLimiter::new(create_test_config(1000, 100, 10));
+        let clone = limiter.clone();
+        assert!(limiter.consume_gas(test_address(), None, 400).is_ok());
+
+        // lowering the capacity caps the available gas of existing buckets
+        clone.reconfigure(&create_test_config(500, 100, 10));
+        assert!(limiter.consume_gas(test_address(), None, 501).is_err());
+        assert!(limiter.consume_gas(test_address(), None, 500).is_ok());
+
+        // disabling the limiter drops the buckets
+        clone.reconfigure(&GasLimiterArgs::default());
+        assert!(limiter.consume_gas(test_address(), None, 1_000_000).is_ok());
+
+        clone.reconfigure(&create_test_config(1000, 100, 10));
+        assert!(limiter.consume_gas(test_address(), None, 1000).is_ok());
+        assert!(limiter.consume_gas(test_address(), None, 1).is_err());
+    }
+
+    fn write_policy(dir: &tempfile::TempDir, policy: &str) -> GasLimiterArgs {
+        let path = dir.path().join("policy.toml");
+        std::fs::write(&path, policy).unwrap();
+        GasLimiterArgs {
+            gas_limiter_policy_file: Some(path),
+            ..create_test_config(1000, 100, 10)
+        }
+    }
+
+    #[test]
+    fn test_policy_overrides_and_bypass() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = write_policy(
+            &dir,
+            r#"
+allowlist = ["0x0202020202020202020202020202020202020202"]
+exempt = ["0x0303030303030303030303030303030303030303"]
+
+[default]
+capacity = 2000
+refill_rate = 100
+
+[[senders]]
+address = "0x0404040404040404040404040404040404040404"
+capacity = 500
+refill_rate = 50
+"#,
+        );
+        let builder = Address::from([0x5; 20]);
+        let limiter = AddressGasLimiter::new(config).with_exempt([builder]);
+
+        // default tier from the policy file
+        assert!(limiter.consume_gas(test_address(), None, 2000).is_ok());
+        assert!(limiter.consume_gas(test_address(), None, 1).is_err());
+
+        // sender override
+        let limited = Address::from([0x4; 20]);
+        assert!(limiter.consume_gas(limited, None, 501).is_err());
+        assert!(limiter.consume_gas(limited, None, 500).is_ok());
+
+        // allowlisted and exempt senders are never limited
+        for sender in [Address::from([0x2; 20]), Address::from([0x3; 20]), builder] {
+            assert!(limiter.consume_gas(sender, None, 1_000_000).is_ok());
+            assert!(limiter.consume_gas(sender, None, 1_000_000).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_contract_buckets() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = write_policy(
+            &dir,
+            r#"
+[[contracts]]
+address = "0x0909090909090909090909090909090909090909"
+capacity = 1500
+refill_rate = 500
+"#,
+        );
+        let limiter = AddressGasLimiter::new(config);
+        let contract = Some(Address::from([0x9; 20]));
+        let searcher1 = Address::from([0x1; 20]);
+        let searcher2 = Address::from([0x2; 20]);
+
+        // the contract bucket is shared by every sender
+        assert!(limiter.consume_gas(searcher1, contract, 1000).is_ok());
+        assert!(matches!(
+            limiter.consume_gas(searcher2, contract, 1000),
+            Err(GasLimitError::ContractLimitExceeded { available: 500, .. })
+        ));
+
+        // the sender is not charged when the contract bucket is empty
+        assert!(limiter.consume_gas(searcher2, None, 1000).is_ok());
+
+        limiter.refresh(1);
+        assert!(limiter.consume_gas(searcher2, contract, 100).is_ok());
+        assert!(limiter.consume_gas(searcher1, contract, 900).is_err());
+    }
+
+    #[test]
+    fn test_policy_hot_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = write_policy(
+            &dir,
+            r#"
+[default]
+capacity = 500
+refill_rate = 0
+"#,
+        );
+        let limiter = AddressGasLimiter::new(config.clone());
+        assert!(limiter.consume_gas(test_address(), None, 501).is_err());
+
+        // make sure the modification time changes on coarse grained file systems
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        write_policy(
+            &dir,
+            r#"allowlist = ["0x0101010101010101010101010101010101010101"]"#,
+        );
+        limiter.refresh(1);
+        assert!(limiter.consume_gas(test_address(), None, 10_000).is_ok());
+
+        // an invalid policy keeps the current one
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        write_policy(&dir, "allowlist = [");
+        limiter.refresh(2);
+        assert!(limiter.consume_gas(test_address(), None, 10_000).is_ok());
+    }
+
+    #[test]
+    fn test_policy_load_failures_are_reported_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = create_test_config(1000, 100, 10);
+        config.gas_limiter_policy_file = Some(dir.path().join("missing.toml"));
+        let limiter = AddressGasLimiter::new(config.clone());
+        let policy_load_failed =
+            |limiter: &AddressGasLimiter| limiter.inner.read().as_ref().unwrap().policy_load_failed;
+        assert!(policy_load_failed(&limiter));
+
+        // the missing file is not loaded again on every block
+        limiter.refresh(1);
+        assert!(policy_load_failed(&limiter));
+
+        // the file is loaded once it shows up
+        std::fs::write(dir.path().join("missing.toml"), "").unwrap();
+        limiter.refresh(2);
+        assert!(!policy_load_failed(&limiter));
+    }
+
+    #[test]
+    fn test_exempt_at_runtime() {
+        let limiter = AddressGasLimiter::new(create_test_config(1000, 100, 10));
+        let tee_address = Address::from([0x6; 20]);
+        assert!(limiter.consume_gas(tee_address, None, 1001).is_err());
+
+        limiter.add_exempt(tee_address);
+        assert!(limiter.consume_gas(tee_address, None, 1_000_000).is_ok());
+        assert!(limiter.check_gas(tee_address, None, 1_000_000).is_ok());
+    }
+
+    #[test]
+    fn test_idle_buckets_are_collected() {
+        let limiter = AddressGasLimiter::new(create_test_config(1000, 1000, 10));
+        let idle = Address::from([0x1; 20]);
+        let drained = Address::from([0x2; 20]);
+        let active = Address::from([0x3; 20]);
+
+        assert!(limiter.consume_gas(idle, None, 1000).is_ok());
+        for block_number in 1..10 {
+            limiter.refresh(block_number);
+            assert!(limiter.consume_gas(active, None, 1).is_ok());
+        }
+        assert_eq!(tracked_addresses(&limiter), 2);
+
+        // the idle bucket was refilled and unused for `cleanup_interval` blocks
+        limiter.refresh(10);
+        assert_eq!(tracked_addresses(&limiter), 1);
+
+        // a bucket that is not full yet is kept, even if unused
+        let limiter = AddressGasLimiter::new(create_test_config(1000, 0, 10));
+        assert!(limiter.consume_gas(drained, None, 1000).is_ok());
+        limiter.refresh(10);
+        limiter.refresh(20);
+        assert_eq!(tracked_addresses(&limiter), 1);
+        assert!(limiter.consume_gas(drained, None, 1).is_err());
+    }
+
+    #[test]
+    fn test_tracked_addresses_are_bounded() {
+        let config = GasLimiterArgs {
+            max_tracked_addresses: 100,
+            ..create_test_config(1000, 100, 1000)
+        };
+        let limiter = AddressGasLimiter::new(config);
+
+        // every sender is seen once and keeps a partially used bucket, so the idle collector
+        // never drops them
+        for block_number in 1..=100u64 {
+            for i in 0..1000u64 {
+                let sender = Address::left_padding_from(&(block_number * 1000 + i).to_be_bytes());
+                assert!(limiter.consume_gas(sender, None, 500).is_ok());
+            }
+            limiter.refresh(block_number);
+            assert!(tracked_addresses(&limiter) <= 100);
+        }
+    }
+
+    #[test]
+    fn test_full_buckets_are_evicted_first() {
+        let config = GasLimiterArgs {
+            max_tracked_addresses: 2,
+            ..create_test_config(1000, 0, 1000)
+        };
+        let limiter = AddressGasLimiter::new(config);
+        let old_full = Address::from([0x1; 20]);
+        let drained = Address::from([0x2; 20]);
+        let new_full = Address::from([0x3; 20]);
+
+        assert!(limiter.consume_gas(old_full, None, 0).is_ok());
+        limiter.refresh(1);
+        assert!(limiter.consume_gas(drained, None, 500).is_ok());
+        assert!(limiter.consume_gas(new_full, None, 0).is_ok());
+        limiter.refresh(2);
+
+        assert_eq!(tracked_addresses(&limiter), 2);
+        let inner = limiter.inner.read();
+        let buckets = &inner.as_ref().unwrap().address_buckets;
+        assert!(!buckets.contains_key(&old_full));
+        assert_eq!(buckets.get(&drained).unwrap().available, 500);
+        assert!(buckets.contains_key(&new_full));
+    }
+
+    #[test]
+    fn test_check_gas_does_not_consume() {
+        let limiter = AddressGasLimiter::new(create_test_config(1000, 100, 10));
+
+        // senders without a bucket are checked against the capacity
+        assert!(limiter.check_gas(test_address(), None, 1000).is_ok());
+        assert!(limiter.check_gas(test_address(), None, 1001).is_err());
+        assert_eq!(tracked_addresses(&limiter), 0);
+
+        assert!(limiter.consume_gas(test_address(), None, 600).is_ok());
+        assert!(limiter.check_gas(test_address(), None, 400).is_ok());
+        assert!(limiter.check_gas(test_address(), None, 400).is_ok());
+        assert!(matches!(
+            limiter.check_gas(test_address(), None, 401),
+            Err(GasLimitError::AddressLimitExceeded { available: 400, .. })
+        ));
     }
 }
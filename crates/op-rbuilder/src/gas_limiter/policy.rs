@@ -0,0 +1,283 @@
+//! Gas limiter policy file.
+//!
+//! The policy sets bucket limits by sender and by destination contract, and lists the senders
+//! that bypass the limiter. It is written in TOML, or in JSON when the file has a `.json`
+//! extension:
+//!
+//! ```toml
+//! # senders that bypass the limiter, such as known market makers
+//! allowlist = ["0x1111111111111111111111111111111111111111"]
+//! # senders that are never limited, such as TEE signers
+//! exempt = ["0x2222222222222222222222222222222222222222"]
+//!
+//! # limits of senders without an override
+//! [default]
+//! capacity = 10000000
+//! refill_rate = 1000000
+//!
+//! [[senders]]
+//! address = "0x3333333333333333333333333333333333333333"
+//! capacity = 1000000
+//! refill_rate = 100000
+//!
+//! # bucket shared by every transaction calling the contract
+//! [[contracts]]
+//! address = "0x4444444444444444444444444444444444444444"
+//! capacity = 5000000
+//! refill_rate = 500000
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use alloy_primitives::Address;
+use serde::Deserialize;
+
+use crate::gas_limiter::error::PolicyError;
+
+/// Capacity and refill rate of a gas bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BucketLimits {
+    /// Maximum gas held by the bucket.
+    pub capacity: u64,
+    /// Gas added to the bucket on every block.
+    pub refill_rate: u64,
+}
+
+/// Bucket limits of a single sender or contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AddressLimits {
+    pub address: Address,
+    pub capacity: u64,
+    pub refill_rate: u64,
+}
+
+impl AddressLimits {
+    fn limits(&self) -> BucketLimits {
+        BucketLimits {
+            capacity: self.capacity,
+            refill_rate: self.refill_rate,
+        }
+    }
+}
+
+/// Policy file contents, see the module documentation for the format.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyFile {
+    #[serde(default)]
+    pub allowlist: Vec<Address>,
+    #[serde(default)]
+    pub exempt: Vec<Address>,
+    pub default: Option<BucketLimits>,
+    #[serde(default)]
+    pub senders: Vec<AddressLimits>,
+    #[serde(default)]
+    pub contracts: Vec<AddressLimits>,
+}
+
+/// How the limiter treats a sender.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum SenderPolicy {
+    /// Never limited.
+    Exempt,
+    /// Bypasses the limiter, tracked in the metrics.
+    Allowlisted,
+    /// Limited with the given bucket limits, the default tier when unset.
+    Limited(Option<BucketLimits>),
+}
+
+/// Gas limiter policy, indexed by address.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GasLimiterPolicy {
+    allowlist: HashSet<Address>,
+    exempt: HashSet<Address>,
+    default: Option<BucketLimits>,
+    senders: HashMap<Address, BucketLimits>,
+    contracts: HashMap<Address, BucketLimits>,
+}
+
+impl GasLimiterPolicy {
+    /// Loads a policy from a TOML file, or a JSON file if the path has a `.json` extension.
+    pub fn load(path: &Path) -> Result<Self, PolicyError> {
+        let contents = std::fs::read_to_string(path)?;
+        let file = if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            serde_json::from_str(&contents)?
+        } else {
+            toml::from_str(&contents)?
+        };
+        Self::try_from(file)
+    }
+
+    pub(super) fn sender_policy(&self, sender: &Address) -> SenderPolicy {
+        if self.exempt.contains(sender) {
+            SenderPolicy::Exempt
+        } else if self.allowlist.contains(sender) {
+            SenderPolicy::Allowlisted
+        } else {
+            SenderPolicy::Limited(self.senders.get(sender).copied())
+        }
+    }
+
+    pub(super) fn default_limits(&self) -> Option<BucketLimits> {
+        self.default
+    }
+
+    pub(super) fn contract_limits(&self, contract: &Address) -> Option<BucketLimits> {
+        self.contracts.get(contract).copied()
+    }
+}
+
+impl TryFrom<PolicyFile> for GasLimiterPolicy {
+    type Error = PolicyError;
+
+    fn try_from(file: PolicyFile) -> Result<Self, Self::Error> {
+        let index = |entries: Vec<AddressLimits>| {
+            let mut limits = HashMap::with_capacity(entries.len());
+            for entry in entries {
+                if limits.insert(entry.address, entry.limits()).is_some() {
+                    return Err(PolicyError::DuplicateAddress(entry.address));
+                }
+            }
+            Ok(limits)
+        };
+
+        Ok(Self {
+            allowlist: file.allowlist.into_iter().collect(),
+            exempt: file.exempt.into_iter().collect(),
+            default: file.default,
+            senders: index(file.senders)?,
+            contracts: index(file.contracts)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+
+    const POLICY: &str = r#"
+allowlist = ["0x1111111111111111111111111111111111111111"]
+exempt = ["0x2222222222222222222222222222222222222222"]
+
+[default]
+capacity = 10000000
+refill_rate = 1000000
+
+[[senders]]
+address = "0x3333333333333333333333333333333333333333"
+capacity = 1000000
+refill_rate = 100000
+
+[[contracts]]
+address = "0x4444444444444444444444444444444444444444"
+capacity = 5000000
+refill_rate = 500000
+"#;
+
+    fn load(name: &str, contents: &str) -> Result<GasLimiterPolicy, PolicyError> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(name);
+        std::fs::write(&path, contents).unwrap();
+        GasLimiterPolicy::load(&path)
+    }
+
+    #[test]
+    fn test_load_toml_policy() {
+        let policy = load("policy.toml", POLICY).unwrap();
+
+        assert_eq!(
+            policy.sender_policy(&address!("0x1111111111111111111111111111111111111111")),
+            SenderPolicy::Allowlisted
+        );
+        assert_eq!(
+            policy.sender_policy(&address!("0x2222222222222222222222222222222222222222")),
+            SenderPolicy::Exempt
+        );
+        assert_eq!(
+            policy.sender_policy(&address!("0x3333333333333333333333333333333333333333")),
+            SenderPolicy::Limited(Some(BucketLimits {
+                capacity: 1_000_000,
+                refill_rate: 100_000,
+            }))
+        );
+        assert_eq!(
+            policy.sender_policy(&Address::ZERO),
+            SenderPolicy::Limited(None)
+        );
+        assert_eq!(
+            policy.default_limits(),
+            Some(BucketLimits {
+                capacity: 10_000_000,
+                refill_rate: 1_000_000,
+            })
+        );
+        assert_eq!(
+            policy.contract_limits(&address!("0x4444444444444444444444444444444444444444")),
+            Some(BucketLimits {
+                capacity: 5_000_000,
+                refill_rate: 500_000,
+            })
+        );
+    }
+
+    #[test]
+    fn test_load_json_policy() {
+        let policy = load(
+            "policy.json",
+            r#"{
+                "allowlist": ["0x1111111111111111111111111111111111111111"],
+                "contracts": [
+                    {"address": "0x4444444444444444444444444444444444444444", "capacity": 5000000, "refill_rate": 500000}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            policy.sender_policy(&address!("0x1111111111111111111111111111111111111111")),
+            SenderPolicy::Allowlisted
+        );
+        assert_eq!(policy.default_limits(), None);
+        assert!(
+            policy
+                .contract_limits(&address!("0x4444444444444444444444444444444444444444"))
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_invalid_policies_are_rejected() {
+        let duplicate = r#"
+[[senders]]
+address = "0x3333333333333333333333333333333333333333"
+capacity = 1
+refill_rate = 1
+
+[[senders]]
+address = "0x3333333333333333333333333333333333333333"
+capacity = 2
+refill_rate = 2
+"#;
+        assert!(matches!(
+            load("policy.toml", duplicate),
+            Err(PolicyError::DuplicateAddress(_))
+        ));
+        assert!(matches!(
+            load("policy.toml", "unknown = 1"),
+            Err(PolicyError::Toml(_))
+        ));
+        assert!(matches!(
+            load("policy.json", "{}}"),
+            Err(PolicyError::Json(_))
+        ));
+    }
+}
@@ -2,12 +2,23 @@ use eyre::Result;
 use reth_optimism_rpc::OpEthApiBuilder;
 
 use crate::{
+    admission::FBPoolBuilder,
     args::*,
     builders::{BuilderConfig, BuilderMode, FlashblocksBuilder, PayloadBuilder, StandardBuilder},
+    flashtestations::{
+        block_proofs::{BlockProofApiServer, BlockProofExt, track_canonical_blocks},
+        status::{
+            FlashtestationsStatusApiServer, FlashtestationsStatusExt, FlashtestationsStatusMonitor,
+        },
+    },
     metrics::{VERSION, record_flag_gauge_metrics},
     monitor_tx_pool::monitor_tx_pool,
     primitives::reth::engine_api_builder::OpEngineApiBuilder,
+    private_tx::{PrivateTransactionApiServer, PrivateTransactionExt},
+    reputation::{ReputationApiServer, ReputationExt},
     revert_protection::{EthApiExtServer, RevertProtectionExt},
+    runtime_config::{BuilderAdminApiServer, BuilderAdminExt},
+    simulation::{BundleSimulationApiServer, BundleSimulationExt},
     tx::FBPooledTransaction,
     tx_data_store::{BaseApiExtServer, TxDataStoreExt},
 };
@@ -22,6 +33,7 @@ use reth_optimism_node::{
     OpNode,
     node::{OpAddOns, OpAddOnsBuilder, OpEngineValidatorBuilder, OpPoolBuilder},
 };
+use reth_provider::CanonStateSubscriptions;
 use reth_transaction_pool::TransactionPool;
 use std::{marker::PhantomData, sync::Arc};
 
@@ -111,6 +123,23 @@ where
         let reverted_cache = Cache::builder().max_capacity(100).build();
         let reverted_cache_copy = reverted_cache.clone();
         let tx_data_store = builder_config.tx_data_store.clone();
+        let runtime_config = builder_config.runtime_config.clone();
+        let sender_reputation = builder_config.sender_reputation.clone();
+        let pending_block = builder_config.pending_block.clone();
+        let block_time = builder_config.block_time;
+        let bundle_range = builder_config.bundle_range;
+        let private_txs = builder_config.private_txs.clone();
+        let block_proofs = builder_config.block_proofs.clone();
+        let rpc_block_proofs = block_proofs.clone();
+        let flashtestations_status = builder_config.flashtestations_status.clone();
+        let rpc_flashtestations_status = flashtestations_status.clone();
+        let flashtestations_args = builder_args.flashtestations.clone();
+        let bundle_gas_limiter = builder_args
+            .gas_limiter
+            .gas_limiter_check_bundles
+            .then(|| builder_config.address_gas_limiter.clone());
+        let admission = builder_config.admission.clone();
+        let simulation_gas_limiter = builder_config.address_gas_limiter.clone();
 
         let mut addons: OpAddOns<
             _,
@@ -133,7 +162,7 @@ where
             .with_components(
                 op_node
                     .components()
-                    .pool(
+                    .pool(FBPoolBuilder::new(
                         OpPoolBuilder::<FBPooledTransaction>::default()
                             .with_enable_tx_conditional(
                                 // Revert protection uses the same internal pool logic as conditional transactions
@@ -145,7 +174,8 @@ where
                                 rollup_args.supervisor_http.clone(),
                                 rollup_args.supervisor_safety_level,
                             ),
-                    )
+                        admission,
+                    ))
                     .payload(B::new_service(builder_config)?),
             )
             .with_add_ons(addons)
@@ -160,6 +190,9 @@ where
                         provider,
                         ctx.registry.eth_api().clone(),
                         reverted_cache,
+                        tx_data_store.clone(),
+                        bundle_gas_limiter,
+                        bundle_range,
                     );
 
                     ctx.modules
@@ -170,6 +203,50 @@ where
                 ctx.modules
                     .add_or_replace_configured(tx_data_store_ext.into_rpc())?;
 
+                if builder_args.private_tx.private_tx_enabled {
+                    let private_tx_ext = PrivateTransactionExt::new(
+                        ctx.provider().clone(),
+                        private_txs,
+                        bundle_range,
+                    );
+                    ctx.modules
+                        .add_or_replace_configured(private_tx_ext.into_rpc())?;
+                }
+
+                let reputation_ext = ReputationExt::new(sender_reputation);
+                ctx.modules
+                    .add_or_replace_configured(reputation_ext.into_rpc())?;
+
+                if builder_args.flashtestations.flashtestations_enabled
+                    && builder_args.flashtestations.enable_block_proofs
+                {
+                    let block_proof_ext = BlockProofExt::new(rpc_block_proofs);
+                    ctx.modules
+                        .add_or_replace_configured(block_proof_ext.into_rpc())?;
+                }
+
+                if builder_args.flashtestations.flashtestations_enabled {
+                    let flashtestations_status_ext =
+                        FlashtestationsStatusExt::new(rpc_flashtestations_status);
+                    ctx.modules
+                        .add_or_replace_configured(flashtestations_status_ext.into_rpc())?;
+                }
+
+                let bundle_simulation_ext = BundleSimulationExt::new(
+                    ctx.provider().clone(),
+                    pending_block,
+                    runtime_config.clone(),
+                    simulation_gas_limiter,
+                    block_time,
+                );
+                ctx.modules
+                    .add_or_replace_configured(bundle_simulation_ext.into_rpc())?;
+
+                // runtime reconfiguration is only served on the authenticated RPC server
+                let builder_admin_ext = BuilderAdminExt::new(runtime_config);
+                ctx.auth_module
+                    .merge_auth_methods(builder_admin_ext.into_rpc())?;
+
                 Ok(())
             })
             .on_node_started(move |ctx| {
@@ -180,6 +257,20 @@ where
                     let task = monitor_tx_pool(listener, reverted_cache_copy);
                     ctx.task_executor.spawn_critical("txlogging", task);
                 }
+                if flashtestations_args.flashtestations_enabled {
+                    let monitor = FlashtestationsStatusMonitor::new(
+                        &flashtestations_args,
+                        flashtestations_status,
+                        ctx.provider.clone(),
+                    );
+                    ctx.task_executor.spawn(monitor.run());
+                    if flashtestations_args.enable_block_proofs {
+                        ctx.task_executor.spawn(track_canonical_blocks(
+                            block_proofs,
+                            ctx.provider.canonical_state_stream(),
+                        ));
+                    }
+                }
                 Ok(())
             })
             .launch()
@@ -1,3 +1,4 @@
+pub mod admission;
 pub mod args;
 pub mod builders;
 pub mod flashtestations;
@@ -6,7 +7,11 @@ pub mod launcher;
 pub mod metrics;
 mod monitor_tx_pool;
 pub mod primitives;
+pub mod private_tx;
+pub mod reputation;
 pub mod revert_protection;
+pub mod runtime_config;
+pub mod simulation;
 pub mod traits;
 pub mod tx;
 pub mod tx_data_store;
@@ -1,14 +1,14 @@
 use alloy_primitives::{Address, hex};
 use metrics::IntoF64;
+use parking_lot::Mutex;
 use reth_metrics::{
     Metrics,
-    metrics::{Counter, Gauge, Histogram, gauge},
+    metrics::{Counter, Gauge, Histogram, counter, gauge},
 };
+use std::{collections::HashSet, sync::LazyLock};
+use tdx_quote_parser::{compute_workload_id_from_parsed, parse_report_body};
 
-use crate::{
-    args::OpRbuilderArgs,
-    flashtestations::attestation::{compute_workload_id_from_parsed, parse_report_body},
-};
+use crate::args::OpRbuilderArgs;
 
 /// The latest version from Cargo.toml.
 pub const CARGO_PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -85,10 +85,18 @@ pub struct OpRBuilderMetrics {
     pub flashblock_byte_size_histogram: Histogram,
     /// Histogram of transactions in a Flashblock
     pub flashblock_num_tx_histogram: Histogram,
+    /// Number of flashblocks published unsigned because signing them failed
+    pub flashblock_signing_failures: Counter,
     /// Number of invalid blocks
     pub invalid_built_blocks_count: Counter,
     /// Number of invalid synced blocks
     pub invalid_synced_blocks_count: Counter,
+    /// Number of flashblock deltas received without matching cached state
+    pub unapplied_flashblock_deltas_count: Counter,
+    /// Whether this builder is the flashblocks leader (1) or a standby (0)
+    pub flashblocks_leader: Gauge,
+    /// Number of times this builder became leader or standby
+    pub leader_changes_count: Counter,
     /// Histogram of fetching transactions from the pool duration
     pub transaction_pool_fetch_duration: Histogram,
     /// Latest time taken to fetch tx from the pool
@@ -179,6 +187,8 @@ pub struct OpRBuilderMetrics {
     pub backrun_bundles_rejected_low_fee_total: Counter,
     /// Number of backrun bundles rejected due to exceeding block limits
     pub backrun_bundles_rejected_over_limits_total: Counter,
+    /// Number of backrun bundles rejected by the address gas limiter during simulation
+    pub backrun_bundles_rejected_gas_limit_total: Counter,
     /// Number of backrun bundles successfully landed in a block
     pub backrun_bundles_landed_total: Counter,
     /// Latency of inserting a backrun bundle into the store
@@ -230,6 +240,45 @@ pub fn record_flag_gauge_metrics(builder_args: &OpRbuilderArgs) {
         .set(builder_args.enable_revert_protection as i32);
 }
 
+/// Record an update of a builder setting through the admin RPC, along with its new value
+pub fn record_runtime_config_update(setting: &'static str, value: f64) {
+    counter!("op_rbuilder_runtime_config_updates_count", "setting" => setting).increment(1);
+    gauge!("op_rbuilder_runtime_config_value", "setting" => setting).set(value);
+}
+
+/// Maximum number of senders with their own series in the gas limiter rejection metrics, the
+/// rejections of further senders are recorded under the "other" sender.
+const MAX_LABELED_REJECTED_SENDERS: usize = 100;
+
+/// Senders with their own series in the gas limiter rejection metrics.
+static LABELED_REJECTED_SENDERS: LazyLock<Mutex<HashSet<Address>>> =
+    LazyLock::new(Default::default);
+
+/// Record gas used by a sender that was rejected by the address gas limiter, labeled by the
+/// source of the rejected gas: "transaction", "backrun" or "bundle". Only the first
+/// [`MAX_LABELED_REJECTED_SENDERS`] rejected senders are labeled by address, every rejection is
+/// logged along with its sender.
+pub fn record_gas_limiter_rejection(sender: Address, source: &'static str, gas: u64) {
+    tracing::debug!(target: "gas_limiter", %sender, source, gas, "gas limiter rejected sender");
+    let sender_label = {
+        let mut labeled = LABELED_REJECTED_SENDERS.lock();
+        if labeled.contains(&sender) || labeled.len() < MAX_LABELED_REJECTED_SENDERS {
+            labeled.insert(sender);
+            sender.to_string()
+        } else {
+            "other".to_string()
+        }
+    };
+    let labels = [("sender", sender_label), ("source", source.to_string())];
+    counter!("op_rbuilder_gas_limiter_sender_rejections_count", &labels).increment(1);
+    counter!("op_rbuilder_gas_limiter_sender_rejected_gas", &labels).increment(gas);
+}
+
+/// Record a header field that didn't match after re-executing a flashblock received from a peer
+pub fn record_flashblock_header_mismatch(field: &'static str) {
+    counter!("op_rbuilder_flashblock_header_mismatch_count", "field" => field).increment(1);
+}
+
 /// Record TEE workload ID and measurement metrics
 /// Parses the quote, computes workload ID, and records workload_id, mr_td (TEE measurement), and rt_mr0 (runtime measurement register 0)
 /// These identify the trusted execution environment configuration provided by GCP
@@ -68,6 +68,8 @@ impl MockFbTransactionFactory {
             reverted_hashes: None,
             flashblock_number_max: None,
             flashblock_number_min: None,
+            flashblock_time_max: None,
+            flashblock_time_min: None,
         })
     }
 
@@ -78,6 +80,8 @@ impl MockFbTransactionFactory {
             reverted_hashes: None,
             flashblock_number_max: max,
             flashblock_number_min: min,
+            flashblock_time_max: None,
+            flashblock_time_min: None,
         })
     }
 
@@ -88,9 +92,27 @@ impl MockFbTransactionFactory {
             reverted_hashes: None,
             flashblock_number_max: None,
             flashblock_number_min: None,
+            flashblock_time_max: None,
+            flashblock_time_min: None,
         })
     }
 
+    /// Creates an EIP-1559 [`MockFbTransaction`] of `sender` paying a priority fee of `tip`.
+    pub fn eip1559_with_tip(&self, sender: Address, nonce: u64, tip: u128) -> MockFbTransaction {
+        MockFbTransaction {
+            inner: MockTransaction::eip1559()
+                .with_sender(sender)
+                .with_nonce(nonce)
+                .with_max_fee(tip + 1)
+                .with_priority_fee(tip),
+            reverted_hashes: None,
+            flashblock_number_max: None,
+            flashblock_number_min: None,
+            flashblock_time_max: None,
+            flashblock_time_min: None,
+        }
+    }
+
     /// Creates a validated EIP-4844 [`MockTransaction`].
     pub fn create_eip4844(&mut self) -> MockValidFbTx {
         self.validated(MockFbTransaction {
@@ -98,6 +120,8 @@ impl MockFbTransactionFactory {
             reverted_hashes: None,
             flashblock_number_max: None,
             flashblock_number_min: None,
+            flashblock_time_max: None,
+            flashblock_time_min: None,
         })
     }
 }
@@ -112,6 +136,9 @@ pub struct MockFbTransaction {
 
     pub flashblock_number_min: Option<u64>,
     pub flashblock_number_max: Option<u64>,
+
+    pub flashblock_time_min: Option<u64>,
+    pub flashblock_time_max: Option<u64>,
 }
 
 /// A validated transaction in the transaction pool, using [`MockTransaction`] as the transaction
@@ -137,6 +164,8 @@ impl PoolTransaction for MockFbTransaction {
             reverted_hashes: None,
             flashblock_number_min: None,
             flashblock_number_max: None,
+            flashblock_time_min: None,
+            flashblock_time_max: None,
         }
     }
 
@@ -450,4 +479,22 @@ impl MaybeFlashblockFilter for MockFbTransaction {
     fn flashblock_number_max(&self) -> Option<u64> {
         self.flashblock_number_max
     }
+
+    fn with_flashblock_time_min(mut self, flashblock_time_min: Option<u64>) -> Self {
+        self.flashblock_time_min = flashblock_time_min;
+        self
+    }
+
+    fn with_flashblock_time_max(mut self, flashblock_time_max: Option<u64>) -> Self {
+        self.flashblock_time_max = flashblock_time_max;
+        self
+    }
+
+    fn flashblock_time_min(&self) -> Option<u64> {
+        self.flashblock_time_min
+    }
+
+    fn flashblock_time_max(&self) -> Option<u64> {
+        self.flashblock_time_max
+    }
 }
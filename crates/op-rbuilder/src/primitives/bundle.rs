@@ -1,15 +1,36 @@
-use alloy_primitives::{B256, Bytes};
+use alloy_primitives::{Address, B256, Bytes, Signature, eip191_hash_message};
 use alloy_rpc_types_eth::erc4337::TransactionConditional;
 use reth_rpc_eth_types::EthApiError;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-/// Maximum number of blocks allowed in the block range for bundle execution.
+/// Default maximum number of blocks allowed in the block range for bundle
+/// execution.
+pub const DEFAULT_MAX_BLOCK_RANGE_BLOCKS: u64 = 10;
+
+/// Limits on the block range of bundles.
 ///
-/// This constant limits how far into the future a bundle can be scheduled to
-/// prevent excessive resource usage and ensure timely execution. When no
-/// maximum block number is specified, this value is added to the current block
-/// number to set the default upper bound.
-pub const MAX_BLOCK_RANGE_BLOCKS: u64 = 10;
+/// The maximum range limits how far into the future a bundle can be scheduled
+/// to prevent excessive resource usage and ensure timely execution. When no
+/// maximum block number is specified, the default range is added to the
+/// current block number to set the upper bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BundleRangeLimits {
+    /// Maximum number of blocks between the current block and `maxBlockNumber`.
+    pub max_range_blocks: u64,
+    /// Number of blocks added to the current block when `maxBlockNumber` is
+    /// not set.
+    pub default_range_blocks: u64,
+}
+
+impl Default for BundleRangeLimits {
+    fn default() -> Self {
+        Self {
+            max_range_blocks: DEFAULT_MAX_BLOCK_RANGE_BLOCKS,
+            default_range_blocks: DEFAULT_MAX_BLOCK_RANGE_BLOCKS,
+        }
+    }
+}
 
 /// A bundle represents a collection of transactions that should be executed
 /// together with specific conditional constraints.
@@ -25,10 +46,14 @@ pub const MAX_BLOCK_RANGE_BLOCKS: u64 = 10;
 /// mempool:
 /// - Block number ranges are valid (min ≤ max)
 /// - Maximum block numbers are not in the past
-/// - Block ranges don't exceed `MAX_BLOCK_RANGE_BLOCKS` (currently 10)
-/// - There's only one transaction in the bundle
+/// - Block ranges don't exceed the configured [`BundleRangeLimits`]
+/// - There's only one transaction in the bundle, or none when it cancels the
+///   bundle with the same replacement uuid, in which case it must be signed by
+///   the signer of that bundle
 /// - Flashblock number ranges are valid (min ≤ max)
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+/// - Timestamp and flashblock time ranges are valid (min ≤ max) and their
+///   maximums are not before the latest block timestamp
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct Bundle {
     /// List of raw transaction data to be included in the bundle.
     ///
@@ -63,7 +88,7 @@ pub struct Bundle {
     ///
     /// If specified, the bundle will be considered invalid for inclusion in
     /// blocks after this block number. If not specified, defaults to the
-    /// current block number plus `MAX_BLOCK_RANGE_BLOCKS`.
+    /// current block number plus the configured default range.
     #[serde(
         default,
         rename = "maxBlockNumber",
@@ -99,10 +124,8 @@ pub struct Bundle {
 
     /// Minimum timestamp (Unix epoch seconds) for bundle inclusion.
     ///
-    /// **Warning**: Not recommended for production use as it depends on the
-    /// builder node's clock, which may not be perfectly synchronized with
-    /// network time. Block number constraints are preferred for deterministic
-    /// behavior.
+    /// Evaluated against the timestamp of the block being built, as set by the
+    /// payload attributes, so it doesn't depend on the builder node's clock.
     #[serde(
         default,
         rename = "minTimestamp",
@@ -112,16 +135,84 @@ pub struct Bundle {
 
     /// Maximum timestamp (Unix epoch seconds) for bundle inclusion.
     ///
-    /// **Warning**: Not recommended for production use as it depends on the
-    /// builder node's clock, which may not be perfectly synchronized with
-    /// network time. Block number constraints are preferred for deterministic
-    /// behavior.
+    /// Evaluated against the timestamp of the block being built, as set by the
+    /// payload attributes, so it doesn't depend on the builder node's clock.
     #[serde(
         default,
         rename = "maxTimestamp",
         skip_serializing_if = "Option::is_none"
     )]
     pub max_timestamp: Option<u64>,
+
+    /// Minimum inclusion time (Unix epoch milliseconds) for bundle inclusion.
+    ///
+    /// In flashblocks mode, this is evaluated against the projected emission
+    /// time of each flashblock, derived from the block timestamp and the
+    /// flashblocks interval, which allows targeting sub-second windows. In
+    /// standard mode, the block timestamp is used.
+    #[serde(
+        default,
+        rename = "minFlashblockTime",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub min_flashblock_time: Option<u64>,
+
+    /// Maximum inclusion time (Unix epoch milliseconds) for bundle inclusion.
+    ///
+    /// Evaluated like `minFlashblockTime`. It also bounds the block timestamp,
+    /// rounded up to the second, so that the bundle leaves the pool once it
+    /// expired.
+    #[serde(
+        default,
+        rename = "maxFlashblockTime",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_flashblock_time: Option<u64>,
+
+    /// Identifier chosen by the sender to replace or cancel the bundle.
+    ///
+    /// A bundle sent with the replacement uuid of a pending bundle replaces
+    /// it, and a bundle without transactions cancels it.
+    #[serde(
+        default,
+        rename = "replacementUuid",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub replacement_uuid: Option<Uuid>,
+
+    /// Signature of the cancellation of a bundle, see
+    /// [`Bundle::cancellation_hash`].
+    ///
+    /// Only the signer of the bundle with the replacement uuid can cancel it.
+    #[serde(
+        default,
+        rename = "cancellationSignature",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cancellation_signature: Option<Bytes>,
+}
+
+impl Bundle {
+    /// Returns whether the bundle cancels the bundle with the same
+    /// replacement uuid.
+    pub fn is_cancellation(&self) -> bool {
+        self.replacement_uuid.is_some() && self.transactions.is_empty()
+    }
+
+    /// Returns the hash signed to cancel the bundle with the given replacement
+    /// uuid, the EIP-191 hash of the bytes of the uuid.
+    pub fn cancellation_hash(replacement_uuid: Uuid) -> B256 {
+        eip191_hash_message(replacement_uuid.as_bytes())
+    }
+
+    /// Returns the signer of a cancellation, if it's signed.
+    pub fn cancellation_signer(&self) -> Option<Address> {
+        let hash = Self::cancellation_hash(self.replacement_uuid?);
+        Signature::try_from(self.cancellation_signature.as_deref()?)
+            .ok()?
+            .recover_address_from_prehash(&hash)
+            .ok()
+    }
 }
 
 impl From<BundleConditionalError> for EthApiError {
@@ -137,37 +228,57 @@ pub enum BundleConditionalError {
     #[error("block_number_max ({max}) is a past block (current: {current})")]
     MaxBlockInPast { max: u64, current: u64 },
     /// To prevent resource exhaustion and ensure timely execution, bundles
-    /// cannot be scheduled more than `MAX_BLOCK_RANGE_BLOCKS` blocks into the
-    /// future.
+    /// cannot be scheduled more than the configured maximum range of blocks
+    /// into the future.
     #[error(
-        "block_number_max ({max}) is too high (current: {current}, max allowed: {max_allowed})"
+        "block_number_max ({max}) is too high (current: {current}, max allowed: {max_allowed}, max range: {max_range} blocks)"
     )]
     MaxBlockTooHigh {
         max: u64,
         current: u64,
         max_allowed: u64,
+        max_range: u64,
     },
     /// When no explicit maximum block number is provided, the system uses
-    /// `current_block + MAX_BLOCK_RANGE_BLOCKS` as the default maximum. This
-    /// error occurs when the specified minimum exceeds this default maximum.
+    /// the current block plus the configured default range as the maximum.
+    /// This error occurs when the specified minimum exceeds this default
+    /// maximum.
     #[error(
-        "block_number_min ({min}) is too high with default max range (max allowed: {max_allowed})"
+        "block_number_min ({min}) is too high with default max range (max allowed: {max_allowed}, default range: {default_range} blocks)"
     )]
-    MinTooHighForDefaultRange { min: u64, max_allowed: u64 },
+    MinTooHighForDefaultRange {
+        min: u64,
+        max_allowed: u64,
+        default_range: u64,
+    },
     #[error("flashblock_number_min ({min}) is greater than flashblock_number_max ({max})")]
     FlashblockMinGreaterThanMax { min: u64, max: u64 },
+    #[error("min_timestamp ({min}) is greater than max_timestamp ({max})")]
+    TimestampMinGreaterThanMax { min: u64, max: u64 },
+    #[error("max_timestamp ({max}) is not after the latest block timestamp ({current})")]
+    MaxTimestampInPast { max: u64, current: u64 },
+    #[error("min_flashblock_time ({min}) is greater than max_flashblock_time ({max})")]
+    FlashblockTimeMinGreaterThanMax { min: u64, max: u64 },
+    /// Flashblock times are in milliseconds, while `current` is the latest
+    /// block timestamp in seconds.
+    #[error("max_flashblock_time ({max}) is not after the latest block timestamp ({current})")]
+    MaxFlashblockTimeInPast { max: u64, current: u64 },
 }
 
 pub struct BundleConditional {
     pub transaction_conditional: TransactionConditional,
     pub flashblock_number_min: Option<u64>,
     pub flashblock_number_max: Option<u64>,
+    pub flashblock_time_min: Option<u64>,
+    pub flashblock_time_max: Option<u64>,
 }
 
 impl Bundle {
     pub fn conditional(
         &self,
+        limits: BundleRangeLimits,
         last_block_number: u64,
+        last_block_timestamp: u64,
     ) -> Result<BundleConditional, BundleConditionalError> {
         let mut block_number_max = self.block_number_max;
         let block_number_min = self.block_number_min;
@@ -190,17 +301,18 @@ impl Bundle {
             }
 
             // Validate that it is not greater than the max_block_range
-            let max_allowed = last_block_number + MAX_BLOCK_RANGE_BLOCKS;
+            let max_allowed = last_block_number + limits.max_range_blocks;
             if max > max_allowed {
                 return Err(BundleConditionalError::MaxBlockTooHigh {
                     max,
                     current: last_block_number,
                     max_allowed,
+                    max_range: limits.max_range_blocks,
                 });
             }
         } else {
-            // If no upper bound is set, use the maximum block range
-            let default_max = last_block_number + MAX_BLOCK_RANGE_BLOCKS;
+            // If no upper bound is set, use the default block range
+            let default_max = last_block_number + limits.default_range_blocks;
             block_number_max = Some(default_max);
 
             // Ensure that the new max is not smaller than the min
@@ -210,6 +322,7 @@ impl Bundle {
                 return Err(BundleConditionalError::MinTooHighForDefaultRange {
                     min,
                     max_allowed: default_max,
+                    default_range: limits.default_range_blocks,
                 });
             }
         }
@@ -222,16 +335,59 @@ impl Bundle {
             return Err(BundleConditionalError::FlashblockMinGreaterThanMax { min, max });
         }
 
+        // Validate timestamp range, the next block has a later timestamp than the latest one
+        if let Some(min) = self.min_timestamp
+            && let Some(max) = self.max_timestamp
+            && min > max
+        {
+            return Err(BundleConditionalError::TimestampMinGreaterThanMax { min, max });
+        }
+        if let Some(max) = self.max_timestamp
+            && max <= last_block_timestamp
+        {
+            return Err(BundleConditionalError::MaxTimestampInPast {
+                max,
+                current: last_block_timestamp,
+            });
+        }
+
+        // Validate flashblock time range
+        if let Some(min) = self.min_flashblock_time
+            && let Some(max) = self.max_flashblock_time
+            && min > max
+        {
+            return Err(BundleConditionalError::FlashblockTimeMinGreaterThanMax { min, max });
+        }
+        if let Some(max) = self.max_flashblock_time
+            && max <= last_block_timestamp.saturating_mul(1000)
+        {
+            return Err(BundleConditionalError::MaxFlashblockTimeInPast {
+                max,
+                current: last_block_timestamp,
+            });
+        }
+
+        // the pool evicts transactions past their max timestamp, expire bundles past their max
+        // flashblock time the same way
+        let timestamp_max = self
+            .max_flashblock_time
+            .map(|max| max.div_ceil(1000))
+            .into_iter()
+            .chain(self.max_timestamp)
+            .min();
+
         Ok(BundleConditional {
             transaction_conditional: TransactionConditional {
                 block_number_min,
                 block_number_max,
                 known_accounts: Default::default(),
-                timestamp_max: self.max_timestamp,
+                timestamp_max,
                 timestamp_min: self.min_timestamp,
             },
             flashblock_number_min: self.flashblock_number_min,
             flashblock_number_max: self.flashblock_number_max,
+            flashblock_time_min: self.min_flashblock_time,
+            flashblock_time_max: self.max_flashblock_time,
         })
     }
 }
@@ -256,6 +412,8 @@ pub struct BundleResult {
 mod tests {
     use super::*;
 
+    const LAST_BLOCK_TIMESTAMP: u64 = 1_700_000_000;
+
     #[test]
     fn test_bundle_conditional_no_bounds() {
         let bundle = Bundle {
@@ -265,14 +423,18 @@ mod tests {
 
         let last_block = 1000;
         let result = bundle
-            .conditional(last_block)
+            .conditional(
+                BundleRangeLimits::default(),
+                last_block,
+                LAST_BLOCK_TIMESTAMP,
+            )
             .unwrap()
             .transaction_conditional;
 
         assert_eq!(result.block_number_min, None);
         assert_eq!(
             result.block_number_max,
-            Some(last_block + MAX_BLOCK_RANGE_BLOCKS)
+            Some(last_block + DEFAULT_MAX_BLOCK_RANGE_BLOCKS)
         );
     }
 
@@ -286,7 +448,11 @@ mod tests {
 
         let last_block = 1000;
         let result = bundle
-            .conditional(last_block)
+            .conditional(
+                BundleRangeLimits::default(),
+                last_block,
+                LAST_BLOCK_TIMESTAMP,
+            )
             .unwrap()
             .transaction_conditional;
 
@@ -303,7 +469,11 @@ mod tests {
         };
 
         let last_block = 1000;
-        let result = bundle.conditional(last_block);
+        let result = bundle.conditional(
+            BundleRangeLimits::default(),
+            last_block,
+            LAST_BLOCK_TIMESTAMP,
+        );
 
         assert!(matches!(
             result,
@@ -322,7 +492,11 @@ mod tests {
         };
 
         let last_block = 1000;
-        let result = bundle.conditional(last_block);
+        let result = bundle.conditional(
+            BundleRangeLimits::default(),
+            last_block,
+            LAST_BLOCK_TIMESTAMP,
+        );
 
         assert!(matches!(
             result,
@@ -341,14 +515,19 @@ mod tests {
         };
 
         let last_block = 1000;
-        let result = bundle.conditional(last_block);
+        let result = bundle.conditional(
+            BundleRangeLimits::default(),
+            last_block,
+            LAST_BLOCK_TIMESTAMP,
+        );
 
         assert!(matches!(
             result,
             Err(BundleConditionalError::MaxBlockTooHigh {
                 max: 1020,
                 current: 1000,
-                max_allowed: 1010
+                max_allowed: 1010,
+                max_range: DEFAULT_MAX_BLOCK_RANGE_BLOCKS
             })
         ));
     }
@@ -361,13 +540,18 @@ mod tests {
         };
 
         let last_block = 1000;
-        let result = bundle.conditional(last_block);
+        let result = bundle.conditional(
+            BundleRangeLimits::default(),
+            last_block,
+            LAST_BLOCK_TIMESTAMP,
+        );
 
         assert!(matches!(
             result,
             Err(BundleConditionalError::MinTooHighForDefaultRange {
                 min: 1015,
-                max_allowed: 1010
+                max_allowed: 1010,
+                default_range: DEFAULT_MAX_BLOCK_RANGE_BLOCKS
             })
         ));
     }
@@ -381,12 +565,16 @@ mod tests {
 
         let last_block = 1000;
         let result = bundle
-            .conditional(last_block)
+            .conditional(
+                BundleRangeLimits::default(),
+                last_block,
+                LAST_BLOCK_TIMESTAMP,
+            )
             .unwrap()
             .transaction_conditional;
 
         assert_eq!(result.block_number_min, Some(1005));
-        assert_eq!(result.block_number_max, Some(1010)); // last_block + MAX_BLOCK_RANGE_BLOCKS
+        assert_eq!(result.block_number_max, Some(1010)); // last_block + DEFAULT_MAX_BLOCK_RANGE_BLOCKS
     }
 
     #[test]
@@ -398,7 +586,11 @@ mod tests {
 
         let last_block = 1000;
         let result = bundle
-            .conditional(last_block)
+            .conditional(
+                BundleRangeLimits::default(),
+                last_block,
+                LAST_BLOCK_TIMESTAMP,
+            )
             .unwrap()
             .transaction_conditional;
 
@@ -415,7 +607,11 @@ mod tests {
 
         let last_block = 1000;
         let result = bundle
-            .conditional(last_block)
+            .conditional(
+                BundleRangeLimits::default(),
+                last_block,
+                LAST_BLOCK_TIMESTAMP,
+            )
             .unwrap()
             .transaction_conditional;
 
@@ -432,7 +628,11 @@ mod tests {
         };
 
         let last_block = 1000;
-        let result = bundle.conditional(last_block);
+        let result = bundle.conditional(
+            BundleRangeLimits::default(),
+            last_block,
+            LAST_BLOCK_TIMESTAMP,
+        );
 
         assert!(matches!(
             result,
@@ -449,7 +649,13 @@ mod tests {
         };
 
         let last_block = 1000;
-        let result = bundle.conditional(last_block).unwrap();
+        let result = bundle
+            .conditional(
+                BundleRangeLimits::default(),
+                last_block,
+                LAST_BLOCK_TIMESTAMP,
+            )
+            .unwrap();
 
         assert_eq!(result.flashblock_number_min, Some(100));
         assert_eq!(result.flashblock_number_max, Some(105));
@@ -463,7 +669,13 @@ mod tests {
         };
 
         let last_block = 1000;
-        let result = bundle.conditional(last_block).unwrap();
+        let result = bundle
+            .conditional(
+                BundleRangeLimits::default(),
+                last_block,
+                LAST_BLOCK_TIMESTAMP,
+            )
+            .unwrap();
 
         assert_eq!(result.flashblock_number_min, Some(100));
         assert_eq!(result.flashblock_number_max, None);
@@ -477,7 +689,13 @@ mod tests {
         };
 
         let last_block = 1000;
-        let result = bundle.conditional(last_block).unwrap();
+        let result = bundle
+            .conditional(
+                BundleRangeLimits::default(),
+                last_block,
+                LAST_BLOCK_TIMESTAMP,
+            )
+            .unwrap();
 
         assert_eq!(result.flashblock_number_min, None);
         assert_eq!(result.flashblock_number_max, Some(105));
@@ -492,9 +710,189 @@ mod tests {
         };
 
         let last_block = 1000;
-        let result = bundle.conditional(last_block).unwrap();
+        let result = bundle
+            .conditional(
+                BundleRangeLimits::default(),
+                last_block,
+                LAST_BLOCK_TIMESTAMP,
+            )
+            .unwrap();
 
         assert_eq!(result.flashblock_number_min, Some(100));
         assert_eq!(result.flashblock_number_max, Some(100));
     }
+
+    #[test]
+    fn test_bundle_conditional_timestamp_min_greater_than_max() {
+        let bundle = Bundle {
+            min_timestamp: Some(LAST_BLOCK_TIMESTAMP + 10),
+            max_timestamp: Some(LAST_BLOCK_TIMESTAMP + 5),
+            ..Default::default()
+        };
+
+        let result = bundle.conditional(BundleRangeLimits::default(), 1000, LAST_BLOCK_TIMESTAMP);
+
+        assert!(matches!(
+            result,
+            Err(BundleConditionalError::TimestampMinGreaterThanMax { .. })
+        ));
+    }
+
+    #[test]
+    fn test_bundle_conditional_max_timestamp_in_past() {
+        let bundle = Bundle {
+            max_timestamp: Some(LAST_BLOCK_TIMESTAMP),
+            ..Default::default()
+        };
+
+        let result = bundle.conditional(BundleRangeLimits::default(), 1000, LAST_BLOCK_TIMESTAMP);
+
+        assert!(matches!(
+            result,
+            Err(BundleConditionalError::MaxTimestampInPast {
+                max: LAST_BLOCK_TIMESTAMP,
+                current: LAST_BLOCK_TIMESTAMP
+            })
+        ));
+    }
+
+    #[test]
+    fn test_bundle_conditional_flashblock_time() {
+        let last_block_time_ms = LAST_BLOCK_TIMESTAMP * 1000;
+        let bundle = Bundle {
+            min_flashblock_time: Some(last_block_time_ms + 200),
+            max_flashblock_time: Some(last_block_time_ms + 400),
+            ..Default::default()
+        };
+
+        let result = bundle
+            .conditional(BundleRangeLimits::default(), 1000, LAST_BLOCK_TIMESTAMP)
+            .unwrap();
+
+        assert_eq!(result.flashblock_time_min, Some(last_block_time_ms + 200));
+        assert_eq!(result.flashblock_time_max, Some(last_block_time_ms + 400));
+        assert_eq!(
+            result.transaction_conditional.timestamp_max,
+            Some(LAST_BLOCK_TIMESTAMP + 1)
+        );
+        assert_eq!(result.transaction_conditional.timestamp_min, None);
+    }
+
+    #[test]
+    fn test_bundle_conditional_max_flashblock_time_bounds_max_timestamp() {
+        let last_block_time_ms = LAST_BLOCK_TIMESTAMP * 1000;
+        let bundle = Bundle {
+            max_timestamp: Some(LAST_BLOCK_TIMESTAMP + 5),
+            max_flashblock_time: Some(last_block_time_ms + 2000),
+            ..Default::default()
+        };
+        let result = bundle
+            .conditional(BundleRangeLimits::default(), 1000, LAST_BLOCK_TIMESTAMP)
+            .unwrap();
+        assert_eq!(
+            result.transaction_conditional.timestamp_max,
+            Some(LAST_BLOCK_TIMESTAMP + 2)
+        );
+
+        let bundle = Bundle {
+            max_timestamp: Some(LAST_BLOCK_TIMESTAMP + 1),
+            max_flashblock_time: Some(last_block_time_ms + 2000),
+            ..Default::default()
+        };
+        let result = bundle
+            .conditional(BundleRangeLimits::default(), 1000, LAST_BLOCK_TIMESTAMP)
+            .unwrap();
+        assert_eq!(
+            result.transaction_conditional.timestamp_max,
+            Some(LAST_BLOCK_TIMESTAMP + 1)
+        );
+    }
+
+    #[test]
+    fn test_bundle_conditional_flashblock_time_min_greater_than_max() {
+        let last_block_time_ms = LAST_BLOCK_TIMESTAMP * 1000;
+        let bundle = Bundle {
+            min_flashblock_time: Some(last_block_time_ms + 400),
+            max_flashblock_time: Some(last_block_time_ms + 200),
+            ..Default::default()
+        };
+
+        let result = bundle.conditional(BundleRangeLimits::default(), 1000, LAST_BLOCK_TIMESTAMP);
+
+        assert!(matches!(
+            result,
+            Err(BundleConditionalError::FlashblockTimeMinGreaterThanMax { .. })
+        ));
+    }
+
+    #[test]
+    fn test_bundle_conditional_max_flashblock_time_in_past() {
+        let bundle = Bundle {
+            max_flashblock_time: Some(LAST_BLOCK_TIMESTAMP * 1000 - 1),
+            ..Default::default()
+        };
+
+        let result = bundle.conditional(BundleRangeLimits::default(), 1000, LAST_BLOCK_TIMESTAMP);
+
+        assert!(matches!(
+            result,
+            Err(BundleConditionalError::MaxFlashblockTimeInPast {
+                current: LAST_BLOCK_TIMESTAMP,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_bundle_conditional_custom_range_limits() {
+        let limits = BundleRangeLimits {
+            max_range_blocks: 50,
+            default_range_blocks: 5,
+        };
+
+        // The default range applies when no max is set
+        let bundle = Bundle::default();
+        let result = bundle
+            .conditional(limits, 1000, LAST_BLOCK_TIMESTAMP)
+            .unwrap()
+            .transaction_conditional;
+        assert_eq!(result.block_number_max, Some(1005));
+
+        // An explicit max can go up to the max range
+        let bundle = Bundle {
+            block_number_max: Some(1050),
+            ..Default::default()
+        };
+        let result = bundle
+            .conditional(limits, 1000, LAST_BLOCK_TIMESTAMP)
+            .unwrap()
+            .transaction_conditional;
+        assert_eq!(result.block_number_max, Some(1050));
+
+        let bundle = Bundle {
+            block_number_max: Some(1051),
+            ..Default::default()
+        };
+        assert!(matches!(
+            bundle.conditional(limits, 1000, LAST_BLOCK_TIMESTAMP),
+            Err(BundleConditionalError::MaxBlockTooHigh {
+                max_allowed: 1050,
+                max_range: 50,
+                ..
+            })
+        ));
+
+        let bundle = Bundle {
+            block_number_min: Some(1006),
+            ..Default::default()
+        };
+        assert!(matches!(
+            bundle.conditional(limits, 1000, LAST_BLOCK_TIMESTAMP),
+            Err(BundleConditionalError::MinTooHighForDefaultRange {
+                max_allowed: 1005,
+                default_range: 5,
+                ..
+            })
+        ));
+    }
 }
@@ -0,0 +1,25 @@
+use clap::{Args, Parser};
+use reth_optimism_cli::commands::Commands;
+
+use crate::args::Cli;
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct PrivateTxArgs {
+    /// Serve `eth_sendPrivateTransaction`. Private transactions are never propagated to peers.
+    #[arg(long = "private-tx.enabled", env, default_value = "false")]
+    pub private_tx_enabled: bool,
+
+    /// Maximum number of private transactions waiting for inclusion.
+    #[arg(long = "private-tx.max-transactions", env, default_value = "10000")]
+    pub private_tx_max_transactions: usize,
+}
+
+impl Default for PrivateTxArgs {
+    fn default() -> Self {
+        let args = Cli::parse_from(["dummy", "node"]);
+        let Commands::Node(node_command) = args.command else {
+            unreachable!()
+        };
+        node_command.ext.private_tx
+    }
+}
@@ -0,0 +1,156 @@
+use alloy_consensus::Transaction;
+use alloy_primitives::Address;
+use reth_payload_util::PayloadTransactions;
+use reth_transaction_pool::PoolTransaction;
+use std::collections::{HashMap, VecDeque};
+
+/// Merges private transactions into the best transactions of the pool, by effective tip.
+///
+/// The transactions of a sender are yielded in nonce order.
+pub(crate) struct PrivateBestTransactions<'a, I: PayloadTransactions> {
+    inner: &'a mut I,
+    /// Next transaction of the inner iterator, compared with the private transactions.
+    peeked: Option<I::Transaction>,
+    /// Private transactions by sender, in nonce order.
+    private: Vec<VecDeque<I::Transaction>>,
+    base_fee: u64,
+}
+
+impl<'a, I> PrivateBestTransactions<'a, I>
+where
+    I: PayloadTransactions<Transaction: PoolTransaction>,
+{
+    pub(crate) fn new(inner: &'a mut I, private: Vec<I::Transaction>, base_fee: u64) -> Self {
+        let mut by_sender: HashMap<Address, Vec<I::Transaction>> = HashMap::new();
+        for tx in private {
+            by_sender.entry(tx.sender()).or_default().push(tx);
+        }
+        let private = by_sender
+            .into_values()
+            .map(|mut txs| {
+                txs.sort_by_key(|tx| tx.nonce());
+                txs.into()
+            })
+            .collect();
+
+        Self {
+            inner,
+            peeked: None,
+            private,
+            base_fee,
+        }
+    }
+
+    fn tip(&self, tx: &I::Transaction) -> u128 {
+        tx.effective_tip_per_gas(self.base_fee).unwrap_or_default()
+    }
+}
+
+impl<I> PayloadTransactions for PrivateBestTransactions<'_, I>
+where
+    I: PayloadTransactions<Transaction: PoolTransaction>,
+{
+    type Transaction = I::Transaction;
+
+    fn next(&mut self, ctx: ()) -> Option<Self::Transaction> {
+        if self.peeked.is_none() {
+            self.peeked = self.inner.next(ctx);
+        }
+
+        let best_private = self
+            .private
+            .iter()
+            .enumerate()
+            .filter_map(|(index, txs)| Some((index, self.tip(txs.front()?))))
+            .max_by_key(|(_, tip)| *tip);
+
+        match best_private {
+            Some((_, tip)) if self.peeked.as_ref().is_some_and(|tx| self.tip(tx) > tip) => {
+                self.peeked.take()
+            }
+            Some((index, _)) => self.private[index].pop_front(),
+            None => self.peeked.take(),
+        }
+    }
+
+    fn mark_invalid(&mut self, sender: Address, nonce: u64) {
+        self.inner.mark_invalid(sender, nonce);
+        if self
+            .peeked
+            .as_ref()
+            .is_some_and(|tx| tx.sender() == sender && tx.nonce() >= nonce)
+        {
+            self.peeked = None;
+        }
+        for txs in &mut self.private {
+            txs.retain(|tx| tx.sender() != sender || tx.nonce() < nonce);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_tx::{MockFbTransaction, MockFbTransactionFactory};
+    use reth_payload_util::BestPayloadTransactions;
+    use reth_transaction_pool::{CoinbaseTipOrdering, pool::PendingPool};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_merge_by_tip() {
+        let mut pool = PendingPool::new(CoinbaseTipOrdering::<MockFbTransaction>::default());
+        let mut f = MockFbTransactionFactory::default();
+        let public = Address::from([0x1; 20]);
+        let private = Address::from([0x2; 20]);
+
+        pool.add_transaction(Arc::new(f.validated(f.eip1559_with_tip(public, 0, 20))), 0);
+        pool.add_transaction(Arc::new(f.validated(f.eip1559_with_tip(public, 1, 5))), 0);
+
+        let mut best = BestPayloadTransactions::new(pool.best());
+        let mut iterator = PrivateBestTransactions::new(
+            &mut best,
+            vec![
+                f.eip1559_with_tip(private, 1, 30),
+                f.eip1559_with_tip(private, 0, 10),
+            ],
+            0,
+        );
+
+        // the private transactions of a sender are yielded in nonce order
+        let order: Vec<_> = std::iter::from_fn(|| iterator.next(()))
+            .map(|tx| (tx.sender(), tx.nonce()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![(public, 0), (private, 0), (private, 1), (public, 1)]
+        );
+    }
+
+    #[test]
+    fn test_mark_invalid() {
+        let mut pool = PendingPool::new(CoinbaseTipOrdering::<MockFbTransaction>::default());
+        let mut f = MockFbTransactionFactory::default();
+        let public = Address::from([0x1; 20]);
+        let private = Address::from([0x2; 20]);
+
+        pool.add_transaction(Arc::new(f.validated(f.eip1559_with_tip(public, 0, 5))), 0);
+
+        let mut best = BestPayloadTransactions::new(pool.best());
+        let mut iterator = PrivateBestTransactions::new(
+            &mut best,
+            vec![
+                f.eip1559_with_tip(private, 0, 10),
+                f.eip1559_with_tip(private, 1, 10),
+            ],
+            0,
+        );
+
+        let tx = iterator.next(()).unwrap();
+        assert_eq!((tx.sender(), tx.nonce()), (private, 0));
+        iterator.mark_invalid(private, 0);
+
+        let tx = iterator.next(()).unwrap();
+        assert_eq!((tx.sender(), tx.nonce()), (public, 0));
+        assert!(iterator.next(()).is_none());
+    }
+}
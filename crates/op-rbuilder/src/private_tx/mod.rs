@@ -0,0 +1,414 @@
+//! Private order flow submitted through `eth_sendPrivateTransaction`.
+//!
+//! Private transactions are kept in a [`PrivateTxPool`] next to the transaction pool, so they are
+//! never gossiped to peers, and are merged with the best transactions of the pool when building
+//! blocks.
+
+use std::{collections::HashMap, sync::Arc};
+
+use alloy_consensus::Transaction;
+use alloy_primitives::{Address, Bytes, TxHash};
+use jsonrpsee::{
+    core::{RpcResult, async_trait},
+    proc_macros::rpc,
+};
+use parking_lot::Mutex;
+use reth_optimism_primitives::OpTransactionSigned;
+use reth_primitives_traits::Recovered;
+use reth_provider::AccountReader;
+use reth_rpc_eth_types::{EthApiError, utils::recover_raw_transaction};
+use reth_transaction_pool::PoolTransaction;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::{
+    primitives::bundle::BundleRangeLimits,
+    private_tx::args::PrivateTxArgs,
+    traits::ClientBounds,
+    tx::{FBPoolTransaction, MaybeRevertingTransaction},
+};
+
+pub mod args;
+mod best_txs;
+
+pub(crate) use best_txs::PrivateBestTransactions;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PrivateTxError {
+    #[error("transaction already known")]
+    AlreadyKnown,
+    #[error("private transaction pool is full ({max} transactions)")]
+    PoolFull { max: usize },
+    #[error("deposit and blob transactions can't be sent privately")]
+    UnsupportedType,
+    #[error("nonce too low (nonce: {nonce}, state nonce: {state_nonce})")]
+    NonceTooLow { nonce: u64, state_nonce: u64 },
+    #[error("maxBlockNumber ({max}) is a past block (current: {current})")]
+    MaxBlockInPast { max: u64, current: u64 },
+    #[error(
+        "maxBlockNumber ({max}) is too high (current: {current}, max range: {max_range} blocks)"
+    )]
+    MaxBlockTooHigh {
+        max: u64,
+        current: u64,
+        max_range: u64,
+    },
+}
+
+impl From<PrivateTxError> for EthApiError {
+    fn from(err: PrivateTxError) -> Self {
+        EthApiError::InvalidParams(err.to_string())
+    }
+}
+
+/// A transaction waiting for inclusion in the [`PrivateTxPool`].
+#[derive(Debug, Clone)]
+pub struct PrivateTransaction {
+    pub tx: Recovered<OpTransactionSigned>,
+    /// Last block in which the transaction can be included.
+    pub max_block_number: u64,
+    /// Whether the transaction is left out of the block if it reverts.
+    pub revert_protected: bool,
+}
+
+impl PrivateTransaction {
+    /// Converts the transaction into a pool transaction of the payload builders.
+    fn to_pool_transaction<T: FBPoolTransaction<Consensus = OpTransactionSigned>>(
+        &self,
+    ) -> Option<T> {
+        let tx = T::try_from_consensus(self.tx.clone()).ok()?;
+        // an empty list of reverting hashes excludes the transaction if it reverts, like a
+        // bundle without `revertingTxHashes`
+        Some(if self.revert_protected {
+            tx.with_reverted_hashes(vec![])
+        } else {
+            tx
+        })
+    }
+}
+
+/// Transactions sent through `eth_sendPrivateTransaction`, by hash.
+///
+/// Transactions are removed once they expire or once the nonce of their sender moved past them,
+/// see [`PrivateTxPool::prune`].
+#[derive(Debug, Clone)]
+pub struct PrivateTxPool {
+    transactions: Arc<Mutex<PrivateTransactions>>,
+    max_transactions: usize,
+}
+
+#[derive(Debug, Default)]
+struct PrivateTransactions {
+    by_hash: HashMap<TxHash, PrivateTransaction>,
+    /// Hash of the transaction of each sender and nonce, to find the transaction replaced by a
+    /// new one.
+    by_sender_nonce: HashMap<(Address, u64), TxHash>,
+}
+
+impl PrivateTxPool {
+    pub fn new(args: PrivateTxArgs) -> Self {
+        Self {
+            transactions: Default::default(),
+            max_transactions: args.private_tx_max_transactions,
+        }
+    }
+
+    /// Adds a transaction. A transaction with the same sender and nonce is replaced.
+    pub fn insert(&self, transaction: PrivateTransaction) -> Result<TxHash, PrivateTxError> {
+        let hash = *transaction.tx.tx_hash();
+        let mut transactions = self.transactions.lock();
+        if transactions.by_hash.contains_key(&hash) {
+            return Err(PrivateTxError::AlreadyKnown);
+        }
+
+        let key = (transaction.tx.signer(), transaction.tx.nonce());
+        match transactions.by_sender_nonce.get(&key).copied() {
+            Some(replaced) => {
+                transactions.by_hash.remove(&replaced);
+            }
+            None if transactions.by_hash.len() >= self.max_transactions => {
+                return Err(PrivateTxError::PoolFull {
+                    max: self.max_transactions,
+                });
+            }
+            None => {}
+        }
+
+        transactions.by_sender_nonce.insert(key, hash);
+        transactions.by_hash.insert(hash, transaction);
+        Ok(hash)
+    }
+
+    /// Removes the transactions that can't be included in `block_number` anymore, because they
+    /// expired or because their nonce is lower than the one of their sender in `state`.
+    pub fn prune(&self, block_number: u64, state: &(impl AccountReader + ?Sized)) {
+        let mut transactions = self.transactions.lock();
+        if transactions.by_hash.is_empty() {
+            return;
+        }
+
+        let PrivateTransactions {
+            by_hash,
+            by_sender_nonce,
+        } = &mut *transactions;
+        let mut nonces = HashMap::new();
+        by_hash.retain(|hash, pending| {
+            if pending.max_block_number < block_number {
+                debug!(target: "private_tx", tx_hash = ?hash, "Private transaction expired");
+                return false;
+            }
+            let state_nonce = *nonces.entry(pending.tx.signer()).or_insert_with(|| {
+                state
+                    .basic_account(&pending.tx.signer())
+                    .ok()
+                    .flatten()
+                    .map_or(0, |account| account.nonce)
+            });
+            pending.tx.nonce() >= state_nonce
+        });
+        by_sender_nonce.retain(|_, hash| by_hash.contains_key(hash));
+    }
+
+    /// Returns the transactions that can be included in `block_number` and are not `excluded`,
+    /// as pool transactions.
+    pub(crate) fn best_transactions<T: FBPoolTransaction<Consensus = OpTransactionSigned>>(
+        &self,
+        block_number: u64,
+        excluded: impl Fn(&TxHash) -> bool,
+    ) -> Vec<T> {
+        self.transactions
+            .lock()
+            .by_hash
+            .iter()
+            .filter(|(hash, pending)| pending.max_block_number >= block_number && !excluded(hash))
+            .filter_map(|(_, pending)| pending.to_pool_transaction())
+            .collect()
+    }
+
+    pub fn get(&self, hash: &TxHash) -> Option<PrivateTransaction> {
+        self.transactions.lock().by_hash.get(hash).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.lock().by_hash.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.lock().by_hash.is_empty()
+    }
+}
+
+impl Default for PrivateTxPool {
+    fn default() -> Self {
+        Self::new(PrivateTxArgs::default())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivateTransactionRequest {
+    /// Raw signed transaction.
+    pub tx: Bytes,
+    /// Last block in which the transaction can be included. Defaults to the default bundle
+    /// block range.
+    #[serde(
+        default,
+        with = "alloy_serde::quantity::opt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_block_number: Option<u64>,
+    /// Leave the transaction out of the block if it reverts.
+    #[serde(default)]
+    pub revert_protection: bool,
+}
+
+#[cfg_attr(not(test), rpc(server, namespace = "eth"))]
+#[cfg_attr(test, rpc(server, client, namespace = "eth"))]
+pub trait PrivateTransactionApi {
+    #[method(name = "sendPrivateTransaction")]
+    async fn send_private_transaction(
+        &self,
+        request: PrivateTransactionRequest,
+    ) -> RpcResult<TxHash>;
+}
+
+pub struct PrivateTransactionExt<Provider> {
+    provider: Provider,
+    private_txs: PrivateTxPool,
+    bundle_range: BundleRangeLimits,
+}
+
+impl<Provider> PrivateTransactionExt<Provider> {
+    pub fn new(
+        provider: Provider,
+        private_txs: PrivateTxPool,
+        bundle_range: BundleRangeLimits,
+    ) -> Self {
+        Self {
+            provider,
+            private_txs,
+            bundle_range,
+        }
+    }
+}
+
+impl<Provider: ClientBounds> PrivateTransactionExt<Provider> {
+    fn private_transaction(
+        &self,
+        request: PrivateTransactionRequest,
+    ) -> Result<PrivateTransaction, EthApiError> {
+        let tx = recover_raw_transaction::<OpTransactionSigned>(&request.tx)?;
+        if tx.is_deposit() || tx.is_eip4844() {
+            return Err(PrivateTxError::UnsupportedType.into());
+        }
+
+        let latest = self
+            .provider
+            .best_block_number()
+            .map_err(EthApiError::from)?;
+        let max_block_number = match request.max_block_number {
+            Some(max) if max <= latest => {
+                return Err(PrivateTxError::MaxBlockInPast {
+                    max,
+                    current: latest,
+                }
+                .into());
+            }
+            Some(max) if max > latest + self.bundle_range.max_range_blocks => {
+                return Err(PrivateTxError::MaxBlockTooHigh {
+                    max,
+                    current: latest,
+                    max_range: self.bundle_range.max_range_blocks,
+                }
+                .into());
+            }
+            Some(max) => max,
+            None => latest + self.bundle_range.default_range_blocks,
+        };
+
+        let state_nonce = self
+            .provider
+            .latest()
+            .map_err(EthApiError::from)?
+            .basic_account(&tx.signer())
+            .map_err(EthApiError::from)?
+            .map_or(0, |account| account.nonce);
+        if tx.nonce() < state_nonce {
+            return Err(PrivateTxError::NonceTooLow {
+                nonce: tx.nonce(),
+                state_nonce,
+            }
+            .into());
+        }
+
+        Ok(PrivateTransaction {
+            tx,
+            max_block_number,
+            revert_protected: request.revert_protection,
+        })
+    }
+}
+
+#[async_trait]
+impl<Provider> PrivateTransactionApiServer for PrivateTransactionExt<Provider>
+where
+    Provider: ClientBounds + Send + Sync + 'static,
+{
+    async fn send_private_transaction(
+        &self,
+        request: PrivateTransactionRequest,
+    ) -> RpcResult<TxHash> {
+        let transaction = self.private_transaction(request)?;
+        let hash = self
+            .private_txs
+            .insert(transaction)
+            .map_err(EthApiError::from)?;
+        Ok(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_consensus::{SignableTransaction, TxEip1559};
+    use alloy_primitives::{Address, Signature, TxKind, U256};
+    use reth_primitives_traits::Account;
+    use reth_provider::ProviderResult;
+
+    struct Nonces(HashMap<Address, u64>);
+
+    impl AccountReader for Nonces {
+        fn basic_account(&self, address: &Address) -> ProviderResult<Option<Account>> {
+            Ok(self.0.get(address).map(|nonce| Account {
+                nonce: *nonce,
+                ..Default::default()
+            }))
+        }
+    }
+
+    fn transaction(sender: Address, nonce: u64, max_block_number: u64) -> PrivateTransaction {
+        let tx = TxEip1559 {
+            chain_id: 1,
+            nonce,
+            gas_limit: 21_000,
+            to: TxKind::Call(Address::ZERO),
+            value: U256::from(max_block_number),
+            ..Default::default()
+        };
+        let signed = OpTransactionSigned::Eip1559(tx.into_signed(Signature::test_signature()));
+        PrivateTransaction {
+            tx: Recovered::new_unchecked(signed, sender),
+            max_block_number,
+            revert_protected: false,
+        }
+    }
+
+    fn pool(max_transactions: usize) -> PrivateTxPool {
+        PrivateTxPool::new(PrivateTxArgs {
+            private_tx_enabled: true,
+            private_tx_max_transactions: max_transactions,
+        })
+    }
+
+    #[test]
+    fn test_insert() {
+        let pool = pool(2);
+        let sender = Address::from([0x1; 20]);
+
+        let hash = pool.insert(transaction(sender, 0, 10)).unwrap();
+        assert!(pool.get(&hash).is_some());
+        assert!(matches!(
+            pool.insert(transaction(sender, 0, 10)),
+            Err(PrivateTxError::AlreadyKnown)
+        ));
+
+        // same sender and nonce replaces the transaction
+        let replacement = pool.insert(transaction(sender, 0, 11)).unwrap();
+        assert_eq!(pool.len(), 1);
+        assert!(pool.get(&hash).is_none());
+        assert!(pool.get(&replacement).is_some());
+
+        pool.insert(transaction(sender, 1, 10)).unwrap();
+        assert!(matches!(
+            pool.insert(transaction(sender, 2, 10)),
+            Err(PrivateTxError::PoolFull { max: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_prune() {
+        let pool = pool(10);
+        let sender = Address::from([0x1; 20]);
+        let other = Address::from([0x2; 20]);
+
+        let expired = pool.insert(transaction(other, 0, 5)).unwrap();
+        let included = pool.insert(transaction(sender, 0, 10)).unwrap();
+        let pending = pool.insert(transaction(sender, 1, 10)).unwrap();
+
+        pool.prune(6, &Nonces(HashMap::from([(sender, 1)])));
+
+        assert!(pool.get(&expired).is_none());
+        assert!(pool.get(&included).is_none());
+        assert!(pool.get(&pending).is_some());
+    }
+}
@@ -0,0 +1,31 @@
+use clap::{Args, Parser};
+use reth_optimism_cli::commands::Commands;
+
+use crate::args::Cli;
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct ReputationArgs {
+    /// Number of blocks over which the transaction outcomes of each sender are tracked.
+    #[arg(long = "reputation.window-blocks", env, default_value = "100")]
+    pub reputation_window_blocks: u64,
+
+    /// Number of blocks of the short window reported alongside the reputation window, to
+    /// surface recent changes in the behavior of a sender.
+    #[arg(long = "reputation.short-window-blocks", env, default_value = "10")]
+    pub reputation_short_window_blocks: u64,
+
+    /// Maximum number of senders tracked. When exceeded, the senders seen least recently are
+    /// forgotten first.
+    #[arg(long = "reputation.max-tracked-senders", env, default_value = "100000")]
+    pub reputation_max_tracked_senders: u64,
+}
+
+impl Default for ReputationArgs {
+    fn default() -> Self {
+        let args = Cli::parse_from(["dummy", "node"]);
+        let Commands::Node(node_command) = args.command else {
+            unreachable!()
+        };
+        node_command.ext.reputation
+    }
+}
@@ -0,0 +1,39 @@
+use metrics::{Counter, Gauge};
+use reth_metrics::Metrics;
+
+use crate::reputation::SenderStats;
+
+#[derive(Metrics, Clone)]
+#[metrics(scope = "op_rbuilder.reputation")]
+pub(super) struct ReputationMetrics {
+    /// Transactions simulated, across all senders
+    pub simulated_txs: Counter,
+
+    /// Transactions included in a block, including reverted ones
+    pub included_txs: Counter,
+
+    /// Transactions that reverted, whether they were included or not
+    pub reverted_txs: Counter,
+
+    /// Gas used by simulated transactions
+    pub gas_used: Counter,
+
+    /// Gas used by transactions that reverted or were not included
+    pub gas_wasted: Counter,
+
+    /// Number of senders tracked after the last block
+    pub tracked_senders: Gauge,
+
+    /// Senders forgotten because the tracked senders cap was exceeded
+    pub capacity_evictions: Counter,
+}
+
+impl ReputationMetrics {
+    pub(super) fn record_outcome(&self, outcome: &SenderStats) {
+        self.simulated_txs.increment(outcome.simulated);
+        self.included_txs.increment(outcome.included);
+        self.reverted_txs.increment(outcome.reverted);
+        self.gas_used.increment(outcome.gas_used);
+        self.gas_wasted.increment(outcome.gas_wasted);
+    }
+}
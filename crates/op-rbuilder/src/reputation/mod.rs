@@ -0,0 +1,545 @@
+//! Per-sender reputation, built from the outcomes of the transactions simulated by the builder.
+//!
+//! Each transaction is counted once per block, with the outcome of its last simulation in the
+//! block: flashblocks re-simulate the transactions left out of a previous flashblock and the
+//! standard builder rebuilds the block several times. Outcomes are aggregated per block and only the last `reputation.window-blocks` blocks are
+//! taken into account, so that a sender recovers its reputation once it stops sending
+//! reverting transactions. A shorter `reputation.short-window-blocks` window is reported
+//! alongside it through the `base_getSenderStats` RPC to surface recent changes in behavior.
+//!
+//! The revert share over the long window is used by the pool admission control, both to evict
+//! the transactions of senders that mostly revert and to build the transactions of senders
+//! that often revert after those of the other senders.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use alloy_primitives::{Address, TxHash};
+use dashmap::DashMap;
+use jsonrpsee::{
+    core::{RpcResult, async_trait},
+    proc_macros::rpc,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    primitives::reth::TxnExecutionResult,
+    reputation::{args::ReputationArgs, metrics::ReputationMetrics},
+};
+
+pub mod args;
+mod metrics;
+
+/// Outcomes of the transactions of a sender over a window of blocks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SenderStats {
+    /// Transactions simulated by the builder.
+    pub simulated: u64,
+    /// Transactions included in a block, including reverted ones.
+    pub included: u64,
+    /// Transactions that reverted, whether they were included or not.
+    pub reverted: u64,
+    /// Gas used by the simulated transactions.
+    pub gas_used: u64,
+    /// Gas used by transactions that reverted or were not included.
+    pub gas_wasted: u64,
+}
+
+impl SenderStats {
+    /// Returns the share of simulated transactions that reverted, in percent.
+    pub fn revert_percent(&self) -> u64 {
+        (self.reverted * 100)
+            .checked_div(self.simulated)
+            .unwrap_or(0)
+    }
+
+    /// Returns the share of simulated transactions that were included, in percent.
+    pub fn inclusion_percent(&self) -> u64 {
+        (self.included * 100)
+            .checked_div(self.simulated)
+            .unwrap_or(0)
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.simulated += other.simulated;
+        self.included += other.included;
+        self.reverted += other.reverted;
+        self.gas_used += other.gas_used;
+        self.gas_wasted += other.gas_wasted;
+    }
+
+    fn sub(&mut self, other: &Self) {
+        self.simulated = self.simulated.saturating_sub(other.simulated);
+        self.included = self.included.saturating_sub(other.included);
+        self.reverted = self.reverted.saturating_sub(other.reverted);
+        self.gas_used = self.gas_used.saturating_sub(other.gas_used);
+        self.gas_wasted = self.gas_wasted.saturating_sub(other.gas_wasted);
+    }
+}
+
+/// Outcomes of the transactions of a sender over the last `blocks` blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowStats {
+    pub blocks: u64,
+    #[serde(flatten)]
+    pub stats: SenderStats,
+    pub inclusion_percent: u64,
+    pub revert_percent: u64,
+}
+
+impl WindowStats {
+    fn new(blocks: u64, stats: SenderStats) -> Self {
+        Self {
+            blocks,
+            stats,
+            inclusion_percent: stats.inclusion_percent(),
+            revert_percent: stats.revert_percent(),
+        }
+    }
+}
+
+/// Reputation of a sender, as returned by `base_getSenderStats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SenderReport {
+    pub sender: Address,
+    /// Block number of the last transaction simulated for the sender.
+    pub last_seen: u64,
+    pub short_window: WindowStats,
+    pub long_window: WindowStats,
+}
+
+#[derive(Debug, Clone, Default)]
+struct SenderHistory {
+    /// Outcomes per block, oldest first.
+    blocks: VecDeque<(u64, SenderStats)>,
+}
+
+impl SenderHistory {
+    fn last_seen(&self) -> u64 {
+        self.blocks
+            .back()
+            .map_or(0, |(block_number, _)| *block_number)
+    }
+
+    fn stats_since(&self, window_start: u64) -> SenderStats {
+        let mut stats = SenderStats::default();
+        self.blocks
+            .iter()
+            .filter(|(block_number, _)| *block_number >= window_start)
+            .for_each(|(_, block_stats)| stats.add(block_stats));
+        stats
+    }
+}
+
+/// Shared store of the reputation of senders.
+#[derive(Debug, Clone)]
+pub struct SenderReputation {
+    inner: Arc<SenderReputationInner>,
+}
+
+#[derive(Debug)]
+struct SenderReputationInner {
+    config: ReputationArgs,
+    senders: DashMap<Address, SenderHistory>,
+    current_block: AtomicU64,
+    /// Last outcome of each transaction simulated in the current block.
+    block_outcomes: Mutex<HashMap<TxHash, SenderStats>>,
+    metrics: ReputationMetrics,
+}
+
+impl SenderReputation {
+    pub fn new(config: ReputationArgs) -> Self {
+        Self {
+            inner: Arc::new(SenderReputationInner {
+                config,
+                senders: DashMap::new(),
+                current_block: AtomicU64::new(0),
+                block_outcomes: Mutex::new(HashMap::new()),
+                metrics: Default::default(),
+            }),
+        }
+    }
+
+    /// Records the outcome of the transaction `tx_hash` of `sender` that used `gas_used` gas,
+    /// replacing the outcome of a previous simulation of the transaction in the same block.
+    /// Outcomes that don't involve simulating the transaction, such as block limits being
+    /// reached, are ignored.
+    pub fn record(
+        &self,
+        sender: Address,
+        tx_hash: TxHash,
+        result: &TxnExecutionResult,
+        gas_used: u64,
+    ) {
+        let outcome = match result {
+            TxnExecutionResult::Success => SenderStats {
+                simulated: 1,
+                included: 1,
+                gas_used,
+                ..Default::default()
+            },
+            TxnExecutionResult::Reverted => SenderStats {
+                simulated: 1,
+                included: 1,
+                reverted: 1,
+                gas_used,
+                gas_wasted: gas_used,
+            },
+            TxnExecutionResult::RevertedAndExcluded => SenderStats {
+                simulated: 1,
+                reverted: 1,
+                gas_used,
+                gas_wasted: gas_used,
+                ..Default::default()
+            },
+            TxnExecutionResult::MaxGasUsageExceeded => SenderStats {
+                simulated: 1,
+                gas_used,
+                gas_wasted: gas_used,
+                ..Default::default()
+            },
+            _ => return,
+        };
+
+        let mut block_outcomes = self.inner.block_outcomes.lock();
+        let previous = block_outcomes.insert(tx_hash, outcome);
+        let current_block = self.inner.current_block.load(Ordering::Relaxed);
+        let mut history = self.inner.senders.entry(sender).or_default();
+        match history.blocks.back_mut() {
+            Some((block_number, stats)) if *block_number == current_block => {
+                if let Some(previous) = previous {
+                    stats.sub(&previous);
+                }
+                stats.add(&outcome);
+            }
+            _ => history.blocks.push_back((current_block, outcome)),
+        }
+    }
+
+    /// Returns the outcomes of the transactions of `sender` over the reputation window, if any.
+    pub fn stats(&self, sender: &Address) -> Option<SenderStats> {
+        let history = self.inner.senders.get(sender)?;
+        let stats =
+            history.stats_since(self.window_start(self.inner.config.reputation_window_blocks));
+        (stats.simulated > 0).then_some(stats)
+    }
+
+    /// Returns the outcomes of the transactions of `sender` over the short and long reputation
+    /// windows, if any transaction of `sender` was simulated within the long window.
+    pub fn report(&self, sender: Address) -> Option<SenderReport> {
+        let config = &self.inner.config;
+        let history = self.inner.senders.get(&sender)?;
+        let long = history.stats_since(self.window_start(config.reputation_window_blocks));
+        if long.simulated == 0 {
+            return None;
+        }
+        let short = history.stats_since(self.window_start(config.reputation_short_window_blocks));
+        Some(SenderReport {
+            sender,
+            last_seen: history.last_seen(),
+            short_window: WindowStats::new(config.reputation_short_window_blocks, short),
+            long_window: WindowStats::new(config.reputation_window_blocks, long),
+        })
+    }
+
+    /// Should be called upon each new block, or build of a block. Forgets the outcomes that left
+    /// the window, and the senders above the tracked senders cap.
+    pub fn on_new_block(&self, block_number: u64) {
+        {
+            let mut block_outcomes = self.inner.block_outcomes.lock();
+            let previous_block = self
+                .inner
+                .current_block
+                .swap(block_number, Ordering::Relaxed);
+            if previous_block != block_number {
+                // the outcomes of the previous block are final
+                for outcome in block_outcomes.values() {
+                    self.inner.metrics.record_outcome(outcome);
+                }
+                block_outcomes.clear();
+            }
+        }
+
+        let window_start = self.window_start(self.inner.config.reputation_window_blocks);
+        self.inner.senders.retain(|_, history| {
+            while history
+                .blocks
+                .front()
+                .is_some_and(|(block_number, _)| *block_number < window_start)
+            {
+                history.blocks.pop_front();
+            }
+            !history.blocks.is_empty()
+        });
+
+        let max_tracked_senders = self.inner.config.reputation_max_tracked_senders as usize;
+        let excess = self.inner.senders.len().saturating_sub(max_tracked_senders);
+        if excess > 0 {
+            let mut candidates: Vec<_> = self
+                .inner
+                .senders
+                .iter()
+                .map(|history| (history.last_seen(), *history.key()))
+                .collect();
+            candidates.select_nth_unstable(excess - 1);
+            for (_, sender) in &candidates[..excess] {
+                self.inner.senders.remove(sender);
+            }
+            self.inner
+                .metrics
+                .capacity_evictions
+                .increment(excess as u64);
+        }
+        self.inner
+            .metrics
+            .tracked_senders
+            .set(self.inner.senders.len() as f64);
+    }
+
+    /// Returns the first block of a window of `window_blocks` blocks ending at the current block.
+    fn window_start(&self, window_blocks: u64) -> u64 {
+        let current_block = self.inner.current_block.load(Ordering::Relaxed);
+        (current_block + 1).saturating_sub(window_blocks)
+    }
+}
+
+impl Default for SenderReputation {
+    fn default() -> Self {
+        Self::new(ReputationArgs::default())
+    }
+}
+
+#[cfg_attr(not(test), rpc(server, namespace = "base"))]
+#[cfg_attr(test, rpc(server, client, namespace = "base"))]
+pub trait ReputationApi {
+    #[method(name = "getSenderStats")]
+    async fn get_sender_stats(&self, sender: Address) -> RpcResult<Option<SenderReport>>;
+}
+
+pub struct ReputationExt {
+    reputation: SenderReputation,
+}
+
+impl ReputationExt {
+    pub fn new(reputation: SenderReputation) -> Self {
+        Self { reputation }
+    }
+}
+
+#[async_trait]
+impl ReputationApiServer for ReputationExt {
+    async fn get_sender_stats(&self, sender: Address) -> RpcResult<Option<SenderReport>> {
+        Ok(self.reputation.report(sender))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reputation(window_blocks: u64, max_tracked_senders: u64) -> SenderReputation {
+        SenderReputation::new(ReputationArgs {
+            reputation_window_blocks: window_blocks,
+            reputation_short_window_blocks: 1,
+            reputation_max_tracked_senders: max_tracked_senders,
+        })
+    }
+
+    #[test]
+    fn test_record_outcomes() {
+        let reputation = reputation(10, 100);
+        let sender = Address::from([0x1; 20]);
+        assert_eq!(reputation.stats(&sender), None);
+
+        reputation.record(sender, TxHash::random(), &TxnExecutionResult::Success, 1000);
+        reputation.record(sender, TxHash::random(), &TxnExecutionResult::Reverted, 200);
+        reputation.record(
+            sender,
+            TxHash::random(),
+            &TxnExecutionResult::RevertedAndExcluded,
+            300,
+        );
+        reputation.record(
+            sender,
+            TxHash::random(),
+            &TxnExecutionResult::MaxGasUsageExceeded,
+            400,
+        );
+        // not simulated
+        reputation.record(
+            sender,
+            TxHash::random(),
+            &TxnExecutionResult::NonceTooLow,
+            0,
+        );
+
+        let stats = reputation.stats(&sender).unwrap();
+        assert_eq!(
+            stats,
+            SenderStats {
+                simulated: 4,
+                included: 2,
+                reverted: 2,
+                gas_used: 1900,
+                gas_wasted: 900,
+            }
+        );
+        assert_eq!(stats.revert_percent(), 50);
+        assert_eq!(stats.inclusion_percent(), 50);
+    }
+
+    #[test]
+    fn test_outcome_counted_once_per_block() {
+        let reputation = reputation(10, 100);
+        let sender = Address::from([0x1; 20]);
+        let tx_hash = TxHash::random();
+
+        // a revert protected transaction is simulated again in each flashblock
+        reputation.on_new_block(1);
+        for _ in 0..3 {
+            reputation.record(
+                sender,
+                tx_hash,
+                &TxnExecutionResult::RevertedAndExcluded,
+                21_000,
+            );
+        }
+        let stats = reputation.stats(&sender).unwrap();
+        assert_eq!(stats.simulated, 1);
+        assert_eq!(stats.reverted, 1);
+        assert_eq!(stats.gas_wasted, 21_000);
+
+        // the last outcome in the block replaces the previous ones, including across rebuilds
+        reputation.on_new_block(1);
+        reputation.record(sender, tx_hash, &TxnExecutionResult::Success, 30_000);
+        assert_eq!(
+            reputation.stats(&sender).unwrap(),
+            SenderStats {
+                simulated: 1,
+                included: 1,
+                gas_used: 30_000,
+                ..Default::default()
+            }
+        );
+
+        // the transaction is counted again in the next block
+        reputation.on_new_block(2);
+        reputation.record(
+            sender,
+            tx_hash,
+            &TxnExecutionResult::RevertedAndExcluded,
+            21_000,
+        );
+        let stats = reputation.stats(&sender).unwrap();
+        assert_eq!(stats.simulated, 2);
+        assert_eq!(stats.reverted, 1);
+    }
+
+    #[test]
+    fn test_outcomes_leave_the_window() {
+        let reputation = reputation(3, 100);
+        let sender = Address::from([0x1; 20]);
+
+        reputation.on_new_block(1);
+        reputation.record(
+            sender,
+            TxHash::random(),
+            &TxnExecutionResult::RevertedAndExcluded,
+            21000,
+        );
+        reputation.on_new_block(2);
+        reputation.record(
+            sender,
+            TxHash::random(),
+            &TxnExecutionResult::Success,
+            21000,
+        );
+        reputation.on_new_block(3);
+        assert_eq!(reputation.stats(&sender).unwrap().simulated, 2);
+
+        reputation.on_new_block(4);
+        let stats = reputation.stats(&sender).unwrap();
+        assert_eq!(stats.simulated, 1);
+        assert_eq!(stats.revert_percent(), 0);
+
+        reputation.on_new_block(5);
+        assert_eq!(reputation.stats(&sender), None);
+        assert!(reputation.inner.senders.is_empty());
+    }
+
+    #[test]
+    fn test_tracked_senders_are_bounded() {
+        let reputation = reputation(1000, 10);
+
+        for block_number in 1..=10u64 {
+            reputation.on_new_block(block_number);
+            for i in 0..100u64 {
+                let sender = Address::left_padding_from(&(block_number * 100 + i).to_be_bytes());
+                reputation.record(
+                    sender,
+                    TxHash::random(),
+                    &TxnExecutionResult::Success,
+                    21000,
+                );
+            }
+        }
+        reputation.on_new_block(11);
+
+        assert_eq!(reputation.inner.senders.len(), 10);
+        // the most recently seen senders are kept
+        let recent = Address::left_padding_from(&1099u64.to_be_bytes());
+        assert!(reputation.stats(&recent).is_some());
+    }
+
+    #[test]
+    fn test_report_windows() {
+        let reputation = SenderReputation::new(ReputationArgs {
+            reputation_window_blocks: 10,
+            reputation_short_window_blocks: 2,
+            reputation_max_tracked_senders: 100,
+        });
+        let sender = Address::from([0x1; 20]);
+        assert_eq!(reputation.report(sender), None);
+
+        reputation.on_new_block(1);
+        for _ in 0..3 {
+            reputation.record(
+                sender,
+                TxHash::random(),
+                &TxnExecutionResult::RevertedAndExcluded,
+                50_000,
+            );
+        }
+        reputation.on_new_block(5);
+        reputation.record(
+            sender,
+            TxHash::random(),
+            &TxnExecutionResult::Success,
+            21_000,
+        );
+
+        let report = reputation.report(sender).unwrap();
+        assert_eq!(report.last_seen, 5);
+        assert_eq!(report.short_window.blocks, 2);
+        assert_eq!(report.short_window.stats.simulated, 1);
+        assert_eq!(report.short_window.revert_percent, 0);
+        assert_eq!(report.short_window.inclusion_percent, 100);
+        assert_eq!(report.long_window.stats.simulated, 4);
+        assert_eq!(report.long_window.stats.gas_wasted, 150_000);
+        assert_eq!(report.long_window.revert_percent, 75);
+        assert_eq!(report.long_window.inclusion_percent, 25);
+
+        let json = serde_json::to_value(report).unwrap();
+        assert_eq!(json["shortWindow"]["gasUsed"], 21_000);
+        assert_eq!(json["longWindow"]["revertPercent"], 75);
+    }
+}
@@ -1,23 +1,29 @@
 use std::{sync::Arc, time::Instant};
 
 use crate::{
-    metrics::OpRBuilderMetrics,
-    primitives::bundle::{Bundle, BundleResult},
-    tx::{FBPooledTransaction, MaybeFlashblockFilter, MaybeRevertingTransaction},
+    gas_limiter::AddressGasLimiter,
+    metrics::{OpRBuilderMetrics, record_gas_limiter_rejection},
+    primitives::bundle::{Bundle, BundleRangeLimits, BundleResult},
+    tx::{
+        FBPoolTransaction, FBPooledTransaction, MaybeFlashblockFilter, MaybeRevertingTransaction,
+    },
+    tx_data_store::{ReplaceableBundle, ReplacementSignerMismatch, TxDataStore, TxDataUpdate},
 };
+use alloy_consensus::{Header, Transaction};
 use alloy_json_rpc::RpcObject;
-use alloy_primitives::B256;
+use alloy_primitives::{Address, B256, TxHash};
 use jsonrpsee::{
     core::{RpcResult, async_trait},
     proc_macros::rpc,
 };
 use moka::future::Cache;
 use reth::rpc::api::eth::{RpcReceipt, helpers::FullEthApi};
-use reth_optimism_txpool::{OpPooledTransaction, conditional::MaybeConditionalTransaction};
-use reth_provider::StateProviderFactory;
+use reth_optimism_txpool::conditional::MaybeConditionalTransaction;
+use reth_provider::{BlockReaderIdExt, StateProviderFactory};
 use reth_rpc_eth_types::{EthApiError, utils::recover_raw_transaction};
 use reth_transaction_pool::{PoolTransaction, TransactionOrigin, TransactionPool};
-use tracing::error;
+use tracing::{error, info};
+use uuid::Uuid;
 
 // Namespace overrides for revert protection support
 #[cfg_attr(not(test), rpc(server, namespace = "eth"))]
@@ -36,6 +42,10 @@ pub struct RevertProtectionExt<Pool, Provider, Eth> {
     eth_api: Eth,
     metrics: Arc<OpRBuilderMetrics>,
     reverted_cache: Cache<B256, ()>,
+    tx_data_store: TxDataStore,
+    /// When set, bundles are rejected if the gas limiter budget of the sender can't cover them.
+    gas_limiter: Option<AddressGasLimiter>,
+    bundle_range: BundleRangeLimits,
 }
 
 impl<Pool, Provider, Eth> RevertProtectionExt<Pool, Provider, Eth>
@@ -49,6 +59,9 @@ where
         provider: Provider,
         eth_api: Eth,
         reverted_cache: Cache<B256, ()>,
+        tx_data_store: TxDataStore,
+        gas_limiter: Option<AddressGasLimiter>,
+        bundle_range: BundleRangeLimits,
     ) -> Self {
         Self {
             pool,
@@ -56,6 +69,9 @@ where
             eth_api,
             metrics: Arc::new(OpRBuilderMetrics::default()),
             reverted_cache,
+            tx_data_store,
+            gas_limiter,
+            bundle_range,
         }
     }
 }
@@ -65,7 +81,8 @@ impl<Pool, Provider, Eth> EthApiExtServer<RpcReceipt<Eth::NetworkTypes>>
     for RevertProtectionExt<Pool, Provider, Eth>
 where
     Pool: TransactionPool<Transaction = FBPooledTransaction> + Clone + 'static,
-    Provider: StateProviderFactory + Send + Sync + Clone + 'static,
+    Provider:
+        StateProviderFactory + BlockReaderIdExt<Header = Header> + Send + Sync + Clone + 'static,
     Eth: FullEthApi + Send + Sync + Clone + 'static,
 {
     async fn send_bundle(&self, bundle: Bundle) -> RpcResult<BundleResult> {
@@ -73,12 +90,15 @@ where
         self.metrics.bundle_requests.increment(1);
 
         let bundle_result = self
-            .send_bundle_inner(bundle)
+            .send_bundle_inner(bundle.clone())
             .await
             .inspect_err(|err| error!("eth_sendBundle request failed: {err:?}"));
 
         if bundle_result.is_ok() {
             self.metrics.valid_bundles.increment(1);
+            // share the bundle with the other builders of the set
+            self.tx_data_store
+                .publish_update(TxDataUpdate::ForwardedBundle(bundle));
         } else {
             self.metrics.failed_bundles.increment(1);
         }
@@ -111,53 +131,194 @@ where
 impl<Pool, Provider, Eth> RevertProtectionExt<Pool, Provider, Eth>
 where
     Pool: TransactionPool<Transaction = FBPooledTransaction> + Clone + 'static,
-    Provider: StateProviderFactory + Send + Sync + Clone + 'static,
+    Provider:
+        StateProviderFactory + BlockReaderIdExt<Header = Header> + Send + Sync + Clone + 'static,
     Eth: FullEthApi + Send + Sync + Clone + 'static,
 {
     async fn send_bundle_inner(&self, bundle: Bundle) -> RpcResult<BundleResult> {
-        let last_block_number = self
-            .provider
-            .best_block_number()
-            .map_err(|_e| EthApiError::InternalEthError)?;
-
-        // Only one transaction in the bundle is expected
-        let bundle_transaction = match bundle.transactions.len() {
-            0 => {
-                return Err(EthApiError::InvalidParams(
-                    "bundle must contain at least one transaction".into(),
-                )
-                .into());
-            }
-            1 => bundle.transactions[0].clone(),
-            _ => {
-                return Err(EthApiError::InvalidParams(
-                    "bundle must contain exactly one transaction".into(),
-                )
-                .into());
-            }
-        };
-
-        let conditional = bundle
-            .conditional(last_block_number)
-            .map_err(EthApiError::from)?;
-
-        let recovered = recover_raw_transaction(&bundle_transaction)?;
-        let pool_transaction =
-            FBPooledTransaction::from(OpPooledTransaction::from_pooled(recovered))
-                .with_reverted_hashes(bundle.reverting_hashes.clone().unwrap_or_default())
-                .with_flashblock_number_min(conditional.flashblock_number_min)
-                .with_flashblock_number_max(conditional.flashblock_number_max)
-                .with_conditional(conditional.transaction_conditional);
-
-        let outcome = self
-            .pool
-            .add_transaction(TransactionOrigin::Local, pool_transaction)
+        if let Some(replacement_uuid) = bundle.replacement_uuid
+            && bundle.is_cancellation()
+        {
+            let signer = bundle
+                .cancellation_signer()
+                .ok_or_else(|| EthApiError::InvalidParams("cancellation must be signed".into()))?;
+            let cancelled = cancel_bundle_transaction(
+                &self.pool,
+                &self.tx_data_store,
+                replacement_uuid,
+                signer,
+            )
             .await
-            .map_err(EthApiError::from)?;
+            .map_err(|err| EthApiError::InvalidParams(err.to_string()))?;
+            return Ok(BundleResult {
+                bundle_hash: cancelled.unwrap_or_default(),
+            });
+        }
+
+        let last_header = self
+            .provider
+            .latest_header()
+            .map_err(|_e| EthApiError::InternalEthError)?
+            .ok_or(EthApiError::InternalEthError)?;
+
+        let pool_transaction: FBPooledTransaction = bundle_pool_transaction(
+            &bundle,
+            self.bundle_range,
+            last_header.number,
+            last_header.timestamp,
+        )?;
 
-        let result = BundleResult {
-            bundle_hash: outcome.hash,
-        };
+        if let Some(gas_limiter) = &self.gas_limiter
+            && let Err(err) = gas_limiter.check_gas(
+                pool_transaction.sender(),
+                pool_transaction.to(),
+                pool_transaction.gas_limit(),
+            )
+        {
+            record_gas_limiter_rejection(
+                pool_transaction.sender(),
+                "bundle",
+                pool_transaction.gas_limit(),
+            );
+            return Err(EthApiError::InvalidParams(err.to_string()).into());
+        }
+
+        let bundle_hash = add_bundle_transaction(
+            &self.pool,
+            &self.tx_data_store,
+            bundle.replacement_uuid,
+            pool_transaction,
+            TransactionOrigin::Local,
+        )
+        .await?;
+
+        let result = BundleResult { bundle_hash };
         Ok(result)
     }
 }
+
+/// Adds the transaction of a bundle to the pool.
+///
+/// When the bundle has a replacement uuid, the transaction of the previous bundle of the same
+/// signer with the same uuid is removed from the pool first, and restored if the new
+/// transaction is rejected. A replacement uuid used by another signer is rejected.
+pub(crate) async fn add_bundle_transaction<Pool: TransactionPool>(
+    pool: &Pool,
+    tx_data_store: &TxDataStore,
+    replacement_uuid: Option<Uuid>,
+    transaction: Pool::Transaction,
+    origin: TransactionOrigin,
+) -> Result<TxHash, EthApiError> {
+    let Some(replacement_uuid) = replacement_uuid else {
+        let outcome = pool.add_transaction(origin, transaction).await?;
+        return Ok(outcome.hash);
+    };
+
+    let signer = transaction.sender();
+    let _guard = tx_data_store.lock_pool_replacements().await;
+    let previous = match tx_data_store
+        .replacement(signer, &replacement_uuid)
+        .map_err(|err| EthApiError::InvalidParams(err.to_string()))?
+    {
+        Some(ReplaceableBundle::Pool(hash)) => pool.remove_transactions(vec![hash]).pop(),
+        _ => None,
+    };
+
+    match pool.add_transaction(origin, transaction).await {
+        Ok(outcome) => {
+            if let Err(err) = tx_data_store.set_replacement(
+                signer,
+                replacement_uuid,
+                ReplaceableBundle::Pool(outcome.hash),
+            ) {
+                // the uuid was taken by a backrun bundle of another signer in the meantime
+                pool.remove_transactions(vec![outcome.hash]);
+                return Err(EthApiError::InvalidParams(err.to_string()));
+            }
+            if let Some(previous) = previous {
+                info!(
+                    replaced_tx = ?previous.hash(),
+                    tx = ?outcome.hash,
+                    ?replacement_uuid,
+                    "Replaced bundle with the same replacement uuid"
+                );
+            }
+            Ok(outcome.hash)
+        }
+        Err(err) => {
+            if let Some(previous) = previous
+                && let Err(restore_err) = pool
+                    .add_transaction(previous.origin, previous.transaction.clone())
+                    .await
+            {
+                error!(
+                    tx = ?previous.hash(),
+                    error = ?restore_err,
+                    "Failed to restore replaced bundle"
+                );
+            }
+            Err(err.into())
+        }
+    }
+}
+
+/// Removes the transaction of the bundle of `signer` with the given replacement uuid from the
+/// pool, returns its hash if it was still pending. A replacement uuid used by another signer is
+/// rejected.
+pub(crate) async fn cancel_bundle_transaction<Pool: TransactionPool>(
+    pool: &Pool,
+    tx_data_store: &TxDataStore,
+    replacement_uuid: Uuid,
+    signer: Address,
+) -> Result<Option<TxHash>, ReplacementSignerMismatch> {
+    let _guard = tx_data_store.lock_pool_replacements().await;
+    // a uuid identifying a backrun bundle is cancelled through the backrun API
+    let Some(ReplaceableBundle::Pool(hash)) =
+        tx_data_store.replacement(signer, &replacement_uuid)?
+    else {
+        return Ok(None);
+    };
+    tx_data_store.take_replacement(&replacement_uuid, |bundle| {
+        *bundle == ReplaceableBundle::Pool(hash)
+    });
+    let removed = pool.remove_transactions(vec![hash]);
+    info!(tx = ?hash, ?replacement_uuid, "Cancelled bundle");
+    Ok((!removed.is_empty()).then_some(hash))
+}
+
+/// Converts a bundle into the pool transaction it wraps, with its reverting hashes and
+/// conditions attached.
+pub(crate) fn bundle_pool_transaction<T: FBPoolTransaction>(
+    bundle: &Bundle,
+    bundle_range: BundleRangeLimits,
+    last_block_number: u64,
+    last_block_timestamp: u64,
+) -> Result<T, EthApiError> {
+    // Only one transaction in the bundle is expected
+    let bundle_transaction = match bundle.transactions.len() {
+        0 => {
+            return Err(EthApiError::InvalidParams(
+                "bundle must contain at least one transaction".into(),
+            ));
+        }
+        1 => &bundle.transactions[0],
+        _ => {
+            return Err(EthApiError::InvalidParams(
+                "bundle must contain exactly one transaction".into(),
+            ));
+        }
+    };
+
+    let conditional = bundle
+        .conditional(bundle_range, last_block_number, last_block_timestamp)
+        .map_err(EthApiError::from)?;
+
+    let recovered = recover_raw_transaction::<T::Pooled>(bundle_transaction)?;
+    Ok(T::from_pooled(recovered)
+        .with_reverted_hashes(bundle.reverting_hashes.clone().unwrap_or_default())
+        .with_flashblock_number_min(conditional.flashblock_number_min)
+        .with_flashblock_number_max(conditional.flashblock_number_max)
+        .with_flashblock_time_min(conditional.flashblock_time_min)
+        .with_flashblock_time_max(conditional.flashblock_time_max)
+        .with_conditional(conditional.transaction_conditional))
+}
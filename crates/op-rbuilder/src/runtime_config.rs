@@ -0,0 +1,470 @@
+use crate::{
+    args::OpRbuilderArgs, gas_limiter::args::GasLimiterArgs, metrics::record_runtime_config_update,
+};
+use core::{fmt::Debug, time::Duration};
+use jsonrpsee::{
+    core::{RpcResult, async_trait},
+    proc_macros::rpc,
+    types::{ErrorObject, error::INVALID_PARAMS_CODE},
+};
+use parking_lot::RwLock;
+use reth_optimism_payload_builder::config::OpDAConfig;
+use serde::{Deserialize, Serialize};
+use serde_with::{DurationMilliSeconds, serde_as};
+use std::sync::Arc;
+use tracing::info;
+
+/// Builder settings that can be changed at runtime through the `builder` RPC namespace.
+///
+/// Payload builders take a snapshot of the settings when they start building a block, so
+/// updates take effect from the next block.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeSettings {
+    /// Maximum gas a transaction can use before being excluded.
+    pub max_gas_per_txn: Option<u64>,
+
+    /// Address gas limiter configuration.
+    #[serde(flatten)]
+    pub gas_limiter: GasLimiterArgs,
+
+    /// How much time is deducted from the first flashblock to account for latencies.
+    #[serde_as(as = "DurationMilliSeconds<u64>")]
+    pub flashblocks_leeway_time: Duration,
+
+    /// Disables dynamic flashblocks number adjustment based on FCU arrival time.
+    pub flashblocks_fixed: bool,
+
+    /// Disables state root calculation for each flashblock.
+    pub flashblocks_disable_state_root: bool,
+}
+
+impl Default for RuntimeSettings {
+    fn default() -> Self {
+        Self {
+            max_gas_per_txn: None,
+            gas_limiter: GasLimiterArgs::default(),
+            flashblocks_leeway_time: Duration::from_millis(50),
+            flashblocks_fixed: false,
+            flashblocks_disable_state_root: false,
+        }
+    }
+}
+
+impl From<&OpRbuilderArgs> for RuntimeSettings {
+    fn from(args: &OpRbuilderArgs) -> Self {
+        Self {
+            max_gas_per_txn: args.max_gas_per_txn,
+            gas_limiter: args.gas_limiter.clone(),
+            flashblocks_leeway_time: Duration::from_millis(
+                args.flashblocks.flashblocks_leeway_time,
+            ),
+            flashblocks_fixed: args.flashblocks.flashblocks_fixed,
+            flashblocks_disable_state_root: args.flashblocks.flashblocks_disable_state_root,
+        }
+    }
+}
+
+/// Partial update of the runtime settings, unset fields are left unchanged.
+///
+/// Setting `maxGasPerTxn`, `maxDaTxSize` or `maxDaBlockSize` to zero removes the limit.
+#[serde_as]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RuntimeSettingsUpdate {
+    pub max_gas_per_txn: Option<u64>,
+    pub gas_limiter_enabled: Option<bool>,
+    pub max_gas_per_address: Option<u64>,
+    pub refill_rate_per_block: Option<u64>,
+    pub cleanup_interval: Option<u64>,
+    pub max_tracked_addresses: Option<u64>,
+    #[serde_as(as = "Option<DurationMilliSeconds<u64>>")]
+    pub flashblocks_leeway_time: Option<Duration>,
+    pub flashblocks_fixed: Option<bool>,
+    pub flashblocks_disable_state_root: Option<bool>,
+    pub max_da_tx_size: Option<u64>,
+    pub max_da_block_size: Option<u64>,
+}
+
+/// Current runtime settings, including the data availability limits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeConfigView {
+    #[serde(flatten)]
+    pub settings: RuntimeSettings,
+    pub max_da_tx_size: Option<u64>,
+    pub max_da_block_size: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RuntimeConfigError {
+    #[error(
+        "flashblocks leeway time {leeway_time:?} exceeds the flashblocks interval {interval:?}"
+    )]
+    LeewayTimeExceedsInterval {
+        leeway_time: Duration,
+        interval: Duration,
+    },
+    #[error("gas limiter cleanup interval must be greater than zero")]
+    ZeroCleanupInterval,
+    #[error("gas limiter max tracked addresses must be greater than zero")]
+    ZeroMaxTrackedAddresses,
+}
+
+/// Shared handle to the runtime settings of the builder.
+///
+/// The data availability limits are kept in the [`OpDAConfig`] shared with the payload builders
+/// and the `miner` RPC namespace, every other setting is stored in a [`RuntimeSettings`] that
+/// is swapped on update.
+#[derive(Debug, Clone)]
+pub struct RuntimeConfig {
+    settings: Arc<RwLock<Arc<RuntimeSettings>>>,
+    da_config: OpDAConfig,
+    /// Upper bound of the flashblocks leeway time.
+    flashblocks_interval: Duration,
+}
+
+impl RuntimeConfig {
+    pub fn new(
+        settings: RuntimeSettings,
+        da_config: OpDAConfig,
+        flashblocks_interval: Duration,
+    ) -> Self {
+        Self {
+            settings: Arc::new(RwLock::new(Arc::new(settings))),
+            da_config,
+            flashblocks_interval,
+        }
+    }
+
+    /// Returns a snapshot of the current settings.
+    pub fn load(&self) -> Arc<RuntimeSettings> {
+        self.settings.read().clone()
+    }
+
+    pub fn view(&self) -> RuntimeConfigView {
+        RuntimeConfigView {
+            settings: self.load().as_ref().clone(),
+            max_da_tx_size: self.da_config.max_da_tx_size(),
+            max_da_block_size: self.da_config.max_da_block_size(),
+        }
+    }
+
+    /// Applies an update, either every field of the update is applied or none of them.
+    pub fn update(
+        &self,
+        update: RuntimeSettingsUpdate,
+    ) -> Result<RuntimeConfigView, RuntimeConfigError> {
+        let mut current = self.settings.write();
+        let mut settings = current.as_ref().clone();
+        let mut changes = Vec::new();
+
+        let limit = |value: u64| (value != 0).then_some(value);
+        set(
+            &mut changes,
+            "max_gas_per_txn",
+            &mut settings.max_gas_per_txn,
+            update.max_gas_per_txn.map(limit),
+            |value| value.unwrap_or_default() as f64,
+        );
+        set(
+            &mut changes,
+            "gas_limiter_enabled",
+            &mut settings.gas_limiter.gas_limiter_enabled,
+            update.gas_limiter_enabled,
+            |value| value as u8 as f64,
+        );
+        set(
+            &mut changes,
+            "max_gas_per_address",
+            &mut settings.gas_limiter.max_gas_per_address,
+            update.max_gas_per_address,
+            |value| value as f64,
+        );
+        set(
+            &mut changes,
+            "refill_rate_per_block",
+            &mut settings.gas_limiter.refill_rate_per_block,
+            update.refill_rate_per_block,
+            |value| value as f64,
+        );
+        set(
+            &mut changes,
+            "cleanup_interval",
+            &mut settings.gas_limiter.cleanup_interval,
+            update.cleanup_interval,
+            |value| value as f64,
+        );
+        set(
+            &mut changes,
+            "max_tracked_addresses",
+            &mut settings.gas_limiter.max_tracked_addresses,
+            update.max_tracked_addresses,
+            |value| value as f64,
+        );
+        set(
+            &mut changes,
+            "flashblocks_leeway_time",
+            &mut settings.flashblocks_leeway_time,
+            update.flashblocks_leeway_time,
+            |value| value.as_millis() as f64,
+        );
+        set(
+            &mut changes,
+            "flashblocks_fixed",
+            &mut settings.flashblocks_fixed,
+            update.flashblocks_fixed,
+            |value| value as u8 as f64,
+        );
+        set(
+            &mut changes,
+            "flashblocks_disable_state_root",
+            &mut settings.flashblocks_disable_state_root,
+            update.flashblocks_disable_state_root,
+            |value| value as u8 as f64,
+        );
+
+        let mut max_da_tx_size = self.da_config.max_da_tx_size();
+        let mut max_da_block_size = self.da_config.max_da_block_size();
+        set(
+            &mut changes,
+            "max_da_tx_size",
+            &mut max_da_tx_size,
+            update.max_da_tx_size.map(limit),
+            |value| value.unwrap_or_default() as f64,
+        );
+        set(
+            &mut changes,
+            "max_da_block_size",
+            &mut max_da_block_size,
+            update.max_da_block_size.map(limit),
+            |value| value.unwrap_or_default() as f64,
+        );
+
+        if settings.flashblocks_leeway_time > self.flashblocks_interval {
+            return Err(RuntimeConfigError::LeewayTimeExceedsInterval {
+                leeway_time: settings.flashblocks_leeway_time,
+                interval: self.flashblocks_interval,
+            });
+        }
+        if settings.gas_limiter.gas_limiter_enabled && settings.gas_limiter.cleanup_interval == 0 {
+            return Err(RuntimeConfigError::ZeroCleanupInterval);
+        }
+        if settings.gas_limiter.gas_limiter_enabled
+            && settings.gas_limiter.max_tracked_addresses == 0
+        {
+            return Err(RuntimeConfigError::ZeroMaxTrackedAddresses);
+        }
+
+        if update.max_da_tx_size.is_some() || update.max_da_block_size.is_some() {
+            self.da_config.set_max_da_size(
+                max_da_tx_size.unwrap_or_default(),
+                max_da_block_size.unwrap_or_default(),
+            );
+        }
+        *current = Arc::new(settings);
+        drop(current);
+
+        for change in changes {
+            info!(
+                target: "runtime_config",
+                setting = change.setting,
+                old = %change.old,
+                new = %change.new,
+                "Builder setting updated"
+            );
+            record_runtime_config_update(change.setting, change.value);
+        }
+
+        Ok(self.view())
+    }
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self::new(
+            RuntimeSettings::default(),
+            OpDAConfig::default(),
+            Duration::from_millis(250),
+        )
+    }
+}
+
+struct SettingChange {
+    setting: &'static str,
+    old: String,
+    new: String,
+    /// New value reported in the metrics.
+    value: f64,
+}
+
+fn set<T: Copy + PartialEq + Debug>(
+    changes: &mut Vec<SettingChange>,
+    setting: &'static str,
+    current: &mut T,
+    new: Option<T>,
+    value: impl Fn(T) -> f64,
+) {
+    let Some(new) = new else {
+        return;
+    };
+    if *current == new {
+        return;
+    }
+    changes.push(SettingChange {
+        setting,
+        old: format!("{current:?}"),
+        new: format!("{new:?}"),
+        value: value(new),
+    });
+    *current = new;
+}
+
+#[cfg_attr(not(test), rpc(server, namespace = "builder"))]
+#[cfg_attr(test, rpc(server, client, namespace = "builder"))]
+pub trait BuilderAdminApi {
+    #[method(name = "getConfig")]
+    async fn get_config(&self) -> RpcResult<RuntimeConfigView>;
+
+    #[method(name = "updateConfig")]
+    async fn update_config(&self, update: RuntimeSettingsUpdate) -> RpcResult<RuntimeConfigView>;
+}
+
+/// Admin RPC reading and updating the runtime settings, served on the authenticated RPC server.
+pub struct BuilderAdminExt {
+    config: RuntimeConfig,
+}
+
+impl BuilderAdminExt {
+    pub fn new(config: RuntimeConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl BuilderAdminApiServer for BuilderAdminExt {
+    async fn get_config(&self) -> RpcResult<RuntimeConfigView> {
+        Ok(self.config.view())
+    }
+
+    async fn update_config(&self, update: RuntimeSettingsUpdate) -> RpcResult<RuntimeConfigView> {
+        self.config
+            .update(update)
+            .map_err(|e| ErrorObject::owned(INVALID_PARAMS_CODE, e.to_string(), None::<()>))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_applies_set_fields_only() {
+        let config = RuntimeConfig::default();
+
+        let view = config
+            .update(RuntimeSettingsUpdate {
+                max_gas_per_txn: Some(25_000),
+                flashblocks_fixed: Some(true),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(view.settings.max_gas_per_txn, Some(25_000));
+        assert!(view.settings.flashblocks_fixed);
+        assert_eq!(
+            view.settings.flashblocks_leeway_time,
+            RuntimeSettings::default().flashblocks_leeway_time
+        );
+        assert_eq!(*config.load(), view.settings);
+
+        // zero removes the limit
+        config
+            .update(RuntimeSettingsUpdate {
+                max_gas_per_txn: Some(0),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(config.load().max_gas_per_txn, None);
+    }
+
+    #[test]
+    fn test_update_da_limits() {
+        let da_config = OpDAConfig::default();
+        let config = RuntimeConfig::new(
+            RuntimeSettings::default(),
+            da_config.clone(),
+            Duration::from_millis(250),
+        );
+
+        config
+            .update(RuntimeSettingsUpdate {
+                max_da_tx_size: Some(100),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(da_config.max_da_tx_size(), Some(100));
+        assert_eq!(da_config.max_da_block_size(), None);
+
+        let view = config
+            .update(RuntimeSettingsUpdate {
+                max_da_block_size: Some(1000),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(view.max_da_tx_size, Some(100));
+        assert_eq!(view.max_da_block_size, Some(1000));
+    }
+
+    #[test]
+    fn test_invalid_update_is_rejected() {
+        let config = RuntimeConfig::default();
+
+        let result = config.update(RuntimeSettingsUpdate {
+            max_gas_per_txn: Some(25_000),
+            flashblocks_leeway_time: Some(Duration::from_millis(500)),
+            ..Default::default()
+        });
+        assert!(matches!(
+            result,
+            Err(RuntimeConfigError::LeewayTimeExceedsInterval { .. })
+        ));
+        // nothing was applied
+        assert_eq!(*config.load(), RuntimeSettings::default());
+
+        let result = config.update(RuntimeSettingsUpdate {
+            gas_limiter_enabled: Some(true),
+            cleanup_interval: Some(0),
+            ..Default::default()
+        });
+        assert_eq!(result, Err(RuntimeConfigError::ZeroCleanupInterval));
+
+        let result = config.update(RuntimeSettingsUpdate {
+            gas_limiter_enabled: Some(true),
+            cleanup_interval: Some(10),
+            max_tracked_addresses: Some(0),
+            ..Default::default()
+        });
+        assert_eq!(result, Err(RuntimeConfigError::ZeroMaxTrackedAddresses));
+    }
+
+    #[test]
+    fn test_update_serialization() {
+        let update: RuntimeSettingsUpdate = serde_json::from_str(
+            r#"{"maxGasPerTxn":25000,"flashblocksLeewayTime":75,"gasLimiterEnabled":true}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            update,
+            RuntimeSettingsUpdate {
+                max_gas_per_txn: Some(25_000),
+                flashblocks_leeway_time: Some(Duration::from_millis(75)),
+                gas_limiter_enabled: Some(true),
+                ..Default::default()
+            }
+        );
+
+        assert!(serde_json::from_str::<RuntimeSettingsUpdate>(r#"{"unknown":1}"#).is_err());
+    }
+}
@@ -0,0 +1,521 @@
+//! Bundle simulation through `eth_callBundle` and `base_simulateBundle`.
+//!
+//! Bundles are executed with the same EVM configuration as the payload builders, either on top
+//! of the latest block or on top of the flashblocks already built for the next block, so that
+//! searchers learn whether a bundle would revert before sending it.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+
+use alloy_consensus::Transaction;
+use alloy_eips::{BlockNumberOrTag, Encodable2718, Typed2718};
+use alloy_primitives::{Address, B256, Bytes, Log, U256, keccak256};
+use jsonrpsee::{
+    core::{RpcResult, async_trait},
+    proc_macros::rpc,
+};
+use parking_lot::RwLock;
+use reth_chain_state::{ExecutedBlock, MemoryOverlayStateProvider};
+use reth_evm::{ConfigureEvm, Evm};
+use reth_optimism_evm::{OpEvmConfig, OpNextBlockEnvAttributes};
+use reth_optimism_primitives::{OpPrimitives, OpTransactionSigned};
+use reth_provider::StateProviderBox;
+use reth_revm::{State, database::StateProviderDatabase};
+use reth_rpc_eth_types::{EthApiError, utils::recover_raw_transaction};
+use revm::{
+    Database, DatabaseCommit,
+    context::result::{ExecutionResult, ResultAndState},
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+
+use crate::{gas_limiter::AddressGasLimiter, runtime_config::RuntimeConfig, traits::ClientBounds};
+
+/// Maximum number of transactions of a simulated bundle.
+const MAX_BUNDLE_TRANSACTIONS: usize = 100;
+
+/// Maximum number of bundles simulated at the same time, further requests wait for a running
+/// simulation to complete.
+const MAX_CONCURRENT_SIMULATIONS: usize = 4;
+
+/// Latest state of the block being built, updated by the flashblocks builder after each
+/// flashblock.
+#[derive(Debug, Clone, Default)]
+pub struct PendingBlock {
+    inner: Arc<RwLock<Option<ExecutedBlock<OpPrimitives>>>>,
+}
+
+impl PendingBlock {
+    pub fn set(&self, block: ExecutedBlock<OpPrimitives>) {
+        *self.inner.write() = Some(block);
+    }
+
+    /// Returns the pending block if it is built on top of `parent_hash`.
+    pub fn get(&self, parent_hash: B256) -> Option<ExecutedBlock<OpPrimitives>> {
+        self.inner
+            .read()
+            .as_ref()
+            .filter(|block| block.recovered_block.header().parent_hash == parent_hash)
+            .cloned()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulateBundleRequest {
+    /// Raw transactions of the bundle, executed in order.
+    #[serde(rename = "txs")]
+    pub transactions: Vec<Bytes>,
+
+    /// Transactions allowed to revert without invalidating the bundle.
+    #[serde(
+        default,
+        rename = "revertingTxHashes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reverting_hashes: Option<Vec<B256>>,
+
+    /// State to simulate the bundle against, `latest` or `pending`. The pending state includes
+    /// the flashblocks already built for the next block, and falls back to the latest state
+    /// when there are none. Defaults to `pending`.
+    #[serde(
+        default,
+        rename = "stateBlockNumber",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub state_block: Option<BlockNumberOrTag>,
+
+    /// Timestamp of the simulated block when there is no pending block. Defaults to the latest
+    /// block timestamp plus the chain block time, rounded up to the second.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulateBundleResponse {
+    /// Keccak of the concatenated transaction hashes.
+    pub bundle_hash: B256,
+    /// Number of the block the bundle was simulated in.
+    #[serde(with = "alloy_serde::quantity")]
+    pub block_number: u64,
+    /// Whether the bundle simulated against the pending flashblocks state.
+    pub pending: bool,
+    /// Whether the builder would include the bundle: every transaction is valid, within the max
+    /// gas per transaction, the DA limits and the gas limiter budget of its sender, and only
+    /// reverts if allowed to.
+    pub success: bool,
+    /// Gas used by the transactions the builder would include.
+    #[serde(with = "alloy_serde::quantity")]
+    pub gas_used: u64,
+    /// Sum of the priority fees paid to the builder by the transactions it would include.
+    pub total_tip: U256,
+    /// DA size of the transactions the builder would include.
+    #[serde(with = "alloy_serde::quantity")]
+    pub total_da_size: u64,
+    pub results: Vec<SimulatedTransaction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedTransaction {
+    pub tx_hash: B256,
+    pub from: Address,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<Address>,
+    #[serde(with = "alloy_serde::quantity")]
+    pub gas_used: u64,
+    pub success: bool,
+    /// Decoded revert reason, or the halt reason, of a failed transaction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    /// Why the transaction could not be executed, or would be excluded by the builder. The
+    /// state changes of such transactions are discarded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub output: Bytes,
+    pub logs: Vec<Log>,
+    pub state_diff: BTreeMap<Address, AccountDiff>,
+    #[serde(with = "alloy_serde::quantity")]
+    pub effective_tip_per_gas: u128,
+    /// Priority fee paid to the builder, `effective_tip_per_gas * gas_used`.
+    pub tip: U256,
+    /// Estimated size of the transaction once compressed in a batch, as counted against the
+    /// DA limits of the builder.
+    #[serde(with = "alloy_serde::quantity")]
+    pub da_size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValueDiff<T> {
+    pub from: T,
+    pub to: T,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountDiff {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance: Option<ValueDiff<U256>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<ValueDiff<u64>>,
+    /// Changed storage slots.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub storage: BTreeMap<U256, ValueDiff<U256>>,
+}
+
+impl AccountDiff {
+    fn is_empty(&self) -> bool {
+        self.balance.is_none() && self.nonce.is_none() && self.storage.is_empty()
+    }
+}
+
+#[cfg_attr(not(test), rpc(server))]
+#[cfg_attr(test, rpc(server, client))]
+pub trait BundleSimulationApi {
+    #[method(name = "eth_callBundle")]
+    async fn call_bundle(&self, bundle: SimulateBundleRequest)
+    -> RpcResult<SimulateBundleResponse>;
+
+    #[method(name = "base_simulateBundle")]
+    async fn simulate_bundle(
+        &self,
+        bundle: SimulateBundleRequest,
+    ) -> RpcResult<SimulateBundleResponse>;
+}
+
+#[derive(Clone)]
+pub struct BundleSimulationExt<Provider> {
+    provider: Provider,
+    evm_config: OpEvmConfig,
+    pending_block: PendingBlock,
+    runtime_config: RuntimeConfig,
+    gas_limiter: AddressGasLimiter,
+    block_time: Duration,
+    /// Bounds the simulations running on the blocking thread pool, the RPC is unauthenticated.
+    simulation_permits: Arc<Semaphore>,
+}
+
+impl<Provider: ClientBounds> BundleSimulationExt<Provider> {
+    pub fn new(
+        provider: Provider,
+        pending_block: PendingBlock,
+        runtime_config: RuntimeConfig,
+        gas_limiter: AddressGasLimiter,
+        block_time: Duration,
+    ) -> Self {
+        Self {
+            evm_config: OpEvmConfig::optimism(provider.chain_spec()),
+            provider,
+            pending_block,
+            runtime_config,
+            gas_limiter,
+            block_time,
+            simulation_permits: Arc::new(Semaphore::new(MAX_CONCURRENT_SIMULATIONS)),
+        }
+    }
+
+    /// Block time in seconds, rounded up, used as the default timestamp offset of the simulated
+    /// block.
+    fn block_time_secs(&self) -> u64 {
+        (self.block_time.as_millis().div_ceil(1000) as u64).max(1)
+    }
+
+    fn simulate(
+        &self,
+        request: SimulateBundleRequest,
+    ) -> Result<SimulateBundleResponse, EthApiError> {
+        if request.transactions.is_empty() {
+            return Err(EthApiError::InvalidParams(
+                "bundle must contain at least one transaction".into(),
+            ));
+        }
+        if request.transactions.len() > MAX_BUNDLE_TRANSACTIONS {
+            return Err(EthApiError::InvalidParams(format!(
+                "bundle must contain at most {MAX_BUNDLE_TRANSACTIONS} transactions"
+            )));
+        }
+        let transactions = request
+            .transactions
+            .iter()
+            .map(|raw| {
+                let tx = recover_raw_transaction::<OpTransactionSigned>(raw)?;
+                if tx.is_deposit() || tx.is_eip4844() {
+                    return Err(EthApiError::InvalidParams(
+                        "deposit and blob transactions can't be bundled".into(),
+                    ));
+                }
+                Ok(tx)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let pending = match request.state_block.unwrap_or(BlockNumberOrTag::Pending) {
+            BlockNumberOrTag::Pending => true,
+            BlockNumberOrTag::Latest => false,
+            _ => {
+                return Err(EthApiError::InvalidParams(
+                    "bundles can only be simulated against the latest or pending state".into(),
+                ));
+            }
+        };
+
+        let latest = self
+            .provider
+            .latest_header()
+            .map_err(EthApiError::from)?
+            .ok_or(EthApiError::HeaderNotFound(BlockNumberOrTag::Latest.into()))?;
+        let historical = self
+            .provider
+            .state_by_block_hash(latest.hash())
+            .map_err(EthApiError::from)?;
+
+        // the pending block is simulated again with the same environment, on top of its
+        // transactions
+        let pending_block = pending
+            .then(|| self.pending_block.get(latest.hash()))
+            .flatten();
+        let (state_provider, block_env_attributes): (StateProviderBox, _) = match &pending_block {
+            Some(block) => {
+                let header = block.recovered_block.header();
+                let attributes = OpNextBlockEnvAttributes {
+                    timestamp: header.timestamp,
+                    suggested_fee_recipient: header.beneficiary,
+                    prev_randao: header.mix_hash,
+                    gas_limit: header.gas_limit,
+                    parent_beacon_block_root: header.parent_beacon_block_root,
+                    extra_data: header.extra_data.clone(),
+                };
+                (
+                    Box::new(MemoryOverlayStateProvider::new(
+                        historical,
+                        vec![block.clone()],
+                    )),
+                    attributes,
+                )
+            }
+            None => {
+                let attributes = OpNextBlockEnvAttributes {
+                    timestamp: request
+                        .timestamp
+                        .unwrap_or(latest.timestamp + self.block_time_secs()),
+                    suggested_fee_recipient: latest.beneficiary,
+                    prev_randao: latest.mix_hash,
+                    gas_limit: latest.gas_limit,
+                    parent_beacon_block_root: latest.parent_beacon_block_root,
+                    extra_data: latest.extra_data.clone(),
+                };
+                (historical, attributes)
+            }
+        };
+
+        let evm_env = self
+            .evm_config
+            .next_evm_env(latest.header(), &block_env_attributes)
+            .map_err(|err| EthApiError::EvmCustom(err.to_string()))?;
+        let base_fee = evm_env.block_env.basefee;
+        let runtime_config = self.runtime_config.view();
+        let max_gas_per_txn = runtime_config.settings.max_gas_per_txn;
+        let reverting_hashes = request.reverting_hashes.unwrap_or_default();
+
+        // the DA used by the pending block counts against the block DA limit, as in the
+        // payload builder
+        let mut cumulative_da_size: u64 = pending_block.as_ref().map_or(0, |block| {
+            block
+                .recovered_block
+                .body()
+                .transactions
+                .iter()
+                .filter(|tx| !tx.is_deposit())
+                .map(|tx| op_alloy_flz::tx_estimated_size_fjord_bytes(&tx.encoded_2718()))
+                .sum()
+        });
+        // gas limiter budgets are checked against the gas used by all the transactions of a
+        // sender in the bundle
+        let mut gas_used_by_sender: HashMap<Address, u64> = HashMap::new();
+
+        let mut db = State::builder()
+            .with_database(StateProviderDatabase::new(state_provider))
+            .build();
+        let mut evm = self.evm_config.evm_with_env(&mut db, evm_env);
+
+        let mut results = Vec::with_capacity(transactions.len());
+        for tx in &transactions {
+            let tx_hash = tx.tx_hash();
+            let da_size = op_alloy_flz::tx_estimated_size_fjord_bytes(&tx.encoded_2718());
+            let effective_tip_per_gas = tx.effective_tip_per_gas(base_fee).unwrap_or_default();
+            let mut simulated = SimulatedTransaction {
+                tx_hash,
+                from: tx.signer(),
+                to: tx.to(),
+                gas_used: 0,
+                success: false,
+                revert_reason: None,
+                error: None,
+                output: Bytes::new(),
+                logs: Vec::new(),
+                state_diff: BTreeMap::new(),
+                effective_tip_per_gas,
+                tip: U256::ZERO,
+                da_size,
+            };
+
+            if runtime_config
+                .max_da_tx_size
+                .is_some_and(|max_da_size| da_size > max_da_size)
+            {
+                simulated.error = Some("DA size exceeds the max DA size per transaction".into());
+                results.push(simulated);
+                continue;
+            }
+            if runtime_config
+                .max_da_block_size
+                .is_some_and(|max_da_size| cumulative_da_size + da_size > max_da_size)
+            {
+                simulated.error = Some("DA size exceeds the remaining DA size of the block".into());
+                results.push(simulated);
+                continue;
+            }
+
+            let ResultAndState { result, state } = match evm.transact(tx) {
+                Ok(res) => res,
+                Err(err) => {
+                    simulated.error = Some(err.to_string());
+                    results.push(simulated);
+                    continue;
+                }
+            };
+
+            simulated.gas_used = result.gas_used();
+            simulated.success = result.is_success();
+            simulated.tip = U256::from(effective_tip_per_gas) * U256::from(result.gas_used());
+            simulated.logs = result.logs().to_vec();
+            match result {
+                ExecutionResult::Success { output, .. } => {
+                    simulated.output = output.into_data();
+                }
+                ExecutionResult::Revert { output, .. } => {
+                    simulated.revert_reason = alloy_sol_types::decode_revert_reason(&output);
+                    simulated.output = output;
+                }
+                ExecutionResult::Halt { reason, .. } => {
+                    simulated.revert_reason = Some(format!("{reason:?}"));
+                }
+            }
+
+            // mirror the exclusions of the payload builder, which charges the gas limiter for
+            // reverted transactions as well
+            let sender_gas_used = gas_used_by_sender.entry(simulated.from).or_default();
+            *sender_gas_used += simulated.gas_used;
+            if let Err(err) =
+                self.gas_limiter
+                    .check_gas(simulated.from, simulated.to, *sender_gas_used)
+            {
+                simulated.error = Some(err.to_string());
+            } else if max_gas_per_txn.is_some_and(|max_gas| simulated.gas_used > max_gas) {
+                simulated.error = Some("gas used exceeds the max gas per transaction".into());
+            } else if !simulated.success && !reverting_hashes.contains(&tx_hash) {
+                simulated.error = Some("transaction reverted".into());
+            }
+            if simulated.error.is_some() {
+                results.push(simulated);
+                continue;
+            }
+
+            for (address, account) in &state {
+                if !account.is_touched() {
+                    continue;
+                }
+                let before = evm
+                    .db_mut()
+                    .basic(*address)
+                    .map_err(EthApiError::from)?
+                    .unwrap_or_default();
+                let diff = AccountDiff {
+                    balance: (before.balance != account.info.balance).then_some(ValueDiff {
+                        from: before.balance,
+                        to: account.info.balance,
+                    }),
+                    nonce: (before.nonce != account.info.nonce).then_some(ValueDiff {
+                        from: before.nonce,
+                        to: account.info.nonce,
+                    }),
+                    storage: account
+                        .changed_storage_slots()
+                        .map(|(slot, value)| {
+                            (
+                                *slot,
+                                ValueDiff {
+                                    from: value.original_value(),
+                                    to: value.present_value(),
+                                },
+                            )
+                        })
+                        .collect(),
+                };
+                if !diff.is_empty() {
+                    simulated.state_diff.insert(*address, diff);
+                }
+            }
+
+            evm.db_mut().commit(state);
+            cumulative_da_size += da_size;
+            results.push(simulated);
+        }
+
+        let bundle_hash = keccak256(
+            transactions
+                .iter()
+                .flat_map(|tx| tx.tx_hash().0)
+                .collect::<Vec<u8>>(),
+        );
+        // transactions with an error would be excluded by the builder, so they don't count
+        // towards the totals
+        let included = || results.iter().filter(|result| result.error.is_none());
+        Ok(SimulateBundleResponse {
+            bundle_hash,
+            block_number: latest.number + 1,
+            pending: pending_block.is_some(),
+            success: included().count() == results.len(),
+            gas_used: included().map(|result| result.gas_used).sum(),
+            total_tip: included().map(|result| result.tip).sum(),
+            total_da_size: included().map(|result| result.da_size).sum(),
+            results,
+        })
+    }
+}
+
+#[async_trait]
+impl<Provider> BundleSimulationApiServer for BundleSimulationExt<Provider>
+where
+    Provider: ClientBounds + Send + Sync + 'static,
+{
+    async fn call_bundle(
+        &self,
+        bundle: SimulateBundleRequest,
+    ) -> RpcResult<SimulateBundleResponse> {
+        self.simulate_bundle(bundle).await
+    }
+
+    async fn simulate_bundle(
+        &self,
+        bundle: SimulateBundleRequest,
+    ) -> RpcResult<SimulateBundleResponse> {
+        let permit = self
+            .simulation_permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| EthApiError::InternalEthError)?;
+        let this = self.clone();
+        let response = tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            this.simulate(bundle)
+        })
+        .await
+        .map_err(|_| EthApiError::InternalEthError)??;
+        Ok(response)
+    }
+}
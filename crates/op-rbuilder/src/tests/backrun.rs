@@ -1,4 +1,9 @@
-use crate::tests::{ChainDriverExt, LocalInstance, framework::ONE_ETH};
+use crate::{
+    args::OpRbuilderArgs,
+    gas_limiter::args::GasLimiterArgs,
+    tests::{ChainDriverExt, LocalInstance, framework::ONE_ETH},
+    tx_data_store::BackrunBundleStatus,
+};
 use alloy_eips::eip2718::Encodable2718;
 use alloy_primitives::{TxHash, U256};
 use alloy_provider::Provider;
@@ -560,3 +565,105 @@ async fn backrun_bundle_rejected_exceeds_da_limit(rbuilder: LocalInstance) -> ey
 
     Ok(())
 }
+
+/// Tests that backrun simulations are charged to the address gas limiter:
+/// - A backrun tx using more gas than the sender budget rejects the whole bundle
+/// - The rejection is reported through the bundle status
+#[rb_test(flashblocks, args = OpRbuilderArgs {
+    gas_limiter: GasLimiterArgs {
+        gas_limiter_enabled: true,
+        max_gas_per_address: 30_000,
+        refill_rate_per_block: 30_000,
+        cleanup_interval: 100,
+        max_tracked_addresses: 100_000,
+        gas_limiter_policy_file: None,
+        gas_limiter_check_bundles: false,
+    },
+    ..Default::default()
+})]
+async fn backrun_bundle_rejected_by_gas_limiter(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let driver = rbuilder.driver().await?;
+    let accounts = driver.fund_accounts(2, ONE_ETH).await?;
+
+    // 1. Build target tx, a plain transfer within the budget of its sender
+    let target_tx = driver
+        .create_transaction()
+        .with_signer(accounts[0])
+        .with_max_priority_fee_per_gas(20)
+        .build()
+        .await;
+    let target_tx_hash = target_tx.tx_hash().clone();
+
+    let provider = rbuilder.provider().await?;
+    let _ = provider
+        .send_raw_transaction(target_tx.encoded_2718().as_slice())
+        .await?;
+
+    // 2. Create a reverting backrun tx, which uses more gas than the 30k budget of its sender
+    let backrun_tx = driver
+        .create_transaction()
+        .with_signer(accounts[1])
+        .with_max_priority_fee_per_gas(50)
+        .with_revert()
+        .build()
+        .await;
+    let backrun_tx_hash = backrun_tx.tx_hash().clone();
+
+    // 3. Insert backrun bundle into store
+    let bundle_id = Uuid::new_v4();
+    let bundle = AcceptedBundle {
+        uuid: bundle_id,
+        txs: vec![target_tx, backrun_tx],
+        block_number: driver.latest().await?.header.number + 1,
+        flashblock_number_min: None,
+        flashblock_number_max: None,
+        min_timestamp: None,
+        max_timestamp: None,
+        reverting_tx_hashes: vec![],
+        replacement_uuid: None,
+        dropping_tx_hashes: vec![],
+        meter_bundle_response: MeterBundleResponse {
+            bundle_gas_price: U256::ZERO,
+            bundle_hash: TxHash::ZERO,
+            coinbase_diff: U256::ZERO,
+            eth_sent_to_coinbase: U256::ZERO,
+            gas_fees: U256::ZERO,
+            results: vec![],
+            state_block_number: 0,
+            state_flashblock_index: None,
+            total_gas_used: 0,
+            total_execution_time_us: 0,
+        },
+    };
+
+    rbuilder
+        .tx_data_store()
+        .insert_backrun_bundle(bundle)
+        .expect("Failed to insert backrun bundle");
+    assert_eq!(
+        rbuilder.tx_data_store().bundle_status(&bundle_id),
+        Some(BackrunBundleStatus::Pending)
+    );
+
+    // 4. Build the block
+    driver.build_new_block().await?;
+
+    // 5. Verify block contents and bundle status
+    let block = driver.latest_full().await?;
+    let tx_hashes: Vec<_> = block.transactions.hashes().collect();
+
+    assert!(
+        tx_hashes.contains(&target_tx_hash),
+        "Target tx should be included in block"
+    );
+    assert!(
+        !tx_hashes.contains(&backrun_tx_hash),
+        "backrun tx should NOT be in block (sender over its gas budget)"
+    );
+    assert_eq!(
+        rbuilder.tx_data_store().bundle_status(&bundle_id),
+        Some(BackrunBundleStatus::RejectedGasLimit)
+    );
+
+    Ok(())
+}
@@ -1,17 +1,27 @@
 use alloy_consensus::Transaction;
 use alloy_network::TransactionResponse;
-use alloy_primitives::{Address, U256};
+use alloy_primitives::{Address, U64, U256};
 use alloy_provider::{Provider, RootProvider};
+use alloy_sol_types::SolCall;
 use macros::{if_flashblocks, if_standard, rb_test};
 use op_alloy_network::Optimism;
+use reth_ipc::client::IpcClientBuilder;
+use std::time::Duration;
 
 use crate::{
     args::{FlashblocksArgs, OpRbuilderArgs},
-    flashtestations::args::FlashtestationsArgs,
+    flashtestations::{
+        IBlockBuilderPolicy,
+        args::FlashtestationsArgs,
+        attestation::AttestationProviderKind,
+        block_proofs::BlockProofApiClient,
+        flashblock_signature::verify_flashblock_signature,
+        status::{FlashtestationsStatus, FlashtestationsStatusApiClient},
+    },
     tests::{
         BLOCK_BUILDER_POLICY_ADDRESS, BundleOpts, ChainDriver, ChainDriverExt,
         FLASHBLOCKS_NUMBER_ADDRESS, FLASHTESTATION_REGISTRY_ADDRESS, LocalInstance,
-        MOCK_DCAP_ADDRESS, TEE_DEBUG_ADDRESS, TransactionBuilderExt,
+        MOCK_DCAP_ADDRESS, TEE_DEBUG_ADDRESS, TEST_QUOTE_PATH, TransactionBuilderExt, WORKLOAD_ID,
         block_builder_policy::BlockBuilderPolicy, builder_signer,
         flashblocks_number_contract::FlashblocksNumber,
         flashtestation_registry::FlashtestationRegistry,
@@ -125,6 +135,73 @@ async fn test_flashtestations_unauthorized_workload(rbuilder: LocalInstance) ->
     Ok(())
 }
 
+#[rb_test(args = OpRbuilderArgs {
+    chain_block_time: 1000,
+    enable_revert_protection: true,
+    flashtestations: FlashtestationsArgs {
+        flashtestations_enabled: true,
+        registry_address: Some(FLASHTESTATION_REGISTRY_ADDRESS),
+        builder_policy_address: Some(BLOCK_BUILDER_POLICY_ADDRESS),
+        debug: true,
+        enable_block_proofs: true,
+        block_proof_batch_size: 2,
+        ..Default::default()
+    },
+    ..Default::default()
+})]
+async fn test_flashtestations_batched_block_proofs(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let driver = rbuilder.driver().await?;
+    let provider = rbuilder.provider().await?;
+    let client = IpcClientBuilder::default()
+        .build(rbuilder.rpc_ipc())
+        .await?;
+    setup_flashtestation_contracts(&driver, &provider, true, true).await?;
+
+    let mut blocks = Vec::new();
+    for _ in 0..3 {
+        blocks.push(driver.build_new_block_with_current_timestamp(None).await?);
+    }
+    // proofs are served once the builder processed the inclusion of the blocks
+    driver.build_new_block_with_current_timestamp(None).await?;
+    // the batch ending at the first even block whose previous block is also built after the setup
+    let (first, last) = if blocks[0].header.number % 2 == 1 {
+        (&blocks[0], &blocks[1])
+    } else {
+        (&blocks[1], &blocks[2])
+    };
+
+    let proof_txs = |block: &alloy_rpc_types_eth::Block<op_alloy_rpc_types::Transaction>| {
+        block
+            .transactions
+            .txns()
+            .filter(|tx| tx.to() == Some(BLOCK_BUILDER_POLICY_ADDRESS))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    assert!(
+        proof_txs(first).is_empty(),
+        "block proof should only be in the last block of the batch"
+    );
+    let last_proof_txs = proof_txs(last);
+    assert_eq!(last_proof_txs.len(), 1, "expected a single block proof");
+    let root = IBlockBuilderPolicy::permitVerifyBlockBuilderProofCall::abi_decode(
+        last_proof_txs[0].input(),
+    )?
+    .blockContentHash;
+
+    for block in [first, last] {
+        let proof = client
+            .get_block_proof(U64::from(block.header.number))
+            .await?
+            .expect("block should have an inclusion proof");
+        assert_eq!(proof.proof_block_number, U64::from(last.header.number));
+        assert_eq!(proof.root, root, "proof should be for the committed root");
+        assert_eq!(proof.proof.len(), 1);
+        assert!(proof.verify());
+    }
+    Ok(())
+}
+
 #[rb_test(flashblocks, args = OpRbuilderArgs {
     chain_block_time: 1000,
     enable_revert_protection: true,
@@ -227,6 +304,33 @@ async fn test_flashtestations_permit_registration(rbuilder: LocalInstance) -> ey
     Ok(())
 }
 
+#[rb_test(args = OpRbuilderArgs {
+    chain_block_time: 1000,
+    enable_revert_protection: true,
+    flashtestations: FlashtestationsArgs {
+        flashtestations_enabled: true,
+        registry_address: Some(FLASHTESTATION_REGISTRY_ADDRESS),
+        builder_policy_address: Some(BLOCK_BUILDER_POLICY_ADDRESS),
+        debug: true,
+        attestation_provider: AttestationProviderKind::File,
+        quote_file: Some(TEST_QUOTE_PATH.to_string()),
+        ..Default::default()
+    },
+    ..Default::default()
+})]
+async fn test_flashtestations_file_attestation_provider(
+    rbuilder: LocalInstance,
+) -> eyre::Result<()> {
+    // no attestation server is spawned, the quote is read from the file
+    assert!(rbuilder.attestation_server().is_none());
+
+    let driver = rbuilder.driver().await?;
+    let provider = rbuilder.provider().await?;
+    // registers the tee key with the quote from the file
+    setup_flashtestation_contracts(&driver, &provider, true, true).await?;
+    Ok(())
+}
+
 #[rb_test(args = OpRbuilderArgs {
     chain_block_time: 1000,
     enable_revert_protection: true,
@@ -470,6 +574,141 @@ async fn test_flashtestations_permit_with_flashblocks_number_permit(
     Ok(())
 }
 
+#[rb_test(flashblocks, args = OpRbuilderArgs {
+    chain_block_time: 1000,
+    enable_revert_protection: true,
+    flashtestations: FlashtestationsArgs {
+        flashtestations_enabled: true,
+        registry_address: Some(FLASHTESTATION_REGISTRY_ADDRESS),
+        builder_policy_address: Some(BLOCK_BUILDER_POLICY_ADDRESS),
+        debug: true,
+        enable_flashblock_signatures: true,
+        ..Default::default()
+    },
+    ..Default::default()
+})]
+async fn test_flashtestations_flashblock_signatures(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let driver = rbuilder.driver().await?;
+    let provider = rbuilder.provider().await?;
+    let flashblocks_listener = rbuilder.spawn_flashblocks_listener();
+    setup_flashtestation_contracts(&driver, &provider, true, true).await?;
+
+    for _ in 0..3 {
+        driver
+            .create_transaction()
+            .random_valid_transfer()
+            .send()
+            .await?;
+        driver.build_new_block_with_current_timestamp(None).await?;
+    }
+
+    let flashblocks = flashblocks_listener.get_flashblocks();
+    assert!(!flashblocks.is_empty(), "expected published flashblocks");
+    for flashblock in &flashblocks {
+        verify_flashblock_signature(flashblock, TEE_DEBUG_ADDRESS)?;
+
+        // a signature doesn't verify for another position in the block
+        let mut moved = flashblock.clone();
+        moved.index += 1;
+        assert!(verify_flashblock_signature(&moved, TEE_DEBUG_ADDRESS).is_err());
+    }
+
+    flashblocks_listener.stop().await
+}
+
+#[rb_test(args = OpRbuilderArgs {
+    chain_block_time: 1000,
+    enable_revert_protection: true,
+    flashtestations: FlashtestationsArgs {
+        flashtestations_enabled: true,
+        registry_address: Some(FLASHTESTATION_REGISTRY_ADDRESS),
+        builder_policy_address: Some(BLOCK_BUILDER_POLICY_ADDRESS),
+        debug: true,
+        enable_block_proofs: true,
+        monitor_interval_secs: 1,
+        ..Default::default()
+    },
+    ..Default::default()
+})]
+async fn test_flashtestations_status(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let driver = rbuilder.driver().await?;
+    let provider = rbuilder.provider().await?;
+    let client = IpcClientBuilder::default()
+        .build(rbuilder.rpc_ipc())
+        .await?;
+    setup_flashtestation_contracts(&driver, &provider, true, false).await?;
+
+    // registered, but the workload is not approved so there are no block proofs
+    driver.build_new_block_with_current_timestamp(None).await?;
+    let latest = provider.get_block_number().await?;
+    let status = wait_for_status(&client, |status| {
+        status.block_number >= Some(U64::from(latest))
+    })
+    .await?;
+    assert_eq!(status.tee_address, Some(TEE_DEBUG_ADDRESS));
+    assert!(status.registered, "the tee address should be registered");
+    assert!(!status.quote_hash.is_zero());
+    assert_eq!(status.workload_id, Some(WORKLOAD_ID));
+    assert!(!status.workload_approved);
+    assert_eq!(status.last_block_proof, None);
+    assert_eq!(status.error, None);
+
+    // approve the workload, the next blocks include block proofs
+    driver
+        .create_transaction()
+        .add_workload_to_policy()
+        .with_to(BLOCK_BUILDER_POLICY_ADDRESS)
+        .with_bundle(BundleOpts::default())
+        .send()
+        .await?;
+    driver.build_new_block_with_current_timestamp(None).await?;
+    let block = driver.build_new_block_with_current_timestamp(None).await?;
+    let proof_tx = block
+        .transactions
+        .txns()
+        .find(|tx| tx.to() == Some(BLOCK_BUILDER_POLICY_ADDRESS))
+        .expect("block should include a block proof")
+        .clone();
+
+    let status = wait_for_status(&client, |status| {
+        status.block_number >= Some(U64::from(block.header.number))
+    })
+    .await?;
+    assert!(status.workload_approved, "the workload should be approved");
+    let last_block_proof = status
+        .last_block_proof
+        .expect("the block proof should be tracked");
+    assert_eq!(
+        last_block_proof.block_number,
+        U64::from(block.header.number)
+    );
+    assert_eq!(last_block_proof.tx_hash, proof_tx.tx_hash());
+    assert_eq!(
+        last_block_proof.block_content_hash,
+        IBlockBuilderPolicy::permitVerifyBlockBuilderProofCall::abi_decode(proof_tx.input())?
+            .blockContentHash
+    );
+    assert_eq!(status.blocks_since_block_proof, Some(U64::ZERO));
+    Ok(())
+}
+
+/// Waits for the flashtestations status monitor to reach a status.
+async fn wait_for_status(
+    client: &(impl FlashtestationsStatusApiClient + Sync),
+    done: impl Fn(&FlashtestationsStatus) -> bool,
+) -> eyre::Result<FlashtestationsStatus> {
+    for _ in 0..20 {
+        let status = client.status().await?;
+        if done(&status) {
+            return Ok(status);
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+    Err(eyre::eyre!(
+        "timed out waiting for the flashtestations status"
+    ))
+}
+
 async fn setup_flashtestation_contracts(
     driver: &ChainDriver,
     provider: &RootProvider<Optimism>,
@@ -1,8 +1,20 @@
 use crate::{
+    admission::{AdmissionControl, FBPoolBuilder},
     args::OpRbuilderArgs,
     builders::{BuilderConfig, FlashblocksBuilder, PayloadBuilder, StandardBuilder},
+    flashtestations::{
+        attestation::AttestationProviderKind,
+        block_proofs::{BlockProofApiServer, BlockProofExt, track_canonical_blocks},
+        status::{
+            FlashtestationsStatusApiServer, FlashtestationsStatusExt, FlashtestationsStatusMonitor,
+        },
+    },
     primitives::reth::engine_api_builder::OpEngineApiBuilder,
+    private_tx::{PrivateTransactionApiServer, PrivateTransactionExt},
+    reputation::{ReputationApiServer, ReputationExt},
     revert_protection::{EthApiExtServer, RevertProtectionExt},
+    runtime_config::{BuilderAdminApiServer, BuilderAdminExt},
+    simulation::{BundleSimulationApiServer, BundleSimulationExt},
     tests::{
         EngineApi, Ipc, TEE_DEBUG_ADDRESS, TransactionPoolObserver, builder_signer, create_test_db,
         framework::driver::ChainDriver, get_available_port,
@@ -44,6 +56,7 @@ use reth_optimism_node::{
     node::{OpAddOns, OpAddOnsBuilder, OpEngineValidatorBuilder, OpPoolBuilder},
 };
 use reth_optimism_rpc::OpEthApiBuilder;
+use reth_provider::CanonStateSubscriptions;
 use reth_transaction_pool::{AllTransactionsEvents, TransactionPool};
 use rollup_boost::FlashblocksPayloadV1;
 use std::{
@@ -101,7 +114,9 @@ impl LocalInstance {
         args.builder_signer = Some(signer);
         args.rollup_args.enable_tx_conditional = true;
 
-        let attestation_server = if args.flashtestations.flashtestations_enabled {
+        let attestation_server = if args.flashtestations.flashtestations_enabled
+            && args.flashtestations.attestation_provider == AttestationProviderKind::Remote
+        {
             let server = spawn_attestation_provider().await?;
             args.flashtestations.quote_provider = Some(server.url());
             tracing::info!("Started attestation server at {}", server.url());
@@ -115,6 +130,24 @@ impl LocalInstance {
         let da_config = builder_config.da_config.clone();
         let gas_limit_config = builder_config.gas_limit_config.clone();
         let tx_data_store = builder_config.tx_data_store.clone();
+        let rpc_tx_data_store = tx_data_store.clone();
+        let runtime_config = builder_config.runtime_config.clone();
+        let sender_reputation = builder_config.sender_reputation.clone();
+        let pending_block = builder_config.pending_block.clone();
+        let block_time = builder_config.block_time;
+        let bundle_range = builder_config.bundle_range;
+        let private_txs = builder_config.private_txs.clone();
+        let block_proofs = builder_config.block_proofs.clone();
+        let rpc_block_proofs = block_proofs.clone();
+        let flashtestations_status = builder_config.flashtestations_status.clone();
+        let rpc_flashtestations_status = flashtestations_status.clone();
+        let flashtestations_args = args.flashtestations.clone();
+        let bundle_gas_limiter = args
+            .gas_limiter
+            .gas_limiter_check_bundles
+            .then(|| builder_config.address_gas_limiter.clone());
+        let admission = builder_config.admission.clone();
+        let simulation_gas_limiter = builder_config.address_gas_limiter.clone();
 
         let addons: OpAddOns<
             _,
@@ -135,7 +168,7 @@ impl LocalInstance {
             .with_components(
                 op_node
                     .components()
-                    .pool(pool_component(&args))
+                    .pool(pool_component(&args, admission))
                     .payload(P::new_service(builder_config)?),
             )
             .with_add_ons(addons)
@@ -150,12 +183,58 @@ impl LocalInstance {
                         provider,
                         ctx.registry.eth_api().clone(),
                         reverted_cache,
+                        rpc_tx_data_store,
+                        bundle_gas_limiter,
+                        bundle_range,
                     );
 
                     ctx.modules
                         .add_or_replace_configured(revert_protection_ext.into_rpc())?;
                 }
 
+                if args.private_tx.private_tx_enabled {
+                    let private_tx_ext = PrivateTransactionExt::new(
+                        ctx.provider().clone(),
+                        private_txs,
+                        bundle_range,
+                    );
+                    ctx.modules
+                        .add_or_replace_configured(private_tx_ext.into_rpc())?;
+                }
+
+                let reputation_ext = ReputationExt::new(sender_reputation);
+                ctx.modules
+                    .add_or_replace_configured(reputation_ext.into_rpc())?;
+
+                if args.flashtestations.flashtestations_enabled
+                    && args.flashtestations.enable_block_proofs
+                {
+                    let block_proof_ext = BlockProofExt::new(rpc_block_proofs);
+                    ctx.modules
+                        .add_or_replace_configured(block_proof_ext.into_rpc())?;
+                }
+
+                if args.flashtestations.flashtestations_enabled {
+                    let flashtestations_status_ext =
+                        FlashtestationsStatusExt::new(rpc_flashtestations_status);
+                    ctx.modules
+                        .add_or_replace_configured(flashtestations_status_ext.into_rpc())?;
+                }
+
+                let bundle_simulation_ext = BundleSimulationExt::new(
+                    ctx.provider().clone(),
+                    pending_block,
+                    runtime_config.clone(),
+                    simulation_gas_limiter,
+                    block_time,
+                );
+                ctx.modules
+                    .add_or_replace_configured(bundle_simulation_ext.into_rpc())?;
+
+                let builder_admin_ext = BuilderAdminExt::new(runtime_config);
+                ctx.auth_module
+                    .merge_auth_methods(builder_admin_ext.into_rpc())?;
+
                 Ok(())
             })
             .on_rpc_started(move |_, _| {
@@ -167,6 +246,21 @@ impl LocalInstance {
                     .send(ctx.pool.all_transactions_event_listener())
                     .expect("Failed to send txpool ready signal");
 
+                if flashtestations_args.flashtestations_enabled {
+                    let monitor = FlashtestationsStatusMonitor::new(
+                        &flashtestations_args,
+                        flashtestations_status,
+                        ctx.provider.clone(),
+                    );
+                    ctx.task_executor.spawn(monitor.run());
+                    if flashtestations_args.enable_block_proofs {
+                        ctx.task_executor.spawn(track_canonical_blocks(
+                            block_proofs,
+                            ctx.provider.canonical_state_stream(),
+                        ));
+                    }
+                }
+
                 Ok(())
             });
 
@@ -366,9 +460,9 @@ fn task_manager() -> TaskManager {
     TaskManager::new(tokio::runtime::Handle::current())
 }
 
-fn pool_component(args: &OpRbuilderArgs) -> OpPoolBuilder<FBPooledTransaction> {
+fn pool_component(args: &OpRbuilderArgs, admission: AdmissionControl) -> FBPoolBuilder {
     let rollup_args = &args.rollup_args;
-    OpPoolBuilder::<FBPooledTransaction>::default()
+    let pool = OpPoolBuilder::<FBPooledTransaction>::default()
         .with_enable_tx_conditional(
             // Revert protection uses the same internal pool logic as conditional transactions
             // to garbage collect transactions out of the bundle range.
@@ -377,7 +471,8 @@ fn pool_component(args: &OpRbuilderArgs) -> OpPoolBuilder<FBPooledTransaction> {
         .with_supervisor(
             rollup_args.supervisor_http.clone(),
             rollup_args.supervisor_safety_level,
-        )
+        );
+    FBPoolBuilder::new(pool, admission)
 }
 
 async fn spawn_attestation_provider() -> eyre::Result<AttestationServer> {
@@ -45,6 +45,12 @@ pub const TEE_DEBUG_ADDRESS: alloy_primitives::Address =
 pub const WORKLOAD_ID: B256 =
     b256!("952569f637f3f7e36cd8f5a7578ae4d03a1cb05ddaf33b35d3054464bb1c862e");
 
+/// Path of the test quote, served by the attestation server of the test instances.
+pub const TEST_QUOTE_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/tests/framework/artifacts/test-quote.bin"
+);
+
 pub const SOURCE_LOCATORS: &[&str] = &[
     "https://github.com/flashbots/flashbots-images/commit/53d431f58a0d1a76f6711518ef8d876ce8181fc2",
 ];
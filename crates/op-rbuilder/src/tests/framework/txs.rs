@@ -19,6 +19,7 @@ use reth_transaction_pool::{AllTransactionsEvents, FullTransactionEvent, Transac
 use std::{collections::VecDeque, sync::Arc};
 use tokio::sync::watch;
 use tracing::debug;
+use uuid::Uuid;
 
 use alloy_eips::eip1559::MIN_PROTOCOL_BASE_FEE;
 
@@ -30,6 +31,9 @@ pub struct BundleOpts {
     flashblock_number_max: Option<u64>,
     min_timestamp: Option<u64>,
     max_timestamp: Option<u64>,
+    min_flashblock_time: Option<u64>,
+    max_flashblock_time: Option<u64>,
+    replacement_uuid: Option<Uuid>,
 }
 
 impl BundleOpts {
@@ -62,6 +66,21 @@ impl BundleOpts {
         self.max_timestamp = Some(max_timestamp);
         self
     }
+
+    pub fn with_min_flashblock_time(mut self, min_flashblock_time: u64) -> Self {
+        self.min_flashblock_time = Some(min_flashblock_time);
+        self
+    }
+
+    pub fn with_max_flashblock_time(mut self, max_flashblock_time: u64) -> Self {
+        self.max_flashblock_time = Some(max_flashblock_time);
+        self
+    }
+
+    pub fn with_replacement_uuid(mut self, replacement_uuid: Uuid) -> Self {
+        self.replacement_uuid = Some(replacement_uuid);
+        self
+    }
 }
 
 #[derive(Clone)]
@@ -221,6 +240,10 @@ impl TransactionBuilder {
                 flashblock_number_max: bundle_opts.flashblock_number_max,
                 min_timestamp: bundle_opts.min_timestamp,
                 max_timestamp: bundle_opts.max_timestamp,
+                min_flashblock_time: bundle_opts.min_flashblock_time,
+                max_flashblock_time: bundle_opts.max_flashblock_time,
+                replacement_uuid: bundle_opts.replacement_uuid,
+                cancellation_signature: None,
             };
 
             let result: BundleResult = provider
@@ -16,6 +16,9 @@ use tracing::info;
         max_gas_per_address: 200000,  // 200k gas per address - low for testing
         refill_rate_per_block: 100000,  // 100k gas refill per block
         cleanup_interval: 100,
+        max_tracked_addresses: 100000,
+        gas_limiter_policy_file: None,
+        gas_limiter_check_bundles: false,
     },
     ..Default::default()
 })]
@@ -23,9 +23,21 @@ mod backrun;
 #[cfg(test)]
 mod ordering;
 
+#[cfg(test)]
+mod private_tx;
+
+#[cfg(test)]
+mod reputation;
+
 #[cfg(test)]
 mod revert;
 
+#[cfg(test)]
+mod runtime_config;
+
+#[cfg(test)]
+mod simulation;
+
 #[cfg(test)]
 mod smoke;
 
@@ -0,0 +1,131 @@
+use crate::{
+    args::OpRbuilderArgs,
+    private_tx::{PrivateTransactionApiClient, PrivateTransactionRequest, args::PrivateTxArgs},
+    tests::{BlockTransactionsExt, ChainDriverExt, LocalInstance, ONE_ETH},
+};
+use alloy_eips::Encodable2718;
+use alloy_provider::Provider;
+use macros::rb_test;
+use reth_ipc::client::IpcClientBuilder;
+
+/// This test ensures that transactions sent through `eth_sendPrivateTransaction` are included
+/// without going through the public pool, and that revert protected ones are left out of the
+/// block when they revert.
+#[rb_test(args = OpRbuilderArgs {
+    private_tx: PrivateTxArgs {
+        private_tx_enabled: true,
+        ..Default::default()
+    },
+    ..Default::default()
+})]
+async fn private_transactions(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let driver = rbuilder.driver().await?;
+    let provider = rbuilder.provider().await?;
+    let client = IpcClientBuilder::default()
+        .build(rbuilder.rpc_ipc())
+        .await?;
+    let accounts = driver.fund_accounts(3, ONE_ETH).await?;
+
+    let private_tx = driver
+        .create_transaction()
+        .with_signer(accounts[0])
+        .build()
+        .await;
+    let reverting_tx = driver
+        .create_transaction()
+        .with_signer(accounts[1])
+        .with_revert()
+        .build()
+        .await;
+    let protected_tx = driver
+        .create_transaction()
+        .with_signer(accounts[2])
+        .with_revert()
+        .build()
+        .await;
+
+    let mut hashes = Vec::new();
+    for (tx, revert_protection) in [
+        (private_tx, false),
+        (reverting_tx, false),
+        (protected_tx, true),
+    ] {
+        let hash = client
+            .send_private_transaction(PrivateTransactionRequest {
+                tx: tx.encoded_2718().into(),
+                max_block_number: None,
+                revert_protection,
+            })
+            .await?;
+        assert_eq!(hash, *tx.tx_hash());
+        hashes.push(hash);
+    }
+
+    // private transactions are not in the public pool
+    for hash in &hashes {
+        assert!(provider.get_transaction_by_hash(*hash).await?.is_none());
+    }
+
+    let block = driver.build_new_block().await?;
+    assert!(block.includes(&hashes[0]));
+    assert!(block.includes(&hashes[1]));
+    assert!(!block.includes(&hashes[2]));
+
+    // included transactions can't be sent again
+    let resend = client
+        .send_private_transaction(PrivateTransactionRequest {
+            tx: driver
+                .create_transaction()
+                .with_signer(accounts[0])
+                .with_nonce(0)
+                .build()
+                .await
+                .encoded_2718()
+                .into(),
+            max_block_number: None,
+            revert_protection: false,
+        })
+        .await;
+    assert!(resend.is_err());
+
+    Ok(())
+}
+
+/// Private transactions are only included up to their max block number.
+#[rb_test(args = OpRbuilderArgs {
+    private_tx: PrivateTxArgs {
+        private_tx_enabled: true,
+        ..Default::default()
+    },
+    ..Default::default()
+})]
+async fn private_transaction_max_block(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let driver = rbuilder.driver().await?;
+    let client = IpcClientBuilder::default()
+        .build(rbuilder.rpc_ipc())
+        .await?;
+    let latest = driver.latest().await?.header.number;
+
+    let request = |tx: Vec<u8>, max_block_number| PrivateTransactionRequest {
+        tx: tx.into(),
+        max_block_number: Some(max_block_number),
+        revert_protection: false,
+    };
+    let tx = driver.create_transaction().build().await;
+
+    // the max block number can't be a past block
+    assert!(
+        client
+            .send_private_transaction(request(tx.encoded_2718(), latest))
+            .await
+            .is_err()
+    );
+
+    let hash = client
+        .send_private_transaction(request(tx.encoded_2718(), latest + 1))
+        .await?;
+    let block = driver.build_new_block().await?;
+    assert!(block.includes(&hash));
+
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use crate::{
+    admission::args::AdmissionArgs,
+    args::OpRbuilderArgs,
+    reputation::ReputationApiClient,
+    tests::{
+        BlockTransactionsExt, BundleOpts, ChainDriverExt, LocalInstance, ONE_ETH,
+        TransactionBuilderExt,
+    },
+};
+use macros::rb_test;
+use reth_ipc::client::IpcClientBuilder;
+
+/// This test ensures that the outcomes of the transactions of a sender are reported through
+/// `base_getSenderStats`.
+#[rb_test]
+async fn sender_stats_rpc(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let driver = rbuilder.driver().await?;
+    let client = IpcClientBuilder::default()
+        .build(rbuilder.rpc_ipc())
+        .await?;
+    let accounts = driver.fund_accounts(2, ONE_ETH).await?;
+    let sender = accounts[0];
+
+    assert_eq!(client.get_sender_stats(sender.address).await?, None);
+
+    let ok_tx = driver
+        .create_transaction()
+        .with_signer(sender)
+        .send()
+        .await?;
+    let reverted_tx = driver
+        .create_transaction()
+        .with_signer(sender)
+        .with_revert()
+        .send()
+        .await?;
+    let block = driver.build_new_block().await?;
+    assert!(block.includes(&vec![*ok_tx.tx_hash(), *reverted_tx.tx_hash()]));
+
+    let report = client
+        .get_sender_stats(sender.address)
+        .await?
+        .expect("sender should have stats");
+    assert_eq!(report.sender, sender.address);
+    assert_eq!(report.long_window.stats.simulated, 2);
+    assert_eq!(report.long_window.stats.included, 2);
+    assert_eq!(report.long_window.stats.reverted, 1);
+    assert_eq!(report.long_window.revert_percent, 50);
+    assert!(report.long_window.stats.gas_wasted > 0);
+    assert!(report.long_window.stats.gas_wasted < report.long_window.stats.gas_used);
+    assert_eq!(report.short_window.stats, report.long_window.stats);
+
+    // senders without transactions in the window are not reported
+    assert_eq!(client.get_sender_stats(accounts[1].address).await?, None);
+
+    Ok(())
+}
+
+/// This test ensures that a revert protected transaction, simulated again in each flashblock of
+/// the block, is counted once in the stats of its sender.
+#[rb_test(flashblocks, args = OpRbuilderArgs {
+    enable_revert_protection: true,
+    ..Default::default()
+})]
+async fn reverting_tx_counted_once_per_block(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let driver = rbuilder.driver().await?;
+    let client = IpcClientBuilder::default()
+        .build(rbuilder.rpc_ipc())
+        .await?;
+    let accounts = driver.fund_accounts(1, ONE_ETH).await?;
+    let sender = accounts[0];
+
+    let reverted_tx = driver
+        .create_transaction()
+        .random_reverting_transaction()
+        .with_signer(sender)
+        .with_bundle(BundleOpts::default())
+        .send()
+        .await?;
+    let block = driver.build_new_block_with_current_timestamp(None).await?;
+    assert!(!block.includes(reverted_tx.tx_hash()));
+
+    let report = client
+        .get_sender_stats(sender.address)
+        .await?
+        .expect("sender should have stats");
+    assert_eq!(report.long_window.stats.simulated, 1);
+    assert_eq!(report.long_window.stats.reverted, 1);
+    assert_eq!(report.long_window.stats.included, 0);
+    assert_eq!(report.long_window.revert_percent, 100);
+
+    Ok(())
+}
+
+/// This test ensures that the transactions of a sender whose recent transactions often
+/// reverted are built after those of the other senders, regardless of their tip.
+#[rb_test(args = OpRbuilderArgs {
+    admission: AdmissionArgs {
+        admission_enabled: true,
+        admission_deprioritize_revert_percent: 25,
+        admission_max_revert_percent: 75,
+        admission_min_simulated_txs: 2,
+    },
+    ..Default::default()
+})]
+async fn reputation_orders_senders(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let driver = rbuilder.driver().await?;
+    let accounts = driver.fund_accounts(2, ONE_ETH).await?;
+    let reverting = accounts[0];
+    let honest = accounts[1];
+
+    let ok_tx = driver
+        .create_transaction()
+        .with_signer(reverting)
+        .send()
+        .await?;
+    let reverted_tx = driver
+        .create_transaction()
+        .with_signer(reverting)
+        .with_revert()
+        .send()
+        .await?;
+    let block = driver.build_new_block().await?;
+    assert!(block.includes(&vec![*ok_tx.tx_hash(), *reverted_tx.tx_hash()]));
+
+    let high_tip_tx = driver
+        .create_transaction()
+        .with_signer(reverting)
+        .with_max_priority_fee_per_gas(50)
+        .send()
+        .await?;
+    let low_tip_tx = driver
+        .create_transaction()
+        .with_signer(honest)
+        .with_max_priority_fee_per_gas(10)
+        .send()
+        .await?;
+    let block = driver.build_new_block().await?;
+
+    let tx_hashes: Vec<_> = block.transactions.hashes().collect();
+    let high_tip_pos = tx_hashes
+        .iter()
+        .position(|h| h == high_tip_tx.tx_hash())
+        .expect("tx of the reverting sender should be included");
+    let low_tip_pos = tx_hashes
+        .iter()
+        .position(|h| h == low_tip_tx.tx_hash())
+        .expect("tx of the honest sender should be included");
+    assert!(
+        low_tip_pos < high_tip_pos,
+        "tx of the reverting sender should be built after the tx of the honest sender"
+    );
+
+    Ok(())
+}
@@ -1,14 +1,17 @@
+use alloy_primitives::{B256, Bytes};
 use alloy_provider::{PendingTransactionBuilder, Provider};
 use macros::{if_flashblocks, if_standard, rb_test};
 use op_alloy_network::Optimism;
+use uuid::Uuid;
 
 use crate::{
-    args::OpRbuilderArgs,
-    primitives::bundle::MAX_BLOCK_RANGE_BLOCKS,
+    args::{BundleArgs, OpRbuilderArgs},
+    primitives::bundle::{Bundle, BundleResult, DEFAULT_MAX_BLOCK_RANGE_BLOCKS},
     tests::{
         BlockTransactionsExt, BundleOpts, ChainDriver, ChainDriverExt, LocalInstance, ONE_ETH,
         OpRbuilderArgsTestExt, TransactionBuilderExt,
     },
+    tx_signer::Signer,
 };
 
 /// This test ensures that the transactions that get reverted and not included in the block,
@@ -239,6 +242,75 @@ async fn bundle_min_timestamp(rbuilder: LocalInstance) -> eyre::Result<()> {
     Ok(())
 }
 
+/// Flashblock time conditions are checked against the block timestamp by the standard builder
+/// and against the time each flashblock is published by the flashblocks builder, which is
+/// earlier than the block timestamp.
+#[rb_test(args = OpRbuilderArgs {
+    enable_revert_protection: true,
+    ..Default::default()
+})]
+async fn bundle_flashblock_time(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let driver = rbuilder.driver().await?;
+    let initial_timestamp = driver.latest().await?.header.timestamp;
+
+    // The window excludes the timestamps of both the next block and the one after it, but
+    // includes the publication time of the first flashblocks of the second block
+    let bundle = driver
+        .create_transaction()
+        .with_bundle(
+            BundleOpts::default()
+                .with_min_flashblock_time((initial_timestamp + 1) * 1000 + 1)
+                .with_max_flashblock_time((initial_timestamp + 2) * 1000 - 1),
+        )
+        .send()
+        .await?;
+
+    let block = driver.build_new_block().await?; // Block 1, initial_timestamp + 1
+    assert!(!block.includes(bundle.tx_hash()));
+
+    let block = driver.build_new_block().await?; // Block 2, initial_timestamp + 2
+
+    if_standard! {
+        assert!(!block.includes(bundle.tx_hash()));
+    }
+
+    if_flashblocks! {
+        assert!(block.includes(bundle.tx_hash()));
+    }
+
+    Ok(())
+}
+
+/// A bundle past its max flashblock time is dropped from the pool.
+#[rb_test(args = OpRbuilderArgs {
+    enable_revert_protection: true,
+    ..Default::default()
+})]
+async fn bundle_flashblock_time_expiry(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let driver = rbuilder.driver().await?;
+    let initial_timestamp = driver.latest().await?.header.timestamp;
+
+    // the bundle reverts, so it stays in the pool until it expires after the second block
+    let bundle = driver
+        .create_transaction()
+        .random_reverting_transaction()
+        .with_bundle(
+            BundleOpts::default().with_max_flashblock_time((initial_timestamp + 1) * 1000 + 500),
+        )
+        .send()
+        .await?;
+
+    let block = driver.build_new_block().await?; // Block 1, initial_timestamp + 1
+    assert!(!block.includes(bundle.tx_hash()));
+    assert!(rbuilder.pool().is_pending(*bundle.tx_hash()));
+
+    driver.build_new_block().await?; // Block 2, initial_timestamp + 2
+    driver.build_new_block().await?; // Block 3, initial_timestamp + 3
+    assert!(rbuilder.pool().is_dropped(*bundle.tx_hash()));
+
+    Ok(())
+}
+
 /// Test the range limits for the revert protection bundle.
 #[rb_test(args = OpRbuilderArgs {
     enable_revert_protection: true,
@@ -267,7 +339,7 @@ async fn bundle_range_limits(rbuilder: LocalInstance) -> eyre::Result<()> {
     let current_block = 2;
     let next_valid_block = current_block + 1;
 
-    for i in next_valid_block..next_valid_block + MAX_BLOCK_RANGE_BLOCKS {
+    for i in next_valid_block..next_valid_block + DEFAULT_MAX_BLOCK_RANGE_BLOCKS {
         assert!(
             send_bundle(&driver, BundleOpts::default().with_block_number_max(i))
                 .await
@@ -280,7 +352,7 @@ async fn bundle_range_limits(rbuilder: LocalInstance) -> eyre::Result<()> {
         send_bundle(
             &driver,
             BundleOpts::default()
-                .with_block_number_max(next_valid_block + MAX_BLOCK_RANGE_BLOCKS + 1)
+                .with_block_number_max(next_valid_block + DEFAULT_MAX_BLOCK_RANGE_BLOCKS + 1)
         )
         .await
         .is_err()
@@ -332,7 +404,7 @@ async fn bundle_range_limits(rbuilder: LocalInstance) -> eyre::Result<()> {
 
     // Test min-only cases (no max specified)
     // A bundle with only min block that's within the default range is valid
-    let default_max = current_block + MAX_BLOCK_RANGE_BLOCKS;
+    let default_max = current_block + DEFAULT_MAX_BLOCK_RANGE_BLOCKS;
     assert!(
         send_bundle(
             &driver,
@@ -371,6 +443,195 @@ async fn bundle_range_limits(rbuilder: LocalInstance) -> eyre::Result<()> {
     Ok(())
 }
 
+/// The block range limits can be configured, in blocks or in seconds converted with the chain
+/// block time, and are reported when a bundle exceeds them.
+#[rb_test(args = OpRbuilderArgs {
+    enable_revert_protection: true,
+    chain_block_time: 1000,
+    bundle: BundleArgs {
+        max_range_secs: Some(20),
+        default_block_range: Some(3),
+        ..Default::default()
+    },
+    ..Default::default()
+})]
+async fn bundle_configured_range_limits(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let driver = rbuilder.driver().await?;
+    let current_block = driver.latest().await?.header.number;
+    let max_range = 20;
+    let default_range = 3;
+
+    async fn send_bundle(
+        driver: &ChainDriver,
+        bundle: BundleOpts,
+    ) -> eyre::Result<PendingTransactionBuilder<Optimism>> {
+        driver.create_transaction().with_bundle(bundle).send().await
+    }
+
+    // The max block can go up to the configured max range, past the default one
+    assert!(
+        send_bundle(
+            &driver,
+            BundleOpts::default().with_block_number_max(current_block + max_range)
+        )
+        .await
+        .is_ok()
+    );
+
+    let err = send_bundle(
+        &driver,
+        BundleOpts::default().with_block_number_max(current_block + max_range + 1),
+    )
+    .await
+    .expect_err("bundle beyond the max range should be rejected");
+    assert!(
+        err.to_string()
+            .contains(&format!("max range: {max_range} blocks")),
+        "unexpected error: {err}"
+    );
+
+    // Without a max block, the configured default range applies
+    assert!(
+        send_bundle(
+            &driver,
+            BundleOpts::default().with_block_number_min(current_block + default_range)
+        )
+        .await
+        .is_ok()
+    );
+
+    let err = send_bundle(
+        &driver,
+        BundleOpts::default().with_block_number_min(current_block + default_range + 1),
+    )
+    .await
+    .expect_err("bundle beyond the default range should be rejected");
+    assert!(
+        err.to_string()
+            .contains(&format!("default range: {default_range} blocks")),
+        "unexpected error: {err}"
+    );
+
+    Ok(())
+}
+
+/// A bundle sent with the replacement uuid of a pending bundle of the same signer replaces it, and
+/// a bundle without transactions signed by the same signer cancels it. Another signer can't reuse
+/// the replacement uuid.
+#[rb_test(args = OpRbuilderArgs {
+    enable_revert_protection: true,
+    ..Default::default()
+})]
+async fn bundle_replacement_uuid(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let driver = rbuilder.driver().await?;
+    let accounts = driver.fund_accounts(3, ONE_ETH).await?;
+    let current_block = driver.latest().await?.header.number;
+
+    let replacement_uuid = Uuid::new_v4();
+    let replaced = driver
+        .create_transaction()
+        .with_signer(accounts[0])
+        .with_nonce(0)
+        .with_bundle(
+            BundleOpts::default()
+                .with_block_number_min(current_block + 2)
+                .with_replacement_uuid(replacement_uuid),
+        )
+        .send()
+        .await?;
+    let hijack = driver
+        .create_transaction()
+        .with_signer(accounts[1])
+        .with_bundle(
+            BundleOpts::default()
+                .with_block_number_min(current_block + 2)
+                .with_replacement_uuid(replacement_uuid),
+        )
+        .send()
+        .await;
+    assert!(
+        hijack.is_err(),
+        "another signer should not be able to replace the bundle"
+    );
+    let replacement = driver
+        .create_transaction()
+        .with_signer(accounts[0])
+        .with_nonce(0)
+        .with_max_priority_fee_per_gas(10)
+        .with_bundle(
+            BundleOpts::default()
+                .with_block_number_min(current_block + 2)
+                .with_replacement_uuid(replacement_uuid),
+        )
+        .send()
+        .await?;
+
+    let cancellation_uuid = Uuid::new_v4();
+    let cancelled = driver
+        .create_transaction()
+        .with_signer(accounts[2])
+        .with_bundle(
+            BundleOpts::default()
+                .with_block_number_min(current_block + 2)
+                .with_replacement_uuid(cancellation_uuid),
+        )
+        .send()
+        .await?;
+
+    let cancellation = |replacement_uuid: Uuid, signer: &Signer| -> eyre::Result<Bundle> {
+        let signature = signer.sign_message(Bundle::cancellation_hash(replacement_uuid))?;
+        Ok(Bundle {
+            replacement_uuid: Some(replacement_uuid),
+            cancellation_signature: Some(Bytes::copy_from_slice(&signature.as_bytes())),
+            ..Default::default()
+        })
+    };
+    let unsigned = Bundle {
+        replacement_uuid: Some(cancellation_uuid),
+        ..Default::default()
+    };
+    for bundle in [unsigned, cancellation(cancellation_uuid, &accounts[1])?] {
+        let result: Result<BundleResult, _> = driver
+            .provider()
+            .client()
+            .request("eth_sendBundle", (bundle,))
+            .await;
+        assert!(
+            result.is_err(),
+            "only the signer of the bundle should be able to cancel it"
+        );
+    }
+
+    let result: BundleResult = driver
+        .provider()
+        .client()
+        .request(
+            "eth_sendBundle",
+            (cancellation(cancellation_uuid, &accounts[2])?,),
+        )
+        .await?;
+    assert_eq!(result.bundle_hash, *cancelled.tx_hash());
+
+    // cancelling an unknown bundle is not an error
+    let result: BundleResult = driver
+        .provider()
+        .client()
+        .request(
+            "eth_sendBundle",
+            (cancellation(Uuid::new_v4(), &accounts[2])?,),
+        )
+        .await?;
+    assert_eq!(result.bundle_hash, B256::ZERO);
+
+    driver.build_new_block().await?;
+    let block = driver.build_new_block().await?;
+    assert!(block.includes(replacement.tx_hash()));
+    assert!(!block.includes(replaced.tx_hash()));
+    assert!(!block.includes(cancelled.tx_hash()));
+
+    Ok(())
+}
+
 /// If a transaction reverts and was sent as a normal transaction through the eth_sendRawTransaction
 /// bundle, the transaction should be included in the block.
 /// This behaviour is the same as the 'disabled' test.
@@ -0,0 +1,83 @@
+use crate::{
+    runtime_config::{BuilderAdminApiClient, RuntimeSettingsUpdate},
+    tests::{BlockTransactionsExt, LocalInstance, TransactionBuilderExt},
+};
+use core::time::Duration;
+use macros::rb_test;
+use reth_ipc::client::IpcClientBuilder;
+
+/// This test ensures that the max gas per transaction can be updated at runtime through
+/// the admin RPC served on the authenticated endpoint.
+#[rb_test]
+async fn max_gas_per_txn_runtime_update(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let driver = rbuilder.driver().await?;
+    let admin = IpcClientBuilder::default()
+        .build(rbuilder.auth_ipc())
+        .await?;
+
+    let big_tx = driver
+        .create_transaction()
+        .random_big_transaction()
+        .send()
+        .await?;
+    let block = driver.build_new_block_with_current_timestamp(None).await?;
+    assert!(
+        block.includes(big_tx.tx_hash()),
+        "big transaction should be included without a limit"
+    );
+
+    let config = admin
+        .update_config(RuntimeSettingsUpdate {
+            max_gas_per_txn: Some(25_000),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(config.settings.max_gas_per_txn, Some(25_000));
+    assert_eq!(admin.get_config().await?, config);
+
+    let tx = driver
+        .create_transaction()
+        .random_valid_transfer()
+        .send()
+        .await?;
+    let big_tx = driver
+        .create_transaction()
+        .random_big_transaction()
+        .send()
+        .await?;
+    let block = driver.build_new_block_with_current_timestamp(None).await?;
+    assert!(
+        block.includes(tx.tx_hash()),
+        "transaction under the limit should be included"
+    );
+    assert!(
+        !block.includes(big_tx.tx_hash()),
+        "transaction above the updated limit should not be included"
+    );
+
+    Ok(())
+}
+
+/// This test ensures that invalid updates are rejected and leave the settings unchanged.
+#[rb_test]
+async fn invalid_runtime_update_is_rejected(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let admin = IpcClientBuilder::default()
+        .build(rbuilder.auth_ipc())
+        .await?;
+    let config = admin.get_config().await?;
+
+    let result = admin
+        .update_config(RuntimeSettingsUpdate {
+            max_gas_per_txn: Some(25_000),
+            flashblocks_leeway_time: Some(Duration::from_secs(10)),
+            ..Default::default()
+        })
+        .await;
+    assert!(
+        result.is_err(),
+        "leeway time above the interval should be rejected"
+    );
+    assert_eq!(admin.get_config().await?, config);
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use alloy_eips::{BlockNumberOrTag, Encodable2718};
+use alloy_primitives::U256;
+use alloy_provider::Provider;
+use macros::rb_test;
+use reth_ipc::client::IpcClientBuilder;
+
+use crate::{
+    simulation::{BundleSimulationApiClient, SimulateBundleRequest},
+    tests::{ChainDriverExt, LocalInstance, ONE_ETH},
+};
+
+/// This test ensures that bundles are simulated against the latest state with the outcome
+/// of every transaction, without being sent to the pool.
+#[rb_test]
+async fn simulate_bundle_latest_state(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let driver = rbuilder.driver().await?;
+    let client = IpcClientBuilder::default()
+        .build(rbuilder.rpc_ipc())
+        .await?;
+    let accounts = driver.fund_accounts(1, ONE_ETH).await?;
+    let signer = accounts[0];
+
+    let transfer = driver
+        .create_transaction()
+        .with_signer(signer)
+        .with_nonce(0)
+        .with_value(1000)
+        .build()
+        .await;
+    let reverting = driver
+        .create_transaction()
+        .with_signer(signer)
+        .with_nonce(1)
+        .with_revert()
+        .build()
+        .await;
+    let request = SimulateBundleRequest {
+        transactions: vec![
+            transfer.encoded_2718().into(),
+            reverting.encoded_2718().into(),
+        ],
+        reverting_hashes: None,
+        state_block: Some(BlockNumberOrTag::Latest),
+        timestamp: None,
+    };
+
+    let response = client.call_bundle(request.clone()).await?;
+    let latest = driver.latest().await?;
+    assert_eq!(response.block_number, latest.header.number + 1);
+    assert!(!response.pending);
+    assert_eq!(response.results.len(), 2);
+
+    let transfer_result = &response.results[0];
+    assert_eq!(transfer_result.tx_hash, *transfer.tx_hash());
+    assert!(transfer_result.success);
+    assert!(transfer_result.error.is_none());
+    assert_eq!(transfer_result.gas_used, 21_000);
+    assert!(transfer_result.da_size > 0);
+    let sender_diff = &transfer_result.state_diff[&signer.address];
+    assert_eq!(sender_diff.nonce.as_ref().map(|nonce| nonce.to), Some(1));
+    assert!(
+        sender_diff
+            .balance
+            .as_ref()
+            .is_some_and(|balance| balance.to < balance.from)
+    );
+
+    let reverting_result = &response.results[1];
+    assert!(!reverting_result.success);
+    assert!(
+        reverting_result.error.is_some(),
+        "a reverting transaction outside of revertingTxHashes invalidates the bundle"
+    );
+    assert!(!response.success);
+    // the excluded transaction doesn't count towards the totals
+    assert_eq!(response.gas_used, transfer_result.gas_used);
+    assert_eq!(response.total_tip, transfer_result.tip);
+
+    // allowing the transaction to revert makes the bundle valid
+    let response = client
+        .simulate_bundle(SimulateBundleRequest {
+            reverting_hashes: Some(vec![*reverting.tx_hash()]),
+            ..request
+        })
+        .await?;
+    assert!(response.success);
+    assert_eq!(
+        response.gas_used,
+        response.results.iter().map(|result| result.gas_used).sum()
+    );
+    assert_eq!(
+        response.total_tip,
+        response
+            .results
+            .iter()
+            .map(|result| result.tip)
+            .sum::<U256>()
+    );
+
+    Ok(())
+}
+
+/// This test ensures that the simulation applies the DA limits of the builder and rejects
+/// bundles with too many transactions.
+#[rb_test]
+async fn simulate_bundle_limits(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let driver = rbuilder.driver().await?;
+    let client = IpcClientBuilder::default()
+        .build(rbuilder.rpc_ipc())
+        .await?;
+    let accounts = driver.fund_accounts(1, ONE_ETH).await?;
+    let signer = accounts[0];
+
+    let transfer = driver
+        .create_transaction()
+        .with_signer(signer)
+        .with_nonce(0)
+        .build()
+        .await;
+    let request = SimulateBundleRequest {
+        transactions: vec![transfer.encoded_2718().into()],
+        reverting_hashes: None,
+        state_block: Some(BlockNumberOrTag::Latest),
+        timestamp: None,
+    };
+    assert!(client.call_bundle(request.clone()).await?.success);
+
+    // Set (max_tx_da_size, max_block_da_size) so that no transaction fits
+    let call = driver
+        .provider()
+        .raw_request::<(i32, i32), bool>("miner_setMaxDASize".into(), (1, 0))
+        .await?;
+    assert!(call, "miner_setMaxDASize should be executed successfully");
+
+    let response = client.call_bundle(request.clone()).await?;
+    assert!(!response.success);
+    assert!(response.results[0].error.is_some());
+    assert_eq!(response.results[0].gas_used, 0, "tx should not be executed");
+
+    let too_many = SimulateBundleRequest {
+        transactions: vec![transfer.encoded_2718().into(); 101],
+        ..request
+    };
+    assert!(client.call_bundle(too_many).await.is_err());
+
+    Ok(())
+}
@@ -1,5 +1,10 @@
-use crate::tests::{
-    BlockTransactionsExt, ChainDriverExt, LocalInstance, ONE_ETH, default_node_config,
+use crate::{
+    admission::args::AdmissionArgs,
+    args::OpRbuilderArgs,
+    tests::{
+        BlockTransactionsExt, BundleOpts, ChainDriverExt, LocalInstance, ONE_ETH,
+        TransactionBuilderExt, default_node_config,
+    },
 };
 use macros::rb_test;
 use reth::args::TxPoolArgs;
@@ -67,3 +72,90 @@ async fn pending_pool_limit(rbuilder: LocalInstance) -> eyre::Result<()> {
 
     Ok(())
 }
+
+/// This test ensures that the pool built by `FBPoolBuilder` runs the conditional maintenance task
+/// of the upstream pool builder, which drops the transactions past their block range.
+/// Revert protection relies on it to drop the reverting bundles that are never included.
+#[rb_test(args = OpRbuilderArgs {
+    enable_revert_protection: true,
+    ..Default::default()
+})]
+async fn conditional_maintenance_task(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let driver = rbuilder.driver().await?;
+    let latest_block_number = driver.latest().await?.header.number;
+
+    let tx = driver
+        .create_transaction()
+        .random_reverting_transaction()
+        .with_bundle(BundleOpts::default().with_block_number_max(latest_block_number + 1))
+        .send()
+        .await?;
+    assert!(rbuilder.pool().is_pending(*tx.tx_hash()));
+
+    let block = driver.build_new_block().await?;
+    assert!(!block.includes(tx.tx_hash()));
+    assert!(
+        rbuilder.pool().is_dropped(*tx.tx_hash()),
+        "tx past its block range should be dropped by the conditional maintenance task"
+    );
+
+    Ok(())
+}
+
+/// This test ensures that once most of the recent transactions of a sender reverted, new
+/// transactions of that sender are rejected by the pool admission control.
+#[rb_test(args = OpRbuilderArgs {
+    admission: AdmissionArgs {
+        admission_enabled: true,
+        admission_deprioritize_revert_percent: 25,
+        admission_max_revert_percent: 50,
+        admission_min_simulated_txs: 2,
+    },
+    ..Default::default()
+})]
+async fn admission_rejects_reverting_senders(rbuilder: LocalInstance) -> eyre::Result<()> {
+    let driver = rbuilder.driver().await?;
+    let accounts = driver.fund_accounts(2, ONE_ETH).await?;
+    let reverting = accounts[0];
+    let honest = accounts[1];
+
+    let mut reverted_txs = Vec::new();
+    for _ in 0..2 {
+        let tx = driver
+            .create_transaction()
+            .with_signer(reverting)
+            .with_revert()
+            .send()
+            .await?;
+        reverted_txs.push(*tx.tx_hash());
+    }
+
+    let block = driver.build_new_block().await?;
+    assert!(
+        block.includes(&reverted_txs),
+        "reverting txs should be included before the sender has a reputation"
+    );
+
+    let rejected = driver
+        .create_transaction()
+        .with_signer(reverting)
+        .send()
+        .await;
+    assert!(
+        rejected.is_err(),
+        "tx from a sender whose txs mostly reverted should be rejected"
+    );
+
+    let tx = driver
+        .create_transaction()
+        .with_signer(honest)
+        .send()
+        .await?;
+    let block = driver.build_new_block().await?;
+    assert!(
+        block.includes(tx.tx_hash()),
+        "tx from another sender should be included"
+    );
+
+    Ok(())
+}
@@ -29,6 +29,10 @@ pub struct FBPooledTransaction {
 
     pub flashblock_number_min: Option<u64>,
     pub flashblock_number_max: Option<u64>,
+
+    /// Inclusion time window of the transaction, in Unix epoch milliseconds.
+    pub flashblock_time_min: Option<u64>,
+    pub flashblock_time_max: Option<u64>,
 }
 
 impl FBPoolTransaction for FBPooledTransaction {}
@@ -60,6 +64,10 @@ pub trait MaybeFlashblockFilter {
     fn with_flashblock_number_max(self, flashblock_number_max: Option<u64>) -> Self;
     fn flashblock_number_min(&self) -> Option<u64>;
     fn flashblock_number_max(&self) -> Option<u64>;
+    fn with_flashblock_time_min(self, flashblock_time_min: Option<u64>) -> Self;
+    fn with_flashblock_time_max(self, flashblock_time_max: Option<u64>) -> Self;
+    fn flashblock_time_min(&self) -> Option<u64>;
+    fn flashblock_time_max(&self) -> Option<u64>;
 }
 
 impl MaybeFlashblockFilter for FBPooledTransaction {
@@ -80,6 +88,24 @@ impl MaybeFlashblockFilter for FBPooledTransaction {
     fn flashblock_number_max(&self) -> Option<u64> {
         self.flashblock_number_max
     }
+
+    fn with_flashblock_time_min(mut self, flashblock_time_min: Option<u64>) -> Self {
+        self.flashblock_time_min = flashblock_time_min;
+        self
+    }
+
+    fn with_flashblock_time_max(mut self, flashblock_time_max: Option<u64>) -> Self {
+        self.flashblock_time_max = flashblock_time_max;
+        self
+    }
+
+    fn flashblock_time_min(&self) -> Option<u64> {
+        self.flashblock_time_min
+    }
+
+    fn flashblock_time_max(&self) -> Option<u64> {
+        self.flashblock_time_max
+    }
 }
 
 impl InMemorySize for FBPooledTransaction {
@@ -109,6 +135,8 @@ impl PoolTransaction for FBPooledTransaction {
             reverted_hashes: None,
             flashblock_number_min: None,
             flashblock_number_max: None,
+            flashblock_time_min: None,
+            flashblock_time_max: None,
         }
     }
 
@@ -269,6 +297,8 @@ impl From<OpPooledTransaction> for FBPooledTransaction {
             reverted_hashes: None,
             flashblock_number_min: None,
             flashblock_number_max: None,
+            flashblock_time_min: None,
+            flashblock_time_max: None,
         }
     }
 }
@@ -295,6 +325,8 @@ impl MaybeConditionalTransaction for FBPooledTransaction {
             reverted_hashes: self.reverted_hashes,
             flashblock_number_min: self.flashblock_number_min,
             flashblock_number_max: self.flashblock_number_max,
+            flashblock_time_min: self.flashblock_time_min,
+            flashblock_time_max: self.flashblock_time_max,
         }
     }
 }
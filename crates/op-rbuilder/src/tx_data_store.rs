@@ -1,22 +1,25 @@
-use crate::{metrics::OpRBuilderMetrics, tx::FBPooledTransaction};
+use crate::{metrics::OpRBuilderMetrics, primitives::bundle::Bundle, tx::FBPooledTransaction};
 use alloy_consensus::Transaction;
 use alloy_primitives::{Address, TxHash};
 use concurrent_queue::ConcurrentQueue;
+use dashmap::mapref::entry::Entry;
 use jsonrpsee::{
     core::{RpcResult, async_trait},
     proc_macros::rpc,
 };
 use reth_optimism_txpool::OpPooledTransaction;
 use reth_transaction_pool::PoolTransaction;
+use serde::{Deserialize, Serialize};
 use std::{
     fmt::Debug,
     sync::{
         Arc,
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
     },
     time::Instant,
 };
 use tips_core::{AcceptedBundle, MeterBundleResponse};
+use tokio::sync::{Mutex, MutexGuard, broadcast};
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
@@ -28,6 +31,78 @@ pub struct StoredBackrunBundle {
     pub total_priority_fee: u128,
 }
 
+/// Outcome of a backrun bundle, as reported by `base_getBackrunBundleStatus`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BackrunBundleStatus {
+    /// Waiting for its target transaction to be included.
+    Pending,
+    /// Included right after its target transaction.
+    Landed,
+    /// A transaction of the bundle reverted, so none of them were included.
+    Reverted,
+    /// The total tip of the bundle was below the tip of the target transaction.
+    RejectedLowFee,
+    /// The bundle did not fit in the remaining block capacity.
+    RejectedOverLimits,
+    /// The sender, or a called contract, exceeded its gas limiter budget during simulation.
+    RejectedGasLimit,
+    /// Replaced by a newer bundle of the same sender for the same target, or by a bundle with
+    /// the same replacement uuid.
+    Replaced,
+    /// Cancelled before its target transaction was included.
+    Cancelled,
+}
+
+/// Bundle currently identified by a replacement uuid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplaceableBundle {
+    /// A bundle received through `eth_sendBundle`, by the hash of its transaction in the pool.
+    Pool(TxHash),
+    /// A backrun bundle, by the hash of its target transaction and its id.
+    Backrun {
+        target_tx_hash: TxHash,
+        bundle_id: Uuid,
+    },
+}
+
+/// Bundle identified by a replacement uuid, along with the signer of the uuid.
+struct Replacement {
+    signer: Address,
+    bundle: ReplaceableBundle,
+    /// Sequence number of the uuid in the LRU, so that a uuid cancelled and used again is not
+    /// evicted through its previous position.
+    sequence: u64,
+}
+
+/// A replacement uuid is already used by another signer, which only can replace or cancel
+/// the bundle it identifies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("replacement uuid {0} is used by another signer")]
+pub struct ReplacementSignerMismatch(pub Uuid);
+
+/// Capacity of the channel publishing local updates, slow subscribers miss the oldest updates.
+const UPDATES_CHANNEL_SIZE: usize = 1024;
+
+/// Update received through the local RPC, shared with the other builders of the set over p2p
+/// so that they all have the same view of bundles and metering information.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TxDataUpdate {
+    /// A bundle received through `eth_sendBundle`, to be added to the pool of peers.
+    ForwardedBundle(Bundle),
+    /// A backrun bundle received through `base_sendBackrunBundle`.
+    BackrunBundle(AcceptedBundle),
+    /// Metering information of a transaction.
+    Metering {
+        tx_hash: TxHash,
+        metering: MeterBundleResponse,
+    },
+    /// All metering information was cleared.
+    ClearMetering,
+    /// The backrun bundles targeting a transaction were cancelled.
+    CancelBackrunBundles { target_tx_hash: TxHash },
+}
+
 #[derive(Clone, Default)]
 pub struct TxData {
     pub metering: Option<MeterBundleResponse>,
@@ -38,6 +113,15 @@ struct StoreData {
     by_tx_hash: dashmap::DashMap<TxHash, TxData>,
     lru: ConcurrentQueue<TxHash>,
     metering_enabled: AtomicBool,
+    updates: broadcast::Sender<TxDataUpdate>,
+    bundle_statuses: dashmap::DashMap<Uuid, BackrunBundleStatus>,
+    bundle_statuses_lru: ConcurrentQueue<Uuid>,
+    replacements: dashmap::DashMap<Uuid, Replacement>,
+    replacements_lru: ConcurrentQueue<(Uuid, u64)>,
+    replacements_sequence: AtomicU64,
+    /// Held while a bundle of the pool is replaced or cancelled, which spans the async
+    /// insertion of its transaction in the pool.
+    pool_replacements: Mutex<()>,
 }
 
 #[derive(Clone)]
@@ -65,6 +149,13 @@ impl TxDataStore {
                 by_tx_hash: dashmap::DashMap::new(),
                 lru: ConcurrentQueue::bounded(buffer_size),
                 metering_enabled: AtomicBool::new(enable_resource_metering),
+                updates: broadcast::channel(UPDATES_CHANNEL_SIZE).0,
+                bundle_statuses: dashmap::DashMap::new(),
+                bundle_statuses_lru: ConcurrentQueue::bounded(buffer_size),
+                replacements: dashmap::DashMap::new(),
+                replacements_lru: ConcurrentQueue::bounded(buffer_size),
+                replacements_sequence: AtomicU64::new(0),
+                pool_replacements: Mutex::new(()),
             }),
             metrics: OpRBuilderMetrics::default(),
         }
@@ -109,7 +200,33 @@ impl TxDataStore {
         data
     }
 
+    /// Stores a backrun bundle.
+    ///
+    /// A bundle with a replacement uuid replaces the previous bundle of the same signer with the
+    /// same uuid, and a bundle without transactions cancels it.
     pub fn insert_backrun_bundle(&self, bundle: AcceptedBundle) -> Result<(), String> {
+        if let Some(replacement_uuid) = bundle.replacement_uuid
+            && bundle.txs.is_empty()
+        {
+            if let Some(ReplaceableBundle::Backrun {
+                target_tx_hash,
+                bundle_id,
+            }) = self.take_replacement(&replacement_uuid, |bundle| {
+                matches!(bundle, ReplaceableBundle::Backrun { .. })
+            }) && self.remove_backrun_bundle(&target_tx_hash, bundle_id)
+            {
+                self.set_bundle_status(bundle_id, BackrunBundleStatus::Cancelled);
+                info!(
+                    target: "tx_data_store",
+                    target_tx = ?target_tx_hash,
+                    ?bundle_id,
+                    ?replacement_uuid,
+                    "Cancelled backrun bundle"
+                );
+            }
+            return Ok(());
+        }
+
         if bundle.txs.len() < 2 {
             return Err("Bundle must have at least 2 transactions (target + backrun)".to_string());
         }
@@ -136,6 +253,21 @@ impl TxDataStore {
 
         let backrun_sender = backrun_txs[0].sender();
 
+        // the replacement uuid stays locked until the previous bundle is replaced, so that
+        // concurrent replacements are applied one after the other
+        let replacement = match bundle.replacement_uuid {
+            Some(replacement_uuid) => {
+                let entry = self.data.replacements.entry(replacement_uuid);
+                if let Entry::Occupied(entry) = &entry
+                    && entry.get().signer != backrun_sender
+                {
+                    return Err(ReplacementSignerMismatch(replacement_uuid).to_string());
+                }
+                Some((replacement_uuid, entry))
+            }
+            None => None,
+        };
+
         self.evict_if_needed();
         let _ = self.data.lru.push(target_tx_hash);
 
@@ -158,11 +290,10 @@ impl TxDataStore {
                 .iter()
                 .position(|b| b.sender == backrun_sender)
             {
-                entry.backrun_bundles[pos] = stored_bundle;
-                true
+                Some(std::mem::replace(&mut entry.backrun_bundles[pos], stored_bundle).bundle_id)
             } else {
                 entry.backrun_bundles.push(stored_bundle);
-                false
+                None
             };
             entry
                 .backrun_bundles
@@ -170,7 +301,51 @@ impl TxDataStore {
             replaced
         };
 
-        if replaced {
+        self.set_bundle_status(*bundle.uuid(), BackrunBundleStatus::Pending);
+        if let Some((replacement_uuid, entry)) = replacement {
+            let current = ReplaceableBundle::Backrun {
+                target_tx_hash,
+                bundle_id: *bundle.uuid(),
+            };
+            let sequence = self
+                .data
+                .replacements_sequence
+                .fetch_add(1, Ordering::Relaxed);
+            let previous = match entry {
+                Entry::Occupied(mut entry) => {
+                    Some(std::mem::replace(&mut entry.get_mut().bundle, current))
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(Replacement {
+                        signer: backrun_sender,
+                        bundle: current,
+                        sequence,
+                    });
+                    None
+                }
+            };
+            match previous {
+                Some(ReplaceableBundle::Backrun {
+                    target_tx_hash: previous_target,
+                    bundle_id: previous_id,
+                }) if previous_id != *bundle.uuid()
+                    && self.remove_backrun_bundle(&previous_target, previous_id) =>
+                {
+                    self.set_bundle_status(previous_id, BackrunBundleStatus::Replaced);
+                    info!(
+                        target: "tx_data_store",
+                        target_tx = ?target_tx_hash,
+                        bundle_id = ?bundle.uuid(),
+                        ?replacement_uuid,
+                        "Replaced backrun bundle with the same replacement uuid"
+                    );
+                }
+                Some(_) => {}
+                None => self.track_replacement(replacement_uuid, sequence),
+            }
+        }
+        if let Some(replaced_bundle_id) = replaced {
+            self.set_bundle_status(replaced_bundle_id, BackrunBundleStatus::Replaced);
             info!(
                 target: "tx_data_store",
                 target_tx = ?target_tx_hash,
@@ -195,10 +370,36 @@ impl TxDataStore {
         Ok(())
     }
 
+    /// Removes a single backrun bundle, returns whether it was stored.
+    fn remove_backrun_bundle(&self, target_tx_hash: &TxHash, bundle_id: Uuid) -> bool {
+        let Some(mut entry) = self.data.by_tx_hash.get_mut(target_tx_hash) else {
+            return false;
+        };
+        let count = entry.backrun_bundles.len();
+        entry.backrun_bundles.retain(|b| b.bundle_id != bundle_id);
+        let removed = entry.backrun_bundles.len() < count;
+
+        if entry.metering.is_none() && entry.backrun_bundles.is_empty() {
+            drop(entry);
+            self.data.by_tx_hash.remove(target_tx_hash);
+        }
+
+        self.metrics
+            .backrun_bundles_in_store
+            .set(self.data.by_tx_hash.len() as f64);
+
+        removed
+    }
+
     pub fn remove_backrun_bundles(&self, target_tx_hash: &TxHash) {
         if let Some(mut entry) = self.data.by_tx_hash.get_mut(target_tx_hash) {
             let bundle_count = entry.backrun_bundles.len();
-            entry.backrun_bundles.clear();
+            for bundle in entry.backrun_bundles.drain(..) {
+                // bundles that were executed already have their outcome
+                if self.bundle_status(&bundle.bundle_id) == Some(BackrunBundleStatus::Pending) {
+                    self.set_bundle_status(bundle.bundle_id, BackrunBundleStatus::Cancelled);
+                }
+            }
 
             if bundle_count > 0 {
                 info!(
@@ -220,6 +421,119 @@ impl TxDataStore {
             .set(self.data.by_tx_hash.len() as f64);
     }
 
+    /// Records the outcome of a backrun bundle. Only the statuses of the last `buffer_size`
+    /// bundles are kept.
+    pub fn set_bundle_status(&self, bundle_id: Uuid, status: BackrunBundleStatus) {
+        if self
+            .data
+            .bundle_statuses
+            .insert(bundle_id, status)
+            .is_none()
+        {
+            if self.data.bundle_statuses_lru.is_full()
+                && let Ok(evicted_id) = self.data.bundle_statuses_lru.pop()
+            {
+                self.data.bundle_statuses.remove(&evicted_id);
+            }
+            let _ = self.data.bundle_statuses_lru.push(bundle_id);
+        }
+    }
+
+    pub fn bundle_status(&self, bundle_id: &Uuid) -> Option<BackrunBundleStatus> {
+        self.data
+            .bundle_statuses
+            .get(bundle_id)
+            .map(|status| *status)
+    }
+
+    /// Associates a replacement uuid of `signer` with a bundle, returns the bundle it was
+    /// associated with. Only the last `buffer_size` replacement uuids are kept.
+    pub fn set_replacement(
+        &self,
+        signer: Address,
+        replacement_uuid: Uuid,
+        bundle: ReplaceableBundle,
+    ) -> Result<Option<ReplaceableBundle>, ReplacementSignerMismatch> {
+        let sequence = self
+            .data
+            .replacements_sequence
+            .fetch_add(1, Ordering::Relaxed);
+        let previous = match self.data.replacements.entry(replacement_uuid) {
+            Entry::Occupied(entry) if entry.get().signer != signer => {
+                return Err(ReplacementSignerMismatch(replacement_uuid));
+            }
+            Entry::Occupied(mut entry) => {
+                Some(std::mem::replace(&mut entry.get_mut().bundle, bundle))
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(Replacement {
+                    signer,
+                    bundle,
+                    sequence,
+                });
+                None
+            }
+        };
+        if previous.is_none() {
+            self.track_replacement(replacement_uuid, sequence);
+        }
+        Ok(previous)
+    }
+
+    /// Keeps track of a new replacement uuid, evicting the oldest one if needed. A uuid that was
+    /// cancelled since it was tracked, and possibly used again, is not evicted.
+    fn track_replacement(&self, replacement_uuid: Uuid, sequence: u64) {
+        if self.data.replacements_lru.is_full()
+            && let Ok((evicted_uuid, evicted_sequence)) = self.data.replacements_lru.pop()
+        {
+            self.data
+                .replacements
+                .remove_if(&evicted_uuid, |_, replacement| {
+                    replacement.sequence == evicted_sequence
+                });
+        }
+        let _ = self
+            .data
+            .replacements_lru
+            .push((replacement_uuid, sequence));
+    }
+
+    /// Returns the bundle currently associated with a replacement uuid of `signer`.
+    pub fn replacement(
+        &self,
+        signer: Address,
+        replacement_uuid: &Uuid,
+    ) -> Result<Option<ReplaceableBundle>, ReplacementSignerMismatch> {
+        match self.data.replacements.get(replacement_uuid).as_deref() {
+            Some(replacement) if replacement.signer != signer => {
+                Err(ReplacementSignerMismatch(*replacement_uuid))
+            }
+            Some(replacement) => Ok(Some(replacement.bundle)),
+            None => Ok(None),
+        }
+    }
+
+    /// Removes a replacement uuid if `is_cancelled` returns true for its bundle, returns the
+    /// bundle it was associated with.
+    pub fn take_replacement(
+        &self,
+        replacement_uuid: &Uuid,
+        is_cancelled: impl FnOnce(&ReplaceableBundle) -> bool,
+    ) -> Option<ReplaceableBundle> {
+        self.data
+            .replacements
+            .remove_if(replacement_uuid, |_, replacement| {
+                is_cancelled(&replacement.bundle)
+            })
+            .map(|(_, replacement)| replacement.bundle)
+    }
+
+    /// Locks the replacements of the bundles in the pool, so that concurrent replacements and
+    /// cancellations are applied one after the other.
+    pub async fn lock_pool_replacements(&self) -> MutexGuard<'_, ()> {
+        self.data.pool_replacements.lock().await
+    }
+
     pub fn insert_metering(&self, tx_hash: TxHash, metering_info: MeterBundleResponse) {
         self.evict_if_needed();
         let _ = self.data.lru.push(tx_hash);
@@ -241,6 +555,35 @@ impl TxDataStore {
         self.data.metering_enabled.store(enabled, Ordering::Relaxed);
     }
 
+    /// Subscribes to the updates received through the local RPC.
+    pub fn subscribe_updates(&self) -> broadcast::Receiver<TxDataUpdate> {
+        self.data.updates.subscribe()
+    }
+
+    /// Publishes an update received through the local RPC to subscribers.
+    pub fn publish_update(&self, update: TxDataUpdate) {
+        // no subscribers when p2p is disabled
+        let _ = self.data.updates.send(update);
+    }
+
+    /// Applies an update received from a peer. The update is not published again.
+    ///
+    /// Forwarded bundles are not stored and must be added to the pool by the caller.
+    pub fn apply_update(&self, update: TxDataUpdate) -> Result<(), String> {
+        match update {
+            TxDataUpdate::ForwardedBundle(_) => {
+                return Err("Forwarded bundles are not stored".to_string());
+            }
+            TxDataUpdate::BackrunBundle(bundle) => self.insert_backrun_bundle(bundle)?,
+            TxDataUpdate::Metering { tx_hash, metering } => self.insert_metering(tx_hash, metering),
+            TxDataUpdate::ClearMetering => self.clear_metering(),
+            TxDataUpdate::CancelBackrunBundles { target_tx_hash } => {
+                self.remove_backrun_bundles(&target_tx_hash)
+            }
+        }
+        Ok(())
+    }
+
     pub fn len(&self) -> usize {
         self.data.by_tx_hash.len()
     }
@@ -274,6 +617,15 @@ pub trait BaseApiExt {
 
     #[method(name = "clearMeteringInformation")]
     async fn clear_metering_information(&self) -> RpcResult<()>;
+
+    #[method(name = "cancelBackrunBundles")]
+    async fn cancel_backrun_bundles(&self, target_tx_hash: TxHash) -> RpcResult<()>;
+
+    #[method(name = "getBackrunBundleStatus")]
+    async fn get_backrun_bundle_status(
+        &self,
+        bundle_id: Uuid,
+    ) -> RpcResult<Option<BackrunBundleStatus>>;
 }
 
 pub struct TxDataStoreExt {
@@ -296,17 +648,21 @@ impl BaseApiExtServer for TxDataStoreExt {
         self.metrics.backrun_bundles_received_total.increment(1);
 
         let start = Instant::now();
-        self.store.insert_backrun_bundle(bundle).map_err(|e| {
-            warn!(target: "tx_data_store", error = %e, "Failed to store bundle");
-            jsonrpsee::types::ErrorObject::owned(
-                jsonrpsee::types::error::INTERNAL_ERROR_CODE,
-                format!("Failed to store bundle: {e}"),
-                None::<()>,
-            )
-        })?;
+        self.store
+            .insert_backrun_bundle(bundle.clone())
+            .map_err(|e| {
+                warn!(target: "tx_data_store", error = %e, "Failed to store bundle");
+                jsonrpsee::types::ErrorObject::owned(
+                    jsonrpsee::types::error::INTERNAL_ERROR_CODE,
+                    format!("Failed to store bundle: {e}"),
+                    None::<()>,
+                )
+            })?;
         self.metrics
             .backrun_bundle_insert_duration
             .record(start.elapsed().as_secs_f64());
+        self.store
+            .publish_update(TxDataUpdate::BackrunBundle(bundle));
 
         Ok(())
     }
@@ -316,7 +672,9 @@ impl BaseApiExtServer for TxDataStoreExt {
         tx_hash: TxHash,
         metering: MeterBundleResponse,
     ) -> RpcResult<()> {
-        self.store.insert_metering(tx_hash, metering);
+        self.store.insert_metering(tx_hash, metering.clone());
+        self.store
+            .publish_update(TxDataUpdate::Metering { tx_hash, metering });
         Ok(())
     }
 
@@ -327,8 +685,23 @@ impl BaseApiExtServer for TxDataStoreExt {
 
     async fn clear_metering_information(&self) -> RpcResult<()> {
         self.store.clear_metering();
+        self.store.publish_update(TxDataUpdate::ClearMetering);
+        Ok(())
+    }
+
+    async fn cancel_backrun_bundles(&self, target_tx_hash: TxHash) -> RpcResult<()> {
+        self.store.remove_backrun_bundles(&target_tx_hash);
+        self.store
+            .publish_update(TxDataUpdate::CancelBackrunBundles { target_tx_hash });
         Ok(())
     }
+
+    async fn get_backrun_bundle_status(
+        &self,
+        bundle_id: Uuid,
+    ) -> RpcResult<Option<BackrunBundleStatus>> {
+        Ok(self.store.bundle_status(&bundle_id))
+    }
 }
 
 #[cfg(test)]
@@ -545,4 +918,269 @@ mod tests {
         assert!(store.get(&tx1).metering.is_none());
         assert!(store.get(&tx2).metering.is_none());
     }
+
+    #[test]
+    fn test_apply_update() {
+        let store = TxDataStore::new(true, 100);
+        let alice = PrivateKeySigner::random();
+        let bob = PrivateKeySigner::random();
+
+        let target_tx = create_recovered_tx(&alice, 0, bob.address());
+        let backrun_tx = create_recovered_tx(&alice, 1, bob.address());
+        let target_tx_hash = target_tx.tx_hash();
+
+        store
+            .apply_update(TxDataUpdate::BackrunBundle(create_test_accepted_bundle(
+                vec![target_tx, backrun_tx],
+            )))
+            .unwrap();
+        store
+            .apply_update(TxDataUpdate::Metering {
+                tx_hash: target_tx_hash,
+                metering: create_test_metering(21000),
+            })
+            .unwrap();
+
+        let data = store.get(&target_tx_hash);
+        assert_eq!(data.backrun_bundles.len(), 1);
+        assert_eq!(data.metering.unwrap().total_gas_used, 21000);
+
+        store
+            .apply_update(TxDataUpdate::CancelBackrunBundles { target_tx_hash })
+            .unwrap();
+        assert!(store.get(&target_tx_hash).backrun_bundles.is_empty());
+
+        store.apply_update(TxDataUpdate::ClearMetering).unwrap();
+        assert!(store.is_empty());
+
+        assert!(
+            store
+                .apply_update(TxDataUpdate::ForwardedBundle(Bundle::default()))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_applied_updates_are_not_published() {
+        let store = TxDataStore::new(true, 100);
+        let mut updates = store.subscribe_updates();
+
+        store.apply_update(TxDataUpdate::ClearMetering).unwrap();
+        assert!(updates.try_recv().is_err());
+
+        store.publish_update(TxDataUpdate::ClearMetering);
+        assert!(matches!(
+            updates.try_recv(),
+            Ok(TxDataUpdate::ClearMetering)
+        ));
+    }
+
+    #[test]
+    fn test_backrun_bundle_status() {
+        let alice = PrivateKeySigner::random();
+        let bob = PrivateKeySigner::random();
+        let store = TxDataStore::new(false, 2);
+
+        let target_tx = create_recovered_tx(&alice, 0, bob.address());
+        let target_tx_hash = target_tx.tx_hash();
+        let bundle = create_test_accepted_bundle(vec![
+            target_tx.clone(),
+            create_recovered_tx(&alice, 1, bob.address()),
+        ]);
+        let bundle_id = *bundle.uuid();
+        store.insert_backrun_bundle(bundle).unwrap();
+        assert_eq!(
+            store.bundle_status(&bundle_id),
+            Some(BackrunBundleStatus::Pending)
+        );
+
+        let replacement = create_test_accepted_bundle(vec![
+            target_tx,
+            create_recovered_tx(&alice, 2, bob.address()),
+        ]);
+        let replacement_id = *replacement.uuid();
+        store.insert_backrun_bundle(replacement).unwrap();
+        assert_eq!(
+            store.bundle_status(&bundle_id),
+            Some(BackrunBundleStatus::Replaced)
+        );
+
+        store.remove_backrun_bundles(&target_tx_hash);
+        assert_eq!(
+            store.bundle_status(&replacement_id),
+            Some(BackrunBundleStatus::Cancelled)
+        );
+
+        // final outcomes are kept when the bundles are removed
+        store.set_bundle_status(replacement_id, BackrunBundleStatus::RejectedGasLimit);
+        store.remove_backrun_bundles(&target_tx_hash);
+        assert_eq!(
+            store.bundle_status(&replacement_id),
+            Some(BackrunBundleStatus::RejectedGasLimit)
+        );
+
+        // only the last `buffer_size` statuses are kept
+        let other_id = Uuid::new_v4();
+        store.set_bundle_status(other_id, BackrunBundleStatus::Landed);
+        assert_eq!(store.bundle_status(&bundle_id), None);
+        assert_eq!(
+            store.bundle_status(&other_id),
+            Some(BackrunBundleStatus::Landed)
+        );
+    }
+
+    #[test]
+    fn test_backrun_bundle_replacement_uuid() {
+        let alice = PrivateKeySigner::random();
+        let bob = PrivateKeySigner::random();
+        let store = TxDataStore::new(false, 100);
+        let replacement_uuid = Uuid::new_v4();
+
+        let first_target = create_recovered_tx(&alice, 0, bob.address());
+        let mut bundle = create_test_accepted_bundle(vec![
+            first_target.clone(),
+            create_recovered_tx(&bob, 0, alice.address()),
+        ]);
+        bundle.replacement_uuid = Some(replacement_uuid);
+        let bundle_id = *bundle.uuid();
+        store.insert_backrun_bundle(bundle).unwrap();
+
+        // another sender can't replace the bundle
+        let charlie = PrivateKeySigner::random();
+        let second_target = create_recovered_tx(&alice, 1, bob.address());
+        let mut hijack = create_test_accepted_bundle(vec![
+            second_target.clone(),
+            create_recovered_tx(&charlie, 0, alice.address()),
+        ]);
+        hijack.replacement_uuid = Some(replacement_uuid);
+        let hijack_id = *hijack.uuid();
+        assert!(store.insert_backrun_bundle(hijack).is_err());
+        assert_eq!(store.bundle_status(&hijack_id), None);
+        assert_eq!(
+            store.bundle_status(&bundle_id),
+            Some(BackrunBundleStatus::Pending)
+        );
+        assert_eq!(
+            store.get(&first_target.tx_hash()).backrun_bundles[0].bundle_id,
+            bundle_id
+        );
+        assert!(
+            store
+                .get(&second_target.tx_hash())
+                .backrun_bundles
+                .is_empty()
+        );
+
+        // the replacement of the same sender targets another transaction
+        let mut replacement = create_test_accepted_bundle(vec![
+            second_target.clone(),
+            create_recovered_tx(&bob, 1, alice.address()),
+        ]);
+        replacement.replacement_uuid = Some(replacement_uuid);
+        let replacement_id = *replacement.uuid();
+        store.insert_backrun_bundle(replacement).unwrap();
+
+        assert_eq!(
+            store.bundle_status(&bundle_id),
+            Some(BackrunBundleStatus::Replaced)
+        );
+        assert!(
+            store
+                .get(&first_target.tx_hash())
+                .backrun_bundles
+                .is_empty()
+        );
+        assert_eq!(
+            store.get(&second_target.tx_hash()).backrun_bundles[0].bundle_id,
+            replacement_id
+        );
+
+        // a bundle without transactions cancels the bundle with the same replacement uuid
+        let mut cancellation = create_test_accepted_bundle(vec![]);
+        cancellation.replacement_uuid = Some(replacement_uuid);
+        store.insert_backrun_bundle(cancellation.clone()).unwrap();
+
+        assert_eq!(
+            store.bundle_status(&replacement_id),
+            Some(BackrunBundleStatus::Cancelled)
+        );
+        assert!(store.is_empty());
+
+        // cancelling again is a no-op, but a bundle without replacement uuid is still invalid
+        store.insert_backrun_bundle(cancellation).unwrap();
+        assert!(
+            store
+                .insert_backrun_bundle(create_test_accepted_bundle(vec![]))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_replacement_uuid_belongs_to_signer() {
+        let store = TxDataStore::new(false, 100);
+        let alice = Address::from([0x1; 20]);
+        let charlie = Address::from([0x2; 20]);
+        let replacement_uuid = Uuid::new_v4();
+        let first = ReplaceableBundle::Pool(TxHash::from([0x1; 32]));
+        let second = ReplaceableBundle::Pool(TxHash::from([0x2; 32]));
+
+        assert_eq!(
+            store.set_replacement(alice, replacement_uuid, first),
+            Ok(None)
+        );
+        assert_eq!(
+            store.set_replacement(charlie, replacement_uuid, second),
+            Err(ReplacementSignerMismatch(replacement_uuid))
+        );
+        assert_eq!(
+            store.replacement(charlie, &replacement_uuid),
+            Err(ReplacementSignerMismatch(replacement_uuid))
+        );
+        assert_eq!(store.replacement(alice, &replacement_uuid), Ok(Some(first)));
+        assert_eq!(
+            store.set_replacement(alice, replacement_uuid, second),
+            Ok(Some(first))
+        );
+
+        // only bundles of the expected kind are cancelled
+        assert_eq!(
+            store.take_replacement(&replacement_uuid, |bundle| {
+                matches!(bundle, ReplaceableBundle::Backrun { .. })
+            }),
+            None
+        );
+        assert_eq!(
+            store.take_replacement(&replacement_uuid, |_| true),
+            Some(second)
+        );
+        assert_eq!(store.replacement(charlie, &replacement_uuid), Ok(None));
+    }
+
+    #[test]
+    fn test_cancelled_replacement_uuid_is_not_evicted_early() {
+        let store = TxDataStore::new(false, 2);
+        let alice = Address::from([0x1; 20]);
+        let replacement_uuid = Uuid::new_v4();
+        let bundle = ReplaceableBundle::Pool(TxHash::from([0x1; 32]));
+
+        store
+            .set_replacement(alice, replacement_uuid, bundle)
+            .unwrap();
+        assert_eq!(
+            store.take_replacement(&replacement_uuid, |_| true),
+            Some(bundle)
+        );
+        store
+            .set_replacement(alice, replacement_uuid, bundle)
+            .unwrap();
+
+        // evicts the position of the cancelled uuid, not the uuid used again
+        store
+            .set_replacement(alice, Uuid::new_v4(), bundle)
+            .unwrap();
+        assert_eq!(
+            store.replacement(alice, &replacement_uuid),
+            Ok(Some(bundle))
+        );
+    }
 }
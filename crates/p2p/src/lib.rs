@@ -1,12 +1,14 @@
 mod behaviour;
 mod outgoing;
+mod scoring;
 
 use behaviour::Behaviour;
 use libp2p_stream::IncomingStreams;
+use scoring::PeerScores;
 
 use eyre::Context;
 use libp2p::{
-    PeerId, Swarm, Transport as _,
+    Swarm, Transport as _,
     identity::{self, ed25519},
     noise,
     swarm::SwarmEvent,
@@ -18,9 +20,14 @@ use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, warn};
 
-pub use libp2p::{Multiaddr, StreamProtocol};
+pub use libp2p::{
+    Multiaddr, PeerId, StreamProtocol,
+    identity::{Keypair, PublicKey},
+};
+pub use scoring::PeerScoreUpdate;
 
 const DEFAULT_MAX_PEER_COUNT: u32 = 50;
+const DEFAULT_BAN_THRESHOLD: i32 = -100;
 
 /// A message that can be sent between peers.
 pub trait Message:
@@ -46,7 +53,8 @@ pub trait Message:
 /// - for each supported protocol, the node will accept incoming streams from remote peers on that protocol.
 /// - when a new connection is established with a peer, the node will open outbound streams to that peer for each supported protocol.
 /// - when a new outgoing message is received on `outgoing_message_rx`, the node will broadcast that message to all connected peers that have an outbound stream open for the message's protocol.
-/// - incoming messages received on incoming streams are handled by `IncomingStreamsHandler`, which reads messages from the stream and sends them to a channel for processing by the consumer of this library.
+/// - incoming messages received on incoming streams are handled by `IncomingStreamsHandler`, which reads messages from the stream and sends them, along with the sending peer, to a channel for processing by the consumer of this library.
+/// - the consumer can report score updates for peers on `peer_score_rx`; peers whose score drops to the ban threshold are disconnected and banned.
 ///
 /// Currently, there is no gossip implemented; messages are simply broadcast to connected peers.
 pub struct Node<M> {
@@ -66,6 +74,12 @@ pub struct Node<M> {
     /// Receiver for outgoing messages to be sent to peers.
     outgoing_message_rx: mpsc::Receiver<M>,
 
+    /// Receiver for peer score updates reported by the consumer of this library.
+    peer_score_rx: mpsc::Receiver<PeerScoreUpdate>,
+
+    /// Scores of connected peers, used to ban misbehaving peers.
+    peer_scores: PeerScores,
+
     /// Handler for managing outgoing streams to peers.
     /// Used to determine what peers to broadcast to when a
     /// new outgoing message is received on `outgoing_message_rx`.
@@ -107,6 +121,8 @@ impl<M: Message + 'static> Node<M> {
             mut swarm,
             known_peers,
             mut outgoing_message_rx,
+            mut peer_score_rx,
+            mut peer_scores,
             mut outgoing_streams_handler,
             cancellation_token,
             incoming_streams_handlers,
@@ -152,6 +168,14 @@ impl<M: Message + 'static> Node<M> {
                         warn!("failed to broadcast message on protocol {protocol}: {e:?}");
                     }
                 }
+                Some(update) = peer_score_rx.recv() => {
+                    if peer_scores.apply(update) {
+                        let peer_id = update.peer_id;
+                        warn!("banning peer {peer_id} after its score dropped below the ban threshold");
+                        outgoing_streams_handler.remove_peer(&peer_id);
+                        let _ = swarm.disconnect_peer_id(peer_id);
+                    }
+                }
                 event = swarm.select_next_some() => {
                     match event {
                         SwarmEvent::NewListenAddr {
@@ -168,6 +192,12 @@ impl<M: Message + 'static> Node<M> {
                             connection_id,
                             ..
                         } => {
+                            if peer_scores.is_banned(&peer_id) {
+                                debug!("disconnecting banned peer {peer_id}");
+                                let _ = swarm.disconnect_peer_id(peer_id);
+                                continue;
+                            }
+
                             // when a new connection is established, open outbound streams for each protocol
                             // and add them to the outgoing streams handler.
                             debug!("connection established with peer {peer_id}");
@@ -209,17 +239,22 @@ impl<M: Message + 'static> Node<M> {
 pub struct NodeBuildResult<M> {
     pub node: Node<M>,
     pub outgoing_message_tx: mpsc::Sender<M>,
-    pub incoming_message_rxs: HashMap<StreamProtocol, mpsc::Receiver<M>>,
+    pub incoming_message_rxs: HashMap<StreamProtocol, mpsc::Receiver<(PeerId, M)>>,
+    pub peer_score_tx: mpsc::Sender<PeerScoreUpdate>,
+    /// The identity of the node, to sign messages on behalf of its peer ID.
+    pub keypair: Keypair,
 }
 
 pub struct NodeBuilder {
     port: Option<u16>,
     listen_addrs: Vec<libp2p::Multiaddr>,
     keypair_hex: Option<String>,
+    keypair: Option<Keypair>,
     known_peers: Vec<Multiaddr>,
     agent_version: Option<String>,
     protocols: Vec<StreamProtocol>,
     max_peer_count: Option<u32>,
+    ban_threshold: Option<i32>,
     cancellation_token: Option<CancellationToken>,
 }
 
@@ -235,10 +270,12 @@ impl NodeBuilder {
             port: None,
             listen_addrs: Vec::new(),
             keypair_hex: None,
+            keypair: None,
             known_peers: Vec::new(),
             agent_version: None,
             protocols: Vec::new(),
             max_peer_count: None,
+            ban_threshold: None,
             cancellation_token: None,
         }
     }
@@ -258,6 +295,12 @@ impl NodeBuilder {
         self
     }
 
+    /// Sets the identity of the node, takes precedence over [`Self::with_keypair_hex_string`].
+    pub fn with_keypair(mut self, keypair: Keypair) -> Self {
+        self.keypair = Some(keypair);
+        self
+    }
+
     pub fn with_agent_version(mut self, agent_version: String) -> Self {
         self.agent_version = Some(agent_version);
         self
@@ -281,6 +324,12 @@ impl NodeBuilder {
         self
     }
 
+    /// Sets the score at or below which peers are banned.
+    pub fn with_ban_threshold(mut self, ban_threshold: i32) -> Self {
+        self.ban_threshold = Some(ban_threshold);
+        self
+    }
+
     pub fn with_known_peers<I, T>(mut self, addresses: I) -> Self
     where
         I: IntoIterator<Item = T>,
@@ -297,10 +346,12 @@ impl NodeBuilder {
             port,
             listen_addrs,
             keypair_hex,
+            keypair,
             known_peers,
             agent_version,
             protocols,
             max_peer_count,
+            ban_threshold,
             cancellation_token,
         } = self;
 
@@ -311,16 +362,11 @@ impl NodeBuilder {
             eyre::bail!("agent version must be set");
         };
 
-        let keypair = match keypair_hex {
-            Some(hex) => {
-                let mut bytes = hex::decode(hex).wrap_err("failed to decode hex string")?;
-                let keypair = ed25519::Keypair::try_from_bytes(&mut bytes)
-                    .wrap_err("failed to create keypair from bytes")?;
-                Some(keypair.into())
-            }
-            None => None,
+        let keypair = match (keypair, keypair_hex) {
+            (Some(keypair), _) => keypair,
+            (None, Some(hex)) => keypair_from_hex(&hex)?,
+            (None, None) => identity::Keypair::generate_ed25519(),
         };
-        let keypair = keypair.unwrap_or(identity::Keypair::generate_ed25519());
         let peer_id = keypair.public().to_peer_id();
 
         let transport = create_transport(&keypair).wrap_err("failed to create transport")?;
@@ -344,7 +390,7 @@ impl NodeBuilder {
             incoming_message_rxs.insert(protocol.clone(), message_rx);
         }
 
-        let swarm = libp2p::SwarmBuilder::with_existing_identity(keypair)
+        let swarm = libp2p::SwarmBuilder::with_existing_identity(keypair.clone())
             .with_tokio()
             .with_other_transport(|_| transport)?
             .with_behaviour(|_| behaviour)?
@@ -375,6 +421,8 @@ impl NodeBuilder {
         }
 
         let (outgoing_message_tx, outgoing_message_rx) = tokio::sync::mpsc::channel(100);
+        let (peer_score_tx, peer_score_rx) = tokio::sync::mpsc::channel(100);
+        let peer_scores = PeerScores::new(ban_threshold.unwrap_or(DEFAULT_BAN_THRESHOLD));
 
         Ok(NodeBuildResult {
             node: Node {
@@ -383,6 +431,8 @@ impl NodeBuilder {
                 listen_addrs,
                 known_peers,
                 outgoing_message_rx,
+                peer_score_rx,
+                peer_scores,
                 outgoing_streams_handler: outgoing::StreamsHandler::new(),
                 cancellation_token,
                 incoming_streams_handlers,
@@ -390,6 +440,8 @@ impl NodeBuilder {
             },
             outgoing_message_tx,
             incoming_message_rxs,
+            peer_score_tx,
+            keypair,
         })
     }
 }
@@ -397,7 +449,7 @@ impl NodeBuilder {
 struct IncomingStreamsHandler<M> {
     protocol: StreamProtocol,
     incoming: IncomingStreams,
-    tx: mpsc::Sender<M>,
+    tx: mpsc::Sender<(PeerId, M)>,
     cancellation_token: CancellationToken,
 }
 
@@ -406,7 +458,7 @@ impl<M: Message + 'static> IncomingStreamsHandler<M> {
         protocol: StreamProtocol,
         incoming: IncomingStreams,
         cancellation_token: CancellationToken,
-    ) -> (Self, mpsc::Receiver<M>) {
+    ) -> (Self, mpsc::Receiver<(PeerId, M)>) {
         const CHANNEL_SIZE: usize = 100;
         let (tx, rx) = mpsc::channel(CHANNEL_SIZE);
         (
@@ -460,7 +512,7 @@ impl<M: Message + 'static> IncomingStreamsHandler<M> {
 async fn handle_incoming_stream<M: Message>(
     peer_id: PeerId,
     stream: libp2p::Stream,
-    payload_tx: mpsc::Sender<M>,
+    payload_tx: mpsc::Sender<(PeerId, M)>,
 ) -> eyre::Result<()> {
     use futures::StreamExt as _;
     use tokio_util::{
@@ -476,7 +528,7 @@ async fn handle_incoming_stream<M: Message>(
             Ok(str) => {
                 let payload = M::from_str(&str).wrap_err("failed to decode stream message")?;
                 debug!("got message from peer {peer_id}: {payload:?}");
-                let _ = payload_tx.send(payload).await;
+                let _ = payload_tx.send((peer_id, payload)).await;
             }
             Err(e) => {
                 return Err(e).wrap_err(format!("failed to read from stream of peer {peer_id}"));
@@ -487,6 +539,14 @@ async fn handle_incoming_stream<M: Message>(
     Ok(())
 }
 
+/// Decodes a hex encoded ed25519 keypair, as accepted by [`NodeBuilder::with_keypair_hex_string`].
+pub fn keypair_from_hex(hex: &str) -> eyre::Result<Keypair> {
+    let mut bytes = hex::decode(hex).wrap_err("failed to decode hex string")?;
+    let keypair = ed25519::Keypair::try_from_bytes(&mut bytes)
+        .wrap_err("failed to create keypair from bytes")?;
+    Ok(keypair.into())
+}
+
 fn create_transport(
     keypair: &identity::Keypair,
 ) -> eyre::Result<libp2p::core::transport::Boxed<(PeerId, libp2p::core::muxing::StreamMuxerBox)>> {
@@ -543,6 +603,8 @@ mod test {
             node: node1,
             outgoing_message_tx: _,
             incoming_message_rxs: mut rx1,
+            peer_score_tx: _,
+            keypair: _,
         } = NodeBuilder::new()
             .with_listen_addr("/ip4/127.0.0.1/tcp/9000".parse().unwrap())
             .with_agent_version(TEST_AGENT_VERSION.to_string())
@@ -553,6 +615,8 @@ mod test {
             node: node2,
             outgoing_message_tx: tx2,
             incoming_message_rxs: _,
+            peer_score_tx: _,
+            keypair: _,
         } = NodeBuilder::new()
             .with_known_peers(node1.multiaddrs())
             .with_protocol(TEST_PROTOCOL)
@@ -561,6 +625,7 @@ mod test {
             .try_build::<TestMessage>()
             .unwrap();
 
+        let node2_peer_id = node2.peer_id;
         tokio::spawn(async move { node1.run().await });
         tokio::spawn(async move { node2.run().await });
         // sleep to allow nodes to connect
@@ -571,7 +636,8 @@ mod test {
         };
         tx2.send(message.clone()).await.unwrap();
 
-        let recv_message: TestMessage = rx1.remove(&TEST_PROTOCOL).unwrap().recv().await.unwrap();
+        let (peer_id, recv_message) = rx1.remove(&TEST_PROTOCOL).unwrap().recv().await.unwrap();
+        assert_eq!(peer_id, node2_peer_id);
         assert_eq!(recv_message, message);
     }
 }
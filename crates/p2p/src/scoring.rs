@@ -0,0 +1,101 @@
+use libp2p::PeerId;
+use std::collections::{HashMap, HashSet};
+
+/// Score a peer starts with.
+const INITIAL_SCORE: i32 = 0;
+
+/// Upper bound of a peer score, so that a long well-behaved history can't
+/// outweigh recent misbehaviour.
+const MAX_SCORE: i32 = 100;
+
+/// Adjustment to the score of a peer, reported by the consumer of this library
+/// after processing a message received from that peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerScoreUpdate {
+    /// The peer the message was received from.
+    pub peer_id: PeerId,
+    /// Amount added to the score of the peer, negative for misbehaviour.
+    pub delta: i32,
+}
+
+/// Keeps track of peer scores and bans peers whose score drops to the ban threshold.
+///
+/// Banned peers are disconnected and not allowed to reconnect until the node restarts.
+#[derive(Debug)]
+pub(crate) struct PeerScores {
+    scores: HashMap<PeerId, i32>,
+    banned: HashSet<PeerId>,
+    ban_threshold: i32,
+}
+
+impl PeerScores {
+    pub(crate) fn new(ban_threshold: i32) -> Self {
+        Self {
+            scores: HashMap::new(),
+            banned: HashSet::new(),
+            ban_threshold,
+        }
+    }
+
+    /// Applies a score update, returns true if the peer got banned as a result.
+    pub(crate) fn apply(&mut self, update: PeerScoreUpdate) -> bool {
+        if self.banned.contains(&update.peer_id) {
+            return false;
+        }
+
+        let score = self.scores.entry(update.peer_id).or_insert(INITIAL_SCORE);
+        *score = score.saturating_add(update.delta).min(MAX_SCORE);
+        if *score > self.ban_threshold {
+            return false;
+        }
+
+        self.scores.remove(&update.peer_id);
+        self.banned.insert(update.peer_id)
+    }
+
+    pub(crate) fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.banned.contains(peer_id)
+    }
+
+    #[cfg(test)]
+    fn score(&self, peer_id: &PeerId) -> Option<i32> {
+        self.scores.get(peer_id).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(peer_id: PeerId, delta: i32) -> PeerScoreUpdate {
+        PeerScoreUpdate { peer_id, delta }
+    }
+
+    #[test]
+    fn scores_are_capped() {
+        let mut scores = PeerScores::new(-100);
+        let peer = PeerId::random();
+
+        assert!(!scores.apply(update(peer, 80)));
+        assert!(!scores.apply(update(peer, 80)));
+        assert_eq!(scores.score(&peer), Some(MAX_SCORE));
+    }
+
+    #[test]
+    fn peer_is_banned_at_threshold() {
+        let mut scores = PeerScores::new(-100);
+        let peer = PeerId::random();
+        let other = PeerId::random();
+
+        assert!(!scores.apply(update(peer, -60)));
+        assert!(!scores.is_banned(&peer));
+        assert!(scores.apply(update(peer, -40)));
+        assert!(scores.is_banned(&peer));
+        assert!(!scores.is_banned(&other));
+
+        // already banned peers are only reported once
+        assert!(!scores.apply(update(peer, -100)));
+        assert!(!scores.apply(update(peer, 100)));
+        assert!(scores.is_banned(&peer));
+    }
+}
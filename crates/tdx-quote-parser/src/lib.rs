@@ -0,0 +1,174 @@
+//! TDX Quote Parser
+//!
+//! Parsing of the measurement registers of raw TDX v4 quotes and computation of the
+//! flashtestations workload ID, shared by op-rbuilder and the TDX quote provider.
+//!
+//! The `mock` feature adds the synthesis of quotes signed by mock keys, and the collateral they
+//! verify against.
+
+use sha3::{Digest, Keccak256};
+
+#[cfg(feature = "mock")]
+pub mod mock_quote;
+
+// Raw TDX v4 quote structure constants
+// Raw quote has a 48-byte header before the TD10ReportBody
+pub const HEADER_LENGTH: usize = 48;
+pub const TD_REPORT10_LENGTH: usize = 584;
+
+// TD10ReportBody field offsets
+// These offsets correspond to the Solidity parseRawReportBody implementation
+const OFFSET_TD_ATTRIBUTES: usize = 120;
+const OFFSET_XFAM: usize = 128;
+const OFFSET_MR_TD: usize = 136;
+const OFFSET_MR_CONFIG_ID: usize = 184;
+const OFFSET_MR_OWNER: usize = 232;
+const OFFSET_MR_OWNER_CONFIG: usize = 280;
+const OFFSET_RT_MR0: usize = 328;
+const OFFSET_RT_MR1: usize = 376;
+const OFFSET_RT_MR2: usize = 424;
+const OFFSET_RT_MR3: usize = 472;
+
+// Field lengths
+const MEASUREMENT_REGISTER_LENGTH: usize = 48;
+const ATTRIBUTE_LENGTH: usize = 8;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum QuoteParseError {
+    #[error("invalid quote length: {length}, expected at least {expected}")]
+    InvalidLength { length: usize, expected: usize },
+}
+
+/// Parsed TDX quote report body containing measurement registers and attributes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedQuote {
+    pub mr_td: [u8; 48],
+    pub rt_mr0: [u8; 48],
+    pub rt_mr1: [u8; 48],
+    pub rt_mr2: [u8; 48],
+    pub rt_mr3: [u8; 48],
+    pub mr_config_id: [u8; 48],
+    pub mr_owner: [u8; 48],
+    pub mr_owner_config: [u8; 48],
+    pub xfam: u64,
+    pub td_attributes: u64,
+}
+
+fn read_field<const N: usize>(report_body: &[u8], offset: usize) -> [u8; N] {
+    report_body[offset..offset + N]
+        .try_into()
+        .expect("the quote length is checked")
+}
+
+/// Parse the TDX report body from a raw quote
+/// Extracts measurement registers and attributes according to TD10ReportBody specification
+/// https://github.com/flashbots/flashtestations/tree/7cc7f68492fe672a823dd2dead649793aac1f216
+pub fn parse_report_body(raw_quote: &[u8]) -> Result<ParsedQuote, QuoteParseError> {
+    // Validate quote length
+    if raw_quote.len() < HEADER_LENGTH + TD_REPORT10_LENGTH {
+        return Err(QuoteParseError::InvalidLength {
+            length: raw_quote.len(),
+            expected: HEADER_LENGTH + TD_REPORT10_LENGTH,
+        });
+    }
+
+    // Skip the 48-byte header to get to the TD10ReportBody
+    let report_body = &raw_quote[HEADER_LENGTH..];
+
+    // Extract fields exactly as parseRawReportBody does in Solidity
+    // Using named offset constants to match Solidity implementation exactly
+    let register = |offset| read_field::<MEASUREMENT_REGISTER_LENGTH>(report_body, offset);
+
+    // Extract xFAM and tdAttributes (8 bytes each)
+    // In Solidity, bytes8 is treated as big-endian for bitwise operations
+    let attribute =
+        |offset| u64::from_be_bytes(read_field::<ATTRIBUTE_LENGTH>(report_body, offset));
+
+    Ok(ParsedQuote {
+        mr_td: register(OFFSET_MR_TD),
+        rt_mr0: register(OFFSET_RT_MR0),
+        rt_mr1: register(OFFSET_RT_MR1),
+        rt_mr2: register(OFFSET_RT_MR2),
+        rt_mr3: register(OFFSET_RT_MR3),
+        mr_config_id: register(OFFSET_MR_CONFIG_ID),
+        mr_owner: register(OFFSET_MR_OWNER),
+        mr_owner_config: register(OFFSET_MR_OWNER_CONFIG),
+        xfam: attribute(OFFSET_XFAM),
+        td_attributes: attribute(OFFSET_TD_ATTRIBUTES),
+    })
+}
+
+/// Compute workload ID from parsed quote data
+/// This corresponds to QuoteParser.parseV4VerifierOutput in Solidity implementation
+/// The workload ID uniquely identifies a TEE workload based on its measurement registers
+pub fn compute_workload_id_from_parsed(parsed: &ParsedQuote) -> [u8; 32] {
+    // Concatenate all fields
+    let mut concatenated = Vec::new();
+    concatenated.extend_from_slice(&parsed.mr_td);
+    concatenated.extend_from_slice(&parsed.rt_mr0);
+    concatenated.extend_from_slice(&parsed.rt_mr1);
+    concatenated.extend_from_slice(&parsed.rt_mr2);
+    concatenated.extend_from_slice(&parsed.rt_mr3);
+    concatenated.extend_from_slice(&parsed.mr_config_id);
+    concatenated.extend_from_slice(&parsed.xfam.to_be_bytes());
+    concatenated.extend_from_slice(&parsed.td_attributes.to_be_bytes());
+
+    // Compute keccak256 hash
+    let mut hasher = Keccak256::new();
+    hasher.update(&concatenated);
+    let result = hasher.finalize();
+
+    let mut workload_id = [0u8; 32];
+    workload_id.copy_from_slice(&result);
+
+    workload_id
+}
+
+/// Compute workload ID from raw quote bytes
+/// This is a convenience function that combines parsing and computation
+pub fn compute_workload_id(raw_quote: &[u8]) -> Result<[u8; 32], QuoteParseError> {
+    let parsed = parse_report_body(raw_quote)?;
+    Ok(compute_workload_id_from_parsed(&parsed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_report_body() {
+        let mut quote = vec![0u8; HEADER_LENGTH + TD_REPORT10_LENGTH];
+        let body = &mut quote[HEADER_LENGTH..];
+        body[OFFSET_MR_TD..OFFSET_MR_TD + 48].fill(1);
+        body[OFFSET_RT_MR0..OFFSET_RT_MR0 + 48].fill(2);
+        body[OFFSET_RT_MR3..OFFSET_RT_MR3 + 48].fill(3);
+        body[OFFSET_XFAM + 7] = 0xe7;
+        body[OFFSET_TD_ATTRIBUTES] = 0x10;
+
+        let parsed = parse_report_body(&quote).unwrap();
+        assert_eq!(parsed.mr_td, [1; 48]);
+        assert_eq!(parsed.rt_mr0, [2; 48]);
+        assert_eq!(parsed.rt_mr1, [0; 48]);
+        assert_eq!(parsed.rt_mr3, [3; 48]);
+        assert_eq!(parsed.xfam, 0xe7);
+        assert_eq!(parsed.td_attributes, 0x10 << 56);
+
+        // the owner fields are not part of the workload ID
+        let workload_id = compute_workload_id(&quote).unwrap();
+        quote[HEADER_LENGTH + OFFSET_MR_OWNER] = 1;
+        assert_eq!(compute_workload_id(&quote).unwrap(), workload_id);
+        quote[HEADER_LENGTH + OFFSET_RT_MR2] = 1;
+        assert_ne!(compute_workload_id(&quote).unwrap(), workload_id);
+    }
+
+    #[test]
+    fn test_invalid_quote_length() {
+        assert_eq!(
+            parse_report_body(&[0; HEADER_LENGTH]),
+            Err(QuoteParseError::InvalidLength {
+                length: HEADER_LENGTH,
+                expected: HEADER_LENGTH + TD_REPORT10_LENGTH,
+            })
+        );
+    }
+}
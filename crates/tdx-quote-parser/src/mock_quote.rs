@@ -0,0 +1,230 @@
+//! Synthetic TDX v4 quotes signed by mock keys, for tests and the mock mode of the TDX quote
+//! provider.
+
+use p256::ecdsa::{Signature, SigningKey, signature::Signer};
+use sha2::{Digest, Sha256};
+
+use crate::{HEADER_LENGTH, TD_REPORT10_LENGTH};
+
+/// PEM encoded mock PCK certificate, followed by the mock root CA it is signed by.
+pub const MOCK_PCK_CERT_CHAIN: &str = include_str!("../mock/pck-cert-chain.pem");
+
+/// Collateral for the quote verification of op-rbuilder, whose root CA is the mock root CA.
+pub const MOCK_COLLATERAL: &str = include_str!("../mock/collateral.json");
+
+/// Private key of the mock attestation key, signing the header and report body of the quotes.
+const MOCK_ATTESTATION_KEY: &str =
+    "5ec371800dd41a844c383b463e3f0419511716ef0a375da0b407cff943e069ac";
+
+/// Private key of the mock PCK certificate, signing the QE report.
+const MOCK_PCK_KEY: &str = "5dec19042d35ff46cf59b1d4d050120e320cbed414305244c6af2c88167645b1";
+
+// Header fields of a TDX v4 quote
+const QUOTE_VERSION: u16 = 4;
+const ATTESTATION_KEY_TYPE_ECDSA_P256: u16 = 2;
+const TEE_TYPE_TDX: u32 = 0x81;
+const OFFSET_QE_VENDOR_ID: usize = 12;
+const INTEL_QE_VENDOR_ID: [u8; 16] = [
+    0x93, 0x9a, 0x72, 0x33, 0xf7, 0x9c, 0x4c, 0xa9, 0x94, 0x0a, 0x0d, 0xb3, 0x95, 0x7f, 0x06, 0x07,
+];
+
+// TD10ReportBody field offsets
+const OFFSET_XFAM: usize = 128;
+const OFFSET_MR_TD: usize = 136;
+const OFFSET_RT_MR0: usize = 328;
+const OFFSET_RT_MR1: usize = 376;
+const OFFSET_RT_MR2: usize = 424;
+const OFFSET_RT_MR3: usize = 472;
+const OFFSET_REPORT_DATA: usize = 520;
+
+/// XFAM of a TD with the usual x87, SSE, AVX, AVX-512, PKRU and AMX features enabled
+const XFAM: [u8; 8] = [0xe7, 0x02, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00];
+
+// ECDSA quote signature data, following the report body
+const QE_REPORT_LENGTH: usize = 384;
+const OFFSET_QE_REPORT_DATA: usize = 320;
+const QE_AUTH_DATA_LENGTH: u8 = 32;
+const CERT_DATA_TYPE_PCK_CERT_CHAIN: u16 = 5;
+const CERT_DATA_TYPE_QE_REPORT: u16 = 6;
+
+/// Measurement registers of the synthetic quotes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockMeasurements {
+    pub mr_td: [u8; 48],
+    pub rt_mr0: [u8; 48],
+    pub rt_mr1: [u8; 48],
+    pub rt_mr2: [u8; 48],
+    pub rt_mr3: [u8; 48],
+}
+
+impl Default for MockMeasurements {
+    fn default() -> Self {
+        Self {
+            mr_td: [0; 48],
+            rt_mr0: [0; 48],
+            rt_mr1: [0; 48],
+            rt_mr2: [0; 48],
+            rt_mr3: [0; 48],
+        }
+    }
+}
+
+/// Mock attestation provider synthesizing TDX v4 quotes for the requested report data.
+///
+/// The quotes have the layout of the quotes of a real quoting enclave: a TDX v4 header, a TD
+/// report body with the configured measurements and the report data, the signature of the
+/// attestation key, a QE report binding the attestation key signed by the mock PCK key, and the
+/// mock PCK certificate chain. They verify against the mock root CA of [`MOCK_COLLATERAL`], but
+/// not against the Intel root CA.
+pub struct SyntheticAttestationProvider {
+    measurements: MockMeasurements,
+    attestation_key: SigningKey,
+    pck_key: SigningKey,
+}
+
+impl SyntheticAttestationProvider {
+    pub fn new(measurements: MockMeasurements) -> Self {
+        Self {
+            measurements,
+            attestation_key: signing_key(MOCK_ATTESTATION_KEY),
+            pck_key: signing_key(MOCK_PCK_KEY),
+        }
+    }
+
+    /// Builds the quote of the report data.
+    pub fn quote(&self, report_data: [u8; 64]) -> Vec<u8> {
+        let mut quote = vec![0u8; HEADER_LENGTH + TD_REPORT10_LENGTH];
+        quote[0..2].copy_from_slice(&QUOTE_VERSION.to_le_bytes());
+        quote[2..4].copy_from_slice(&ATTESTATION_KEY_TYPE_ECDSA_P256.to_le_bytes());
+        quote[4..8].copy_from_slice(&TEE_TYPE_TDX.to_le_bytes());
+        quote[OFFSET_QE_VENDOR_ID..OFFSET_QE_VENDOR_ID + 16].copy_from_slice(&INTEL_QE_VENDOR_ID);
+
+        let report_body = &mut quote[HEADER_LENGTH..];
+        let mut write = |offset: usize, data: &[u8]| {
+            report_body[offset..offset + data.len()].copy_from_slice(data)
+        };
+        write(OFFSET_XFAM, &XFAM);
+        write(OFFSET_MR_TD, &self.measurements.mr_td);
+        write(OFFSET_RT_MR0, &self.measurements.rt_mr0);
+        write(OFFSET_RT_MR1, &self.measurements.rt_mr1);
+        write(OFFSET_RT_MR2, &self.measurements.rt_mr2);
+        write(OFFSET_RT_MR3, &self.measurements.rt_mr3);
+        write(OFFSET_REPORT_DATA, &report_data);
+
+        // the attestation key signs the header and the report body
+        let signature: Signature = self.attestation_key.sign(&quote);
+        let attestation_key = self.attestation_key.verifying_key().to_encoded_point(false);
+        // raw x || y coordinates, without the SEC1 tag
+        let attestation_key = &attestation_key.as_bytes()[1..];
+
+        // the QE report binds the attestation key, and is signed by the PCK key
+        let qe_auth_data: Vec<u8> = (0..QE_AUTH_DATA_LENGTH).collect();
+        let mut qe_report = [0u8; QE_REPORT_LENGTH];
+        let key_hash = Sha256::new()
+            .chain_update(attestation_key)
+            .chain_update(&qe_auth_data)
+            .finalize();
+        qe_report[OFFSET_QE_REPORT_DATA..OFFSET_QE_REPORT_DATA + 32].copy_from_slice(&key_hash);
+        let qe_report_signature: Signature = self.pck_key.sign(&qe_report);
+
+        // the PCK certificate chain is null terminated
+        let pck_cert_chain = [MOCK_PCK_CERT_CHAIN.as_bytes(), &[0]].concat();
+
+        let mut certification_data = Vec::new();
+        certification_data.extend_from_slice(&qe_report);
+        certification_data.extend_from_slice(&qe_report_signature.to_bytes());
+        certification_data.extend_from_slice(&(qe_auth_data.len() as u16).to_le_bytes());
+        certification_data.extend_from_slice(&qe_auth_data);
+        certification_data.extend_from_slice(&CERT_DATA_TYPE_PCK_CERT_CHAIN.to_le_bytes());
+        certification_data.extend_from_slice(&(pck_cert_chain.len() as u32).to_le_bytes());
+        certification_data.extend_from_slice(&pck_cert_chain);
+
+        let mut signature_data = Vec::new();
+        signature_data.extend_from_slice(&signature.to_bytes());
+        signature_data.extend_from_slice(attestation_key);
+        signature_data.extend_from_slice(&CERT_DATA_TYPE_QE_REPORT.to_le_bytes());
+        signature_data.extend_from_slice(&(certification_data.len() as u32).to_le_bytes());
+        signature_data.extend_from_slice(&certification_data);
+
+        quote.extend_from_slice(&(signature_data.len() as u32).to_le_bytes());
+        quote.extend_from_slice(&signature_data);
+        quote
+    }
+}
+
+fn signing_key(key: &str) -> SigningKey {
+    let key = hex::decode(key).expect("mock key is valid hex");
+    SigningKey::from_slice(&key).expect("mock key is a valid P-256 key")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_report_body;
+    use p256::ecdsa::{VerifyingKey, signature::Verifier};
+
+    #[test]
+    fn test_synthetic_quote() {
+        let measurements = MockMeasurements {
+            mr_td: [1; 48],
+            rt_mr0: [2; 48],
+            rt_mr3: [3; 48],
+            ..Default::default()
+        };
+        let provider = SyntheticAttestationProvider::new(measurements.clone());
+        let quote = provider.quote([7; 64]);
+
+        let parsed = parse_report_body(&quote).unwrap();
+        assert_eq!(parsed.mr_td, measurements.mr_td);
+        assert_eq!(parsed.rt_mr0, measurements.rt_mr0);
+        assert_eq!(parsed.rt_mr1, [0; 48]);
+        assert_eq!(parsed.rt_mr3, measurements.rt_mr3);
+        assert_eq!(
+            quote[HEADER_LENGTH + OFFSET_REPORT_DATA..HEADER_LENGTH + TD_REPORT10_LENGTH],
+            [7; 64]
+        );
+
+        // signature of the header and report body by the attestation key
+        let signed_length = HEADER_LENGTH + TD_REPORT10_LENGTH;
+        let signature_data = &quote[signed_length + 4..];
+        assert_eq!(
+            u32::from_le_bytes(quote[signed_length..signed_length + 4].try_into().unwrap())
+                as usize,
+            signature_data.len()
+        );
+        let signature = Signature::from_slice(&signature_data[..64]).unwrap();
+        let attestation_key =
+            VerifyingKey::from_sec1_bytes(&[&[0x04], &signature_data[64..128]].concat()).unwrap();
+        assert!(
+            attestation_key
+                .verify(&quote[..signed_length], &signature)
+                .is_ok()
+        );
+
+        // QE report signed by the PCK key, binding the attestation key
+        let certification_data = &signature_data[128 + 6..];
+        let qe_report = &certification_data[..QE_REPORT_LENGTH];
+        let qe_report_signature =
+            Signature::from_slice(&certification_data[QE_REPORT_LENGTH..QE_REPORT_LENGTH + 64])
+                .unwrap();
+        assert!(
+            signing_key(MOCK_PCK_KEY)
+                .verifying_key()
+                .verify(qe_report, &qe_report_signature)
+                .is_ok()
+        );
+        let qe_auth_data: Vec<u8> = (0..QE_AUTH_DATA_LENGTH).collect();
+        let key_hash = Sha256::new()
+            .chain_update(&signature_data[64..128])
+            .chain_update(&qe_auth_data)
+            .finalize();
+        assert_eq!(
+            qe_report[OFFSET_QE_REPORT_DATA..OFFSET_QE_REPORT_DATA + 32],
+            key_hash[..]
+        );
+
+        // the quote is bound to the report data
+        assert_ne!(provider.quote([8; 64]), quote);
+        assert_eq!(provider.quote([7; 64]), quote);
+    }
+}
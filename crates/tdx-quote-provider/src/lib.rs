@@ -3,5 +3,8 @@
 //! This crate provides functionality for generating and managing TDX attestation quotes.
 
 pub mod metrics;
+pub mod mock_quote;
 pub mod provider;
+pub mod rate_limit;
 pub mod server;
+pub mod tls;
@@ -1,14 +1,22 @@
 use clap::Parser;
 use dotenvy::dotenv;
 use metrics_exporter_prometheus::PrometheusBuilder;
+use std::{path::PathBuf, time::Duration};
 use tracing::{Level, info};
 use tracing_subscriber::filter::EnvFilter;
 
-use crate::server::{Server, ServerConfig};
+use crate::{
+    mock_quote::MockMeasurements,
+    server::{DEFAULT_EVENT_LOG_PATH, Server, ServerConfig},
+    tls::TlsConfig,
+};
 
 mod metrics;
+mod mock_quote;
 mod provider;
+mod rate_limit;
 mod server;
+mod tls;
 
 #[derive(Clone, Parser, Debug)]
 #[command(about = "TDX Quote Provider CLI")]
@@ -37,10 +45,78 @@ struct Args {
     #[arg(long, env, default_value = "false")]
     pub mock: bool,
 
-    /// Path to the mock attestation file
+    /// Path to the mock attestation file, quotes are synthesized for the report data if empty
     #[arg(long, env, default_value = "")]
     pub mock_attestation_path: String,
 
+    /// Hex encoded MRTD of the synthesized mock quotes
+    #[arg(long, env, value_parser = parse_measurement, default_value = "")]
+    pub mock_mrtd: [u8; 48],
+
+    /// Hex encoded RTMR0 of the synthesized mock quotes
+    #[arg(long, env, value_parser = parse_measurement, default_value = "")]
+    pub mock_rtmr0: [u8; 48],
+
+    /// Hex encoded RTMR1 of the synthesized mock quotes
+    #[arg(long, env, value_parser = parse_measurement, default_value = "")]
+    pub mock_rtmr1: [u8; 48],
+
+    /// Hex encoded RTMR2 of the synthesized mock quotes
+    #[arg(long, env, value_parser = parse_measurement, default_value = "")]
+    pub mock_rtmr2: [u8; 48],
+
+    /// Hex encoded RTMR3 of the synthesized mock quotes
+    #[arg(long, env, value_parser = parse_measurement, default_value = "")]
+    pub mock_rtmr3: [u8; 48],
+
+    /// Maximum number of quotes cached by report data, 0 disables the cache
+    #[arg(long, env, default_value = "1024")]
+    pub quote_cache_size: u64,
+
+    /// Time in seconds a quote is cached for
+    #[arg(long, env, default_value = "3600")]
+    pub quote_cache_ttl_secs: u64,
+
+    /// Path to the TDX event log returned in attestation bundles
+    #[arg(long, env, default_value = DEFAULT_EVENT_LOG_PATH)]
+    pub event_log_path: String,
+
+    /// Bearer token required on attestation requests
+    #[arg(long, env)]
+    pub auth_token: Option<String>,
+
+    /// Path to the PEM encoded TLS certificate chain, serves HTTPS when set with the key
+    #[arg(long, env, requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// Path to the PEM encoded TLS private key
+    #[arg(long, env, requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+
+    /// Path to the PEM encoded CA certificates of the clients, requires client certificates (mTLS)
+    #[arg(long, env, requires = "tls_cert")]
+    pub tls_client_ca: Option<PathBuf>,
+
+    /// Attestation requests per second allowed by client IP address, 0 disables the rate limit
+    #[arg(long, env, default_value = "0")]
+    pub rate_limit: u32,
+
+    /// Attestation requests a client can make at once before being rate limited
+    #[arg(long, env, default_value = "10")]
+    pub rate_limit_burst: u32,
+
+    /// Maximum size in bytes of the URI and of the body of attestation requests
+    #[arg(long, env, default_value = "4096")]
+    pub max_request_size: usize,
+
+    /// Maximum number of concurrent calls to the TDX device
+    #[arg(long, env, default_value = "4", value_parser = clap::value_parser!(u64).range(1..))]
+    pub max_concurrent_attestations: u64,
+
+    /// Time in seconds an attestation can wait for and run on the TDX device
+    #[arg(long, env, default_value = "30")]
+    pub attestation_timeout_secs: u64,
+
     /// Log level
     #[arg(long, env, default_value = "info")]
     pub log_level: Level,
@@ -50,6 +126,17 @@ struct Args {
     pub log_format: String,
 }
 
+/// Parses a hex encoded 48 bytes measurement register, zero if empty.
+fn parse_measurement(value: &str) -> Result<[u8; 48], String> {
+    if value.is_empty() {
+        return Ok([0; 48]);
+    }
+    let bytes = hex::decode(value.trim_start_matches("0x")).map_err(|e| e.to_string())?;
+    bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| format!("expected 48 bytes, got {}", bytes.len()))
+}
+
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
     dotenv().ok();
@@ -89,6 +176,30 @@ async fn main() -> eyre::Result<()> {
             .unwrap(),
         use_mock: args.mock,
         mock_attestation_path: args.mock_attestation_path,
+        mock_measurements: MockMeasurements {
+            mr_td: args.mock_mrtd,
+            rt_mr0: args.mock_rtmr0,
+            rt_mr1: args.mock_rtmr1,
+            rt_mr2: args.mock_rtmr2,
+            rt_mr3: args.mock_rtmr3,
+        },
+        quote_cache_size: args.quote_cache_size,
+        quote_cache_ttl: Duration::from_secs(args.quote_cache_ttl_secs),
+        event_log_path: args.event_log_path,
+        auth_token: args.auth_token,
+        tls: args
+            .tls_cert
+            .zip(args.tls_key)
+            .map(|(cert_path, key_path)| TlsConfig {
+                cert_path,
+                key_path,
+                client_ca_path: args.tls_client_ca,
+            }),
+        rate_limit: args.rate_limit,
+        rate_limit_burst: args.rate_limit_burst,
+        max_request_size: args.max_request_size,
+        max_concurrent_attestations: args.max_concurrent_attestations as usize,
+        attestation_timeout: Duration::from_secs(args.attestation_timeout_secs),
     });
 
     server.listen().await
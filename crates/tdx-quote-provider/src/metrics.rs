@@ -1,4 +1,4 @@
-use metrics::Histogram;
+use metrics::{Counter, Histogram};
 use metrics_derive::Metrics;
 
 #[derive(Metrics, Clone)]
@@ -6,4 +6,18 @@ use metrics_derive::Metrics;
 pub struct Metrics {
     /// Duration of attestation request
     pub attest_duration: Histogram,
+    /// Number of attestation requests served from the quote cache
+    pub quote_cache_hits: Counter,
+    /// Number of attestation requests that generated a quote
+    pub quote_cache_misses: Counter,
+    /// Duration of the TDX device calls
+    pub device_duration: Histogram,
+    /// Number of attestations that timed out
+    pub attestation_timeouts: Counter,
+    /// Number of requests rejected for a missing or invalid bearer token
+    pub unauthorized_requests: Counter,
+    /// Number of requests rejected by the rate limit of the client
+    pub rate_limited_requests: Counter,
+    /// Number of requests rejected for exceeding the maximum request size
+    pub oversized_requests: Counter,
 }
@@ -0,0 +1,9 @@
+pub use tdx_quote_parser::mock_quote::{MockMeasurements, SyntheticAttestationProvider};
+
+use crate::provider::{AttestationError, AttestationProvider};
+
+impl AttestationProvider for SyntheticAttestationProvider {
+    fn get_attestation(&self, report_data: [u8; 64]) -> Result<Vec<u8>, AttestationError> {
+        Ok(self.quote(report_data))
+    }
+}
@@ -1,14 +1,20 @@
-use std::{fs::File, io::Read, sync::Arc};
+use std::{fs::File, io::Read, sync::Arc, time::Duration};
 use tdx::{Tdx, device::DeviceOptions, error::TdxError};
 use thiserror::Error;
 use tracing::info;
 
+use crate::mock_quote::{MockMeasurements, SyntheticAttestationProvider};
+
 #[derive(Error, Debug)]
 pub enum AttestationError {
     #[error("Failed to get attestation: {0}")]
     GetAttestationFailed(TdxError),
     #[error("Failed to read mock attestation file: {0}")]
     ReadMockAttestationFailed(std::io::Error),
+    #[error("Attestation timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("Attestation task failed: {0}")]
+    TaskFailed(tokio::task::JoinError),
 }
 
 /// Configuration for attestation
@@ -16,8 +22,10 @@ pub enum AttestationError {
 pub struct AttestationConfig {
     /// If true, uses the mock attestation provider instead of real TDX hardware
     pub mock: bool,
-    /// Path to the mock attestation file
+    /// Path to the mock attestation file, quotes are synthesized for the report data if empty
     pub mock_attestation_path: String,
+    /// Measurement registers of the synthesized mock quotes
+    pub mock_measurements: MockMeasurements,
 }
 
 /// Trait for attestation providers
@@ -85,7 +93,9 @@ impl AttestationProvider for MockAttestationProvider {
 pub fn get_attestation_provider(
     config: AttestationConfig,
 ) -> Arc<dyn AttestationProvider + Send + Sync> {
-    if config.mock {
+    if config.mock && config.mock_attestation_path.is_empty() {
+        Arc::new(SyntheticAttestationProvider::new(config.mock_measurements))
+    } else if config.mock {
         Arc::new(MockAttestationProvider::new(config.mock_attestation_path))
     } else {
         Arc::new(TdxAttestationProvider::new())
@@ -0,0 +1,119 @@
+use std::{collections::HashMap, net::IpAddr, sync::Mutex, time::Instant};
+
+/// Maximum number of tracked clients. Once reached, the clients with a full bucket are dropped,
+/// then the client with the most tokens left if none has a full bucket.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket rate limiter by client IP address.
+///
+/// Each client can make `burst` requests at once, refilled at `rate` requests per second.
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    clients: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(rate: u32, burst: u32) -> Self {
+        Self {
+            rate: rate as f64,
+            burst: burst.max(1) as f64,
+            clients: Mutex::default(),
+        }
+    }
+
+    /// Returns whether a request of the client is allowed, consuming a token if so.
+    pub fn check(&self, client: IpAddr) -> bool {
+        self.check_at(client, Instant::now())
+    }
+
+    fn check_at(&self, client: IpAddr, now: Instant) -> bool {
+        let mut clients = self.clients.lock().unwrap();
+        if clients.len() >= MAX_TRACKED_CLIENTS && !clients.contains_key(&client) {
+            // a full bucket is the same as no bucket
+            clients.retain(|_, bucket| self.refill(bucket, now) < self.burst);
+            if clients.len() >= MAX_TRACKED_CLIENTS
+                && let Some(evicted) = clients
+                    .iter()
+                    .max_by(|(_, a), (_, b)| a.tokens.total_cmp(&b.tokens))
+                    .map(|(client, _)| *client)
+            {
+                clients.remove(&evicted);
+            }
+        }
+
+        let bucket = clients.entry(client).or_insert(Bucket {
+            tokens: self.burst,
+            updated: now,
+        });
+        if self.refill(bucket, now) < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+
+    fn refill(&self, bucket: &mut Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.updated = now;
+        bucket.tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(2, 3);
+        let client: IpAddr = [127, 0, 0, 1].into();
+        let other: IpAddr = [127, 0, 0, 2].into();
+        let now = Instant::now();
+
+        // the burst is allowed at once
+        for _ in 0..3 {
+            assert!(limiter.check_at(client, now));
+        }
+        assert!(!limiter.check_at(client, now));
+        // clients have their own bucket
+        assert!(limiter.check_at(other, now));
+
+        // one token is refilled every 500ms
+        assert!(!limiter.check_at(client, now + Duration::from_millis(400)));
+        assert!(limiter.check_at(client, now + Duration::from_millis(500)));
+        assert!(!limiter.check_at(client, now + Duration::from_millis(500)));
+
+        // the bucket is capped to the burst
+        let later = now + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert!(limiter.check_at(client, later));
+        }
+        assert!(!limiter.check_at(client, later));
+    }
+
+    #[test]
+    fn test_rate_limiter_tracked_clients() {
+        let limiter = RateLimiter::new(1, 2);
+        let now = Instant::now();
+
+        // clients with a partially used bucket fill the map
+        for i in 0..MAX_TRACKED_CLIENTS as u32 {
+            assert!(limiter.check_at(IpAddr::from(i.to_be_bytes()), now));
+        }
+        let client: IpAddr = [255, 255, 255, 255].into();
+        assert!(limiter.check_at(client, now + Duration::from_millis(1)));
+        assert_eq!(limiter.clients.lock().unwrap().len(), MAX_TRACKED_CLIENTS);
+        assert!(limiter.check_at(client, now + Duration::from_millis(1)));
+        assert!(!limiter.check_at(client, now + Duration::from_millis(1)));
+    }
+}
@@ -1,22 +1,42 @@
-use std::{net::SocketAddr, sync::Arc, time::Instant};
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use axum::{
     Router,
     body::Body,
-    extract::{Path, State},
-    http::{Response, StatusCode},
+    extract::{ConnectInfo, Path, Request, State, connect_info::Connected},
+    http::{
+        Response, StatusCode,
+        header::{AUTHORIZATION, CONTENT_LENGTH, WWW_AUTHENTICATE},
+    },
+    middleware::{self, Next},
     response::IntoResponse,
     routing::get,
+    serve::IncomingStream,
 };
+use moka::future::Cache;
+use serde::Serialize;
 use serde_json::json;
-use tokio::{net::TcpListener, signal};
+use tdx_quote_parser::{compute_workload_id_from_parsed, parse_report_body};
+use tokio::{net::TcpListener, signal, sync::Semaphore};
 use tracing::info;
 
 use crate::{
     metrics::Metrics,
-    provider::{AttestationConfig, AttestationProvider, get_attestation_provider},
+    mock_quote::MockMeasurements,
+    provider::{
+        AttestationConfig, AttestationError, AttestationProvider, get_attestation_provider,
+    },
+    rate_limit::RateLimiter,
+    tls::{TlsConfig, TlsListener},
 };
 
+/// Default path of the TDX event log, exposed by the kernel as the CCEL ACPI table.
+pub const DEFAULT_EVENT_LOG_PATH: &str = "/sys/firmware/acpi/tables/data/CCEL";
+
 /// Server configuration
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
@@ -24,8 +44,30 @@ pub struct ServerConfig {
     pub listen_addr: SocketAddr,
     /// Whether to use mock attestation
     pub use_mock: bool,
-    /// Path to the mock attestation file
+    /// Path to the mock attestation file, quotes are synthesized for the report data if empty
     pub mock_attestation_path: String,
+    /// Measurement registers of the synthesized mock quotes
+    pub mock_measurements: MockMeasurements,
+    /// Maximum number of quotes cached by report data, 0 disables the cache
+    pub quote_cache_size: u64,
+    /// Time a quote is cached for
+    pub quote_cache_ttl: Duration,
+    /// Path to the TDX event log returned in attestation bundles
+    pub event_log_path: String,
+    /// Bearer token required on attestation requests
+    pub auth_token: Option<String>,
+    /// TLS configuration, serves plain HTTP if not set
+    pub tls: Option<TlsConfig>,
+    /// Attestation requests per second allowed by client IP address, 0 disables the rate limit
+    pub rate_limit: u32,
+    /// Attestation requests a client can make at once before being rate limited
+    pub rate_limit_burst: u32,
+    /// Maximum size in bytes of the URI and of the body of attestation requests
+    pub max_request_size: usize,
+    /// Maximum number of concurrent calls to the TDX device
+    pub max_concurrent_attestations: usize,
+    /// Time an attestation can wait for and run on the TDX device
+    pub attestation_timeout: Duration,
 }
 
 impl Default for ServerConfig {
@@ -34,6 +76,17 @@ impl Default for ServerConfig {
             listen_addr: "127.0.0.1:8181".parse().unwrap(),
             use_mock: false,
             mock_attestation_path: "".to_string(),
+            mock_measurements: MockMeasurements::default(),
+            quote_cache_size: 1024,
+            quote_cache_ttl: Duration::from_secs(3600),
+            event_log_path: DEFAULT_EVENT_LOG_PATH.to_string(),
+            auth_token: None,
+            tls: None,
+            rate_limit: 0,
+            rate_limit_burst: 10,
+            max_request_size: 4096,
+            max_concurrent_attestations: 4,
+            attestation_timeout: Duration::from_secs(30),
         }
     }
 }
@@ -41,13 +94,123 @@ impl Default for ServerConfig {
 #[derive(Clone)]
 struct ServerState {
     quote_provider: Arc<dyn AttestationProvider + Send + Sync>,
+    quotes: Cache<[u8; 64], Arc<Vec<u8>>>,
+    event_log_path: String,
+    auth_token: Option<Arc<str>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    max_request_size: usize,
+    attestation_permits: Arc<Semaphore>,
+    attestation_timeout: Duration,
     metrics: Metrics,
 }
 
+impl ServerState {
+    /// Returns the quote for the report data, from the cache or the quote provider. Concurrent
+    /// requests for the same report data share a single quote generation.
+    async fn get_quote(
+        &self,
+        report_data: [u8; 64],
+    ) -> Result<Arc<Vec<u8>>, Arc<AttestationError>> {
+        let entry = self
+            .quotes
+            .entry(report_data)
+            .or_try_insert_with(async {
+                let start = Instant::now();
+                let quote = self.attest(report_data).await?;
+                self.metrics
+                    .attest_duration
+                    .record(start.elapsed().as_secs_f64());
+                Ok(Arc::new(quote))
+            })
+            .await?;
+        if entry.is_fresh() {
+            self.metrics.quote_cache_misses.increment(1);
+        } else {
+            self.metrics.quote_cache_hits.increment(1);
+        }
+        Ok(entry.into_value())
+    }
+
+    /// Generates a quote on the blocking pool, as the TDX device call blocks. The call holds an
+    /// attestation permit until it returns, even after timing out, so that hanging device calls
+    /// cannot pile up on the blocking pool.
+    async fn attest(&self, report_data: [u8; 64]) -> Result<Vec<u8>, AttestationError> {
+        let quote_provider = self.quote_provider.clone();
+        let permits = self.attestation_permits.clone();
+        let metrics = self.metrics.clone();
+        let attestation = async move {
+            let permit = permits
+                .acquire_owned()
+                .await
+                .expect("the attestation semaphore is never closed");
+            tokio::task::spawn_blocking(move || {
+                let _permit = permit;
+                let start = Instant::now();
+                let quote = quote_provider.get_attestation(report_data);
+                metrics
+                    .device_duration
+                    .record(start.elapsed().as_secs_f64());
+                quote
+            })
+            .await
+            .map_err(AttestationError::TaskFailed)?
+        };
+
+        tokio::time::timeout(self.attestation_timeout, attestation)
+            .await
+            .map_err(|_| {
+                self.metrics.attestation_timeouts.increment(1);
+                AttestationError::Timeout(self.attestation_timeout)
+            })?
+    }
+
+    /// Reads the TDX event log, if available.
+    async fn event_log(&self) -> Option<Vec<u8>> {
+        match tokio::fs::read(&self.event_log_path).await {
+            Ok(event_log) => Some(event_log),
+            Err(e) => {
+                info!(target: "tdx_quote_provider", error = %e, path = self.event_log_path, "TDX event log not available");
+                None
+            }
+        }
+    }
+}
+
+/// Measurement registers of a quote
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Measurements {
+    pub mrtd: String,
+    pub rtmr0: String,
+    pub rtmr1: String,
+    pub rtmr2: String,
+    pub rtmr3: String,
+}
+
+/// Quote with its parsed measurements, workload ID and the event log of the TD, all hex encoded
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationBundle {
+    pub quote: String,
+    pub measurements: Measurements,
+    pub workload_id: String,
+    pub event_log: Option<String>,
+}
+
+fn hex_0x(data: impl AsRef<[u8]>) -> String {
+    format!("0x{}", hex::encode(data))
+}
+
 #[derive(Clone)]
 pub struct Server {
     /// Quote provider
     quote_provider: Arc<dyn AttestationProvider + Send + Sync>,
+    /// Quotes by report data
+    quotes: Cache<[u8; 64], Arc<Vec<u8>>>,
+    /// Rate limiter of the attestation requests, if enabled
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// Permits of the concurrent calls to the TDX device
+    attestation_permits: Arc<Semaphore>,
     /// Metrics for the server
     metrics: Metrics,
     /// Server configuration
@@ -59,107 +222,273 @@ impl Server {
         let attestation_config = AttestationConfig {
             mock: config.use_mock,
             mock_attestation_path: config.mock_attestation_path.clone(),
+            mock_measurements: config.mock_measurements.clone(),
         };
         let quote_provider = get_attestation_provider(attestation_config);
+        let quotes = Cache::builder()
+            .max_capacity(config.quote_cache_size)
+            .time_to_live(config.quote_cache_ttl)
+            .build();
+        let rate_limiter = (config.rate_limit > 0)
+            .then(|| Arc::new(RateLimiter::new(config.rate_limit, config.rate_limit_burst)));
+        let attestation_permits =
+            Arc::new(Semaphore::new(config.max_concurrent_attestations.max(1)));
         Self {
             quote_provider,
+            quotes,
+            rate_limiter,
+            attestation_permits,
             metrics: Metrics::default(),
             config,
         }
     }
 
     pub async fn listen(self) -> eyre::Result<()> {
+        let state = ServerState {
+            quote_provider: self.quote_provider,
+            quotes: self.quotes,
+            event_log_path: self.config.event_log_path.clone(),
+            auth_token: self.config.auth_token.as_deref().map(Arc::from),
+            rate_limiter: self.rate_limiter,
+            max_request_size: self.config.max_request_size,
+            attestation_permits: self.attestation_permits,
+            attestation_timeout: self.config.attestation_timeout,
+            metrics: self.metrics,
+        };
+        // the request size is checked first, then the rate limit so that clients cannot guess
+        // the token at will
+        let attest_router = Router::new()
+            .route("/attest/{appdata}", get(attest_handler))
+            .route("/attest/{appdata}/bundle", get(attest_bundle_handler))
+            .route_layer(middleware::from_fn_with_state(state.clone(), authenticate))
+            .route_layer(middleware::from_fn_with_state(state.clone(), rate_limit))
+            .route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                limit_request_size,
+            ));
         let router = Router::new()
             .route("/healthz", get(healthz_handler))
-            .route("/attest/{appdata}", get(attest_handler))
-            .with_state(ServerState {
-                quote_provider: self.quote_provider,
-                metrics: self.metrics,
-            });
+            .merge(attest_router)
+            .with_state(state);
+        let service = router.into_make_service_with_connect_info::<ClientAddr>();
 
+        let tls_config = self
+            .config
+            .tls
+            .as_ref()
+            .map(TlsConfig::server_config)
+            .transpose()?;
         let listener = TcpListener::bind(self.config.listen_addr).await?;
         info!(
             message = "starting server",
-            address = listener.local_addr()?.to_string()
+            address = listener.local_addr()?.to_string(),
+            tls = tls_config.is_some()
         );
 
-        axum::serve(
-            listener,
-            router.into_make_service_with_connect_info::<SocketAddr>(),
-        )
-        .with_graceful_shutdown(shutdown_signal())
-        .await
+        match tls_config {
+            Some(tls_config) => {
+                axum::serve(TlsListener::new(listener, tls_config)?, service)
+                    .with_graceful_shutdown(shutdown_signal())
+                    .await
+            }
+            None => {
+                axum::serve(listener, service)
+                    .with_graceful_shutdown(shutdown_signal())
+                    .await
+            }
+        }
         .map_err(|e| eyre::eyre!("failed to start server: {}", e))
     }
 }
 
+/// Address of the client of a connection
+#[derive(Debug, Clone, Copy)]
+struct ClientAddr(SocketAddr);
+
+impl Connected<IncomingStream<'_, TcpListener>> for ClientAddr {
+    fn connect_info(stream: IncomingStream<'_, TcpListener>) -> Self {
+        Self(*stream.remote_addr())
+    }
+}
+
+impl Connected<IncomingStream<'_, TlsListener>> for ClientAddr {
+    fn connect_info(stream: IncomingStream<'_, TlsListener>) -> Self {
+        Self(*stream.remote_addr())
+    }
+}
+
+/// Compares the bytes in a time independent of their content.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+async fn limit_request_size(
+    State(server): State<ServerState>,
+    request: Request,
+    next: Next,
+) -> Response<Body> {
+    let uri_size = request
+        .uri()
+        .path_and_query()
+        .map_or(0, |uri| uri.as_str().len());
+    let body_size = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok()?.parse::<usize>().ok())
+        .unwrap_or_default();
+    if uri_size > server.max_request_size || body_size > server.max_request_size {
+        server.metrics.oversized_requests.increment(1);
+        return error_response(StatusCode::PAYLOAD_TOO_LARGE, "Request too large");
+    }
+    next.run(request).await
+}
+
+async fn rate_limit(
+    State(server): State<ServerState>,
+    ConnectInfo(ClientAddr(client)): ConnectInfo<ClientAddr>,
+    request: Request,
+    next: Next,
+) -> Response<Body> {
+    if let Some(rate_limiter) = &server.rate_limiter
+        && !rate_limiter.check(client.ip())
+    {
+        server.metrics.rate_limited_requests.increment(1);
+        return error_response(StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded");
+    }
+    next.run(request).await
+}
+
+async fn authenticate(
+    State(server): State<ServerState>,
+    request: Request,
+    next: Next,
+) -> Response<Body> {
+    if let Some(auth_token) = &server.auth_token {
+        let authorized = request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok()?.strip_prefix("Bearer "))
+            .is_some_and(|token| constant_time_eq(token.as_bytes(), auth_token.as_bytes()));
+        if !authorized {
+            server.metrics.unauthorized_requests.increment(1);
+            let mut response =
+                error_response(StatusCode::UNAUTHORIZED, "Missing or invalid bearer token");
+            response
+                .headers_mut()
+                .insert(WWW_AUTHENTICATE, "Bearer".parse().unwrap());
+            return response;
+        }
+    }
+    next.run(request).await
+}
+
 async fn healthz_handler() -> impl IntoResponse {
     StatusCode::OK
 }
 
+fn error_response(status: StatusCode, message: &str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::from(json!({ "message": message }).to_string()))
+        .unwrap()
+}
+
+/// Status and message of a failed request
+type RequestError = (StatusCode, &'static str);
+
+/// Decodes the hex encoded report data of a request.
+fn parse_report_data(appdata: &str) -> Result<[u8; 64], RequestError> {
+    // Decode hex
+    let appdata = hex::decode(appdata).map_err(|e| {
+        info!(target: "tdx_quote_provider", error = %e, "Invalid hex in report data for attestation");
+        (
+            StatusCode::BAD_REQUEST,
+            "Invalid hex in report data for attestation",
+        )
+    })?;
+
+    // Convert to report data
+    appdata.try_into().map_err(|e| {
+        info!(target: "tdx_quote_provider", error = ?e, "Invalid report data length for attestation");
+        (
+            StatusCode::BAD_REQUEST,
+            "Invalid report data length for attestation",
+        )
+    })
+}
+
+async fn get_quote(server: &ServerState, appdata: &str) -> Result<Arc<Vec<u8>>, RequestError> {
+    let report_data = parse_report_data(appdata)?;
+    server.get_quote(report_data).await.map_err(|e| {
+        tracing::error!(target: "tdx_quote_provider", error = %e, "Failed to get TDX attestation");
+        match *e {
+            AttestationError::Timeout(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Timed out waiting for TDX attestation",
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to get TDX attestation",
+            ),
+        }
+    })
+}
+
 async fn attest_handler(
     Path(appdata): Path<String>,
     State(server): State<ServerState>,
 ) -> impl IntoResponse {
-    let start = Instant::now();
+    match get_quote(&server, &appdata).await {
+        Ok(attestation) => Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "application/octet-stream")
+            .body(Body::from(attestation.to_vec()))
+            .unwrap()
+            .into_response(),
+        Err((status, message)) => error_response(status, message).into_response(),
+    }
+}
 
-    // Decode hex
-    let appdata = match hex::decode(appdata) {
-        Ok(data) => data,
-        Err(e) => {
-            info!(target: "tdx_quote_provider", error = %e, "Invalid hex in report data for attestation");
-            return Response::builder()
-                .status(StatusCode::BAD_REQUEST)
-                .body(Body::from(
-                    json!({"message": "Invalid hex in report data for attestation"}).to_string(),
-                ))
-                .unwrap()
-                .into_response();
-        }
+async fn attest_bundle_handler(
+    Path(appdata): Path<String>,
+    State(server): State<ServerState>,
+) -> impl IntoResponse {
+    let quote = match get_quote(&server, &appdata).await {
+        Ok(quote) => quote,
+        Err((status, message)) => return error_response(status, message).into_response(),
     };
 
-    // Convert to report data
-    let report_data: [u8; 64] = match appdata.try_into() {
-        Ok(data) => data,
+    let parsed = match parse_report_body(&quote) {
+        Ok(parsed) => parsed,
         Err(e) => {
-            info!(target: "tdx_quote_provider", error = ?e, "Invalid report data length for attestation");
-            return Response::builder()
-                .status(StatusCode::BAD_REQUEST)
-                .body(Body::from(
-                    json!({"message": "Invalid report data length for attestation"}).to_string(),
-                ))
-                .unwrap()
-                .into_response();
+            tracing::error!(target: "tdx_quote_provider", error = %e, "Failed to parse TDX quote");
+            return error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to parse TDX quote",
+            )
+            .into_response();
         }
     };
 
-    // Get attestation
-    match server.quote_provider.get_attestation(report_data) {
-        Ok(attestation) => {
-            let duration = start.elapsed();
-            server
-                .metrics
-                .attest_duration
-                .record(duration.as_secs_f64());
-
-            Response::builder()
-                .status(StatusCode::OK)
-                .header("Content-Type", "application/octet-stream")
-                .body(Body::from(attestation))
-                .unwrap()
-                .into_response()
-        }
-        Err(e) => {
-            tracing::error!(target: "tdx_quote_provider", error = %e, "Failed to get TDX attestation");
-            Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(Body::from(
-                    json!({"message": "Failed to get TDX attestation"}).to_string(),
-                ))
-                .unwrap()
-                .into_response()
-        }
-    }
+    let bundle = AttestationBundle {
+        quote: hex_0x(quote.as_slice()),
+        measurements: Measurements {
+            mrtd: hex_0x(parsed.mr_td),
+            rtmr0: hex_0x(parsed.rt_mr0),
+            rtmr1: hex_0x(parsed.rt_mr1),
+            rtmr2: hex_0x(parsed.rt_mr2),
+            rtmr3: hex_0x(parsed.rt_mr3),
+        },
+        workload_id: hex_0x(compute_workload_id_from_parsed(&parsed)),
+        event_log: server.event_log().await.map(hex_0x),
+    };
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&bundle).unwrap()))
+        .unwrap()
+        .into_response()
 }
 
 async fn shutdown_signal() {
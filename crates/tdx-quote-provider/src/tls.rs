@@ -0,0 +1,159 @@
+use std::{
+    io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use eyre::WrapErr;
+use rustls::{
+    RootCertStore,
+    pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+    server::WebPkiClientVerifier,
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::{Semaphore, mpsc},
+};
+use tokio_rustls::{TlsAcceptor, server::TlsStream};
+use tracing::{debug, warn};
+
+/// Time a client has to complete the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Number of established connections waiting to be served.
+const PENDING_CONNECTIONS: usize = 64;
+
+/// Number of TLS handshakes in progress at once, further connections wait to be accepted.
+const MAX_CONCURRENT_HANDSHAKES: usize = 256;
+
+/// TLS configuration of the server
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    /// Path to the PEM encoded certificate chain of the server
+    pub cert_path: PathBuf,
+    /// Path to the PEM encoded private key of the server
+    pub key_path: PathBuf,
+    /// Path to the PEM encoded CA certificates of the clients. When set, clients must present a
+    /// certificate signed by one of them (mTLS).
+    pub client_ca_path: Option<PathBuf>,
+}
+
+impl TlsConfig {
+    /// Loads the certificates and key into a rustls server configuration.
+    pub fn server_config(&self) -> eyre::Result<Arc<rustls::ServerConfig>> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+
+        let certs = read_certs(&self.cert_path)?;
+        let key = PrivateKeyDer::from_pem_file(&self.key_path).wrap_err_with(|| {
+            format!("failed to read TLS private key {}", self.key_path.display())
+        })?;
+
+        let builder = rustls::ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()?;
+        let builder = match &self.client_ca_path {
+            Some(path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in read_certs(path)? {
+                    roots.add(cert)?;
+                }
+                let verifier =
+                    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                        .build()?;
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        let mut config = builder.with_single_cert(certs, key)?;
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Ok(Arc::new(config))
+    }
+}
+
+fn read_certs(path: &Path) -> eyre::Result<Vec<CertificateDer<'static>>> {
+    CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .wrap_err_with(|| format!("failed to read TLS certificates {}", path.display()))
+}
+
+/// Listener serving TLS connections.
+///
+/// The TLS handshakes are done on their own tasks, so that slow clients do not hold up the
+/// accepted connections of the other clients. At most [`MAX_CONCURRENT_HANDSHAKES`] are in
+/// progress at once.
+pub struct TlsListener {
+    local_addr: SocketAddr,
+    connections: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+}
+
+impl TlsListener {
+    pub fn new(listener: TcpListener, config: Arc<rustls::ServerConfig>) -> io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let acceptor = TlsAcceptor::from(config);
+        let (tx, connections) = mpsc::channel(PENDING_CONNECTIONS);
+        let handshakes = Arc::new(Semaphore::new(MAX_CONCURRENT_HANDSHAKES));
+
+        tokio::spawn(async move {
+            loop {
+                let permit = tokio::select! {
+                    _ = tx.closed() => break,
+                    permit = handshakes.clone().acquire_owned() => {
+                        permit.expect("handshake semaphore is never closed")
+                    }
+                };
+                let (stream, addr) = tokio::select! {
+                    _ = tx.closed() => break,
+                    accepted = listener.accept() => match accepted {
+                        Ok(accepted) => accepted,
+                        Err(e) => {
+                            warn!(target: "tdx_quote_provider", error = %e, "Failed to accept connection");
+                            tokio::time::sleep(Duration::from_millis(100)).await;
+                            continue;
+                        }
+                    },
+                };
+
+                let acceptor = acceptor.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    let _permit = permit;
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                        Ok(Ok(stream)) => {
+                            let _ = tx.send((stream, addr)).await;
+                        }
+                        Ok(Err(e)) => {
+                            debug!(target: "tdx_quote_provider", error = %e, client = %addr, "TLS handshake failed");
+                        }
+                        Err(_) => {
+                            debug!(target: "tdx_quote_provider", client = %addr, "TLS handshake timed out");
+                        }
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            local_addr,
+            connections,
+        })
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.connections.recv().await {
+            Some(connection) => connection,
+            // the accept task only stops once the listener is dropped
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
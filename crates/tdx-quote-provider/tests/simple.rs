@@ -1,11 +1,19 @@
 use axum::body::Bytes;
-use std::{error::Error, net::SocketAddr, time::Duration};
-use tdx_quote_provider::server::{Server, ServerConfig};
+use reqwest::{Certificate, Client, Identity, StatusCode};
+use std::{error::Error, net::SocketAddr, path::PathBuf, time::Duration};
+use tdx_quote_parser::{HEADER_LENGTH, compute_workload_id, parse_report_body};
+use tdx_quote_provider::{
+    mock_quote::MockMeasurements,
+    server::{Server, ServerConfig},
+    tls::TlsConfig,
+};
 use tokio::net::TcpListener;
 
 struct TestHarness {
     server: Server,
-    server_addr: SocketAddr,
+    client: Client,
+    base_url: String,
+    auth_token: Option<String>,
 }
 
 impl TestHarness {
@@ -17,19 +25,33 @@ impl TestHarness {
 
     fn new(addr: SocketAddr) -> TestHarness {
         let path = format!("{}/tests/test_data/quote.bin", env!("CARGO_MANIFEST_DIR"));
+        Self::with_config(ServerConfig {
+            listen_addr: addr,
+            use_mock: true,
+            mock_attestation_path: path,
+            ..Default::default()
+        })
+    }
+
+    fn with_config(config: ServerConfig) -> TestHarness {
         Self {
-            server: Server::new(ServerConfig {
-                listen_addr: addr,
-                use_mock: true,
-                mock_attestation_path: path,
-            }),
-            server_addr: addr,
+            base_url: format!("http://{}", config.listen_addr),
+            client: Client::new(),
+            auth_token: None,
+            server: Server::new(config),
+        }
+    }
+
+    async fn get(&self, path: &str) -> reqwest::Result<reqwest::Response> {
+        let mut request = self.client.get(format!("{}{}", self.base_url, path));
+        if let Some(auth_token) = &self.auth_token {
+            request = request.bearer_auth(auth_token);
         }
+        request.send().await
     }
 
     async fn healthcheck(&self) -> Result<(), Box<dyn Error>> {
-        let url = format!("http://{}/healthz", self.server_addr);
-        let response = reqwest::get(url).await?;
+        let response = self.get("/healthz").await?;
         match response.error_for_status() {
             Ok(_) => Ok(()),
             Err(e) => Err(e.into()),
@@ -37,8 +59,7 @@ impl TestHarness {
     }
 
     async fn attest(&self, app_data: String) -> Result<Bytes, Box<dyn Error>> {
-        let url = format!("http://{}/attest/{}", self.server_addr, app_data);
-        let response = reqwest::get(url).await?;
+        let response = self.get(&format!("/attest/{app_data}")).await?;
         match response.error_for_status() {
             Ok(response) => {
                 let body = response.bytes().await?;
@@ -48,6 +69,21 @@ impl TestHarness {
         }
     }
 
+    async fn attest_status(&self, app_data: String) -> StatusCode {
+        self.get(&format!("/attest/{app_data}"))
+            .await
+            .unwrap()
+            .status()
+    }
+
+    async fn attest_bundle(&self, app_data: String) -> Result<serde_json::Value, Box<dyn Error>> {
+        let response = self
+            .get(&format!("/attest/{app_data}/bundle"))
+            .await?
+            .error_for_status()?;
+        Ok(response.json().await?)
+    }
+
     async fn start_server(&mut self) {
         let mut healthy = true;
 
@@ -112,3 +148,291 @@ async fn test_attest_invalid_report_data() {
     let response = harness.attest("aede".to_string()).await;
     assert!(response.is_err());
 }
+
+#[tokio::test]
+async fn test_quote_cache() {
+    let addr = TestHarness::alloc_port().await;
+    let dir = tempfile::tempdir().unwrap();
+    let quote_path = dir.path().join("quote.bin");
+    std::fs::write(&quote_path, b"first quote").unwrap();
+
+    let mut harness = TestHarness::with_config(ServerConfig {
+        listen_addr: addr,
+        use_mock: true,
+        mock_attestation_path: quote_path.to_str().unwrap().to_string(),
+        ..Default::default()
+    });
+    harness.start_server().await;
+
+    let report_data = hex::encode([1; 64]);
+    assert_eq!(
+        harness.attest(report_data.clone()).await.unwrap(),
+        Bytes::from_static(b"first quote")
+    );
+
+    // the quote of the same report data is served from the cache
+    std::fs::write(&quote_path, b"second quote").unwrap();
+    assert_eq!(
+        harness.attest(report_data).await.unwrap(),
+        Bytes::from_static(b"first quote")
+    );
+    assert_eq!(
+        harness.attest(hex::encode([2; 64])).await.unwrap(),
+        Bytes::from_static(b"second quote")
+    );
+}
+
+#[tokio::test]
+async fn test_attest_bundle() {
+    let addr = TestHarness::alloc_port().await;
+    let dir = tempfile::tempdir().unwrap();
+    let event_log_path = dir.path().join("CCEL");
+    std::fs::write(&event_log_path, [0xaa, 0xbb]).unwrap();
+
+    let mut harness = TestHarness::with_config(ServerConfig {
+        listen_addr: addr,
+        use_mock: true,
+        mock_attestation_path: format!("{}/tests/test_data/quote.bin", env!("CARGO_MANIFEST_DIR")),
+        event_log_path: event_log_path.to_str().unwrap().to_string(),
+        ..Default::default()
+    });
+    harness.start_server().await;
+
+    let quote = include_bytes!("./test_data/quote.bin");
+    let bundle = harness.attest_bundle(hex::encode([0; 64])).await.unwrap();
+    assert_eq!(bundle["quote"], format!("0x{}", hex::encode(quote)));
+    assert_eq!(
+        bundle["workloadId"],
+        format!("0x{}", hex::encode(compute_workload_id(quote).unwrap()))
+    );
+    // MRTD is at offset 136 of the report body, after the 48 bytes header
+    assert_eq!(
+        bundle["measurements"]["mrtd"],
+        format!("0x{}", hex::encode(&quote[184..232]))
+    );
+    assert_eq!(bundle["eventLog"], "0xaabb");
+
+    let response = harness.attest_bundle("aede".to_string()).await;
+    assert!(response.is_err());
+}
+
+fn mock_quote_config(listen_addr: SocketAddr) -> ServerConfig {
+    ServerConfig {
+        listen_addr,
+        use_mock: true,
+        mock_attestation_path: format!("{}/tests/test_data/quote.bin", env!("CARGO_MANIFEST_DIR")),
+        ..Default::default()
+    }
+}
+
+fn tls_test_data(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/test_data/tls")
+        .join(name)
+}
+
+#[tokio::test]
+async fn test_auth_token() {
+    let addr = TestHarness::alloc_port().await;
+    let mut harness = TestHarness::with_config(ServerConfig {
+        auth_token: Some("secret".to_string()),
+        ..mock_quote_config(addr)
+    });
+    // the health check does not require the token
+    harness.start_server().await;
+
+    let report_data = hex::encode([0; 64]);
+    assert_eq!(
+        harness.attest_status(report_data.clone()).await,
+        StatusCode::UNAUTHORIZED
+    );
+    harness.auth_token = Some("secre".to_string());
+    assert_eq!(
+        harness.attest_status(report_data.clone()).await,
+        StatusCode::UNAUTHORIZED
+    );
+    harness.auth_token = Some("secret".to_string());
+    assert_eq!(harness.attest_status(report_data).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_rate_limit() {
+    let addr = TestHarness::alloc_port().await;
+    let mut harness = TestHarness::with_config(ServerConfig {
+        rate_limit: 1,
+        rate_limit_burst: 2,
+        ..mock_quote_config(addr)
+    });
+    harness.start_server().await;
+
+    let report_data = hex::encode([0; 64]);
+    assert_eq!(
+        harness.attest_status(report_data.clone()).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        harness.attest_status(report_data.clone()).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        harness.attest_status(report_data.clone()).await,
+        StatusCode::TOO_MANY_REQUESTS
+    );
+    // the health check is not rate limited
+    assert!(harness.healthcheck().await.is_ok());
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(harness.attest_status(report_data).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_max_request_size() {
+    let addr = TestHarness::alloc_port().await;
+    let mut harness = TestHarness::with_config(ServerConfig {
+        max_request_size: 200,
+        ..mock_quote_config(addr)
+    });
+    harness.start_server().await;
+
+    assert_eq!(
+        harness.attest_status(hex::encode([0; 64])).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        harness.attest_status(hex::encode([0; 128])).await,
+        StatusCode::PAYLOAD_TOO_LARGE
+    );
+}
+
+#[tokio::test]
+async fn test_attestation_timeout() {
+    let addr = TestHarness::alloc_port().await;
+    let dir = tempfile::tempdir().unwrap();
+    // reading the quote from a FIFO blocks until it is written, like a hanging TDX device
+    let quote_path = dir.path().join("quote.fifo");
+    let status = std::process::Command::new("mkfifo")
+        .arg(&quote_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let mut harness = TestHarness::with_config(ServerConfig {
+        listen_addr: addr,
+        use_mock: true,
+        mock_attestation_path: quote_path.to_str().unwrap().to_string(),
+        max_concurrent_attestations: 1,
+        attestation_timeout: Duration::from_millis(200),
+        ..Default::default()
+    });
+    harness.start_server().await;
+
+    assert_eq!(
+        harness.attest_status(hex::encode([1; 64])).await,
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+    // the hanging device call does not block the server
+    assert!(harness.healthcheck().await.is_ok());
+    // and holds the device, so other attestations time out waiting for it
+    assert_eq!(
+        harness.attest_status(hex::encode([2; 64])).await,
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+
+    // release the hanging device call
+    let path = quote_path.clone();
+    tokio::task::spawn_blocking(move || std::fs::write(path, b"first quote").unwrap())
+        .await
+        .unwrap();
+
+    // the timed out attestation is not cached and the device is available again
+    let writer =
+        tokio::task::spawn_blocking(move || std::fs::write(quote_path, b"second quote").unwrap());
+    assert_eq!(
+        harness.attest(hex::encode([1; 64])).await.unwrap(),
+        Bytes::from_static(b"second quote")
+    );
+    writer.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_mtls() {
+    let addr = TestHarness::alloc_port().await;
+    let mut harness = TestHarness::with_config(ServerConfig {
+        tls: Some(TlsConfig {
+            cert_path: tls_test_data("server.pem"),
+            key_path: tls_test_data("server.key"),
+            client_ca_path: Some(tls_test_data("ca.pem")),
+        }),
+        ..mock_quote_config(addr)
+    });
+    let ca = Certificate::from_pem(&std::fs::read(tls_test_data("ca.pem")).unwrap()).unwrap();
+    let identity = Identity::from_pem(
+        &[
+            std::fs::read(tls_test_data("client.pem")).unwrap(),
+            std::fs::read(tls_test_data("client.key")).unwrap(),
+        ]
+        .concat(),
+    )
+    .unwrap();
+    harness.base_url = format!("https://localhost:{}", addr.port());
+    harness.client = Client::builder()
+        .use_rustls_tls()
+        .add_root_certificate(ca.clone())
+        .identity(identity)
+        .build()
+        .unwrap();
+    harness.start_server().await;
+
+    assert_eq!(
+        harness.attest(hex::encode([0; 64])).await.unwrap(),
+        Bytes::from_static(include_bytes!("./test_data/quote.bin"))
+    );
+
+    // clients without a certificate are rejected
+    harness.client = Client::builder()
+        .use_rustls_tls()
+        .add_root_certificate(ca)
+        .build()
+        .unwrap();
+    assert!(harness.healthcheck().await.is_err());
+}
+
+#[tokio::test]
+async fn test_synthetic_mock_attest() {
+    let addr = TestHarness::alloc_port().await;
+    let measurements = MockMeasurements {
+        mr_td: [1; 48],
+        rt_mr2: [2; 48],
+        ..Default::default()
+    };
+    // without a mock attestation file, quotes are synthesized for the report data
+    let mut harness = TestHarness::with_config(ServerConfig {
+        listen_addr: addr,
+        use_mock: true,
+        mock_measurements: measurements.clone(),
+        ..Default::default()
+    });
+    harness.start_server().await;
+
+    let report_data = [0xab; 64];
+    let quote = harness.attest(hex::encode(report_data)).await.unwrap();
+    let parsed = parse_report_body(&quote).unwrap();
+    assert_eq!(parsed.mr_td, measurements.mr_td);
+    assert_eq!(parsed.rt_mr2, measurements.rt_mr2);
+    // the report data is the last field of the report body
+    assert_eq!(quote[HEADER_LENGTH + 520..HEADER_LENGTH + 584], report_data);
+
+    let bundle = harness
+        .attest_bundle(hex::encode([0xcd; 64]))
+        .await
+        .unwrap();
+    assert_eq!(
+        bundle["measurements"]["mrtd"],
+        format!("0x{}", hex::encode([1; 48]))
+    );
+    assert_eq!(
+        bundle["workloadId"],
+        format!("0x{}", hex::encode(compute_workload_id(&quote).unwrap()))
+    );
+    assert_ne!(bundle["quote"], format!("0x{}", hex::encode(&quote)));
+}